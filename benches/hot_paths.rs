@@ -0,0 +1,81 @@
+// Micro-benchmarks of the work the proxy does on every request: key validation, JSON parsing,
+// schema validation and response serialization.
+//
+//   cargo bench --bench hot_paths
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rustredis::schema::{is_valid_key, schema_for, validate_json_schema, KEY_PATTERN};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+// Define the response shape the proxy writes back (mirrors its Response struct)
+#[derive(Serialize)]
+struct Response {
+    status: String,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+// Function to build request lines of increasing size, as producers send them
+fn requests() -> Vec<(&'static str, String)> {
+    let small = json!({"action": "set", "key": "cs:DiskUsage:object1", "value": {"version": 1, "disk": "/", "usage": 42.5}});
+    let medium = json!({
+        "action": "set",
+        "key": "cs:ModemWatcher:object2:wwan0",
+        "value": {"version": 2, "status": "connected", "signal_strength": -71, "bands": (1..=32).collect::<Vec<_>>()},
+    });
+    let readings: Vec<Value> = (0..200).map(|i| json!({"t": 1_700_000_000 + i, "v": i as f64 / 3.0, "ok": i % 7 != 0})).collect();
+    let large = json!({"action": "xadd", "key": "cs:Psmon:object1:sensor", "value": {"version": 1, "readings": readings}, "maxlen": 1000});
+    vec![("small", small.to_string()), ("medium", medium.to_string()), ("large", large.to_string())]
+}
+
+// Function to benchmark key validation against the key grammar
+fn key_matching(c: &mut Criterion) {
+    let mut group = c.benchmark_group("key_regex");
+    for key in ["cs:DiskUsage:object1", "cs:ModemWatcher:object2:wwan0:status", "cs:Unknown:object9:x", "not-a-key"] {
+        group.bench_with_input(BenchmarkId::from_parameter(key), key, |b, key| b.iter(|| is_valid_key(black_box(key))));
+    }
+    group.bench_function("captures", |b| b.iter(|| KEY_PATTERN.captures(black_box("cs:ModemWatcher:object2:wwan0:status")).is_some()));
+    group.finish();
+}
+
+// Function to benchmark parsing request lines
+fn json_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("json_parse");
+    for (name, line) in requests() {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &line, |b, line| {
+            b.iter(|| serde_json::from_str::<Value>(black_box(line)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+// Function to benchmark schema validation as the proxy does it (compiling the schema on every
+// call) against validating with a schema compiled once
+fn schema_validation(c: &mut Criterion) {
+    let key = "cs:DiskUsage:object1";
+    let value = json!({"version": 1, "disk": "/", "usage": 42.5});
+    let schema = schema_for(key).expect("built-in schema");
+    let compiled = jsonschema::JSONSchema::compile(&schema).unwrap();
+
+    let mut group = c.benchmark_group("schema");
+    group.bench_function("compile", |b| b.iter(|| jsonschema::JSONSchema::compile(black_box(&schema)).unwrap()));
+    group.bench_function("compile_and_validate", |b| b.iter(|| validate_json_schema(black_box(key), black_box(&value))));
+    group.bench_function("precompiled_validate", |b| b.iter(|| compiled.is_valid(black_box(&value))));
+    group.finish();
+}
+
+// Function to benchmark serializing responses without and with a payload
+fn response_serialization(c: &mut Criterion) {
+    let ack = Response { status: "ok".to_string(), message: "Value stored".to_string(), data: None };
+    let readings: Vec<Value> = (0..200).map(|i| json!({"t": i, "v": i as f64 / 3.0})).collect();
+    let get = Response { status: "ok".to_string(), message: "Value read".to_string(), data: Some(json!({"found": true, "value": {"readings": readings}})) };
+
+    let mut group = c.benchmark_group("response");
+    group.bench_function("ack", |b| b.iter(|| serde_json::to_string(black_box(&ack)).unwrap()));
+    group.bench_function("get", |b| b.iter(|| serde_json::to_string(black_box(&get)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, key_matching, json_parse, schema_validation, response_serialization);
+criterion_main!(benches);
@@ -0,0 +1,37 @@
+/*
+ * Example producer and consumer using the C interface.
+ *
+ *   cc ffi/example.c -Iffi -Ltarget/release -lrustredis -o example
+ *   LD_LIBRARY_PATH=target/release ./example
+ */
+#include <stdio.h>
+#include "rustredis.h"
+
+int main(void) {
+    RrClient *client = rr_connect(NULL);
+    if (client == NULL) {
+        fprintf(stderr, "cannot connect to the proxy\n");
+        return 1;
+    }
+
+    if (rr_set(client, "cs:DiskUsage:object1", "{\"version\": 1, \"disk\": \"/dev/sda1\", \"usage\": 42.5}") != 0) {
+        fprintf(stderr, "set failed: %s\n", rr_last_error(client));
+    }
+
+    char *value = rr_get(client, "cs:DiskUsage:object1");
+    if (value != NULL) {
+        printf("stored: %s\n", value);
+        rr_free_string(value);
+    }
+
+    if (rr_subscribe(client, "cs:DiskUsage:*", "value.usage > 90") == 0) {
+        char *event = rr_next_event(client);
+        if (event != NULL) {
+            printf("event: %s\n", event);
+            rr_free_string(event);
+        }
+    }
+
+    rr_close(client);
+    return 0;
+}
@@ -0,0 +1,48 @@
+/*
+ * C interface to the Redis proxy client of the rustredis library.
+ *
+ * Build the shared library with `cargo build --release` (target/release/librustredis.so) and
+ * link with -lrustredis. Strings are NUL-terminated UTF-8; values and events are JSON text.
+ * Strings returned by rr_get and rr_next_event belong to the caller and are released with
+ * rr_free_string. Calls returning int return 0 on success and -1 on failure, with the reason
+ * in rr_last_error. A handle must not be used from several threads at once.
+ */
+#ifndef RUSTREDIS_H
+#define RUSTREDIS_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RrClient RrClient;
+
+/* Connects to the proxy socket (/tmp/redis_proxy.sock if socket_path is NULL); NULL on failure. */
+RrClient *rr_connect(const char *socket_path);
+
+/* Closes the connection and frees the handle. */
+void rr_close(RrClient *client);
+
+/* Stores a JSON value under a key, e.g. rr_set(c, "cs:DiskUsage:object1", "{\"version\":1,...}"). */
+int rr_set(RrClient *client, const char *key, const char *value_json);
+
+/* Reads a key; returns {"found": bool, "value": ...} as JSON, or NULL on failure. */
+char *rr_get(RrClient *client, const char *key);
+
+/* Subscribes to the events of keys matching a glob pattern, optionally filtered server-side
+ * (e.g. "value.usage > 90", NULL for all events). The connection then only carries events. */
+int rr_subscribe(RrClient *client, const char *pattern, const char *filter);
+
+/* Waits for the next event; returns {"key", "action", "value"} as JSON, or NULL on failure. */
+char *rr_next_event(RrClient *client);
+
+/* Message of the last failed call on the handle; owned by the handle. */
+const char *rr_last_error(const RrClient *client);
+
+/* Frees a string returned by rr_get or rr_next_event. */
+void rr_free_string(char *text);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RUSTREDIS_H */
@@ -0,0 +1,92 @@
+"""Python bindings of the Redis proxy client, over the C interface of librustredis.
+
+Mirrors ffi/rustredis.h with ctypes; values and events are exchanged as Python objects.
+
+    from rustredis import ProxyClient
+
+    with ProxyClient() as proxy:
+        proxy.set("cs:DiskUsage:object1", {"version": 1, "disk": "/dev/sda1", "usage": 42.5})
+        print(proxy.get("cs:DiskUsage:object1"))
+        proxy.subscribe("cs:DiskUsage:*", "value.usage > 90")
+        for event in proxy.events():
+            print(event)
+
+Set RUSTREDIS_LIB to the path of librustredis.so if it is not on the loader's search path.
+"""
+
+import ctypes
+import json
+import os
+
+_lib = ctypes.CDLL(os.environ.get("RUSTREDIS_LIB", "librustredis.so"))
+
+_lib.rr_connect.argtypes = [ctypes.c_char_p]
+_lib.rr_connect.restype = ctypes.c_void_p
+_lib.rr_close.argtypes = [ctypes.c_void_p]
+_lib.rr_close.restype = None
+_lib.rr_set.argtypes = [ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p]
+_lib.rr_set.restype = ctypes.c_int
+_lib.rr_get.argtypes = [ctypes.c_void_p, ctypes.c_char_p]
+_lib.rr_get.restype = ctypes.c_void_p  # Freed with rr_free_string, so not converted automatically
+_lib.rr_subscribe.argtypes = [ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p]
+_lib.rr_subscribe.restype = ctypes.c_int
+_lib.rr_next_event.argtypes = [ctypes.c_void_p]
+_lib.rr_next_event.restype = ctypes.c_void_p
+_lib.rr_last_error.argtypes = [ctypes.c_void_p]
+_lib.rr_last_error.restype = ctypes.c_char_p
+_lib.rr_free_string.argtypes = [ctypes.c_void_p]
+_lib.rr_free_string.restype = None
+
+
+class ProxyError(Exception):
+    """Raised when the proxy rejects a request or the connection fails."""
+
+
+class ProxyClient:
+    """Connection to the Redis proxy socket."""
+
+    def __init__(self, socket_path=None):
+        self._handle = _lib.rr_connect(socket_path.encode() if socket_path else None)
+        if not self._handle:
+            raise ProxyError("cannot connect to the proxy")
+
+    def close(self):
+        if self._handle:
+            _lib.rr_close(self._handle)
+            self._handle = None
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *exc):
+        self.close()
+
+    def _check(self, status):
+        if status != 0:
+            raise ProxyError(_lib.rr_last_error(self._handle).decode())
+
+    def _json(self, pointer):
+        if not pointer:
+            raise ProxyError(_lib.rr_last_error(self._handle).decode())
+        try:
+            return json.loads(ctypes.string_at(pointer).decode())
+        finally:
+            _lib.rr_free_string(pointer)
+
+    def set(self, key, value):
+        """Stores a JSON-serializable value under a key."""
+        self._check(_lib.rr_set(self._handle, key.encode(), json.dumps(value).encode()))
+
+    def get(self, key):
+        """Returns the value stored under a key, or None if there is none."""
+        payload = self._json(_lib.rr_get(self._handle, key.encode()))
+        return payload.get("value")
+
+    def subscribe(self, pattern, filter=None):
+        """Subscribes to the events of keys matching a glob pattern, optionally filtered server-side."""
+        self._check(_lib.rr_subscribe(self._handle, pattern.encode(), filter.encode() if filter else None))
+
+    def events(self):
+        """Yields the events of the subscription ({"key", "action", "value"}) as they arrive."""
+        while True:
+            yield self._json(_lib.rr_next_event(self._handle))
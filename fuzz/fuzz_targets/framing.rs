@@ -0,0 +1,27 @@
+// Fuzz target: the proxy's newline framing fed arbitrary bytes in arbitrary chunks.
+//
+//   cargo +nightly fuzz run framing
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustredis::framing::LineFramer;
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte picks the chunk size, as reads from the socket split messages anywhere
+    let Some((&chunk, data)) = data.split_first() else { return };
+    let mut framer = LineFramer::new();
+    let mut messages = Vec::new();
+    for piece in data.chunks(chunk as usize + 1) {
+        framer.push(piece);
+        while let Some(message) = framer.next_message() {
+            assert_eq!(message.iter().filter(|&&b| b == b'\n').count(), 1);
+            assert_eq!(message.last(), Some(&b'\n'));
+            messages.push(message);
+        }
+    }
+    // Nothing is lost, duplicated or reordered
+    assert!(!framer.pending().contains(&b'\n'));
+    let mut rebuilt = messages.concat();
+    rebuilt.extend_from_slice(framer.pending());
+    assert_eq!(rebuilt, data);
+});
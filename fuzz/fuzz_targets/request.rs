@@ -0,0 +1,57 @@
+// Fuzz target: untrusted request lines through the proxy's request handling and dispatch, backed
+// by the in-memory store instead of Redis, and through the library code it runs on them (key
+// grammar, schema validation and normalization, redaction, subscription filters, binary values
+// and event parsing).
+//
+//   cargo +nightly fuzz run request
+#![no_main]
+
+#[allow(dead_code)] // Only the dispatch is driven, not the listeners
+#[path = "../../src/bin/redis_proxy/main.rs"]
+mod proxy;
+
+use libfuzzer_sys::fuzz_target;
+use rustredis::events::parse_event;
+use rustredis::filter::Filter;
+use rustredis::framing::LineFramer;
+use rustredis::schema::{base_key, is_valid_key, key_producer, normalize, pattern_producer, redact, redact_message, validate_json_schema};
+use rustredis::base64;
+use serde_json::Value;
+
+fuzz_target!(|data: &[u8]| {
+    let mut framer = LineFramer::new();
+    framer.push(data);
+    let mut requests = Vec::new();
+    while let Some(line) = framer.next_message() {
+        let Ok(text) = String::from_utf8(line) else { continue };
+        requests.push(text);
+    }
+    for response in proxy::handle_in_memory(&requests) {
+        assert!(serde_json::from_str::<Value>(&response).is_ok(), "unparsable response {}", response);
+    }
+
+    for text in &requests {
+        let Ok(request) = serde_json::from_str::<Value>(text.trim()) else { continue };
+        let key = request["key"].as_str().unwrap_or_default();
+        let value = &request["value"];
+
+        if is_valid_key(key) {
+            assert!(key_producer(key).is_some());
+            assert!(base_key(key).starts_with("cs:"));
+            if let Err(err) = validate_json_schema(key, value) {
+                let _ = redact_message(key, value, &err);
+            }
+            let _ = normalize(key, value);
+            let _ = redact(key, value);
+        }
+        if let Some(pattern) = request["pattern"].as_str() {
+            let _ = pattern_producer(pattern);
+        }
+        if let Some(Ok(filter)) = request["filter"].as_str().map(Filter::parse) {
+            let _ = filter.matches(&parse_event(key, &value.to_string()));
+        }
+        if let Some(Ok(bytes)) = request["value_b64"].as_str().map(base64::decode) {
+            assert_eq!(base64::decode(&base64::encode(&bytes)).unwrap(), bytes);
+        }
+    }
+});
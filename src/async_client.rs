@@ -0,0 +1,211 @@
+// Import necessary crates and modules
+use crate::client::{hello_request, ClientError, ProxyResponse, DEFAULT_SOCKET_PATH}; // For the protocol shared with the blocking client
+use futures_core::Stream; // For subscription streams
+use serde_json::{json, Value}; // For building requests
+use std::collections::VecDeque; // For the responses still awaited, in request order
+use std::io; // For connection errors
+use std::pin::Pin; // For polling subscription streams
+use std::sync::{Arc, Mutex}; // For sharing the awaited responses with the reader task
+use std::task::{Context, Poll}; // For polling subscription streams
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader}; // For line-based socket I/O
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf}; // For reading and writing the socket from different tasks
+use tokio::net::UnixStream; // For connecting to the proxy
+use tokio::sync::{mpsc, oneshot}; // For handing responses and events to callers
+use tokio::task::JoinHandle; // For stopping the reader task
+
+// Define how many events a subscription buffers before the reader waits for the consumer
+const EVENT_BUFFER: usize = 256;
+
+// Define a caller waiting for the response to its request
+type Waiter = oneshot::Sender<Result<ProxyResponse, ClientError>>;
+
+// Function to build the error of a connection that went away
+fn closed() -> ClientError {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "proxy closed the connection").into()
+}
+
+// Function to write one newline-terminated request
+async fn send(writer: &mut OwnedWriteHalf, request: &Value) -> io::Result<()> {
+    writer.write_all(format!("{}\n", request).as_bytes()).await
+}
+
+// Function to read the next response line, None once the proxy closed the connection
+async fn read_response(reader: &mut BufReader<OwnedReadHalf>) -> Result<Option<ProxyResponse>, ClientError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    let response = serde_json::from_str(line.trim()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(Some(response))
+}
+
+// Function to connect and negotiate newline-framed responses
+async fn handshake(socket_path: &str) -> Result<(BufReader<OwnedReadHalf>, OwnedWriteHalf), ClientError> {
+    let (read_half, mut writer) = UnixStream::connect(socket_path).await?.into_split();
+    let mut reader = BufReader::new(read_half);
+    send(&mut writer, &hello_request()).await?;
+    let hello = read_response(&mut reader).await?.ok_or_else(closed)?;
+    if !hello.is_ok() {
+        return Err(ClientError::Proxy(hello.message));
+    }
+    Ok((reader, writer))
+}
+
+/// Tokio client for the proxy's newline-delimited JSON protocol.
+///
+/// Requests may be issued concurrently from several tasks over the one connection: the proxy
+/// answers in request order, so each response is handed to the oldest request still waiting.
+pub struct AsyncProxyClient {
+    writer: Arc<tokio::sync::Mutex<OwnedWriteHalf>>, // Held while a request is queued and written, keeping both in the same order
+    waiting: Arc<Mutex<VecDeque<Waiter>>>, // Requests awaiting their response, oldest first
+    reader: JoinHandle<()>, // Task dispatching responses and answering keepalive pings
+}
+
+impl AsyncProxyClient {
+    /// Connects to the proxy at the default socket path.
+    pub async fn connect_default() -> Result<Self, ClientError> {
+        Self::connect(DEFAULT_SOCKET_PATH).await
+    }
+
+    /// Connects to the proxy and negotiates newline-framed responses.
+    pub async fn connect(socket_path: &str) -> Result<Self, ClientError> {
+        let (mut reader, writer) = handshake(socket_path).await?;
+        let writer = Arc::new(tokio::sync::Mutex::new(writer));
+        let waiting: Arc<Mutex<VecDeque<Waiter>>> = Arc::default();
+
+        let (reader_writer, reader_waiting) = (Arc::clone(&writer), Arc::clone(&waiting));
+        let reader = tokio::spawn(async move {
+            loop {
+                let response = match read_response(&mut reader).await {
+                    Ok(Some(response)) => response,
+                    Ok(None) | Err(_) => break,
+                };
+                if response.status == "ping" {
+                    if send(&mut *reader_writer.lock().await, &json!({"action": "pong"})).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                let waiter = reader_waiting.lock().unwrap().pop_front();
+                if let Some(waiter) = waiter {
+                    let _ = waiter.send(Ok(response)); // The caller may have given up on it
+                }
+            }
+            // Fail every request still waiting; nothing will answer them
+            for waiter in reader_waiting.lock().unwrap().drain(..) {
+                let _ = waiter.send(Err(closed()));
+            }
+        });
+        Ok(AsyncProxyClient { writer, waiting, reader })
+    }
+
+    /// Sends a raw request and waits for its response.
+    pub async fn request(&self, request: &Value) -> Result<ProxyResponse, ClientError> {
+        let (waiter, response) = oneshot::channel();
+        {
+            let mut writer = self.writer.lock().await;
+            if self.reader.is_finished() {
+                return Err(closed());
+            }
+            self.waiting.lock().unwrap().push_back(waiter);
+            send(&mut writer, request).await?;
+        }
+        response.await.map_err(|_| closed())?
+    }
+
+    /// Stores a JSON value under a key.
+    pub async fn set(&self, key: &str, value: &Value) -> Result<(), ClientError> {
+        self.read(&json!({"action": "set", "key": key, "value": value})).await.map(|_| ())
+    }
+
+    /// Reads the value stored under a key (`{"found", "value"}`).
+    pub async fn get(&self, key: &str) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "get", "key": key})).await
+    }
+
+    /// Appends a JSON value to a stream capped at roughly `maxlen` entries, returning the entry id.
+    pub async fn xadd(&self, key: &str, value: &Value, maxlen: Option<usize>) -> Result<String, ClientError> {
+        let data = self.read(&json!({"action": "xadd", "key": key, "value": value, "maxlen": maxlen})).await?;
+        Ok(data["id"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Deletes a key.
+    pub async fn del(&self, key: &str) -> Result<(), ClientError> {
+        self.read(&json!({"action": "del", "key": key})).await.map(|_| ())
+    }
+
+    /// Returns the proxy's health report.
+    pub async fn stats(&self) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "stats"})).await
+    }
+
+    /// Subscribes to the events of keys matching a glob pattern, optionally only those passing a
+    /// [`crate::filter::Filter`] expression. Subscribed connections only carry events, so the
+    /// subscription gets its own connection and this client stays usable for requests.
+    pub async fn subscribe(socket_path: &str, pattern: &str, filter: Option<&str>) -> Result<EventStream, ClientError> {
+        let (mut reader, mut writer) = handshake(socket_path).await?;
+        send(&mut writer, &json!({"action": "subscribe", "pattern": pattern, "filter": filter})).await?;
+        let subscribed = read_response(&mut reader).await?.ok_or_else(closed)?;
+        if !subscribed.is_ok() {
+            return Err(ClientError::Proxy(subscribed.message));
+        }
+
+        let (sender, events) = mpsc::channel(EVENT_BUFFER);
+        let task = tokio::spawn(async move {
+            loop {
+                let event = match read_response(&mut reader).await {
+                    Ok(Some(response)) if response.status == "event" => Ok(response.data.unwrap_or(Value::Null)),
+                    Ok(Some(response)) if response.status == "ping" => {
+                        match send(&mut writer, &json!({"action": "pong"})).await {
+                            Ok(()) => continue,
+                            Err(err) => Err(err.into()),
+                        }
+                    }
+                    Ok(Some(response)) => Err(ClientError::Proxy(response.message)),
+                    Ok(None) => Err(closed()),
+                    Err(err) => Err(err),
+                };
+                let failed = event.is_err();
+                if sender.send(event).await.is_err() || failed {
+                    break; // Stream dropped, or its last item was the error that ended it
+                }
+            }
+        });
+        Ok(EventStream { events, task })
+    }
+
+    // Function to send a request and return its payload, turning a proxy-side rejection into an error
+    async fn read(&self, request: &Value) -> Result<Value, ClientError> {
+        let response = self.request(request).await?;
+        if !response.is_ok() {
+            return Err(ClientError::Proxy(response.message));
+        }
+        Ok(response.data.unwrap_or(Value::Null))
+    }
+}
+
+impl Drop for AsyncProxyClient {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Events of a subscription (`{"key", "action", "value"}`); ends after the error that closed it.
+pub struct EventStream {
+    events: mpsc::Receiver<Result<Value, ClientError>>,
+    task: JoinHandle<()>, // Task reading the subscription's connection
+}
+
+impl Stream for EventStream {
+    type Item = Result<Value, ClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        self.task.abort(); // Closes the subscription's connection
+    }
+}
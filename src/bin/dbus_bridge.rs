@@ -0,0 +1,291 @@
+// D-Bus to Redis proxy bridge.
+//
+// Signals are received through `busctl monitor` and properties are polled with
+// `busctl get-property`, so the bridge needs systemd's busctl but no D-Bus library.
+// Example configuration:
+//
+// {
+//   "bus": "system",
+//   "signals": [
+//     {"match": "type='signal',sender='org.freedesktop.NetworkManager',member='StateChanged'",
+//      "key": "cs:ModemWatcher:object2:nm"}
+//   ],
+//   "properties": [
+//     {"service": "org.freedesktop.UPower", "path": "/org/freedesktop/UPower/devices/DisplayDevice",
+//      "interface": "org.freedesktop.UPower.Device", "property": "Percentage",
+//      "key": "cs:Psmon:object1:battery", "interval": 60}
+//   ]
+// }
+
+use clap::Parser;
+use rustredis::check::ConfigCheck;
+use rustredis::client::{ProxyClient, DEFAULT_SOCKET_PATH};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::process::{self, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// D-Bus signal and property bridge to the Redis proxy
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Path of the JSON configuration file
+    #[arg(long)]
+    config: String,
+
+    /// Check the configuration file, print the diagnostics as JSON and exit (status 1 if invalid)
+    #[arg(long)]
+    check_config: bool,
+
+    /// With --check-config, also connect to the proxy
+    #[arg(long, requires = "check_config")]
+    check_connectivity: bool,
+}
+
+// Define the bridge configuration
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default = "default_bus")]
+    bus: String, // "system" or "user"
+    #[serde(default = "default_proxy_socket")]
+    proxy_socket: String, // Unix socket of the Redis proxy
+    #[serde(default)]
+    signals: Vec<SignalRule>, // Signals republished as proxy writes
+    #[serde(default)]
+    properties: Vec<PropertyRule>, // Properties polled and republished on change
+}
+
+// Define a D-Bus match rule and the key its signals are written to
+#[derive(Deserialize)]
+struct SignalRule {
+    #[serde(rename = "match")]
+    match_rule: String, // D-Bus match rule, e.g. "type='signal',interface='...',member='...'"
+    key: String, // Proxy key the signal is written to
+}
+
+// Define a polled D-Bus property and the key its value is written to
+#[derive(Deserialize)]
+struct PropertyRule {
+    service: String, // Bus name owning the object
+    path: String, // Object path
+    interface: String, // Interface declaring the property
+    property: String, // Property name
+    key: String, // Proxy key the value is written to
+    #[serde(default = "default_interval")]
+    interval: u64, // Seconds between polls
+}
+
+fn default_bus() -> String {
+    "system".to_string()
+}
+
+fn default_proxy_socket() -> String {
+    DEFAULT_SOCKET_PATH.to_string()
+}
+
+fn default_interval() -> u64 {
+    60
+}
+
+// Replace busctl's {"type": ..., "data": ...} variant wrappers with their plain data
+fn unwrap_variants(value: Value) -> Value {
+    match value {
+        Value::Object(map) if map.len() == 2 && map.contains_key("type") && map.contains_key("data") => {
+            unwrap_variants(map.into_iter().find(|(k, _)| k == "data").unwrap().1)
+        }
+        Value::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (k, unwrap_variants(v))).collect::<Map<_, _>>()),
+        Value::Array(items) => Value::Array(items.into_iter().map(unwrap_variants).collect()),
+        other => other,
+    }
+}
+
+// Turn a message printed by `busctl monitor --json=short` into the value written to the proxy
+fn signal_value(message: &Value) -> Value {
+    let args = unwrap_variants(message["payload"]["data"].clone());
+    let mut value = json!({
+        "sender": message["sender"],
+        "path": message["path"],
+        "interface": message["interface"],
+        "member": message["member"],
+        "args": args
+    });
+    // PropertiesChanged(interface, changed, invalidated) is flattened so consumers see the changed properties directly
+    if message["member"] == "PropertiesChanged" {
+        value["changed_interface"] = args[0].clone();
+        value["changed"] = args[1].clone();
+    }
+    value
+}
+
+// Build a busctl command on the configured bus
+fn busctl(bus: &str) -> Command {
+    let mut command = Command::new("busctl");
+    command.arg(format!("--{}", bus)).arg("--json=short");
+    command
+}
+
+// Forward every signal matching a rule to the proxy
+fn run_signals(config: &'static Config) -> Result<(), String> {
+    let mut proxy = ProxyClient::connect(&config.proxy_socket).map_err(|e| e.to_string())?;
+
+    let mut command = busctl(&config.bus);
+    command.arg("monitor");
+    for rule in &config.signals {
+        command.arg(format!("--match={}", rule.match_rule));
+        println!("Bridging D-Bus signals {} -> {}", rule.match_rule, rule.key);
+    }
+    let mut child = command.stdout(Stdio::piped()).spawn().map_err(|e| format!("failed to start busctl: {}", e))?;
+    let stdout = child.stdout.take().unwrap();
+
+    for line in BufReader::new(stdout).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue; // busctl prints non-JSON notices on startup
+        };
+        if message["type"] != "signal" {
+            continue;
+        }
+
+        // busctl merges all match rules into one monitor, so each signal is checked against every rule
+        for rule in config.signals.iter().filter(|rule| signal_matches(&rule.match_rule, &message)) {
+            if let Err(err) = proxy.set(&rule.key, &signal_value(&message)) {
+                eprintln!("Failed to write signal to {}: {}", rule.key, err);
+            }
+        }
+    }
+
+    Err(format!("busctl monitor exited: {:?}", child.wait()))
+}
+
+// Check the sender/path/interface/member clauses of a match rule against a signal
+fn signal_matches(match_rule: &str, message: &Value) -> bool {
+    match_rule.split(',').all(|clause| {
+        let Some((field, expected)) = clause.split_once('=') else {
+            return true;
+        };
+        let expected = expected.trim_matches('\'');
+        match field.trim() {
+            "path" | "interface" | "member" => message[field.trim()] == expected,
+            // Signals carry the unique sender name, so well-known names cannot be compared here
+            _ => true,
+        }
+    })
+}
+
+// Read the current value of a property
+fn read_property(config: &Config, rule: &PropertyRule) -> Result<Value, String> {
+    let output = busctl(&config.bus)
+        .args(["get-property", &rule.service, &rule.path, &rule.interface, &rule.property])
+        .output()
+        .map_err(|e| format!("failed to run busctl: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    serde_json::from_slice(&output.stdout).map(unwrap_variants).map_err(|e| e.to_string())
+}
+
+// Poll every property on its interval, writing values that changed since the last poll
+fn run_properties(config: &'static Config) -> Result<(), String> {
+    let mut proxy = ProxyClient::connect(&config.proxy_socket).map_err(|e| e.to_string())?;
+    let mut last_values: Vec<Option<Value>> = vec![None; config.properties.len()];
+    let mut next_poll: Vec<Instant> = vec![Instant::now(); config.properties.len()];
+
+    loop {
+        for (i, rule) in config.properties.iter().enumerate() {
+            if Instant::now() < next_poll[i] {
+                continue;
+            }
+            next_poll[i] = Instant::now() + Duration::from_secs(rule.interval);
+
+            match read_property(config, rule) {
+                Ok(value) if last_values[i].as_ref() != Some(&value) => {
+                    let payload = json!({
+                        "service": rule.service,
+                        "path": rule.path,
+                        "interface": rule.interface,
+                        "property": rule.property,
+                        "value": value
+                    });
+                    match proxy.set(&rule.key, &payload) {
+                        Ok(()) => last_values[i] = Some(value),
+                        Err(err) => eprintln!("Failed to write property to {}: {}", rule.key, err),
+                    }
+                }
+                Ok(_) => {} // Unchanged
+                Err(err) => eprintln!("Failed to read {}.{}: {}", rule.interface, rule.property, err),
+            }
+        }
+
+        let next = next_poll.iter().min().copied().unwrap_or_else(Instant::now);
+        thread::sleep(next.saturating_duration_since(Instant::now()));
+    }
+}
+
+// Check the configuration file and busctl, then exit with the diagnostics
+fn check_config(args: &Args) -> ! {
+    let mut check = ConfigCheck::new("dbus_bridge", args.check_connectivity);
+    let Some(config) = check.parse_json::<Config>(&args.config) else { check.exit() };
+    if matches!(config.bus.as_str(), "system" | "user") {
+        check.ok("bus", "valid bus");
+    } else {
+        check.error("bus", format!("expected \"system\" or \"user\", got \"{}\"", config.bus));
+    }
+    match Command::new("busctl").arg("--version").stdout(Stdio::null()).status() {
+        Ok(status) if status.success() => check.ok("busctl", "available"),
+        _ => check.error("busctl", "the bridge requires systemd's busctl"),
+    }
+    check.proxy("proxy_socket", &config.proxy_socket);
+    if config.signals.is_empty() && config.properties.is_empty() {
+        check.warn("signals", "no signals or properties, nothing is bridged");
+    }
+    for (i, rule) in config.signals.iter().enumerate() {
+        if rule.match_rule.is_empty() {
+            check.error(&format!("signals[{}].match", i), "empty match rule");
+        }
+        check.key(&format!("signals[{}].key", i), &rule.key);
+    }
+    for (i, rule) in config.properties.iter().enumerate() {
+        if !rule.path.starts_with('/') {
+            check.error(&format!("properties[{}].path", i), format!("'{}' is not an object path", rule.path));
+        }
+        if rule.interval == 0 {
+            check.error(&format!("properties[{}].interval", i), "must be at least 1 second");
+        }
+        check.key(&format!("properties[{}].key", i), &rule.key);
+    }
+    check.exit()
+}
+
+fn main() {
+    let args = Args::parse();
+    if args.check_config {
+        check_config(&args);
+    }
+    let config_text = fs::read_to_string(&args.config).expect("Failed to read configuration file");
+    let config: &'static Config = Box::leak(Box::new(serde_json::from_str(&config_text).expect("Invalid configuration file")));
+
+    let mut workers = Vec::new();
+    if !config.signals.is_empty() {
+        workers.push(spawn_worker(move || run_signals(config)));
+    }
+    if !config.properties.is_empty() {
+        workers.push(spawn_worker(move || run_properties(config)));
+    }
+
+    for worker in workers {
+        worker.join().expect("Bridge thread panicked");
+    }
+}
+
+// Run one side of the bridge, exiting the process if it fails so a supervisor can restart it
+fn spawn_worker(worker: impl FnOnce() -> Result<(), String> + Send + 'static) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        if let Err(err) = worker() {
+            eprintln!("D-Bus bridge stopped: {}", err);
+            process::exit(1);
+        }
+    })
+}
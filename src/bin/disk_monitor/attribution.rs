@@ -0,0 +1,86 @@
+// Import necessary crates and modules
+use clap::ValueEnum; // For the --attribute-by flag
+use serde::Serialize; // For the reports
+use std::collections::HashMap; // For the usage of every consumer
+use std::fs; // For walking the roots
+use std::os::unix::fs::MetadataExt; // For owners, devices and allocated blocks
+use std::path::Path; // For the roots
+
+// Define what usage under a root is attributed to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum AttributeBy {
+    Owner, // The user owning each top-level entry (e.g. /home/<user>)
+    Directory, // Each top-level entry itself (e.g. /var/lib/docker/volumes/<volume>)
+}
+
+// Define one consumer of a root
+#[derive(Serialize)]
+pub struct Consumer {
+    pub name: String, // User name (or uid) or top-level entry name
+    pub bytes: u64, // Space allocated on disk
+}
+
+// Define the report of one root
+#[derive(Serialize)]
+pub struct Attribution {
+    pub _timestamp: u128,
+    pub root: String,
+    pub by: String,
+    pub total_bytes: u64, // Usage of all consumers, not only the top ones
+    pub top: Vec<Consumer>, // Largest consumers first
+}
+
+// Function to map uids to user names from /etc/passwd
+fn user_names() -> HashMap<u32, String> {
+    let passwd = fs::read_to_string("/etc/passwd").unwrap_or_default();
+    passwd.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect()
+}
+
+// Function to sum the space allocated under a path, staying on its filesystem and not following symlinks
+fn usage(path: &Path, device: u64) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else { return 0 }; // Removed meanwhile
+    if metadata.dev() != device {
+        return 0; // Another mount, reported on its own
+    }
+    let mut bytes = metadata.blocks() * 512; // Allocated rather than apparent size, so sparse files count what they use
+    if metadata.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            bytes += entries.flatten().map(|entry| usage(&entry.path(), device)).sum::<u64>();
+        }
+    }
+    bytes
+}
+
+// Function to attribute the usage under a root to its top `top` consumers
+pub fn attribute(root: &Path, by: AttributeBy, top: usize, timestamp: u128) -> std::io::Result<Attribution> {
+    let device = fs::metadata(root)?.dev();
+    let names = if by == AttributeBy::Owner { user_names() } else { HashMap::new() };
+    let mut consumers: HashMap<String, u64> = HashMap::new();
+    for entry in fs::read_dir(root)?.flatten() {
+        let Ok(metadata) = entry.metadata() else { continue };
+        let name = match by {
+            AttributeBy::Owner => names.get(&metadata.uid()).cloned().unwrap_or_else(|| metadata.uid().to_string()),
+            AttributeBy::Directory => entry.file_name().to_string_lossy().to_string(),
+        };
+        *consumers.entry(name).or_default() += usage(&entry.path(), device);
+    }
+
+    let total_bytes = consumers.values().sum();
+    let mut consumers: Vec<Consumer> = consumers.into_iter().map(|(name, bytes)| Consumer { name, bytes }).collect();
+    consumers.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+    consumers.truncate(top);
+    Ok(Attribution {
+        _timestamp: timestamp,
+        root: root.to_string_lossy().to_string(),
+        by: format!("{:?}", by).to_lowercase(),
+        total_bytes,
+        top: consumers,
+    })
+}
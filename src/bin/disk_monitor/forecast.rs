@@ -0,0 +1,65 @@
+// Import necessary crates and modules
+use std::collections::{HashMap, HashSet, VecDeque}; // For the sample windows and the mounts already warned about
+
+// Define the fewest samples a growth estimate is made from
+const MIN_SAMPLES: usize = 3;
+
+// Define the seconds in a day
+const DAY_SECS: f64 = 86400.0;
+
+// Define the rolling usage history of every mount and the estimates made from it
+pub struct Forecaster {
+    window: usize, // Samples kept per mount
+    samples: HashMap<String, VecDeque<(f64, f64)>>, // (seconds since the epoch, used bytes) by mount, oldest first
+    warned: HashSet<String>, // Mounts whose estimate is below the horizon and were warned about
+}
+
+impl Forecaster {
+    // Function to create a forecaster keeping `window` samples per mount
+    pub fn new(window: usize) -> Self {
+        Forecaster { window: window.max(MIN_SAMPLES), samples: HashMap::new(), warned: HashSet::new() }
+    }
+
+    // Function to add a sample of a mount and estimate the days until it is full, if it is growing
+    pub fn add(&mut self, path: &str, at_secs: f64, used: u64, free: u64) -> Option<f64> {
+        let samples = self.samples.entry(path.to_string()).or_default();
+        samples.push_back((at_secs, used as f64));
+        if samples.len() > self.window {
+            samples.pop_front();
+        }
+        let slope = growth_rate(samples)?; // Bytes per second
+        Some(free as f64 / slope / DAY_SECS)
+    }
+
+    // Function to check whether an estimate warrants an early warning: only when it first drops
+    // below the horizon, again only after it recovered
+    pub fn should_warn(&mut self, path: &str, days_until_full: Option<f64>, horizon_days: f64) -> bool {
+        match days_until_full {
+            Some(days) if days < horizon_days => self.warned.insert(path.to_string()),
+            _ => {
+                self.warned.remove(path);
+                false
+            }
+        }
+    }
+}
+
+// Function to fit used bytes over time with least squares; returns the slope if usage grows
+fn growth_rate(samples: &VecDeque<(f64, f64)>) -> Option<f64> {
+    if samples.len() < MIN_SAMPLES {
+        return None;
+    }
+    let n = samples.len() as f64;
+    let origin = samples[0].0; // Keeps the sums small enough for f64
+    let (mean_t, mean_used) = samples.iter().fold((0.0, 0.0), |(t, u), (at, used)| (t + (at - origin) / n, u + used / n));
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (at, used) in samples {
+        let dt = at - origin - mean_t;
+        covariance += dt * (used - mean_used);
+        variance += dt * dt;
+    }
+    if variance == 0.0 {
+        return None; // All samples at the same time
+    }
+    Some(covariance / variance).filter(|slope| *slope > 0.0)
+}
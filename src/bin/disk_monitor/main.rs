@@ -0,0 +1,219 @@
+mod attribution; // Opt-in breakdown of usage under configured roots
+mod forecast; // Days-until-full estimates from recent growth
+
+use attribution::AttributeBy;
+use clap::Parser;
+use forecast::Forecaster;
+use sysinfo::Disks;
+use redis::{Commands, Connection};
+use rustredis::check::ConfigCheck;
+use serde::{Serialize, Deserialize};
+use std::{collections::HashMap, path::PathBuf, process::Command, thread, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+/// Disk space monitor publishing the usage of every mount to Redis
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Also append each sample to the stream stream:disk:<mount>, so recent history survives consumer restarts
+    #[arg(long)]
+    stream: bool,
+
+    /// Approximate number of samples each stream keeps (a week at the 5 minute interval by default)
+    #[arg(long, default_value_t = 2016)]
+    stream_maxlen: usize,
+
+    /// Samples per mount the growth used for days_until_full is fitted over (a day at the 5 minute interval by default)
+    #[arg(long, default_value_t = 288)]
+    forecast_window: usize,
+
+    /// Publish a warning on system_disk_alerts when a mount is estimated to fill up within this many days
+    #[arg(long, default_value_t = 7.0)]
+    forecast_horizon_days: f64,
+
+    /// Root whose usage is broken down into its top consumers in system_disk_attribution (repeatable; none by default)
+    #[arg(long = "attribute-root")]
+    attribute_roots: Vec<PathBuf>,
+
+    /// Attribute usage under a root to the owners of its top-level entries or to the entries themselves (e.g. container volumes)
+    #[arg(long, value_enum, default_value_t = AttributeBy::Owner)]
+    attribute_by: AttributeBy,
+
+    /// Number of consumers reported per root
+    #[arg(long, default_value_t = 10)]
+    attribute_top: usize,
+
+    /// Break usage down every this many samples, since walking the roots is expensive (hourly by default)
+    #[arg(long, default_value_t = 12)]
+    attribute_every: u64,
+
+    /// Check the arguments and attribution roots, print the diagnostics as JSON and exit (status 1 if invalid)
+    #[arg(long)]
+    check_config: bool,
+
+    /// With --check-config, also connect to Redis
+    #[arg(long, requires = "check_config")]
+    check_connectivity: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DiskInfo {
+	_timestamp: u128,
+    total_space: u64,
+    free_space: u64,
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    days_until_full: Option<f64>, // Estimated from the recent growth, absent while usage is not growing
+}
+
+// List the mount point, total bytes and available bytes of every mount
+fn mounts() -> Vec<(String, u64, u64)> {
+    let disks = Disks::new_with_refreshed_list();
+    let mounts: Vec<(String, u64, u64)> = disks.iter()
+        .map(|disk| (disk.mount_point().to_string_lossy().to_string(), disk.total_space(), disk.available_space()))
+        .collect();
+    if mounts.is_empty() { df_mounts() } else { mounts }
+}
+
+// List the mounts with df, for devices where sysinfo finds none (e.g. the overlay and ubifs
+// root filesystems of routers); busybox df supports -kP too
+fn df_mounts() -> Vec<(String, u64, u64)> {
+    let Ok(output) = Command::new("df").arg("-kP").output() else { return Vec::new() };
+    String::from_utf8_lossy(&output.stdout).lines()
+        .skip(1) // Header
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let total: u64 = fields[1].parse().ok()?;
+            let available: u64 = fields[3].parse().ok()?;
+            Some((fields[5..].join(" "), total * 1024, available * 1024))
+        })
+        .filter(|(_, total, _)| *total > 0) // Pseudo filesystems
+        .collect()
+}
+
+// Collect the usage of every mount, with the early warnings of mounts about to fill up
+fn get_disk_space(forecaster: &mut Forecaster, horizon_days: f64) -> (HashMap<String, String>, Vec<String>) {
+
+    let mut disk_map = HashMap::new();
+    let mut warnings = Vec::new();
+	let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+
+    for (path, total_space, free_space) in mounts() {
+        let used = total_space.saturating_sub(free_space);
+        let days_until_full = forecaster.add(&path, timestamp as f64 / 1e9, used, free_space);
+        if forecaster.should_warn(&path, days_until_full, horizon_days) {
+            warnings.push(serde_json::json!({"event": "disk_full_forecast", "path": path, "days_until_full": days_until_full}).to_string());
+        }
+        let disk_info = DiskInfo {
+			_timestamp: timestamp,
+            total_space,
+            free_space,
+            path,
+            days_until_full,
+        };
+
+        if let Ok(json_str) = serde_json::to_string(&disk_info) {
+            disk_map.insert(disk_info.path.clone(), json_str);
+        }
+    }
+
+    (disk_map, warnings)
+}
+
+fn store_in_redis(disk_map: HashMap<String, String>, warnings: Vec<String>, args: &Args) -> redis::RedisResult<()> {
+    let client = redis::Client::open("redis://127.0.0.1/")?;
+    let mut con: Connection = client.get_connection()?;
+
+    for (mount_point, json_value) in disk_map {
+        let json_value_clone = json_value.clone();
+        let _: () = con.hset("system_disk_space", &mount_point, &json_value)?;
+        let _: () = con.publish("system_disk_space", json_value_clone)?;
+        if args.stream {
+            // Approximate trimming (~) lets Redis drop whole nodes, which is much cheaper than exact
+            let _: String = redis::cmd("XADD").arg(format!("stream:disk:{}", mount_point)).arg("MAXLEN").arg("~").arg(args.stream_maxlen)
+                .arg("*").arg("data").arg(&json_value).query(&mut con)?;
+        }
+    }
+
+    for warning in warnings {
+        let _: () = con.publish("system_disk_alerts", warning)?;
+    }
+
+    Ok(())
+}
+
+// Function to break down the usage under the configured roots and store the reports in Redis
+fn store_attribution(args: &Args) -> redis::RedisResult<()> {
+    let client = redis::Client::open("redis://127.0.0.1/")?;
+    let mut con: Connection = client.get_connection()?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+
+    for root in &args.attribute_roots {
+        match attribution::attribute(root, args.attribute_by, args.attribute_top, timestamp) {
+            Ok(report) => {
+                let json_value = serde_json::to_string(&report).unwrap_or_default();
+                let _: () = con.hset("system_disk_attribution", &report.root, &json_value)?;
+                let _: () = con.publish("system_disk_attribution", json_value)?;
+            }
+            Err(e) => eprintln!("Error attributing disk usage under {}: {}", root.display(), e),
+        }
+    }
+    Ok(())
+}
+
+// Check the arguments, the attribution roots and Redis, then exit with the diagnostics
+fn check_config(args: &Args) -> ! {
+    let mut check = ConfigCheck::new("disk_monitor", args.check_connectivity);
+    check.redis("redis", "redis://127.0.0.1/");
+    if args.stream && args.stream_maxlen == 0 {
+        check.error("--stream-maxlen", "must be at least 1");
+    }
+    if args.forecast_horizon_days <= 0.0 {
+        check.warn("--forecast-horizon-days", "no mount is ever warned about");
+    }
+    for root in &args.attribute_roots {
+        if root.is_dir() {
+            check.ok("--attribute-root", format!("{} exists", root.display()));
+        } else {
+            check.error("--attribute-root", format!("{} is not a directory", root.display()));
+        }
+    }
+    if !args.attribute_roots.is_empty() && args.attribute_top == 0 {
+        check.error("--attribute-top", "must be at least 1");
+    }
+    check.exit()
+}
+
+pub fn main() {
+    let args = Args::parse_from(rustredis::multicall::args());
+    if args.check_config {
+        check_config(&args);
+    }
+    let mut forecaster = Forecaster::new(args.forecast_window);
+    let mut samples: u64 = 0;
+    loop {
+        println!("Fetching disk space information...");
+        let (disk_data, warnings) = get_disk_space(&mut forecaster, args.forecast_horizon_days);
+
+        match store_in_redis(disk_data, warnings, &args) {
+            Ok(_) => println!("Disk data stored in Redis successfully."),
+            Err(e) => eprintln!("Error storing disk data in Redis: {:?}", e),
+        }
+
+        if !args.attribute_roots.is_empty() && samples.is_multiple_of(args.attribute_every.max(1)) {
+            println!("Attributing disk usage...");
+            if let Err(e) = store_attribution(&args) {
+                eprintln!("Error storing disk attribution in Redis: {:?}", e);
+            }
+        }
+        samples += 1;
+
+        // Wait for 5 minutes
+        thread::sleep(Duration::from_secs(300));
+    }
+}
@@ -0,0 +1,347 @@
+// Event archiver: appends proxy events to a local SQLite database.
+//
+// Every object type with a schema gets its own table (`<producer>_<object>`) whose
+// columns mirror the schema's top-level properties; events for keys without a schema
+// go to the generic `events` table. The database is rotated by size. SQLite is linked
+// in (the archive feature); a statement that fails stops the archiver.
+
+// Import necessary crates and modules
+mod sqlite; // SQLite access
+
+use clap::{Parser, Subcommand}; // For command line argument parsing
+use rusqlite::types::Value as SqlValue; // For the values bound to inserts
+use rustredis::check::ConfigCheck; // For the --check-config mode
+use rustredis::events::parse_event; // For structuring pub/sub events
+use rustredis::glob::glob_match; // For filtering rotated files
+use rustredis::schema::{base_key, schema_for}; // For schema-aware tables
+use serde::Deserialize; // For deserializing the configuration file
+use serde_json::Value; // For event values
+use sqlite::Database; // For writing to the database
+use std::collections::HashSet; // For remembering created tables
+use std::fs; // For rotation
+use std::path::Path; // For building rotated file names
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH}; // For timestamps and commit batching
+
+// Define the table receiving events of keys without a schema
+const GENERIC_TABLE: &str = "events";
+
+/// Archive proxy events to SQLite and query the archive
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Subscribe to proxy events and archive them
+    Run {
+        /// Path of the JSON configuration file
+        #[arg(long)]
+        config: String,
+
+        /// Check the configuration file, print the diagnostics as JSON and exit (status 1 if invalid)
+        #[arg(long)]
+        check_config: bool,
+
+        /// With --check-config, also connect to Redis
+        #[arg(long, requires = "check_config")]
+        check_connectivity: bool,
+    },
+    /// Print archived events as JSON
+    Query {
+        /// Database file to query
+        #[arg(long)]
+        db: String,
+
+        /// Table to read (`<producer>_<object>` or `events`)
+        #[arg(long, default_value = GENERIC_TABLE)]
+        table: String,
+
+        /// Only events whose key matches this glob pattern
+        #[arg(long)]
+        key: Option<String>,
+
+        /// Only events received at or after this time (milliseconds since the epoch)
+        #[arg(long)]
+        since: Option<u64>,
+
+        /// Maximum number of rows, newest first
+        #[arg(long, default_value_t = 100)]
+        limit: u64,
+
+        /// Run this SQL instead of the filters above
+        #[arg(long)]
+        sql: Option<String>,
+    },
+}
+
+// Define the archiver configuration
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default = "default_redis_url")]
+    redis_url: String, // Redis the proxy publishes events on
+    #[serde(default = "default_pattern")]
+    pattern: String, // Channel pattern to archive
+    db_path: String, // Current database file
+    #[serde(default = "default_rotate_bytes")]
+    rotate_bytes: u64, // Size at which the database is rotated
+    #[serde(default = "default_keep")]
+    keep: usize, // Rotated databases to keep
+    #[serde(default = "default_commit_interval_ms")]
+    commit_interval_ms: u64, // Events are committed in one transaction per interval
+}
+
+fn default_redis_url() -> String {
+    "redis://127.0.0.1/".to_string()
+}
+
+fn default_pattern() -> String {
+    "cs:*".to_string()
+}
+
+fn default_rotate_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn default_keep() -> usize {
+    8
+}
+
+fn default_commit_interval_ms() -> u64 {
+    1000
+}
+
+// Map a JSON schema type to an SQLite column type
+fn column_type(schema: &Value) -> &'static str {
+    match schema["type"].as_str() {
+        Some("integer") | Some("boolean") => "INTEGER",
+        Some("number") => "REAL",
+        _ => "TEXT",
+    }
+}
+
+// Return the table of a key and the schema columns it has beyond the common ones
+fn table_for(key: &str) -> (String, Vec<(String, &'static str)>) {
+    let Some(schema) = schema_for(key) else {
+        return (GENERIC_TABLE.to_string(), Vec::new());
+    };
+    let table = base_key(key).trim_start_matches("cs:").replace(':', "_");
+    let columns = schema["properties"].as_object()
+        .map(|props| props.iter().map(|(name, prop)| (name.clone(), column_type(prop))).collect())
+        .unwrap_or_default();
+    (table, columns)
+}
+
+// Build the CREATE TABLE statement of a table
+fn create_table_sql(table: &str, columns: &[(String, &'static str)]) -> String {
+    let mut sql = format!(
+        "CREATE TABLE IF NOT EXISTS {} (received_at INTEGER NOT NULL, key TEXT NOT NULL, action TEXT NOT NULL, value TEXT",
+        sqlite::quote_ident(table)
+    );
+    for (name, kind) in columns {
+        sql.push_str(&format!(", {} {}", sqlite::quote_ident(name), kind));
+    }
+    sql.push_str(&format!(
+        "); CREATE INDEX IF NOT EXISTS {} ON {} (received_at);",
+        sqlite::quote_ident(&format!("{}_received_at", table)),
+        sqlite::quote_ident(table)
+    ));
+    sql
+}
+
+// Define the archive being written: the database, tables created in it and the open transaction
+struct Archive {
+    db: Database,
+    tables: HashSet<String>,
+    in_transaction: bool,
+}
+
+impl Archive {
+    fn open(path: &str) -> Archive {
+        let db = Database::open(path).expect("Failed to open archive");
+        Archive { db, tables: HashSet::new(), in_transaction: false }
+    }
+
+    // Append one event, creating its table on first use
+    fn append(&mut self, event: &Value) -> rusqlite::Result<()> {
+        let key = event["key"].as_str().unwrap_or_default();
+        let (table, columns) = table_for(key);
+        if !self.tables.contains(&table) {
+            self.db.execute(&create_table_sql(&table, &columns))?;
+            self.tables.insert(table.clone());
+        }
+        if !self.in_transaction {
+            self.db.execute("BEGIN;")?;
+            self.in_transaction = true;
+        }
+
+        let value = &event["value"];
+        let received_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
+        let mut names = vec!["received_at".to_string(), "key".to_string(), "action".to_string(), "value".to_string()];
+        let mut values = vec![
+            SqlValue::Integer(received_at as i64),
+            SqlValue::Text(key.to_string()),
+            sqlite::sql_value(&event["action"]),
+            if value.is_null() { SqlValue::Null } else { SqlValue::Text(value.to_string()) },
+        ];
+        for (name, _) in &columns {
+            names.push(sqlite::quote_ident(name));
+            values.push(sqlite::sql_value(&value[name.as_str()]));
+        }
+        let placeholders: Vec<String> = (1..=values.len()).map(|i| format!("?{}", i)).collect();
+        self.db.insert(
+            &format!("INSERT INTO {} ({}) VALUES ({})", sqlite::quote_ident(&table), names.join(", "), placeholders.join(", ")),
+            values,
+        )
+    }
+
+    fn commit(&mut self) -> rusqlite::Result<()> {
+        if self.in_transaction {
+            self.db.execute("COMMIT;")?;
+            self.in_transaction = false;
+        }
+        Ok(())
+    }
+}
+
+// Report a failed statement and stop rather than drop events unnoticed; the uncommitted batch is rolled back
+fn fail(archive: Archive, context: &str, err: rusqlite::Error) -> ! {
+    eprintln!("{}: {}", context, err);
+    let _ = archive.db.close();
+    std::process::exit(1);
+}
+
+// Move the current database aside as <name>.<timestamp> and drop the oldest rotated files
+fn rotate(config: &Config) {
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let rotated = format!("{}.{}", config.db_path, stamp);
+    if let Err(err) = fs::rename(&config.db_path, &rotated) {
+        eprintln!("Failed to rotate {}: {}", config.db_path, err);
+        return;
+    }
+    println!("Rotated archive to {}", rotated);
+
+    let path = Path::new(&config.db_path);
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let prefix = format!("{}.*", path.file_name().unwrap().to_string_lossy());
+    let mut old: Vec<_> = fs::read_dir(dir).map(|entries| {
+        entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|p| p.file_name().is_some_and(|name| glob_match(&prefix, &name.to_string_lossy())))
+            .collect()
+    }).unwrap_or_default();
+    old.sort(); // Timestamps of equal length sort chronologically
+    while old.len() > config.keep {
+        let oldest = old.remove(0);
+        if let Err(err) = fs::remove_file(&oldest) {
+            eprintln!("Failed to remove {}: {}", oldest.display(), err);
+        }
+    }
+}
+
+// Function to check the configuration file, then exit with the diagnostics
+fn check_config(config_path: &str, connectivity: bool) -> ! {
+    let mut check = ConfigCheck::new("event_archiver", connectivity);
+    let Some(config) = check.parse_json::<Config>(config_path) else { check.exit() };
+    check.redis("redis_url", &config.redis_url);
+    check.key_pattern("pattern", &config.pattern);
+    let dir = Path::new(&config.db_path).parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    if dir.is_dir() {
+        check.ok("db_path", "directory exists");
+    } else {
+        check.error("db_path", format!("directory {} does not exist", dir.display()));
+    }
+    if config.rotate_bytes == 0 {
+        check.error("rotate_bytes", "must be at least 1");
+    }
+    if config.commit_interval_ms == 0 {
+        check.warn("commit_interval_ms", "every event is committed on its own");
+    }
+    check.ok("sqlite", format!("SQLite {} linked", rusqlite::version()));
+    check.exit()
+}
+
+fn run(config_path: &str) {
+    let config_text = fs::read_to_string(config_path).expect("Failed to read configuration file");
+    let config: Config = serde_json::from_str(&config_text).expect("Invalid configuration file");
+    let commit_interval = Duration::from_millis(config.commit_interval_ms);
+
+    let client = rustredis::tunnel::open(&config.redis_url).expect("Failed to create Redis client");
+    let mut conn = client.get_connection().expect("Failed to connect to Redis");
+    let mut pubsub = conn.as_pubsub();
+    pubsub.psubscribe(&config.pattern).expect("Failed to subscribe to proxy events");
+    pubsub.set_read_timeout(Some(commit_interval)).expect("Failed to set read timeout");
+    println!("Archiving {} to {}", config.pattern, config.db_path);
+
+    let mut archive = Archive::open(&config.db_path);
+    let mut last_commit = Instant::now();
+    loop {
+        match pubsub.get_message() {
+            Ok(message) => {
+                let key = message.get_channel_name().to_string();
+                let payload: String = message.get_payload().unwrap_or_default();
+                if let Err(err) = archive.append(&parse_event(&key, &payload)) {
+                    fail(archive, "Failed to archive event", err);
+                }
+            }
+            Err(err) if err.is_timeout() => {}
+            Err(err) => {
+                eprintln!("Redis subscription lost: {}", err);
+                if let Err(err) = archive.commit() {
+                    fail(archive, "Failed to commit events", err);
+                }
+                let _ = archive.db.close();
+                std::process::exit(1);
+            }
+        }
+
+        if last_commit.elapsed() >= commit_interval {
+            if let Err(err) = archive.commit() {
+                fail(archive, "Failed to commit events", err);
+            }
+            last_commit = Instant::now();
+
+            if fs::metadata(&config.db_path).is_ok_and(|meta| meta.len() >= config.rotate_bytes) {
+                archive.db.close().expect("Failed to close archive");
+                rotate(&config);
+                archive = Archive::open(&config.db_path);
+            }
+        }
+    }
+}
+
+// Print archived events matching the filters, or the rows of a custom query (SQLite's GLOB uses the same `*` and `?` wildcards)
+fn query(db: &str, table: &str, key: Option<&str>, since: Option<u64>, limit: u64, sql: Option<&str>) {
+    let sql = match sql {
+        Some(sql) => sql.to_string(),
+        None => {
+            let mut conditions = Vec::new();
+            if let Some(pattern) = key {
+                conditions.push(format!("key GLOB {}", sqlite::quote(pattern)));
+            }
+            if let Some(since) = since {
+                conditions.push(format!("received_at >= {}", since));
+            }
+            let filter = if conditions.is_empty() { String::new() } else { format!(" WHERE {}", conditions.join(" AND ")) };
+            format!("SELECT * FROM {}{} ORDER BY received_at DESC LIMIT {}", sqlite::quote_ident(table), filter, limit)
+        }
+    };
+
+    match sqlite::query_json(db, &sql) {
+        Ok(rows) => println!("{}", serde_json::to_string_pretty(&rows).unwrap()),
+        Err(err) => {
+            eprintln!("Query failed: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    match Args::parse().command {
+        Command::Run { config, check_config: true, check_connectivity } => check_config(&config, check_connectivity),
+        Command::Run { config, .. } => run(&config),
+        Command::Query { db, table, key, since, limit, sql } => query(&db, &table, key.as_deref(), since, limit, sql.as_deref()),
+    }
+}
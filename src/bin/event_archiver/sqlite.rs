@@ -0,0 +1,81 @@
+// SQLite access of the archiver, linked in so every statement's result is seen
+
+// Import necessary crates and modules
+use rusqlite::types::{Value as SqlValue, ValueRef}; // For binding event fields and reading query results
+use rusqlite::{params_from_iter, Connection, OpenFlags}; // For the database connection
+use serde_json::{Map, Value}; // For event values and query results
+
+// Define an open archive database
+pub struct Database {
+    conn: Connection, // Connection to the database file
+}
+
+impl Database {
+    // Function to open (or create) a database file
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        Ok(Database { conn: Connection::open(path)? })
+    }
+
+    // Function to run one or more statements without parameters
+    pub fn execute(&self, sql: &str) -> rusqlite::Result<()> {
+        self.conn.execute_batch(sql)
+    }
+
+    // Function to insert one row, binding the values to the statement's placeholders
+    pub fn insert(&self, sql: &str, values: Vec<SqlValue>) -> rusqlite::Result<()> {
+        self.conn.prepare_cached(sql)?.execute(params_from_iter(values)).map(drop)
+    }
+
+    // Function to close the database, reporting a failure to release it
+    pub fn close(self) -> rusqlite::Result<()> {
+        self.conn.close().map_err(|(_, err)| err)
+    }
+}
+
+// Function to quote a string as an SQL literal
+pub fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+// Function to quote an identifier (table or column name)
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// Function to convert a JSON value to the SQL value stored for it
+pub fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => n.as_i64().map_or_else(|| SqlValue::Real(n.as_f64().unwrap_or(f64::NAN)), SqlValue::Integer),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()), // Nested values are stored as JSON text
+    }
+}
+
+// Function to convert a column of a result row to JSON
+fn json_value(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(n) => Value::from(n),
+        ValueRef::Real(n) => Value::from(n),
+        ValueRef::Text(text) | ValueRef::Blob(text) => Value::String(String::from_utf8_lossy(text).into_owned()),
+    }
+}
+
+// Function to run a read-only query and return the rows as JSON objects
+pub fn query_json(path: &str, sql: &str) -> Result<Value, String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(|e| format!("failed to open {}: {}", path, e))?;
+    let mut statement = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let names: Vec<String> = statement.column_names().into_iter().map(str::to_string).collect();
+    let mut rows = statement.query([]).map_err(|e| e.to_string())?;
+    let mut results = Vec::new();
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let mut object = Map::new();
+        for (i, name) in names.iter().enumerate() {
+            object.insert(name.clone(), json_value(row.get_ref(i).map_err(|e| e.to_string())?));
+        }
+        results.push(Value::Object(object));
+    }
+    Ok(Value::Array(results))
+}
@@ -0,0 +1,199 @@
+// Alert routing rules: events matching a condition are sent to a named sink, throttled and held back during quiet hours
+
+// Import necessary crates and modules
+use crate::{Record, Sink, SinkConfig}; // For sending alerts through the connector's sinks
+use rustredis::filter::Filter; // For rule conditions
+use rustredis::keys::render_template; // For alert topics
+use serde::Deserialize; // For the rules file
+use serde_json::json; // For alert payloads
+use std::collections::HashMap; // For sinks by name and throttling state
+use std::fs; // For reading the rules file
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH}; // For throttling and quiet hours
+
+// Define the severities an event's value.severity may carry, lowest first
+const SEVERITIES: [&str; 6] = ["debug", "info", "notice", "warning", "error", "critical"];
+
+// Define the rules file (TOML), e.g.
+//
+//   utc_offset_minutes = 120
+//
+//   [sink.modem.exec]
+//   command = ["/usr/sbin/modem-restart", "{id}"]
+//
+//   [[rule]]
+//   name = "signal-loss"
+//   when = "key matches cs:ModemWatcher:* and value.signal < -110"
+//   min_severity = "warning"
+//   sink = "modem"
+//   throttle_secs = 600
+//   quiet_hours = "22:00-07:00"
+#[derive(Deserialize)]
+struct AlertsConfig {
+    #[serde(default)]
+    utc_offset_minutes: i64, // Offset of the device's local time, for quiet hours
+    #[serde(default, rename = "sink")]
+    sinks: HashMap<String, SinkConfig>, // Sinks alerts are routed to, by name
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleConfig>, // Every matching rule fires, in order
+}
+
+// Define a routing rule
+#[derive(Deserialize)]
+struct RuleConfig {
+    name: String,
+    #[serde(default)]
+    when: Option<String>, // Filter expression on key, action and value fields; every event if absent
+    #[serde(default)]
+    min_severity: Option<String>, // Least value.severity of the events routed; events without one are not
+    sink: String, // Name of the sink the alerts go to
+    #[serde(default)]
+    topic: Option<String>, // Topic template with key placeholders; the rule name if absent
+    #[serde(default)]
+    throttle_secs: u64, // Least time between two alerts of the rule for the same key
+    #[serde(default)]
+    quiet_hours: Option<String>, // Local HH:MM-HH:MM window in which alerts are held back
+    #[serde(default)]
+    quiet_hours_min_severity: Option<String>, // Severity still routed during quiet hours
+}
+
+// Define a compiled rule
+struct Rule {
+    name: String,
+    when: Option<Filter>,
+    min_severity: Option<usize>,
+    sink: String,
+    topic: String,
+    throttle: Duration,
+    quiet_hours: Option<(u32, u32)>, // Start and end, in minutes of the day
+    quiet_hours_min_severity: Option<usize>,
+}
+
+// Define the throttling state of a rule for one key
+struct Throttle {
+    last_sent: Instant,
+    suppressed: u64, // Alerts held back since, reported with the next one
+}
+
+// Function to rank a severity name
+fn severity_rank(name: &str) -> Option<usize> {
+    SEVERITIES.iter().position(|severity| severity.eq_ignore_ascii_case(name))
+}
+
+// Function to parse a configured severity
+fn parse_severity(rule: &str, name: &Option<String>) -> Result<Option<usize>, String> {
+    name.as_deref()
+        .map(|name| severity_rank(name).ok_or_else(|| format!("rule {}: unknown severity '{}', expected one of {}", rule, name, SEVERITIES.join(", "))))
+        .transpose()
+}
+
+// Function to parse an HH:MM-HH:MM window into minutes of the day
+fn parse_quiet_hours(window: &str) -> Result<(u32, u32), String> {
+    let minutes = |time: &str| -> Option<u32> {
+        let (hours, minutes) = time.trim().split_once(':')?;
+        let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+        (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+    };
+    let (start, end) = window.split_once('-').ok_or_else(|| format!("invalid quiet hours '{}', expected HH:MM-HH:MM", window))?;
+    match (minutes(start), minutes(end)) {
+        (Some(start), Some(end)) => Ok((start, end)),
+        _ => Err(format!("invalid quiet hours '{}', expected HH:MM-HH:MM", window)),
+    }
+}
+
+// Function to check if a minute of the day falls in a window, which may span midnight
+fn in_window(minute: u32, (start, end): (u32, u32)) -> bool {
+    if start <= end { (start..end).contains(&minute) } else { minute >= start || minute < end }
+}
+
+// Define the alert router
+pub struct AlertRouter {
+    rules: Vec<Rule>,
+    sinks: HashMap<String, Sink>,
+    utc_offset_minutes: i64,
+    throttles: HashMap<(usize, String), Throttle>, // By rule index and key
+}
+
+impl AlertRouter {
+    // Function to load and check the rules file
+    pub fn load(path: &str) -> Result<AlertRouter, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+        let config: AlertsConfig = toml::from_str(&text).map_err(|e| format!("invalid rules file {}: {}", path, e))?;
+
+        let mut rules = Vec::new();
+        for rule in config.rules {
+            if !config.sinks.contains_key(&rule.sink) {
+                return Err(format!("rule {}: unknown sink '{}'", rule.name, rule.sink));
+            }
+            rules.push(Rule {
+                when: rule.when.as_deref().map(Filter::parse).transpose().map_err(|e| format!("rule {}: {}", rule.name, e))?,
+                min_severity: parse_severity(&rule.name, &rule.min_severity)?,
+                quiet_hours: rule.quiet_hours.as_deref().map(parse_quiet_hours).transpose().map_err(|e| format!("rule {}: {}", rule.name, e))?,
+                quiet_hours_min_severity: parse_severity(&rule.name, &rule.quiet_hours_min_severity)?,
+                topic: rule.topic.unwrap_or_else(|| rule.name.clone()),
+                throttle: Duration::from_secs(rule.throttle_secs),
+                sink: rule.sink,
+                name: rule.name,
+            });
+        }
+        let sinks = config.sinks.iter().map(|(name, sink)| (name.clone(), Sink::new(sink))).collect();
+        Ok(AlertRouter { rules, sinks, utc_offset_minutes: config.utc_offset_minutes, throttles: HashMap::new() })
+    }
+
+    // Function to return the current minute of the device's local day
+    fn local_minute(&self) -> u32 {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+        ((now / 60 + self.utc_offset_minutes).rem_euclid(24 * 60)) as u32
+    }
+
+    // Function to route a batch of events, sending each sink its alerts at once. Alerts are
+    // best effort: a sink failing is logged and its alerts dropped, so the event stream moves on
+    pub fn route(&mut self, records: &[&Record]) {
+        let minute = self.local_minute();
+        let mut alerts: HashMap<&str, Vec<(String, Record)>> = HashMap::new();
+        for record in records {
+            let severity = record.payload["value"]["severity"].as_str().and_then(severity_rank);
+            for (index, rule) in self.rules.iter().enumerate() {
+                if rule.when.as_ref().is_some_and(|when| !when.matches(&record.payload)) {
+                    continue;
+                }
+                if rule.min_severity.is_some_and(|min| severity.is_none_or(|severity| severity < min)) {
+                    continue;
+                }
+                if rule.quiet_hours.is_some_and(|window| in_window(minute, window))
+                    && rule.quiet_hours_min_severity.is_none_or(|min| severity.is_none_or(|severity| severity < min))
+                {
+                    continue;
+                }
+
+                // Throttle per rule and key, counting what was held back
+                let suppressed = match self.throttles.get_mut(&(index, record.key.clone())) {
+                    Some(throttle) if throttle.last_sent.elapsed() < rule.throttle => {
+                        throttle.suppressed += 1;
+                        continue;
+                    }
+                    Some(throttle) => std::mem::replace(throttle, Throttle { last_sent: Instant::now(), suppressed: 0 }).suppressed,
+                    None => {
+                        self.throttles.insert((index, record.key.clone()), Throttle { last_sent: Instant::now(), suppressed: 0 });
+                        0
+                    }
+                };
+
+                let mut payload = record.payload.clone();
+                payload["alert"] = json!({"rule": rule.name, "suppressed": suppressed});
+                let alert = Record { key: record.key.clone(), payload, stream_id: None };
+                alerts.entry(rule.sink.as_str()).or_default().push((render_template(&rule.topic, &record.key), alert));
+            }
+        }
+
+        for (name, batch) in alerts {
+            let batch: Vec<(String, &Record)> = batch.iter().map(|(topic, alert)| (topic.clone(), alert)).collect();
+            if let Err(err) = self.sinks.get_mut(name).expect("Rules only name configured sinks").send(&batch) {
+                eprintln!("Failed to send {} alerts to sink {}: {}", batch.len(), name, err);
+            }
+        }
+
+        // Forget throttles that have run out, keeping the map bounded by the keys alerting recently
+        let rules = &self.rules;
+        self.throttles.retain(|(index, _), throttle| throttle.suppressed > 0 || throttle.last_sent.elapsed() < rules[*index].throttle);
+    }
+}
@@ -0,0 +1,166 @@
+// Window of recently forwarded events, dropping retransmissions, kept in memory or in RedisBloom cuckoo filters
+
+// Import necessary crates and modules
+use serde::Deserialize; // For the dedup section of the configuration
+use serde_json::Value; // For reading events
+use std::collections::{HashMap, VecDeque}; // For the events in the window, oldest first
+use std::hash::{Hash, Hasher}; // For content hashes
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH}; // For the window and the filter of the current window
+
+// Define the dedup section of the configuration
+#[derive(Deserialize)]
+pub struct DedupConfig {
+    pub window_secs: u64, // How long an event suppresses identical ones
+    #[serde(default)]
+    pub idempotency_field: Option<String>, // Field of the event value identifying it (e.g. "msg_id"); content hash if absent
+    #[serde(default)]
+    pub cuckoo_filter: Option<String>, // Key prefix of RedisBloom cuckoo filters remembering events across restarts
+    #[serde(default = "default_filter_capacity")]
+    pub filter_capacity: u64, // Events the filter of one window is sized for
+}
+
+fn default_filter_capacity() -> u64 {
+    100_000
+}
+
+// Define a 64-bit FNV-1a hasher; unlike the standard hasher its output is stable across builds,
+// so identities stored in Redis stay comparable after the connector is upgraded
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// Define the cuckoo filters of the window: one per window-long bucket, expiring after the next,
+// so an event is remembered for one to two windows
+struct CuckooFilter {
+    conn: redis::Connection,
+    prefix: String,
+    capacity: u64,
+    reserved: u64, // Bucket whose filter was last reserved
+}
+
+impl CuckooFilter {
+    // Function to check an identity against the filters of the current and previous buckets, adding it to the current one
+    fn check(&mut self, identity: u64, window: Duration) -> redis::RedisResult<bool> {
+        let window = window.as_secs().max(1);
+        let bucket = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / window;
+        let current = format!("{}:{}", self.prefix, bucket);
+        if self.reserved != bucket {
+            // Sized up front; reserving a filter another connector instance already made fails harmlessly
+            let _: redis::RedisResult<()> = redis::cmd("CF.RESERVE").arg(&current).arg(self.capacity).query(&mut self.conn);
+            self.reserved = bucket;
+        }
+        let (seen_before, added): (bool, bool) = redis::pipe()
+            .cmd("CF.EXISTS").arg(format!("{}:{}", self.prefix, bucket.saturating_sub(1))).arg(identity)
+            .cmd("CF.ADDNX").arg(&current).arg(identity)
+            .cmd("EXPIRE").arg(&current).arg(window * 2).ignore()
+            .query(&mut self.conn)?;
+        Ok(seen_before || !added)
+    }
+}
+
+// Define the window of forwarded events
+pub struct Deduplicator {
+    window: Duration,
+    idempotency_field: Option<String>,
+    seen: HashMap<u64, Instant>, // Identity of each event in the window and when it was last seen
+    order: VecDeque<(Instant, u64)>, // Same events by time, for expiring them
+    filter: Option<CuckooFilter>, // Filters in Redis used instead of the in-memory window while they work
+}
+
+impl Deduplicator {
+    // Function to create an empty window
+    pub fn new(config: &DedupConfig) -> Self {
+        Deduplicator {
+            window: Duration::from_secs(config.window_secs),
+            idempotency_field: config.idempotency_field.clone(),
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            filter: None,
+        }
+    }
+
+    // Function to remember events in cuckoo filters under the given key prefix if RedisBloom is
+    // loaded, so retransmissions are dropped across restarts; otherwise the in-memory window stays
+    pub fn use_cuckoo_filter(&mut self, client: &redis::Client, prefix: &str, capacity: u64) -> redis::RedisResult<()> {
+        let mut conn = client.get_connection()?;
+        let modules: Vec<Vec<redis::Value>> = redis::cmd("MODULE").arg("LIST").query(&mut conn)?;
+        let has_bloom = modules.iter()
+            .filter_map(|module| module.get(1))
+            .any(|name| redis::from_redis_value::<String>(name).is_ok_and(|name| name.eq_ignore_ascii_case("bf")));
+        if !has_bloom {
+            eprintln!("RedisBloom is not loaded, duplicates are only dropped within this run");
+            return Ok(());
+        }
+        self.filter = Some(CuckooFilter { conn, prefix: prefix.to_string(), capacity, reserved: u64::MAX });
+        Ok(())
+    }
+
+    // Function to identify an event: its key and idempotency key if it has one, else its key,
+    // action and value without the fields the proxy stamps (_received_at, _device), which differ
+    // between a write and its retransmission
+    fn identity(&self, key: &str, payload: &Value) -> u64 {
+        let mut hasher = Fnv1a::default();
+        key.hash(&mut hasher);
+        let idempotency_key = self.idempotency_field.as_ref().and_then(|field| payload["value"].get(field));
+        match idempotency_key {
+            Some(id) => id.to_string().hash(&mut hasher),
+            None => {
+                payload["action"].to_string().hash(&mut hasher);
+                let mut value = payload["value"].clone();
+                if let Some(fields) = value.as_object_mut() {
+                    fields.retain(|name, _| !name.starts_with('_'));
+                }
+                value.to_string().hash(&mut hasher); // Objects serialize with sorted keys
+            }
+        }
+        hasher.finish()
+    }
+
+    // Function to check whether an event was already forwarded within the window, recording it otherwise
+    pub fn is_duplicate(&mut self, key: &str, payload: &Value) -> bool {
+        let identity = self.identity(key, payload);
+        if let Some(ref mut filter) = self.filter {
+            match filter.check(identity, self.window) {
+                Ok(duplicate) => return duplicate,
+                Err(err) => {
+                    eprintln!("Cuckoo filter failed, falling back to the in-memory dedup window: {}", err);
+                    self.filter = None;
+                }
+            }
+        }
+
+        let now = Instant::now();
+        while let Some(&(at, identity)) = self.order.front() {
+            if now.duration_since(at) < self.window {
+                break;
+            }
+            self.order.pop_front();
+            if self.seen.get(&identity) == Some(&at) {
+                self.seen.remove(&identity); // Not seen again since
+            }
+        }
+
+        let duplicate = self.seen.contains_key(&identity);
+        if !duplicate {
+            self.seen.insert(identity, now);
+            self.order.push_back((now, identity));
+        }
+        duplicate
+    }
+}
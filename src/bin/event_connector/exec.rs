@@ -0,0 +1,188 @@
+// Sink running a command per event, with templated arguments, bounded concurrency and timeouts
+
+// Import necessary crates and modules
+use rustredis::keys::render_template; // For the key placeholders
+use serde::Deserialize; // For the exec sink configuration
+use serde_json::Value; // For reading event fields
+use std::io::{Read, Write}; // For the command's stdin and output
+use std::process::{Child, Command, Stdio}; // For running the command
+use std::thread::{self, JoinHandle}; // For capturing output while the command runs
+use std::time::{Duration, Instant}; // For timeouts
+
+// Define how often a running command is checked for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Define the most output kept per stream of one run
+const MAX_OUTPUT: usize = 4096;
+
+// Define the exec sink configuration
+#[derive(Clone, Deserialize)]
+pub struct ExecConfig {
+    pub command: Vec<String>, // Program and argument templates, run without a shell
+    #[serde(default)]
+    pub stdin: Option<String>, // Template written to the command's stdin
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: usize, // Commands running at once
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64, // Runs longer than this are killed
+    #[serde(default)]
+    pub working_dir: Option<String>, // Directory the command runs in
+    #[serde(default)]
+    pub env: Vec<String>, // Variables passed through from the connector's environment (besides PATH)
+}
+
+fn default_max_concurrent() -> usize {
+    1
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+// Function to read an event field for a placeholder: strings as they are, anything else as JSON
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+// Function to fill a template from an event: the key placeholders of render_template, plus {topic},
+// {action}, {value} and {value.FIELD.SUBFIELD}; unknown placeholders are left as they are
+pub fn render(template: &str, topic: &str, key: &str, payload: &Value) -> String {
+    let template = render_template(template, key);
+    let mut rendered = String::new();
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else { break };
+        let name = &rest[start + 1..start + len];
+        let field = match name {
+            "topic" => Some(topic.to_string()),
+            "action" => Some(field_text(&payload["action"])),
+            "value" => Some(field_text(&payload["value"])),
+            _ => name.strip_prefix("value.").map(|path| {
+                let pointer = format!("/{}", path.replace('.', "/"));
+                field_text(payload["value"].pointer(&pointer).unwrap_or(&Value::Null))
+            }),
+        };
+        match field {
+            Some(text) => rendered.push_str(&text),
+            None => rendered.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+// Define a command started for one event
+struct Run {
+    label: String, // Program and key, for the logs
+    child: Child,
+    started: Instant,
+    stdout: JoinHandle<String>,
+    stderr: JoinHandle<String>,
+}
+
+// Function to collect up to MAX_OUTPUT bytes of an output stream, draining the rest so the command never blocks
+fn capture(mut stream: impl Read + Send + 'static) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let mut buffer = [0; 1024];
+        while let Ok(read) = stream.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            let room = MAX_OUTPUT.saturating_sub(output.len());
+            output.extend_from_slice(&buffer[..read.min(room)]);
+        }
+        String::from_utf8_lossy(&output).trim_end().to_string()
+    })
+}
+
+// Function to start the command for one event, with a clean environment and no shell
+fn start(config: &ExecConfig, topic: &str, key: &str, payload: &Value) -> std::io::Result<Run> {
+    let argv: Vec<String> = config.command.iter().map(|arg| render(arg, topic, key, payload)).collect();
+    let (program, args) = argv.split_first().ok_or_else(|| std::io::Error::other("empty exec command"))?;
+    let mut command = Command::new(program);
+    command.args(args).env_clear().stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    for name in config.env.iter().map(String::as_str).chain(["PATH"]) {
+        if let Ok(value) = std::env::var(name) {
+            command.env(name, value);
+        }
+    }
+    if let Some(ref dir) = config.working_dir {
+        command.current_dir(dir);
+    }
+
+    let mut child = command.spawn()?;
+    let input = config.stdin.as_ref().map(|template| render(template, topic, key, payload)).unwrap_or_default();
+    if let Some(mut stdin) = child.stdin.take() {
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes()); // The command may not read it; dropping stdin closes it
+        });
+    }
+    let stdout = capture(child.stdout.take().unwrap());
+    let stderr = capture(child.stderr.take().unwrap());
+    Ok(Run { label: format!("{} for {}", program, key), child, started: Instant::now(), stdout, stderr })
+}
+
+// Function to wait for a command, killing it at the timeout, and log how it ended with its output
+fn finish(mut run: Run, timeout: Duration) {
+    let status = loop {
+        match run.child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if run.started.elapsed() >= timeout => {
+                let _ = run.child.kill();
+                let _ = run.child.wait();
+                break None;
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(err) => {
+                eprintln!("Exec {}: failed to wait: {}", run.label, err);
+                break None;
+            }
+        }
+    };
+    let stdout = run.stdout.join().unwrap_or_default();
+    let stderr = run.stderr.join().unwrap_or_default();
+    match status {
+        Some(status) if status.success() => println!("Exec {}: ok", run.label),
+        Some(status) => eprintln!("Exec {}: {}", run.label, status),
+        None => eprintln!("Exec {}: killed after {:?}", run.label, timeout),
+    }
+    for (stream, output) in [("stdout", stdout), ("stderr", stderr)] {
+        for line in output.lines() {
+            println!("Exec {} {}: {}", run.label, stream, line);
+        }
+    }
+}
+
+// Function to run the command for every event, at most max_concurrent at a time. Only failing to
+// start a command is an error (and retries the batch); failures of the command itself are logged,
+// since rerunning scripts like a modem restart is rarely what we want
+pub fn run_batch(config: &ExecConfig, batch: &[(String, &str, &Value)]) -> Result<(), String> {
+    let timeout = Duration::from_secs(config.timeout_secs);
+    for chunk in batch.chunks(config.max_concurrent.max(1)) {
+        let mut runs = Vec::new();
+        let mut failed = None;
+        for (topic, key, payload) in chunk {
+            match start(config, topic, key, payload) {
+                Ok(run) => runs.push(run),
+                Err(err) => {
+                    failed = Some(format!("failed to start {:?}: {}", config.command.first(), err));
+                    break;
+                }
+            }
+        }
+        for run in runs {
+            finish(run, timeout);
+        }
+        if let Some(err) = failed {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,494 @@
+// Import necessary crates and modules
+mod alerts; // Alert routing rules
+mod dedup; // Window of recently forwarded events
+mod exec; // Commands run per event
+mod nats; // Minimal NATS client
+
+use alerts::AlertRouter; // For routing alerts to their sinks
+use clap::Parser; // For command line argument parsing
+use dedup::{DedupConfig, Deduplicator}; // For dropping retransmitted events
+use exec::ExecConfig; // For running commands per event
+use nats::NatsClient; // For publishing to NATS
+use redis::streams::{StreamReadOptions, StreamReadReply}; // For consumer group reads
+use redis::{Commands, FromRedisValue}; // For Redis operations
+use rustredis::check::ConfigCheck; // For the --check-config mode
+use rustredis::events::parse_event; // For structuring pub/sub events
+use rustredis::glob::glob_match; // For matching keys against topic rules
+use rustredis::http; // For the Kafka REST proxy
+use rustredis::keys::render_template; // For building topics from keys
+use serde::Deserialize; // For deserializing the configuration file
+use serde_json::{json, Value}; // For building records
+use std::collections::HashMap; // For grouping records per topic
+use std::fs; // For reading the configuration file
+use std::thread::sleep; // For retry backoff
+use std::time::{Duration, Instant}; // For batching deadlines
+
+// Define the first delay before retrying a failed batch, doubled after each failure
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+// Define the longest delay between two retries of a batch
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Egress connector forwarding proxy events to Kafka or NATS, or running a command per event
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Path of the JSON configuration file
+    #[arg(long)]
+    config: String,
+
+    /// Check the configuration file and alert rules, print the diagnostics as JSON and exit (status 1 if invalid)
+    #[arg(long)]
+    check_config: bool,
+
+    /// With --check-config, also connect to Redis and the sink
+    #[arg(long, requires = "check_config")]
+    check_connectivity: bool,
+}
+
+// Define the connector configuration
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default = "default_redis_url")]
+    redis_url: String, // Redis holding the events
+    source: SourceConfig, // Where events are read from
+    sink: SinkConfig, // Where events are published to
+    #[serde(default)]
+    topics: Vec<TopicRule>, // Key pattern -> topic mappings, first match wins
+    #[serde(default = "default_batch_size")]
+    batch_size: usize, // Maximum records per published batch
+    #[serde(default = "default_batch_timeout_ms")]
+    batch_timeout_ms: u64, // Maximum time a record waits for its batch to fill
+    #[serde(default)]
+    dedup: Option<DedupConfig>, // Drop events identical to one forwarded within a window, e.g. producers retransmitting after reconnecting
+    #[serde(default)]
+    alerts: Option<String>, // TOML file of alert routing rules, applied to every event besides the topic rules
+}
+
+// Define the event sources
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SourceConfig {
+    // Redis streams read through a consumer group; entries are acknowledged only after
+    // the sink confirmed them, giving at-least-once delivery
+    Streams {
+        keys: Vec<String>, // Stream keys to consume
+        #[serde(default = "default_group")]
+        group: String, // Consumer group name
+        #[serde(default = "default_consumer")]
+        consumer: String, // Consumer name within the group
+    },
+    // Proxy pub/sub events; best effort, events published while the connector is down are lost
+    Pubsub {
+        #[serde(default = "default_pattern")]
+        pattern: String, // Channel pattern to subscribe to
+    },
+    // Proxy events published with SPUBLISH (proxy run with --sharded-pubsub); sharded channels have
+    // no patterns, so the keys are listed, and each is followed on the node owning its slot
+    // (given by a MOVED reply, which is followed once). Best effort like pubsub
+    ShardedPubsub {
+        channels: Vec<String>, // Keys whose channels to subscribe to
+    },
+}
+
+// Define the sinks
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SinkConfig {
+    Nats {
+        address: String, // NATS server as host:port
+    },
+    KafkaRest {
+        url: String, // Base http:// URL of a Kafka REST proxy (v2 API)
+    },
+    // Command run for every event (e.g. restarting the modem on signal loss); the topic is
+    // available to its templates as {topic}
+    Exec(ExecConfig),
+}
+
+// Define a mapping of keys to topics (NATS subjects or Kafka topics)
+#[derive(Deserialize)]
+struct TopicRule {
+    key_pattern: String, // Glob pattern of the keys to forward
+    topic: String, // Topic template with {key}, {producer}, {object}, {id} and {function} placeholders
+}
+
+fn default_redis_url() -> String {
+    "redis://127.0.0.1/".to_string()
+}
+
+fn default_batch_size() -> usize {
+    100
+}
+
+fn default_batch_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_group() -> String {
+    "event_connector".to_string()
+}
+
+fn default_consumer() -> String {
+    "event_connector-1".to_string()
+}
+
+fn default_pattern() -> String {
+    "cs:*".to_string()
+}
+
+// Define an event read from the source
+struct Record {
+    key: String, // Key the event belongs to
+    payload: Value, // Structured event
+    stream_id: Option<String>, // Entry id to acknowledge (stream sources only)
+}
+
+// Define a connected source
+enum Source {
+    Streams {
+        conn: redis::Connection,
+        keys: Vec<String>,
+        group: String,
+        consumer: String,
+        pending_done: bool, // Whether entries delivered before a restart were re-read
+    },
+    Pubsub {
+        pubsub: redis::PubSub<'static>, // Dropping it would unsubscribe, so it lives as long as the connector
+    },
+    ShardedPubsub {
+        conns: Vec<redis::Connection>, // One subscribed connection per channel, read in turn
+    },
+}
+
+// Function to open a connection subscribed to one sharded channel, following a MOVED redirection to its node
+fn ssubscribe(client: &redis::Client, channel: &str) -> redis::RedisResult<redis::Connection> {
+    let mut conn = client.get_connection()?;
+    match redis::cmd("SSUBSCRIBE").arg(channel).query::<()>(&mut conn) {
+        Err(err) if err.kind() == redis::ErrorKind::Moved => {
+            let node = err.redirect_node().map(|(node, _)| node.to_string()).ok_or(err)?;
+            let mut conn = redis::Client::open(format!("redis://{}/", node))?.get_connection()?;
+            redis::cmd("SSUBSCRIBE").arg(channel).query::<()>(&mut conn)?;
+            Ok(conn)
+        }
+        result => result.map(|_| conn),
+    }
+}
+
+impl Source {
+    // Function to connect the configured source
+    fn open(config: &Config) -> redis::RedisResult<Source> {
+        let client = rustredis::tunnel::open(&config.redis_url)?;
+        let mut conn = client.get_connection()?;
+        match &config.source {
+            SourceConfig::Streams { keys, group, consumer } => {
+                for key in keys {
+                    // Creating an existing group fails with BUSYGROUP, which is fine
+                    let created: redis::RedisResult<()> = conn.xgroup_create_mkstream(key, group, "$");
+                    if let Err(err) = created {
+                        if err.code() != Some("BUSYGROUP") {
+                            return Err(err);
+                        }
+                    }
+                }
+                Ok(Source::Streams { conn, keys: keys.clone(), group: group.clone(), consumer: consumer.clone(), pending_done: false })
+            }
+            SourceConfig::Pubsub { pattern } => {
+                let mut pubsub = Box::leak(Box::new(conn)).as_pubsub();
+                pubsub.psubscribe(pattern)?;
+                Ok(Source::Pubsub { pubsub })
+            }
+            SourceConfig::ShardedPubsub { channels } => {
+                let conns = channels.iter().map(|channel| ssubscribe(&client, channel)).collect::<redis::RedisResult<_>>()?;
+                Ok(Source::ShardedPubsub { conns })
+            }
+        }
+    }
+
+    // Function to read up to `max` records, waiting at most `timeout` for the batch to fill
+    fn next_batch(&mut self, max: usize, timeout: Duration) -> redis::RedisResult<Vec<Record>> {
+        match self {
+            Source::Streams { conn, keys, group, consumer, pending_done } => {
+                // After a restart first re-read entries delivered to us but never acknowledged
+                let id = if *pending_done { ">" } else { "0" };
+                let mut options = StreamReadOptions::default().group(group.as_str(), consumer.as_str()).count(max);
+                if *pending_done {
+                    options = options.block(timeout.as_millis() as usize);
+                }
+                let ids = vec![id; keys.len()];
+                let reply: Option<StreamReadReply> = conn.xread_options(keys, &ids, &options)?;
+
+                let mut records = Vec::new();
+                for stream in reply.map(|r| r.keys).unwrap_or_default() {
+                    for entry in stream.ids {
+                        let data = entry.map.get("data").map(String::from_redis_value).transpose()?.unwrap_or_default();
+                        let value = serde_json::from_str(&data).unwrap_or(Value::String(data));
+                        records.push(Record {
+                            key: stream.key.clone(),
+                            payload: json!({"key": stream.key, "action": "xadd", "id": entry.id, "value": value}),
+                            stream_id: Some(entry.id),
+                        });
+                    }
+                }
+                if records.is_empty() && !*pending_done {
+                    *pending_done = true;
+                }
+                Ok(records)
+            }
+            Source::Pubsub { pubsub } => {
+                let deadline = Instant::now() + timeout;
+                let mut records = Vec::new();
+                while records.len() < max {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        break;
+                    }
+                    pubsub.set_read_timeout(Some(remaining))?;
+                    match pubsub.get_message() {
+                        Ok(message) => {
+                            let key = message.get_channel_name().to_string();
+                            let event: String = message.get_payload()?;
+                            records.push(Record { payload: parse_event(&key, &event), key, stream_id: None });
+                        }
+                        Err(err) if err.is_timeout() => break,
+                        Err(err) => return Err(err),
+                    }
+                }
+                Ok(records)
+            }
+            Source::ShardedPubsub { conns } => {
+                let deadline = Instant::now() + timeout;
+                let mut records = Vec::new();
+                // Poll the channels' connections in turn, each briefly, until the batch is full or due
+                let slice = (timeout / conns.len().max(1) as u32).max(Duration::from_millis(1));
+                while records.len() < max && Instant::now() < deadline {
+                    for conn in conns.iter_mut() {
+                        conn.set_read_timeout(Some(slice))?;
+                        let parts: Vec<String> = match conn.recv_response() {
+                            Ok(reply) => redis::from_redis_value(&reply)?,
+                            Err(err) if err.is_timeout() => continue,
+                            Err(err) => return Err(err),
+                        };
+                        if let [kind, key, event] = parts.as_slice() {
+                            if kind == "smessage" {
+                                records.push(Record { payload: parse_event(key, event), key: key.clone(), stream_id: None });
+                            }
+                        }
+                    }
+                }
+                Ok(records)
+            }
+        }
+    }
+
+    // Function to acknowledge delivered records so they are not read again
+    fn ack(&mut self, records: &[Record]) -> redis::RedisResult<()> {
+        if let Source::Streams { conn, group, .. } = self {
+            for record in records {
+                if let Some(ref id) = record.stream_id {
+                    let _: i64 = conn.xack(&record.key, group.as_str(), &[id])?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Define a sink, reconnected lazily after failures
+enum Sink {
+    Nats {
+        address: String,
+        client: Option<NatsClient>,
+    },
+    KafkaRest {
+        url: String,
+    },
+    Exec(ExecConfig),
+}
+
+impl Sink {
+    // Function to create the sink of its configuration; connections are made on the first send
+    fn new(config: &SinkConfig) -> Sink {
+        match config {
+            SinkConfig::Nats { address } => Sink::Nats { address: address.clone(), client: None },
+            SinkConfig::KafkaRest { url } => Sink::KafkaRest { url: url.clone() },
+            SinkConfig::Exec(exec) => Sink::Exec(exec.clone()),
+        }
+    }
+
+    // Function to publish a batch of (topic, record) pairs, returning only once the sink confirmed all of them
+    fn send(&mut self, batch: &[(String, &Record)]) -> Result<(), String> {
+        match self {
+            Sink::Nats { address, client } => {
+                if client.is_none() {
+                    *client = Some(NatsClient::connect(address, "rustredis-event-connector").map_err(|e| format!("connect to {}: {}", address, e))?);
+                }
+                let nats = client.as_mut().unwrap();
+                let result = batch.iter()
+                    .try_for_each(|(topic, record)| nats.publish(topic, record.payload.to_string().as_bytes()))
+                    .and_then(|_| nats.flush());
+                if let Err(err) = result {
+                    *client = None; // Reconnect on the next attempt
+                    return Err(err.to_string());
+                }
+                Ok(())
+            }
+            Sink::KafkaRest { url } => {
+                let mut by_topic: HashMap<&str, Vec<Value>> = HashMap::new();
+                for (topic, record) in batch {
+                    by_topic.entry(topic.as_str()).or_default().push(json!({"key": record.key, "value": record.payload}));
+                }
+                for (topic, records) in by_topic {
+                    let endpoint = format!("{}/topics/{}", url.trim_end_matches('/'), topic);
+                    let body = json!({"records": records}).to_string();
+                    match http::post(&endpoint, "application/vnd.kafka.json.v2+json", &body)? {
+                        200..=299 => {}
+                        status => return Err(format!("Kafka REST proxy answered {} for topic {}", status, topic)),
+                    }
+                }
+                Ok(())
+            }
+            Sink::Exec(config) => {
+                let events: Vec<(String, &str, &Value)> = batch.iter().map(|(topic, record)| (topic.clone(), record.key.as_str(), &record.payload)).collect();
+                exec::run_batch(config, &events)
+            }
+        }
+    }
+}
+
+// Function to check the configuration file and its alert rules, then exit with the diagnostics
+fn check_config(args: &Args) -> ! {
+    let mut check = ConfigCheck::new("event_connector", args.check_connectivity);
+    let Some(config) = check.parse_json::<Config>(&args.config) else { check.exit() };
+    check.redis("redis_url", &config.redis_url);
+    match config.source {
+        SourceConfig::Streams { ref keys, .. } => {
+            if keys.is_empty() {
+                check.error("source.keys", "no stream to consume");
+            }
+            for (i, key) in keys.iter().enumerate() {
+                check.key(&format!("source.keys[{}]", i), key);
+            }
+        }
+        SourceConfig::Pubsub { ref pattern } => check.key_pattern("source.pattern", pattern),
+        SourceConfig::ShardedPubsub { ref channels } => {
+            if channels.is_empty() {
+                check.error("source.channels", "no channel to subscribe to");
+            }
+            for (i, key) in channels.iter().enumerate() {
+                check.key(&format!("source.channels[{}]", i), key);
+            }
+        }
+    }
+    match config.sink {
+        SinkConfig::Nats { ref address } => check.tcp("sink.address", address),
+        SinkConfig::KafkaRest { ref url } => check.http("sink.url", url),
+        SinkConfig::Exec(ref exec) => {
+            match exec.command.first() {
+                Some(program) if !program.is_empty() => check.ok("sink.command", "valid command"),
+                _ => check.error("sink.command", "no program to run"),
+            }
+            if exec.max_concurrent == 0 {
+                check.error("sink.max_concurrent", "must be at least 1");
+            }
+            if let Some(ref dir) = exec.working_dir {
+                if !std::path::Path::new(dir).is_dir() {
+                    check.error("sink.working_dir", format!("{} is not a directory", dir));
+                }
+            }
+        }
+    }
+    if config.topics.is_empty() {
+        check.warn("topics", "no topic rules, every event is dropped");
+    }
+    for (i, rule) in config.topics.iter().enumerate() {
+        check.key_pattern(&format!("topics[{}].key_pattern", i), &rule.key_pattern);
+        check.template(&format!("topics[{}].topic", i), &rule.topic, &[]);
+    }
+    if config.batch_size == 0 {
+        check.error("batch_size", "must be at least 1");
+    }
+    if let Some(ref dedup) = config.dedup {
+        if dedup.window_secs == 0 {
+            check.warn("dedup.window_secs", "a zero window drops no duplicates");
+        }
+    }
+    if let Some(ref path) = config.alerts {
+        check.check("alerts", AlertRouter::load(path).map(drop));
+    }
+    check.exit()
+}
+
+fn main() {
+    let args = Args::parse();
+    if args.check_config {
+        check_config(&args);
+    }
+    let config_text = fs::read_to_string(&args.config).expect("Failed to read configuration file");
+    let config: Config = serde_json::from_str(&config_text).expect("Invalid configuration file");
+
+    let mut source = Source::open(&config).expect("Failed to open event source");
+    let mut sink = Sink::new(&config.sink);
+    let mut alerts = config.alerts.as_deref().map(|path| AlertRouter::load(path).unwrap_or_else(|err| {
+        eprintln!("Invalid alert rules: {}", err);
+        std::process::exit(1);
+    }));
+    let batch_timeout = Duration::from_millis(config.batch_timeout_ms);
+    let mut dedup = config.dedup.as_ref().map(|cfg| {
+        let mut dedup = Deduplicator::new(cfg);
+        if let Some(ref prefix) = cfg.cuckoo_filter {
+            let result = rustredis::tunnel::open(&config.redis_url).and_then(|client| dedup.use_cuckoo_filter(&client, prefix, cfg.filter_capacity));
+            if let Err(err) = result {
+                eprintln!("Failed to set up the cuckoo filter, using the in-memory dedup window: {}", err);
+            }
+        }
+        dedup
+    });
+    println!("Event connector started");
+
+    loop {
+        let records = match source.next_batch(config.batch_size, batch_timeout) {
+            Ok(records) => records,
+            Err(err) => {
+                eprintln!("Event source failed: {}", err);
+                std::process::exit(1);
+            }
+        };
+        if records.is_empty() {
+            continue;
+        }
+
+        // Duplicates, and records without a matching topic rule, are dropped (and acknowledged)
+        let fresh: Vec<&Record> = records.iter()
+            .filter(|record| !dedup.as_mut().is_some_and(|dedup| dedup.is_duplicate(&record.key, &record.payload)))
+            .collect();
+        if let Some(ref mut alerts) = alerts {
+            alerts.route(&fresh);
+        }
+        let batch: Vec<(String, &Record)> = fresh.into_iter()
+            .filter_map(|record| {
+                config.topics.iter()
+                    .find(|rule| glob_match(&rule.key_pattern, &record.key))
+                    .map(|rule| (render_template(&rule.topic, &record.key), record))
+            })
+            .collect();
+
+        // Retry until the sink takes the batch; acknowledging only afterwards gives at-least-once delivery
+        let mut backoff = INITIAL_BACKOFF;
+        while !batch.is_empty() {
+            match sink.send(&batch) {
+                Ok(()) => break,
+                Err(err) => {
+                    eprintln!("Failed to publish {} records, retrying in {:?}: {}", batch.len(), backoff, err);
+                    sleep(backoff);
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+
+        if let Err(err) = source.ack(&records) {
+            eprintln!("Failed to acknowledge records: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+// Minimal NATS core client: publish with PING/PONG flush confirmation
+
+// Import necessary crates and modules
+use std::io::{self, BufRead, BufReader, Write}; // For the line-based protocol
+use std::net::TcpStream; // For the server connection
+use std::time::Duration; // For I/O timeouts
+
+// Define how long a flush may wait for the server's PONG
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Define a connection to a NATS server
+pub struct NatsClient {
+    reader: BufReader<TcpStream>, // Read half for INFO, PONG and errors
+    writer: TcpStream, // Write half for commands
+}
+
+impl NatsClient {
+    // Function to connect and complete the CONNECT handshake
+    pub fn connect(address: &str, name: &str) -> io::Result<Self> {
+        let writer = TcpStream::connect(address)?;
+        writer.set_read_timeout(Some(FLUSH_TIMEOUT))?;
+        let mut client = NatsClient { reader: BufReader::new(writer.try_clone()?), writer };
+
+        let info = client.read_line()?; // The server greets with INFO {...}
+        if !info.starts_with("INFO") {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unexpected greeting: {}", info)));
+        }
+        let connect = serde_json::json!({"verbose": false, "pedantic": false, "name": name, "lang": "rust"});
+        client.writer.write_all(format!("CONNECT {}\r\n", connect).as_bytes())?;
+        client.flush()?;
+        Ok(client)
+    }
+
+    // Function to queue a message for publication
+    pub fn publish(&mut self, subject: &str, payload: &[u8]) -> io::Result<()> {
+        self.writer.write_all(format!("PUB {} {}\r\n", subject, payload.len()).as_bytes())?;
+        self.writer.write_all(payload)?;
+        self.writer.write_all(b"\r\n")
+    }
+
+    // Function to wait until the server has processed everything sent so far
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.write_all(b"PING\r\n")?;
+        loop {
+            let line = self.read_line()?;
+            match line.as_str() {
+                "PONG" => return Ok(()),
+                "PING" => self.writer.write_all(b"PONG\r\n")?, // Server-initiated keepalive
+                _ if line.starts_with("-ERR") => return Err(io::Error::other(line)),
+                _ => {} // +OK and INFO updates
+            }
+        }
+    }
+
+    // Function to read one protocol line without its CRLF
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "NATS server closed the connection"));
+        }
+        Ok(line.trim_end().to_string())
+    }
+}
@@ -0,0 +1,194 @@
+// Golden transcript tool.
+//
+// Sends a scripted set of requests (one JSON request per line) through a running proxy while
+// capturing what reaches Redis: the commands the proxy issues (via MONITOR) and the pub/sub
+// messages it publishes. The proxy's responses, the commands and the messages are written as a
+// transcript and compared against a golden file, so refactors that must not change the protocol
+// (such as moving the proxy to async I/O) can show the same behavior. Against an empty Redis:
+//
+//   golden_events tests/golden/basic.jsonl --record   # writes tests/golden/basic.golden
+//   golden_events tests/golden/basic.jsonl            # compares, exits 1 on any difference
+//
+// Values that differ between runs are normalized: stream entry ids become <stream-id> and numbers
+// of ten or more digits (timestamps) become <n>. Commands in --ignore (health checks by default)
+// are left out, as their timing is not part of the behavior under test.
+
+use clap::Parser;
+use regex::Regex;
+use rustredis::client::{ProxyClient, DEFAULT_SOCKET_PATH};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// Define how often the capture threads check whether to stop
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Record or check the Redis commands and messages a proxy produces for scripted requests
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Script of requests, one JSON request per line (blank lines and lines starting with # are skipped)
+    script: PathBuf,
+
+    /// Golden transcript (the script path with a .golden extension if omitted)
+    #[arg(long)]
+    golden: Option<PathBuf>,
+
+    /// Write the transcript as the new golden file instead of comparing against it
+    #[arg(long)]
+    record: bool,
+
+    /// Proxy socket the requests are sent to
+    #[arg(long, default_value = DEFAULT_SOCKET_PATH)]
+    socket: String,
+
+    /// Redis the proxy writes to, watched with MONITOR and PSUBSCRIBE
+    #[arg(long, default_value = "redis://127.0.0.1/")]
+    redis_url: String,
+
+    /// Milliseconds to keep capturing after the last response, for late messages
+    #[arg(long, default_value_t = 300)]
+    settle_ms: u64,
+
+    /// Commands left out of the transcript (repeatable)
+    #[arg(long = "ignore", default_values_t = ["PING".to_string(), "INFO".to_string(), "ROLE".to_string()])]
+    ignore: Vec<String>,
+}
+
+// Function to fail with a message
+fn fail(message: String) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(2);
+}
+
+// Function to read the requests of a script
+fn read_script(path: &Path) -> Vec<Value> {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|e| fail(format!("Failed to read {}: {}", path.display(), e)));
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| fail(format!("Invalid request '{}': {}", line, e))))
+        .collect()
+}
+
+// Function to capture the commands Redis executes, as `"CMD" "arg" ...` without timestamp and client
+fn capture_commands(conn: &mut redis::Connection, stop: &AtomicBool) -> Vec<String> {
+    let mut commands = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        match conn.recv_response() {
+            Ok(redis::Value::Status(line)) => {
+                if let Some((_, command)) = line.split_once("] ") {
+                    commands.push(command.to_string());
+                }
+            }
+            Ok(_) => {}
+            Err(err) if err.is_timeout() => {}
+            Err(err) => fail(format!("MONITOR connection lost: {}", err)),
+        }
+    }
+    commands
+}
+
+// Function to start a capture thread on a fresh connection prepared by `setup`
+fn start_capture<T: Send + 'static>(
+    client: &redis::Client,
+    stop: &Arc<AtomicBool>,
+    setup: impl FnOnce(&mut redis::Connection) -> redis::RedisResult<()>,
+    capture: impl FnOnce(&mut redis::Connection, &AtomicBool) -> T + Send + 'static,
+) -> JoinHandle<T> {
+    let mut conn = client.get_connection().unwrap_or_else(|e| fail(format!("Failed to connect to Redis: {}", e)));
+    setup(&mut conn).unwrap_or_else(|e| fail(format!("Failed to start capturing: {}", e)));
+    conn.set_read_timeout(Some(POLL_INTERVAL)).unwrap_or_else(|e| fail(e.to_string()));
+    let stop = Arc::clone(stop);
+    thread::spawn(move || capture(&mut conn, &stop))
+}
+
+// Function to replace the parts of a line that differ between runs
+fn normalize(line: &str, patterns: &[(Regex, &str)]) -> String {
+    patterns.iter().fold(line.to_string(), |line, (pattern, replacement)| pattern.replace_all(&line, *replacement).into_owned())
+}
+
+// Function to list the differences between the golden and the new transcript, line by line
+fn differences(golden: &str, transcript: &str) -> Vec<String> {
+    let (golden, transcript): (Vec<&str>, Vec<&str>) = (golden.lines().collect(), transcript.lines().collect());
+    (0..golden.len().max(transcript.len()))
+        .filter_map(|i| match (golden.get(i), transcript.get(i)) {
+            (Some(expected), Some(actual)) if expected == actual => None,
+            (expected, actual) => Some(format!("line {}:\n  - {}\n  + {}", i + 1, expected.unwrap_or(&"<missing>"), actual.unwrap_or(&"<missing>"))),
+        })
+        .collect()
+}
+
+fn main() {
+    let args = Args::parse();
+    let requests = read_script(&args.script);
+    let golden_path = args.golden.clone().unwrap_or_else(|| args.script.with_extension("golden"));
+
+    let redis = rustredis::tunnel::open(&args.redis_url).unwrap_or_else(|e| fail(format!("Invalid Redis URL: {}", e)));
+    let stop = Arc::new(AtomicBool::new(false));
+    let (sender, messages): (_, Receiver<String>) = mpsc::channel();
+    let subscriber = start_capture(&redis, &stop, |conn| redis::cmd("PSUBSCRIBE").arg("*").query(conn), move |conn, stop| {
+        while !stop.load(Ordering::Relaxed) {
+            match conn.recv_response() {
+                Ok(redis::Value::Bulk(parts)) if parts.len() == 4 => {
+                    let text: Vec<String> = parts[2..].iter().map(|part| redis::from_redis_value(part).unwrap_or_default()).collect();
+                    let _ = sender.send(format!("{} {}", text[0], text[1]));
+                }
+                Ok(_) => {}
+                Err(err) if err.is_timeout() => {}
+                Err(err) => fail(format!("Subscription lost: {}", err)),
+            }
+        }
+    });
+    let monitor = start_capture(&redis, &stop, |conn| redis::cmd("MONITOR").query(conn), capture_commands);
+
+    let mut client = ProxyClient::connect(&args.socket).unwrap_or_else(|e| fail(format!("Failed to connect to {}: {}", args.socket, e)));
+    let mut responses = Vec::new();
+    for request in &requests {
+        let response = client.request(request).unwrap_or_else(|e| fail(format!("Request {} failed: {}", request, e)));
+        responses.push(format!("{} -> {} {} {}", request, response.status, response.message, response.data.unwrap_or(Value::Null)));
+    }
+    thread::sleep(Duration::from_millis(args.settle_ms));
+    stop.store(true, Ordering::Relaxed);
+    let commands = monitor.join().expect("Monitor thread panicked");
+    subscriber.join().expect("Subscriber thread panicked");
+
+    let patterns = [
+        (Regex::new(r"\d{10,}-\d+").unwrap(), "<stream-id>"),
+        (Regex::new(r"\d{10,}").unwrap(), "<n>"),
+    ];
+    let mut transcript = String::new();
+    for (section, lines) in [("responses", responses), ("commands", commands), ("messages", messages.try_iter().collect())] {
+        transcript.push_str(&format!("# {}\n", section));
+        for line in lines {
+            let command = line.trim_start_matches('"').split('"').next().unwrap_or_default().to_ascii_uppercase();
+            if section == "commands" && args.ignore.iter().any(|ignored| ignored.eq_ignore_ascii_case(&command)) {
+                continue;
+            }
+            transcript.push_str(&normalize(&line, &patterns));
+            transcript.push('\n');
+        }
+    }
+
+    if args.record {
+        std::fs::write(&golden_path, &transcript).unwrap_or_else(|e| fail(format!("Failed to write {}: {}", golden_path.display(), e)));
+        println!("Recorded {} requests to {}", requests.len(), golden_path.display());
+        return;
+    }
+    let golden = std::fs::read_to_string(&golden_path)
+        .unwrap_or_else(|e| fail(format!("Failed to read {} (record it with --record): {}", golden_path.display(), e)));
+    let differences = differences(&golden, &transcript);
+    if differences.is_empty() {
+        println!("{} requests match {}", requests.len(), golden_path.display());
+        return;
+    }
+    for difference in &differences {
+        println!("{}", difference);
+    }
+    println!("{} lines differ from {}", differences.len(), golden_path.display());
+    std::process::exit(1);
+}
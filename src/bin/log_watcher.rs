@@ -0,0 +1,261 @@
+// Log tailer producer.
+//
+// Follows the journal (through `journalctl -f -o json`) or a plain log file, runs every
+// line through the configured regex extractors and appends each match as a structured
+// event to a capped Redis stream through the proxy. Example configuration:
+//
+// {
+//   "source": {"journald": {"units": ["ModemManager.service"]}},
+//   "maxlen": 5000,
+//   "extractors": [
+//     {"name": "modem_error", "regex": "error: (?P<code>\\d+) (?P<reason>.*)",
+//      "key": "cs:ModemWatcher:object2:log"}
+//   ]
+// }
+
+use clap::Parser;
+use regex::Regex;
+use rustredis::check::ConfigCheck;
+use rustredis::client::{ProxyClient, DEFAULT_SOCKET_PATH};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// How long to wait for new lines at the end of a followed file
+const FILE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Journald/log file tailer writing regex matches to Redis streams through the proxy
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Path of the JSON configuration file
+    #[arg(long)]
+    config: String,
+
+    /// Check the configuration file and extractors, print the diagnostics as JSON and exit (status 1 if invalid)
+    #[arg(long)]
+    check_config: bool,
+
+    /// With --check-config, also connect to the proxy
+    #[arg(long, requires = "check_config")]
+    check_connectivity: bool,
+}
+
+// Define the watcher configuration
+#[derive(Deserialize)]
+struct Config {
+    source: Source, // Where log lines come from
+    #[serde(default = "default_proxy_socket")]
+    proxy_socket: String, // Unix socket of the Redis proxy
+    maxlen: Option<usize>, // Approximate cap of each stream (the proxy's limit applies if unset or larger)
+    extractors: Vec<ExtractorConfig>, // Patterns turning lines into events
+}
+
+// Define the log sources
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Source {
+    Journald {
+        #[serde(default)]
+        units: Vec<String>, // Units to follow (the whole journal if empty)
+    },
+    File {
+        path: String, // Log file to follow, reopened when rotated
+    },
+}
+
+// Define a regex extractor and the stream its matches go to
+#[derive(Deserialize)]
+struct ExtractorConfig {
+    name: String, // Name recorded in every event
+    regex: String, // Pattern, named groups become event fields
+    key: String, // Stream key the events are appended to
+}
+
+// Define a compiled extractor
+struct Extractor {
+    name: String,
+    regex: Regex,
+    key: String,
+}
+
+// Define one log line and where it came from
+struct LogLine {
+    message: String, // Text of the line
+    origin: String, // Unit or file name
+    timestamp: u64, // Microseconds since the epoch
+}
+
+fn default_proxy_socket() -> String {
+    DEFAULT_SOCKET_PATH.to_string()
+}
+
+fn now_micros() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_micros() as u64
+}
+
+// Convert a captured string to a number when it looks like one so consumers can compare fields
+fn capture_value(text: &str) -> Value {
+    if let Ok(n) = text.parse::<i64>() {
+        json!(n)
+    } else if let Ok(f) = text.parse::<f64>() {
+        json!(f)
+    } else {
+        json!(text)
+    }
+}
+
+// Build the event for a line matching an extractor
+fn build_event(extractor: &Extractor, line: &LogLine) -> Option<Value> {
+    let captures = extractor.regex.captures(&line.message)?;
+    let fields: Map<String, Value> = extractor.regex.capture_names()
+        .flatten()
+        .filter_map(|name| captures.name(name).map(|m| (name.to_string(), capture_value(m.as_str()))))
+        .collect();
+    Some(json!({
+        "extractor": extractor.name,
+        "origin": line.origin,
+        "timestamp": line.timestamp,
+        "message": line.message,
+        "fields": fields
+    }))
+}
+
+// Follow the journal, calling `handle` for every entry with a text message
+fn follow_journald(units: &[String], mut handle: impl FnMut(LogLine)) -> Result<(), String> {
+    let mut command = Command::new("journalctl");
+    command.args(["--follow", "--output=json", "--lines=0"]);
+    for unit in units {
+        command.arg(format!("--unit={}", unit));
+    }
+    let mut child = command.stdout(Stdio::piped()).spawn().map_err(|e| format!("failed to start journalctl: {}", e))?;
+    let stdout = child.stdout.take().unwrap();
+
+    for line in BufReader::new(stdout).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        let Ok(entry) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let Some(message) = entry["MESSAGE"].as_str() else {
+            continue; // Binary messages are exported as byte arrays
+        };
+        handle(LogLine {
+            message: message.to_string(),
+            origin: entry["_SYSTEMD_UNIT"].as_str().or(entry["SYSLOG_IDENTIFIER"].as_str()).unwrap_or("journal").to_string(),
+            timestamp: entry["__REALTIME_TIMESTAMP"].as_str().and_then(|t| t.parse().ok()).unwrap_or_else(now_micros),
+        });
+    }
+
+    Err(format!("journalctl exited: {:?}", child.wait()))
+}
+
+// Follow a file from its current end like `tail -F`, calling `handle` for every line
+fn follow_file(path: &str, mut handle: impl FnMut(LogLine)) -> Result<(), String> {
+    let open = |from_end: bool| -> Result<(BufReader<File>, u64), String> {
+        let mut file = File::open(path).map_err(|e| format!("failed to open {}: {}", path, e))?;
+        let inode = file.metadata().map_err(|e| e.to_string())?.ino();
+        if from_end {
+            file.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+        }
+        Ok((BufReader::new(file), inode))
+    };
+
+    let (mut reader, mut inode) = open(true)?;
+    let mut line = String::new();
+    loop {
+        let read = reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        if read > 0 && line.ends_with('\n') {
+            handle(LogLine {
+                message: line.trim_end().to_string(),
+                origin: path.to_string(),
+                timestamp: now_micros(),
+            });
+            line.clear();
+            continue;
+        }
+
+        // At the end of the file: wait, then reopen if the file was rotated or truncated
+        sleep(FILE_POLL_INTERVAL);
+        let position = reader.stream_position().map_err(|e| e.to_string())?;
+        match std::fs::metadata(path) {
+            Ok(meta) if meta.ino() != inode || meta.len() < position => {
+                (reader, inode) = open(false)?; // Read the new file from its start
+                line.clear();
+            }
+            _ => {}
+        }
+    }
+}
+
+// Check the configuration file, its extractors and the log source, then exit with the diagnostics
+fn check_config(args: &Args) -> ! {
+    let mut check = ConfigCheck::new("log_watcher", args.check_connectivity);
+    let Some(config) = check.parse_json::<Config>(&args.config) else { check.exit() };
+    match config.source {
+        Source::Journald { .. } => match Command::new("journalctl").arg("--version").stdout(Stdio::null()).status() {
+            Ok(status) if status.success() => check.ok("source.journald", "journalctl is available"),
+            _ => check.error("source.journald", "following the journal requires journalctl"),
+        },
+        Source::File { ref path } => match File::open(path) {
+            Ok(_) => check.ok("source.file.path", "readable"),
+            Err(err) => check.error("source.file.path", format!("failed to open {}: {}", path, err)),
+        },
+    }
+    check.proxy("proxy_socket", &config.proxy_socket);
+    if config.maxlen == Some(0) {
+        check.error("maxlen", "must be at least 1");
+    }
+    if config.extractors.is_empty() {
+        check.warn("extractors", "no extractors, every line is dropped");
+    }
+    for (i, extractor) in config.extractors.iter().enumerate() {
+        let subject = format!("extractors[{}].regex", i);
+        if let Some(regex) = check.check(&subject, Regex::new(&extractor.regex).map_err(|e| e.to_string())) {
+            if regex.capture_names().flatten().next().is_none() {
+                check.warn(&subject, "no named groups, events carry no fields");
+            }
+        }
+        check.key(&format!("extractors[{}].key", i), &extractor.key);
+    }
+    check.exit()
+}
+
+fn main() {
+    let args = Args::parse();
+    if args.check_config {
+        check_config(&args);
+    }
+    let config_text = std::fs::read_to_string(&args.config).expect("Failed to read configuration file");
+    let config: Config = serde_json::from_str(&config_text).expect("Invalid configuration file");
+
+    let extractors: Vec<Extractor> = config.extractors.iter().map(|e| Extractor {
+        name: e.name.clone(),
+        regex: Regex::new(&e.regex).unwrap_or_else(|err| panic!("Invalid regex for extractor {}: {}", e.name, err)),
+        key: e.key.clone(),
+    }).collect();
+
+    let mut proxy = ProxyClient::connect(&config.proxy_socket).expect("Failed to connect to Redis Proxy");
+    let mut handle = |line: LogLine| {
+        for extractor in &extractors {
+            if let Some(event) = build_event(extractor, &line) {
+                if let Err(err) = proxy.xadd(&extractor.key, &event, config.maxlen) {
+                    eprintln!("Failed to append event to {}: {}", extractor.key, err);
+                }
+            }
+        }
+    };
+
+    let result = match &config.source {
+        Source::Journald { units } => follow_journald(units, &mut handle),
+        Source::File { path } => follow_file(path, &mut handle),
+    };
+    if let Err(err) = result {
+        eprintln!("Log watcher stopped: {}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,216 @@
+// Mock producer.
+//
+// Writes plausible, evolving values for producer objects through the proxy, so dashboards and
+// consumers can be developed and demonstrated without the real hardware. Values follow the
+// objects' JSON schemas (the built-in ones, or those of --schema-dir):
+//
+//   numbers and integers  random walk within minimum/maximum (0..100 without bounds)
+//   enums                 state machine that mostly stays put and sometimes moves to another state
+//   booleans              flip now and then
+//   other strings         a fixed value per run
+//   arrays and objects    built from their item and property schemas
+//
+// Example, two DiskUsage updates per second for ten seconds:
+//
+//   mock_producer --key cs:DiskUsage:object1 --rate 2 --count 20
+//
+// Runs are reproducible with --seed; --dry-run prints the values instead of sending them.
+
+use clap::Parser;
+use rustredis::client::{ProxyClient, DEFAULT_SOCKET_PATH};
+use rustredis::rng::{entropy_seed, Rng};
+use rustredis::schema::{base_key, read_schema_dir, SCHEMAS};
+use serde_json::{json, Map, Number, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Define how likely an enum changes state on one update
+const STATE_CHANGE_PROBABILITY: f64 = 0.1;
+
+// Define how likely a boolean flips on one update
+const FLIP_PROBABILITY: f64 = 0.05;
+
+// Define the largest step of a random walk, as a fraction of its range
+const MAX_STEP: f64 = 0.02;
+
+/// Generate evolving mock values for producer objects and write them through the proxy
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Base key of an object to mock, as cs:<producer>:<object> (repeatable, all objects with a schema if omitted)
+    #[arg(long = "key")]
+    keys: Vec<String>,
+
+    /// Instance id the values are written under (cs:<producer>:<object>:<id>)
+    #[arg(long)]
+    id: Option<String>,
+
+    /// Directory of <producer>.<object>.json schemas to use instead of the built-in ones
+    #[arg(long)]
+    schema_dir: Option<PathBuf>,
+
+    /// Updates per second of each object
+    #[arg(long, default_value_t = 1.0)]
+    rate: f64,
+
+    /// Updates of each object before exiting (runs until interrupted if omitted)
+    #[arg(long)]
+    count: Option<u64>,
+
+    /// Seed of the generated values (random if omitted)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Proxy socket to write to
+    #[arg(long, default_value = DEFAULT_SOCKET_PATH)]
+    socket: String,
+
+    /// Print the values instead of sending them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+// Define the evolving state of one value of an object
+enum Generator {
+    Walk { value: f64, min: f64, max: f64, integer: bool }, // Numbers and integers
+    States { states: Vec<Value>, current: usize }, // Enums
+    Flag(bool), // Booleans
+    Fixed(Value), // Strings and anything else without a better model
+    List(Vec<Generator>), // Arrays
+    Object(Vec<(String, Generator)>), // Objects, by property
+}
+
+impl Generator {
+    // Function to build the generator of a schema with a random starting point
+    fn new(name: &str, schema: &Value, rng: &mut Rng) -> Self {
+        if let Some(states) = schema["enum"].as_array().filter(|states| !states.is_empty()) {
+            return Generator::States { states: states.clone(), current: rng.below(states.len() as u64) as usize };
+        }
+        match schema["type"].as_str() {
+            Some(kind @ ("number" | "integer")) => {
+                let min = schema["minimum"].as_f64().unwrap_or(0.0);
+                let max = schema["maximum"].as_f64().unwrap_or(min + 100.0).max(min);
+                if name == "version" {
+                    return Generator::Fixed(json!(1)); // A schema version, not a measurement
+                }
+                Generator::Walk { value: min + rng.next_f64() * (max - min), min, max, integer: kind == "integer" }
+            }
+            Some("boolean") => Generator::Flag(rng.below(2) == 1),
+            Some("string") => Generator::Fixed(json!(format!("{}-{}", name, rng.hex(4)))),
+            Some("array") => Generator::List((0..3).map(|_| Generator::new(name, &schema["items"], rng)).collect()),
+            Some("object") => {
+                let properties = schema["properties"].as_object().cloned().unwrap_or_default();
+                Generator::Object(properties.iter().map(|(property, schema)| (property.clone(), Generator::new(property, schema, rng))).collect())
+            }
+            _ => Generator::Fixed(Value::Null),
+        }
+    }
+
+    // Function to advance the state by one update and return the value
+    fn next(&mut self, rng: &mut Rng) -> Value {
+        match self {
+            Generator::Walk { value, min, max, integer } => {
+                let step = (rng.next_f64() * 2.0 - 1.0) * MAX_STEP * (*max - *min);
+                *value = (*value + step).clamp(*min, *max);
+                if *integer {
+                    json!(value.round() as i64)
+                } else {
+                    Number::from_f64((*value * 100.0).round() / 100.0).map_or(Value::Null, Value::Number)
+                }
+            }
+            Generator::States { states, current } => {
+                if states.len() > 1 && rng.next_f64() < STATE_CHANGE_PROBABILITY {
+                    *current = (*current + 1 + rng.below(states.len() as u64 - 1) as usize) % states.len(); // Any other state
+                }
+                states[*current].clone()
+            }
+            Generator::Flag(flag) => {
+                if rng.next_f64() < FLIP_PROBABILITY {
+                    *flag = !*flag;
+                }
+                json!(*flag)
+            }
+            Generator::Fixed(value) => value.clone(),
+            Generator::List(items) => Value::Array(items.iter_mut().map(|item| item.next(rng)).collect()),
+            Generator::Object(properties) => {
+                Value::Object(properties.iter_mut().map(|(property, generator)| (property.clone(), generator.next(rng))).collect::<Map<_, _>>())
+            }
+        }
+    }
+}
+
+// Function to load the schemas of the objects to mock, by base key
+fn schemas(args: &Args) -> Result<BTreeMap<String, Value>, String> {
+    let available: BTreeMap<String, Value> = match args.schema_dir {
+        Some(ref dir) => read_schema_dir(dir)?.0.into_iter().collect(),
+        None => SCHEMAS.iter().map(|(key, schema)| (key.to_string(), schema.clone())).collect(),
+    };
+    if args.keys.is_empty() {
+        return Ok(available);
+    }
+    args.keys.iter()
+        .map(|key| {
+            let key = base_key(key);
+            let schema = available.get(&key).cloned().ok_or_else(|| format!("no schema for {}", key))?;
+            Ok((key, schema))
+        })
+        .collect()
+}
+
+fn main() {
+    let args = Args::parse();
+    let schemas = schemas(&args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    if schemas.is_empty() || args.rate <= 0.0 {
+        eprintln!("Nothing to generate: need at least one object with a schema and a positive --rate");
+        std::process::exit(2);
+    }
+
+    let seed = args.seed.unwrap_or_else(entropy_seed);
+    println!("Mocking {} objects at {}/s each (seed {})", schemas.len(), args.rate, seed);
+    let mut rng = Rng::new(seed);
+    let mut objects: Vec<(String, Generator)> = schemas.iter()
+        .map(|(key, schema)| {
+            let key = match args.id {
+                Some(ref id) => format!("{}:{}", key, id),
+                None => key.clone(),
+            };
+            (key, Generator::new("", schema, &mut rng))
+        })
+        .collect();
+
+    let mut client = if args.dry_run {
+        None
+    } else {
+        Some(ProxyClient::connect(&args.socket).unwrap_or_else(|err| {
+            eprintln!("Failed to connect to {}: {}", args.socket, err);
+            std::process::exit(1);
+        }))
+    };
+
+    let interval = Duration::from_secs_f64(1.0 / args.rate);
+    let started = Instant::now();
+    let mut update = 0;
+    while args.count.is_none_or(|count| update < count) {
+        for (key, generator) in objects.iter_mut() {
+            let value = generator.next(&mut rng);
+            match client {
+                Some(ref mut client) => {
+                    if let Err(err) = client.set(key, &value) {
+                        eprintln!("Failed to write {}: {}", key, err);
+                    }
+                }
+                None => println!("{} {}", key, value),
+            }
+        }
+        update += 1;
+        // Pace by the schedule rather than sleeping a fixed interval, so slow writes don't lower the rate
+        if let Some(wait) = (started + interval * update as u32).checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+    }
+}
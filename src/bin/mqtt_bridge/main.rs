@@ -0,0 +1,234 @@
+// Import necessary crates and modules
+mod mqtt; // Minimal MQTT 3.1.1 client
+
+use clap::Parser; // For command line argument parsing
+use mqtt::{match_topic, MqttClient, Publisher}; // For talking to the MQTT broker
+use rustredis::check::ConfigCheck; // For the --check-config mode
+use rustredis::client::{new_traceparent, ProxyClient, DEFAULT_SOCKET_PATH}; // For validated, optionally traced writes through the proxy
+use rustredis::events::parse_event; // For structuring proxy events
+use rustredis::glob::glob_match; // For matching event keys against outbound rules
+use rustredis::keys::render_template; // For building topics from keys
+use rustredis::rng::{self, Rng}; // For the trace ids of bridged messages
+use serde::Deserialize; // For deserializing the configuration file
+use serde_json::Value; // For parsing payloads
+use std::fs; // For reading the configuration file
+use std::process; // For exiting when one direction of the bridge fails
+use std::thread; // For running both directions concurrently
+
+/// MQTT <-> Redis proxy bridge
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Path of the JSON configuration file
+    #[arg(long)]
+    config: String,
+
+    /// Check the configuration file, print the diagnostics as JSON and exit (status 1 if invalid)
+    #[arg(long)]
+    check_config: bool,
+
+    /// With --check-config, also connect to the broker, the proxy and Redis
+    #[arg(long, requires = "check_config")]
+    check_connectivity: bool,
+}
+
+// Define the bridge configuration
+//
+// Keep inbound topics and outbound topic templates disjoint, otherwise a write bridged
+// from MQTT is published back to MQTT and bridged again.
+#[derive(Deserialize)]
+struct Config {
+    broker: String, // MQTT broker address as host:port
+    #[serde(default = "default_client_id")]
+    client_id: String, // MQTT client id
+    username: Option<String>, // MQTT username (optional)
+    password: Option<String>, // MQTT password (optional)
+    #[serde(default = "default_keepalive")]
+    keepalive: u16, // MQTT keepalive in seconds
+    #[serde(default = "default_proxy_socket")]
+    proxy_socket: String, // Unix socket of the Redis proxy
+    #[serde(default = "default_redis_url")]
+    redis_url: String, // Redis URL used to subscribe to proxy events
+    #[serde(default)]
+    trace: bool, // Start a trace (W3C traceparent) for every bridged MQTT message, exported by the proxy's --otlp-endpoint
+    #[serde(default)]
+    inbound: Vec<InboundRule>, // MQTT topic -> proxy key mappings
+    #[serde(default)]
+    outbound: Vec<OutboundRule>, // Proxy key -> MQTT topic mappings
+}
+
+// Define a mapping of MQTT messages to proxy writes
+#[derive(Deserialize)]
+struct InboundRule {
+    topic: String, // Topic filter, `+` and `#` wildcards allowed
+    key: String, // Key template, {1}, {2}... are replaced by the levels matched by wildcards
+}
+
+// Define a mapping of proxy events to MQTT messages
+#[derive(Deserialize)]
+struct OutboundRule {
+    key_pattern: String, // Glob pattern of the keys to forward
+    topic: String, // Topic template with {key}, {producer}, {object}, {id} and {function} placeholders
+    #[serde(default)]
+    retain: bool, // Publish as retained messages
+}
+
+fn default_client_id() -> String {
+    "rustredis-mqtt-bridge".to_string()
+}
+
+fn default_keepalive() -> u16 {
+    60
+}
+
+fn default_proxy_socket() -> String {
+    DEFAULT_SOCKET_PATH.to_string()
+}
+
+fn default_redis_url() -> String {
+    "redis://127.0.0.1/".to_string()
+}
+
+// Function to fill a key template with the topic levels captured by wildcards
+fn render_key(template: &str, captures: &[String]) -> String {
+    let mut key = template.to_string();
+    for (i, capture) in captures.iter().enumerate() {
+        key = key.replace(&format!("{{{}}}", i + 1), capture);
+    }
+    key
+}
+
+// Function to forward MQTT messages matching inbound rules to the proxy
+fn run_inbound(mut mqtt: MqttClient, config: &Config) -> Result<(), String> {
+    let mut proxy = ProxyClient::connect(&config.proxy_socket).map_err(|e| e.to_string())?;
+    let mut rng = Rng::new(rng::entropy_seed());
+    for rule in &config.inbound {
+        mqtt.subscribe(&rule.topic).map_err(|e| format!("subscribe to {}: {}", rule.topic, e))?;
+        println!("Bridging MQTT {} -> {}", rule.topic, rule.key);
+    }
+
+    loop {
+        let message = mqtt.next_message().map_err(|e| format!("MQTT connection lost: {}", e))?;
+        let value: Value = match serde_json::from_slice(&message.payload) {
+            Ok(value) => value,
+            Err(err) => {
+                eprintln!("Ignoring non-JSON payload on {}: {}", message.topic, err);
+                continue;
+            }
+        };
+
+        // The writes of one message belong to the same trace
+        let traceparent = if config.trace { Some(new_traceparent(&mut rng)) } else { None };
+        proxy.set_traceparent(traceparent.as_deref());
+        for rule in &config.inbound {
+            if let Some(captures) = match_topic(&rule.topic, &message.topic) {
+                let key = render_key(&rule.key, &captures);
+                if let Err(err) = proxy.set(&key, &value) {
+                    eprintln!("Failed to bridge {} to {}: {}", message.topic, key, err); // Validation failures only drop this message
+                }
+            }
+        }
+    }
+}
+
+// Function to forward proxy events matching outbound rules to MQTT
+fn run_outbound(publisher: Publisher, config: &Config) -> Result<(), String> {
+    let client = rustredis::tunnel::open(&config.redis_url).map_err(|e| e.to_string())?;
+    let mut conn = client.get_connection().map_err(|e| e.to_string())?;
+    let mut pubsub = conn.as_pubsub();
+    for rule in &config.outbound {
+        pubsub.psubscribe(&rule.key_pattern).map_err(|e| e.to_string())?;
+        println!("Bridging {} -> MQTT {}", rule.key_pattern, rule.topic);
+    }
+
+    loop {
+        let message = pubsub.get_message().map_err(|e| format!("Redis subscription lost: {}", e))?;
+        let key = message.get_channel_name().to_string();
+        let event: String = message.get_payload().map_err(|e| e.to_string())?;
+        let payload = parse_event(&key, &event).to_string();
+
+        for rule in config.outbound.iter().filter(|rule| glob_match(&rule.key_pattern, &key)) {
+            let topic = render_template(&rule.topic, &key);
+            publisher.publish(&topic, payload.as_bytes(), rule.retain).map_err(|e| format!("MQTT publish failed: {}", e))?;
+        }
+    }
+}
+
+// Function to check a topic filter: `#` only as the last level, wildcards only as whole levels;
+// returns the number of levels the wildcards capture
+fn check_topic_filter(filter: &str) -> Result<usize, String> {
+    let levels: Vec<&str> = filter.split('/').collect();
+    for (i, level) in levels.iter().enumerate() {
+        if (*level == "#" && i + 1 != levels.len()) || (level.len() > 1 && level.contains(['+', '#'])) {
+            return Err(format!("invalid topic filter '{}'", filter));
+        }
+    }
+    Ok(levels.iter().filter(|level| matches!(**level, "+" | "#")).count())
+}
+
+// Function to check the configuration file, then exit with the diagnostics
+fn check_config(args: &Args) -> ! {
+    let mut check = ConfigCheck::new("mqtt_bridge", args.check_connectivity);
+    let Some(config) = check.parse_json::<Config>(&args.config) else { check.exit() };
+    check.tcp("broker", &config.broker);
+    if config.password.is_some() && config.username.is_none() {
+        check.warn("password", "ignored without a username");
+    }
+    if !config.inbound.is_empty() {
+        check.proxy("proxy_socket", &config.proxy_socket);
+    }
+    if !config.outbound.is_empty() {
+        check.redis("redis_url", &config.redis_url);
+    }
+    if config.inbound.is_empty() && config.outbound.is_empty() {
+        check.warn("inbound", "no inbound or outbound rules, nothing is bridged");
+    }
+    for (i, rule) in config.inbound.iter().enumerate() {
+        let Some(captures) = check.check(&format!("inbound[{}].topic", i), check_topic_filter(&rule.topic)) else { continue };
+        let subject = format!("inbound[{}].key", i);
+        let unfilled = rule.key.split('{').skip(1).filter_map(|rest| rest.split_once('}')?.0.parse::<usize>().ok()).find(|n| *n == 0 || *n > captures);
+        match unfilled {
+            Some(n) => check.error(&subject, format!("{{{}}} is never filled, the topic filter has {} wildcards", n, captures)),
+            None => check.key(&subject, &rule.key),
+        }
+    }
+    for (i, rule) in config.outbound.iter().enumerate() {
+        check.key_pattern(&format!("outbound[{}].key_pattern", i), &rule.key_pattern);
+        check.template(&format!("outbound[{}].topic", i), &rule.topic, &[]);
+        // A topic bridged back in would loop between MQTT and the proxy
+        let sample = rule.topic.replace(['{', '}'], "");
+        if let Some(inbound) = config.inbound.iter().find(|inbound| match_topic(&inbound.topic, &sample).is_some()) {
+            check.warn(&format!("outbound[{}].topic", i), format!("may match inbound topic filter '{}', bridging writes back and forth", inbound.topic));
+        }
+    }
+    check.exit()
+}
+
+fn main() {
+    let args = Args::parse();
+    if args.check_config {
+        check_config(&args);
+    }
+    let config_text = fs::read_to_string(&args.config).expect("Failed to read configuration file");
+    let config: &'static Config = Box::leak(Box::new(serde_json::from_str(&config_text).expect("Invalid configuration file")));
+
+    let credentials = config.username.as_deref().map(|user| (user, config.password.as_deref().unwrap_or("")));
+    let mqtt = MqttClient::connect(&config.broker, &config.client_id, credentials, config.keepalive)
+        .expect("Failed to connect to MQTT broker");
+    println!("Connected to MQTT broker at {}", config.broker);
+
+    if !config.outbound.is_empty() {
+        let publisher = mqtt.publisher();
+        thread::spawn(move || {
+            if let Err(err) = run_outbound(publisher, config) {
+                eprintln!("Outbound bridge stopped: {}", err);
+                process::exit(1);
+            }
+        });
+    }
+
+    if let Err(err) = run_inbound(mqtt, config) {
+        eprintln!("Inbound bridge stopped: {}", err);
+        process::exit(1);
+    }
+}
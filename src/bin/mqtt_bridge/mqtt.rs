@@ -0,0 +1,216 @@
+// Minimal MQTT 3.1.1 client: clean session, QoS 0 only
+
+// Import necessary crates and modules
+use std::io::{self, Read, Write}; // For reading and writing packets
+use std::net::TcpStream; // For the broker connection
+use std::sync::{Arc, Mutex}; // For sharing the write half between threads
+use std::thread; // For the keepalive thread
+use std::time::Duration; // For the keepalive interval
+
+// Define the MQTT control packet types used by the bridge
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PUBACK: u8 = 0x40;
+const SUBSCRIBE: u8 = 0x82; // SUBSCRIBE requires the reserved flag bits 0010
+const SUBACK: u8 = 0x90;
+const PINGREQ: u8 = 0xC0;
+const PINGRESP: u8 = 0xD0;
+
+// Define a message received from the broker
+pub struct Message {
+    pub topic: String, // Topic the message was published to
+    pub payload: Vec<u8>, // Raw message payload
+}
+
+// Define a connection to an MQTT broker
+pub struct MqttClient {
+    reader: TcpStream, // Read half of the connection
+    writer: Arc<Mutex<TcpStream>>, // Write half shared with publishers and the keepalive thread
+    next_packet_id: u16, // Packet id for the next SUBSCRIBE
+}
+
+// Function to append an MQTT UTF-8 string (length prefixed)
+fn put_string(buf: &mut Vec<u8>, value: &str) {
+    buf.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buf.extend_from_slice(value.as_bytes());
+}
+
+// Function to frame a packet with its fixed header and variable-length remaining length
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![header];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80; // More length bytes follow
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(body);
+    out
+}
+
+// Function to read one packet, returning its fixed header byte and body
+fn read_packet(stream: &mut TcpStream) -> io::Result<(u8, Vec<u8>)> {
+    let mut header = [0u8; 1];
+    stream.read_exact(&mut header)?;
+
+    let mut len = 0usize;
+    let mut multiplier = 1usize;
+    loop {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte)?;
+        len += (byte[0] & 0x7F) as usize * multiplier;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        multiplier *= 128;
+        if multiplier > 128 * 128 * 128 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed remaining length"));
+        }
+    }
+
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body)?;
+    Ok((header[0], body))
+}
+
+// Function to read a length-prefixed string at an offset, returning it and the offset after it
+fn get_string(body: &[u8], offset: usize) -> io::Result<(String, usize)> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "truncated string");
+    let len_bytes = body.get(offset..offset + 2).ok_or_else(invalid)?;
+    let len = u16::from_be_bytes([len_bytes[0], len_bytes[1]]) as usize;
+    let bytes = body.get(offset + 2..offset + 2 + len).ok_or_else(invalid)?;
+    Ok((String::from_utf8_lossy(bytes).to_string(), offset + 2 + len))
+}
+
+impl MqttClient {
+    // Function to connect to a broker and start sending keepalive pings
+    pub fn connect(address: &str, client_id: &str, credentials: Option<(&str, &str)>, keepalive: u16) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(address)?;
+
+        let mut body = Vec::new();
+        put_string(&mut body, "MQTT");
+        body.push(4); // Protocol level 3.1.1
+        let mut flags = 0x02; // Clean session
+        if credentials.is_some() {
+            flags |= 0xC0; // Username and password present
+        }
+        body.push(flags);
+        body.extend_from_slice(&keepalive.to_be_bytes());
+        put_string(&mut body, client_id);
+        if let Some((username, password)) = credentials {
+            put_string(&mut body, username);
+            put_string(&mut body, password);
+        }
+        stream.write_all(&packet(CONNECT, &body))?;
+
+        let (header, ack) = read_packet(&mut stream)?;
+        if header & 0xF0 != CONNACK || ack.len() < 2 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "expected CONNACK"));
+        }
+        if ack[1] != 0 {
+            return Err(io::Error::new(io::ErrorKind::ConnectionRefused, format!("broker refused connection (code {})", ack[1])));
+        }
+
+        let writer = Arc::new(Mutex::new(stream.try_clone()?));
+        let ping_writer = Arc::clone(&writer);
+        let interval = Duration::from_secs(u64::from(keepalive.max(2)) / 2);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            if ping_writer.lock().unwrap().write_all(&packet(PINGREQ, &[])).is_err() {
+                break; // Connection is gone, the reader reports the error
+            }
+        });
+
+        Ok(MqttClient { reader: stream, writer, next_packet_id: 1 })
+    }
+
+    // Function to get a handle that can publish from other threads
+    pub fn publisher(&self) -> Publisher {
+        Publisher { writer: Arc::clone(&self.writer) }
+    }
+
+    // Function to subscribe to a topic filter at QoS 0
+    pub fn subscribe(&mut self, filter: &str) -> io::Result<()> {
+        let mut body = self.next_packet_id.to_be_bytes().to_vec();
+        self.next_packet_id = self.next_packet_id.wrapping_add(1).max(1);
+        put_string(&mut body, filter);
+        body.push(0); // Requested QoS 0
+        self.writer.lock().unwrap().write_all(&packet(SUBSCRIBE, &body))
+    }
+
+    // Function to block until the next PUBLISH from the broker
+    pub fn next_message(&mut self) -> io::Result<Message> {
+        loop {
+            let (header, body) = read_packet(&mut self.reader)?;
+            match header & 0xF0 {
+                PUBLISH => {
+                    let qos = (header >> 1) & 0x03;
+                    let (topic, mut offset) = get_string(&body, 0)?;
+                    if qos > 0 {
+                        // Brokers may deliver retained messages above the granted QoS; acknowledge them
+                        let id = body.get(offset..offset + 2).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing packet id"))?;
+                        self.writer.lock().unwrap().write_all(&packet(PUBACK, id))?;
+                        offset += 2;
+                    }
+                    return Ok(Message { topic, payload: body[offset..].to_vec() });
+                }
+                SUBACK => {
+                    if body.get(2) == Some(&0x80) {
+                        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "broker rejected subscription"));
+                    }
+                }
+                PINGRESP => {}
+                other => eprintln!("Ignoring unexpected MQTT packet type 0x{:02X}", other),
+            }
+        }
+    }
+}
+
+// Define a cloneable handle for publishing
+#[derive(Clone)]
+pub struct Publisher {
+    writer: Arc<Mutex<TcpStream>>, // Write half of the broker connection
+}
+
+impl Publisher {
+    // Function to publish a payload at QoS 0
+    pub fn publish(&self, topic: &str, payload: &[u8], retain: bool) -> io::Result<()> {
+        let mut body = Vec::new();
+        put_string(&mut body, topic);
+        body.extend_from_slice(payload);
+        let header = if retain { PUBLISH | 0x01 } else { PUBLISH };
+        self.writer.lock().unwrap().write_all(&packet(header, &body))
+    }
+}
+
+// Function to match a topic against a filter with `+` (one level) and `#` (remaining levels) wildcards,
+// returning the levels matched by wildcards in order
+pub fn match_topic(filter: &str, topic: &str) -> Option<Vec<String>> {
+    let mut captures = Vec::new();
+    let mut topic_levels = topic.split('/');
+    for level in filter.split('/') {
+        match level {
+            "#" => {
+                captures.push(topic_levels.collect::<Vec<_>>().join("/"));
+                return Some(captures);
+            }
+            "+" => captures.push(topic_levels.next()?.to_string()),
+            literal => {
+                if topic_levels.next()? != literal {
+                    return None;
+                }
+            }
+        }
+    }
+    if topic_levels.next().is_some() {
+        return None; // Topic is deeper than the filter
+    }
+    Some(captures)
+}
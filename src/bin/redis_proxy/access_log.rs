@@ -0,0 +1,163 @@
+// Import necessary crates and modules
+use rustredis::rng::{entropy_seed, Rng}; // For sampling requests
+use serde_json::{json, Value}; // For log lines and the summary
+use std::cell::Cell; // For the rejection reason of the request being processed
+use std::fs::{self, File, OpenOptions}; // For the log file and its rotation
+use std::io::{BufWriter, Write}; // For batching writes to flash
+use std::path::{Path, PathBuf}; // For the log path
+use std::sync::atomic::{AtomicU64, Ordering}; // For the written and dropped line counts
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender}; // For handing lines to the writer thread
+use std::sync::{Mutex, OnceLock}; // For the sampler and the writer's queue
+use std::thread; // For the writer thread
+use std::time::{Duration, SystemTime, UNIX_EPOCH}; // For flush intervals and timestamps
+
+// Define how many lines may wait for the writer before further ones are dropped
+const QUEUE_LINES: usize = 1024;
+
+// Define how often buffered lines are flushed to the file, so flash sees few small writes
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+// Define how requests are logged
+#[derive(Clone, Debug)]
+pub struct AccessLogConfig {
+    pub path: PathBuf, // File lines are appended to
+    pub sample: f64, // Share of requests logged
+    pub failures: bool, // Whether every failed request is logged, sampled or not
+    pub max_bytes: u64, // Size at which the file is moved to PATH.1, replacing the previous one
+}
+
+// Define the running access log
+struct AccessLog {
+    config: AccessLogConfig,
+    sampler: Mutex<Rng>, // Draws which requests are logged
+    lines: SyncSender<String>, // Queue of the writer thread
+}
+
+// Define the access log, if configured
+static ACCESS_LOG: OnceLock<AccessLog> = OnceLock::new();
+
+// Define the lines written and dropped because the writer fell behind
+static WRITTEN: AtomicU64 = AtomicU64::new(0);
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // Reason the request the handler thread is processing was rejected for, if it was
+    static REJECTION: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+// Function to note why the current request was rejected; its log line gives the reason instead of
+// the response message, which may quote the value
+pub fn reject(reason: &'static str) {
+    REJECTION.with(|rejection| rejection.set(Some(reason)));
+}
+
+// Function to parse the share of requests to log, from 0 to 1
+pub fn parse_sample(text: &str) -> Result<f64, String> {
+    let share: f64 = text.parse().map_err(|_| format!("invalid share '{}'", text))?;
+    if !(0.0..=1.0).contains(&share) {
+        return Err(format!("share {} is not between 0 and 1", share));
+    }
+    Ok(share)
+}
+
+// Function to open the log file for appending
+fn open(path: &Path) -> std::io::Result<BufWriter<File>> {
+    OpenOptions::new().create(true).append(true).open(path).map(BufWriter::new)
+}
+
+// Function to open the log and start its writer thread
+pub fn start(config: AccessLogConfig) -> std::io::Result<()> {
+    let mut file = open(&config.path)?;
+    let mut size = fs::metadata(&config.path)?.len();
+    let (lines, queue) = mpsc::sync_channel::<String>(QUEUE_LINES);
+    let (path, max_bytes) = (config.path.clone(), config.max_bytes);
+    thread::spawn(move || loop {
+        match queue.recv_timeout(FLUSH_INTERVAL) {
+            Ok(line) => {
+                if size + line.len() as u64 > max_bytes && size > 0 {
+                    let _ = file.flush();
+                    let rotated = PathBuf::from(format!("{}.1", path.display()));
+                    match fs::rename(&path, &rotated).and_then(|_| open(&path)) {
+                        Ok(reopened) => {
+                            file = reopened;
+                            size = 0;
+                        }
+                        Err(err) => eprintln!("Failed to rotate access log {}: {}", path.display(), err),
+                    }
+                }
+                match file.write_all(line.as_bytes()) {
+                    Ok(()) => {
+                        size += line.len() as u64;
+                        WRITTEN.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(err) => eprintln!("Failed to write access log {}: {}", path.display(), err),
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = file.flush();
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    });
+    let sampler = Mutex::new(Rng::new(entropy_seed()));
+    let _ = ACCESS_LOG.set(AccessLog { config, sampler, lines });
+    Ok(())
+}
+
+// Function to check if requests are logged, so callers only measure what a line needs when they are
+pub fn enabled() -> bool {
+    ACCESS_LOG.get().is_some()
+}
+
+// Define what is logged of one request; values never are, only their size
+pub struct Entry<'a> {
+    pub listener: &'a str, // Socket the request came through
+    pub action: &'a str, // Empty if the request could not be parsed
+    pub key: &'a str,
+    pub value_bytes: Option<usize>, // Size of the serialized value (or decoded binary value) sent
+    pub status: &'a str, // Status of the response
+    pub message: &'a str, // Message of the response, logged for failures other than rejections
+    pub took: Duration, // Time from receiving the request to its response
+}
+
+// Function to log a request if it is sampled, or failed and failures are all logged
+pub fn record(entry: Entry) {
+    let rejection = REJECTION.with(Cell::take);
+    let Some(log) = ACCESS_LOG.get() else { return };
+    let failed = entry.status == "error";
+    let sampled = log.config.sample > 0.0 && log.sampler.lock().unwrap().next_f64() < log.config.sample;
+    if !(sampled || (failed && log.config.failures)) {
+        return;
+    }
+    let mut line = json!({
+        "ts_ms": SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+        "listener": entry.listener,
+        "action": entry.action,
+        "key": entry.key,
+        "value_bytes": entry.value_bytes,
+        "status": entry.status,
+        "took_us": entry.took.as_micros() as u64
+    });
+    match rejection {
+        Some(reason) => line["rejected"] = Value::from(reason),
+        None if failed => line["error"] = Value::String(entry.message.to_string()),
+        None => {}
+    }
+    let mut line = line.to_string();
+    line.push('\n');
+    if log.lines.try_send(line).is_err() {
+        DROPPED.fetch_add(1, Ordering::Relaxed); // Requests never wait for the log
+    }
+}
+
+// Function to report the log's settings and counts, for stats
+pub fn summary() -> Value {
+    let Some(log) = ACCESS_LOG.get() else { return Value::Null };
+    json!({
+        "path": log.config.path.display().to_string(),
+        "sample": log.config.sample,
+        "failures": log.config.failures,
+        "written": WRITTEN.load(Ordering::Relaxed),
+        "dropped": DROPPED.load(Ordering::Relaxed)
+    })
+}
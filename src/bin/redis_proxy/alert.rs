@@ -0,0 +1,103 @@
+// Import necessary crates and modules
+use super::publish; // For announcing alerts with the configured pub/sub flavour
+use super::router::Router; // For storing alerts on the backend of their key
+use redis::Commands; // For storing alerts and publishing their events
+use rustredis::storage::Storage; // For the connection alerts are stored through
+use serde_json::{json, Value}; // For alert records and events
+use std::collections::HashMap; // For the unacknowledged alerts by key
+use std::sync::{Arc, Mutex}; // For the alerts shared with the escalation thread
+use std::thread; // For the escalation thread
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH}; // For escalation windows and timestamps
+
+// Define the namespace alerts are kept in (cs:<producer>:... -> cs:_alert:<producer>:...)
+const ALERT_PREFIX: &str = "cs:_alert:";
+
+// Define the severities an alert may have, least severe first
+pub const SEVERITIES: [&str; 3] = ["info", "warning", "critical"];
+
+// Define how often the escalation thread checks the unacknowledged alerts
+const ESCALATION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Define an alert that has not been acknowledged yet
+struct Pending {
+    record: Value, // Alert as stored
+    raised: Instant, // When it was raised
+    expires: Instant, // When its key expires and escalation stops
+    escalations: usize, // Escalation windows already passed
+}
+
+// Define the unacknowledged alerts by producer key (lost on restart, the stored alerts stay)
+static PENDING: Mutex<Option<HashMap<String, Pending>>> = Mutex::new(None);
+
+// Function to return the key an alert of a producer key is stored under
+pub fn alert_key(key: &str) -> String {
+    format!("{}{}", ALERT_PREFIX, key.trim_start_matches("cs:"))
+}
+
+// Function to store an alert for `ttl` seconds and announce it on the key's channel; a new alert
+// replaces the previous one and restarts its escalation
+pub fn raise(conn: &mut Storage, key: &str, severity: &str, value: Option<&Value>, ttl: u64) -> redis::RedisResult<Value> {
+    let mut record = json!({
+        "severity": severity,
+        "value": value,
+        "raised_at": SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        "acked": false
+    });
+    super::device::stamp(&mut record);
+    conn.set_ex::<String, String, ()>(alert_key(key), record.to_string(), ttl)?;
+    publish::send(conn, key, &format!("alert: {}", record))?;
+
+    let now = Instant::now();
+    let pending = Pending { record: record.clone(), raised: now, expires: now + Duration::from_secs(ttl), escalations: 0 };
+    PENDING.lock().unwrap().get_or_insert_with(HashMap::new).insert(key.to_string(), pending);
+    Ok(record)
+}
+
+// Function to acknowledge the alert of a key, stopping its escalation; returns false if it has none
+pub fn ack(conn: &mut Storage, key: &str) -> redis::RedisResult<bool> {
+    if let Some(pending) = PENDING.lock().unwrap().as_mut() {
+        pending.remove(key);
+    }
+    let Some(stored) = conn.get::<String, Option<String>>(alert_key(key))? else {
+        return Ok(false); // Never raised, or expired
+    };
+    let mut record: Value = serde_json::from_str(&stored).unwrap_or_else(|_| json!({}));
+    record["acked"] = json!(true);
+    redis::cmd("SET").arg(alert_key(key)).arg(record.to_string()).arg("KEEPTTL").query::<()>(conn)?;
+    publish::send(conn, key, &format!("ack: {}", record))?;
+    Ok(true)
+}
+
+// Function to start republishing unacknowledged alerts as `escalation` events once each window
+// (seconds since the alert was raised, ascending) has passed
+pub fn start_escalation(router: &Arc<Router>, mut windows: Vec<u64>) {
+    windows.sort_unstable();
+    let windows: Vec<Duration> = windows.into_iter().map(Duration::from_secs).collect();
+    let router = Arc::clone(router);
+    thread::spawn(move || loop {
+        thread::sleep(ESCALATION_CHECK_INTERVAL);
+        let now = Instant::now();
+        let mut due = Vec::new();
+        if let Some(pending) = PENDING.lock().unwrap().as_mut() {
+            pending.retain(|_, alert| alert.expires > now); // Expired alerts no longer escalate
+            for (key, alert) in pending.iter_mut() {
+                let passed = windows.iter().take_while(|window| alert.raised + **window <= now).count();
+                if passed > alert.escalations {
+                    alert.escalations = passed;
+                    due.push((key.clone(), json!({
+                        "level": passed,
+                        "unacked_secs": now.duration_since(alert.raised).as_secs(),
+                        "alert": alert.record
+                    })));
+                }
+            }
+        }
+        for (key, escalation) in due {
+            let result = router.backend_for(&alert_key(&key)).connection() // Where the alert and its events live
+                .and_then(|mut conn| publish::send(&mut *conn, &key, &format!("escalation: {}", escalation)));
+            if let Err(err) = result {
+                eprintln!("Failed to publish escalation of the alert on {}: {}", key, err);
+            }
+        }
+    });
+}
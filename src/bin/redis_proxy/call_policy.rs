@@ -0,0 +1,202 @@
+// Import necessary crates and modules
+use super::metrics::{self, METRICS}; // For counting retries and hedged reads
+use super::router::{Backend, PooledConnection}; // For borrowing connections to retry and hedge on
+use super::READ_ACTIONS; // For the actions safe to send twice
+use rustredis::storage::Storage; // For the connections reads run on
+use std::sync::{mpsc, Arc, OnceLock}; // For the configured policies and the replies of hedged reads
+use std::thread; // For backoff waits and the reads racing a hedge
+use std::time::{Duration, Instant}; // For timeouts, backoff and deadlines
+
+// Define the actions whose Redis calls a policy may cover
+const POLICY_ACTIONS: [&str; 11] = ["get", "smembers", "hgetall", "set", "del", "soft-delete", "restore", "sadd", "srem", "heartbeat", "xadd"];
+
+// Define how long the first retry waits unless backoff_ms says otherwise
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(50);
+
+// Define the most retries a policy may ask for
+const MAX_RETRIES: u32 = 10;
+
+// Define the Redis call policy of one action
+#[derive(Clone, Debug)]
+pub struct CallPolicy {
+    pub action: String, // Action the policy applies to, or * for every action without its own
+    pub timeout: Option<Duration>, // Longest each Redis call of the action may block
+    pub retries: u32, // Further attempts after failed connects, and for reads after timeouts and dropped connections
+    pub backoff: Duration, // Wait before the first retry, doubled for each further one
+    pub hedge: Option<Duration>, // Delay after which a read without a reply is also sent to another node
+}
+
+impl CallPolicy {
+    // Function to parse a policy specification of the form ACTION[,timeout_ms=N][,retries=N][,backoff_ms=N][,hedge_ms=N]
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut parts = spec.split(',');
+        let action = parts.next().filter(|a| !a.is_empty()).ok_or("missing action")?;
+        if action != "*" && !POLICY_ACTIONS.contains(&action) {
+            return Err(format!("unknown action '{}', expected * or one of {}", action, POLICY_ACTIONS.join(", ")));
+        }
+        let mut policy = CallPolicy { action: action.to_string(), timeout: None, retries: 0, backoff: DEFAULT_BACKOFF, hedge: None };
+
+        for option in parts {
+            let millis = |value: &str| value.parse::<u64>().map(Duration::from_millis).map_err(|_| format!("invalid milliseconds '{}'", value));
+            match option.split_once('=') {
+                Some(("timeout_ms", ms)) => policy.timeout = Some(millis(ms)?).filter(|timeout| !timeout.is_zero()),
+                Some(("retries", retries)) => policy.retries = retries.parse().map_err(|_| format!("invalid retry count '{}'", retries))?,
+                Some(("backoff_ms", ms)) => policy.backoff = millis(ms)?,
+                Some(("hedge_ms", ms)) => policy.hedge = Some(millis(ms)?),
+                _ => return Err(format!("unknown policy option '{}'", option)),
+            }
+        }
+        if policy.retries > MAX_RETRIES {
+            return Err(format!("at most {} retries are allowed", MAX_RETRIES));
+        }
+        if policy.hedge.is_some() && action != "*" && !READ_ACTIONS.contains(&action) {
+            return Err(format!("only reads can be hedged, not {}", action));
+        }
+        if policy.hedge.is_some() && policy.timeout.is_none() {
+            return Err("hedge_ms needs timeout_ms, which bounds the read that loses".to_string());
+        }
+        Ok(policy)
+    }
+}
+
+// Define the configured policies, set at startup
+static POLICIES: OnceLock<Vec<CallPolicy>> = OnceLock::new();
+
+// Function to install the configured policies
+pub fn start(policies: Vec<CallPolicy>) {
+    let _ = POLICIES.set(policies);
+}
+
+// Function to find the policy of an action: its own, else the * policy
+pub fn for_action(action: &str) -> Option<&'static CallPolicy> {
+    let policies = POLICIES.get()?;
+    policies.iter().find(|policy| policy.action == action).or_else(|| policies.iter().find(|policy| policy.action == "*"))
+}
+
+// Function to tell failures another attempt may get past: refused, dropped or timed out connections
+fn is_transient(err: &redis::RedisError) -> bool {
+    err.is_io_error() || err.is_timeout() || err.is_connection_dropped() || err.is_connection_refusal()
+}
+
+// Function to wait before another attempt if the policy allows one and the deadline leaves time for it
+fn retry(policy: Option<&CallPolicy>, attempt: &mut u32, err: &redis::RedisError, deadline: Option<Instant>) -> bool {
+    let Some(policy) = policy.filter(|policy| *attempt < policy.retries && is_transient(err)) else {
+        return false;
+    };
+    let wait = policy.backoff.saturating_mul(1 << *attempt);
+    if deadline.is_some_and(|deadline| Instant::now() + wait >= deadline) {
+        return false;
+    }
+    *attempt += 1;
+    metrics::incr(&METRICS.redis_retries);
+    thread::sleep(wait);
+    true
+}
+
+// Function to borrow a connection for an action (from a replica for reads), retrying failed connects as its policy says
+pub fn connect<'a>(backend: &'a Backend, reading: bool, policy: Option<&CallPolicy>, deadline: Option<Instant>) -> redis::RedisResult<PooledConnection<'a>> {
+    let mut attempt = 0;
+    loop {
+        match if reading { backend.read_connection() } else { backend.connection() } {
+            Err(ref err) if retry(policy, &mut attempt, err, deadline) => continue,
+            result => return result,
+        }
+    }
+}
+
+// Function to bound each Redis call on a connection by the tighter of the policy's timeout and
+// what is left of the request's deadline
+pub fn bound(conn: &mut PooledConnection, policy: Option<&CallPolicy>, deadline: Option<Instant>) -> redis::RedisResult<()> {
+    let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()).max(Duration::from_millis(1)));
+    match remaining.into_iter().chain(policy.and_then(|policy| policy.timeout)).min() {
+        Some(timeout) => conn.set_deadline(timeout),
+        None => Ok(()),
+    }
+}
+
+// Function to run a read as its policy says: hedged on another node of the backend once it has
+// waited hedge_ms, and retried on a new connection after timeouts and dropped connections; the
+// call owns what it reads, since a hedged read runs it on other threads
+pub fn read<'a, T, F>(backend: &'a Backend, conn: &mut PooledConnection<'a>, policy: Option<&CallPolicy>, deadline: Option<Instant>, call: F) -> redis::RedisResult<T>
+where
+    T: Send + 'static,
+    F: Fn(&mut Storage) -> redis::RedisResult<T> + Send + Sync + 'static,
+{
+    let call = Arc::new(call);
+    let mut attempt = 0;
+    loop {
+        let result = match policy.and_then(|policy| policy.hedge) {
+            Some(delay) => hedged(backend, conn, policy, deadline, delay, &call),
+            None => (*call)(conn),
+        };
+        match result {
+            Err(ref err) if retry(policy, &mut attempt, err, deadline) => {
+                conn.discard(); // A late reply would be read as the answer to the next call
+                *conn = backend.read_connection()?;
+                bound(conn, policy, deadline)?;
+            }
+            result => return result,
+        }
+    }
+}
+
+// Define the reply of one of the reads racing a hedge: whether it was the hedge, its connection and result
+type Reply<T> = (bool, Storage, redis::RedisResult<T>);
+
+// Function to run a read on another thread, sending back its reply with the connection
+fn spawn_read<T, F>(hedge: bool, mut connection: Storage, call: Arc<F>, replies: mpsc::Sender<Reply<T>>)
+where
+    T: Send + 'static,
+    F: Fn(&mut Storage) -> redis::RedisResult<T> + Send + Sync + 'static,
+{
+    thread::spawn(move || {
+        let result = (*call)(&mut connection);
+        let _ = replies.send((hedge, connection, result)); // Nobody listens once the other read has won
+    });
+}
+
+// Function to run a read and, if it has no reply after `delay`, the same read on another node of
+// the backend; the first reply wins, and the connection of the other read is closed once it ends
+fn hedged<'a, T, F>(backend: &'a Backend, conn: &mut PooledConnection<'a>, policy: Option<&CallPolicy>, deadline: Option<Instant>, delay: Duration, call: &Arc<F>) -> redis::RedisResult<T>
+where
+    T: Send + 'static,
+    F: Fn(&mut Storage) -> redis::RedisResult<T> + Send + Sync + 'static,
+{
+    let (sender, replies) = mpsc::channel();
+    spawn_read(false, conn.take().expect("A borrowed connection is set"), Arc::clone(call), sender.clone());
+    let mut hedge = None; // Connection of the hedged read, once sent
+    let (from_hedge, connection, result) = match replies.recv_timeout(delay) {
+        Ok(reply) => reply,
+        Err(_) => {
+            hedge = backend.hedge_connection(conn).and_then(|mut other| bound(&mut other, policy, deadline).ok().map(|_| other));
+            match hedge {
+                Some(ref mut other) => {
+                    metrics::incr(&METRICS.hedged_reads);
+                    spawn_read(true, other.take().expect("A borrowed connection is set"), Arc::clone(call), sender);
+                }
+                None => drop(sender), // No other node to ask, wait for the read
+            }
+            replies.recv().expect("A hedged read panicked")
+        }
+    };
+    if from_hedge {
+        metrics::incr(&METRICS.hedges_won);
+        conn.discard(); // The slow read's connection closes once its reply comes
+        hedge.as_mut().expect("The hedge was sent").put_back(connection);
+    } else {
+        conn.put_back(connection);
+    }
+    result
+}
+
+// Function to list the configured policies, for stats
+pub fn summary() -> serde_json::Value {
+    let policies = POLICIES.get().map(Vec::as_slice).unwrap_or_default();
+    policies.iter().map(|policy| serde_json::json!({
+        "action": policy.action,
+        "timeout_ms": policy.timeout.map(|timeout| timeout.as_millis() as u64),
+        "retries": policy.retries,
+        "backoff_ms": policy.backoff.as_millis() as u64,
+        "hedge_ms": policy.hedge.map(|hedge| hedge.as_millis() as u64)
+    })).collect()
+}
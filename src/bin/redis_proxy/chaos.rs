@@ -0,0 +1,77 @@
+// Import necessary crates and modules
+use rustredis::rng::{entropy_seed, Rng}; // For drawing which requests a fault hits
+use serde::{Deserialize, Serialize}; // For faults as the chaos action sends and reports them
+use serde_json::{json, Value}; // For the fault report
+use std::collections::BTreeMap; // For faults by action
+use std::sync::Mutex; // For the faults changed through admin sockets
+use std::thread; // For injected latency
+use std::time::Duration; // For injected latency
+
+// Define the faults injected into the requests of one action
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct Fault {
+    latency_ms: u64, // Delay added before Redis is called
+    latency: f64, // Share of requests delayed by latency_ms
+    error: f64, // Share of requests answered with a Redis error instead of calling Redis
+    drop: f64, // Share of requests processed without sending their response
+}
+
+// Define the injected faults and the generator drawing which requests they hit
+struct Chaos {
+    faults: BTreeMap<String, Fault>, // Faults by action, or * for every action without its own
+    rng: Rng, // Seeded by the chaos action for reproducible runs
+}
+
+// Define the faults in effect, none until an admin socket sets some
+static CHAOS: Mutex<Option<Chaos>> = Mutex::new(None);
+
+// Function to replace the injected faults, given by action (or *) as {latency_ms, latency, error, drop};
+// an empty set turns injection off. Returns the faults in effect
+pub fn configure(faults: &serde_json::Map<String, Value>, seed: Option<u64>) -> Result<Value, String> {
+    let mut parsed = BTreeMap::new();
+    for (action, fault) in faults {
+        let fault: Fault = serde_json::from_value(fault.clone()).map_err(|e| format!("Invalid fault of {}: {}", action, e))?;
+        if [fault.latency, fault.error, fault.drop].iter().any(|p| !(0.0..=1.0).contains(p)) {
+            return Err(format!("Fault probabilities of {} must be between 0 and 1", action));
+        }
+        parsed.insert(action.clone(), fault);
+    }
+    let mut chaos = CHAOS.lock().unwrap();
+    *chaos = if parsed.is_empty() { None } else { Some(Chaos { faults: parsed, rng: Rng::new(seed.unwrap_or_else(entropy_seed)) }) };
+    match *chaos {
+        Some(ref chaos) => eprintln!("Warning: injecting faults into {}", chaos.faults.keys().cloned().collect::<Vec<_>>().join(", ")),
+        None => println!("Fault injection turned off"),
+    }
+    drop(chaos);
+    Ok(report())
+}
+
+// Function to report the faults in effect
+pub fn report() -> Value {
+    let chaos = CHAOS.lock().unwrap();
+    json!({"faults": chaos.as_ref().map(|chaos| &chaos.faults)})
+}
+
+// Function to draw whether a fault of the action hits the current request
+fn draw(action: &str, probability: impl Fn(&Fault) -> f64) -> Option<Fault> {
+    let mut chaos = CHAOS.lock().unwrap();
+    let chaos = chaos.as_mut()?;
+    let fault = chaos.faults.get(action).or_else(|| chaos.faults.get("*"))?.clone();
+    let p = probability(&fault);
+    (p > 0.0 && chaos.rng.next_f64() < p).then_some(fault)
+}
+
+// Function to inject the action's latency and Redis errors before Redis is called; returns the
+// error to answer with instead of calling Redis
+pub fn before_redis(action: &str) -> Option<String> {
+    if let Some(fault) = draw(action, |fault| fault.latency) {
+        thread::sleep(Duration::from_millis(fault.latency_ms));
+    }
+    draw(action, |fault| fault.error).map(|_| format!("Injected Redis error: {} failed (chaos)", action))
+}
+
+// Function to draw whether the response of a processed request is dropped
+pub fn drop_response(action: &str) -> bool {
+    action != "chaos" && draw(action, |fault| fault.drop).is_some() // Injection can always be turned off again
+}
@@ -0,0 +1,103 @@
+// Import necessary crates and modules
+use rustredis::storage::Storage; // For reading chunks back
+use serde_json::{json, Value}; // For manifests
+use std::sync::OnceLock; // For the global chunking settings
+
+// Define the header marking a manifest; a leading NUL never starts a JSON value
+const MANIFEST_MARKER: &[u8] = b"\0chunks";
+
+// Define the script storing a chunked value: drops the chunks of the previous value, then stores the new
+// chunks (ARGV[2..]) under KEYS[2] followed by their number and the manifest (ARGV[1]) under KEYS[1]
+const WRITE_SCRIPT: &str = r"
+local i = 0
+while redis.call('DEL', KEYS[2] .. i) == 1 do i = i + 1 end
+for n = 2, #ARGV do redis.call('SET', KEYS[2] .. (n - 2), ARGV[n]) end
+redis.call('SET', KEYS[1], ARGV[1])
+return #ARGV - 1
+";
+
+// Define the script dropping the chunks under KEYS[1] followed by their number
+const DELETE_SCRIPT: &str = r"
+local i = 0
+while redis.call('DEL', KEYS[1] .. i) == 1 do i = i + 1 end
+return i
+";
+
+// Define the script reading a manifest (KEYS[1]) and its chunks (KEYS[2] followed by their number) at once,
+// so a concurrent write can't mix chunks of two values
+const READ_SCRIPT: &str = r"
+local parts = {redis.call('GET', KEYS[1])}
+if not parts[1] then return {} end
+local i = 0
+while true do
+  local chunk = redis.call('GET', KEYS[2] .. i)
+  if not chunk then break end
+  parts[#parts + 1] = chunk
+  i = i + 1
+end
+return parts
+";
+
+// Define when values are chunked and how large the chunks are
+struct Chunking {
+    above: usize, // Values stored as more bytes than this are chunked
+    size: usize, // Bytes per chunk
+}
+
+// Define the chunking settings, unset if values are never chunked
+static CHUNKING: OnceLock<Chunking> = OnceLock::new();
+
+// Function to chunk values stored as more than `above` bytes into chunks of `size` bytes
+pub fn start(above: usize, size: usize) {
+    let _ = CHUNKING.set(Chunking { above, size: size.max(1) });
+}
+
+// Function to return the prefix of a key's chunk keys, followed by the chunk number
+fn chunk_prefix(key: &str) -> String {
+    format!("cs:_chunks:{}:", key)
+}
+
+// Function to check if a value stored as `size` bytes is to be chunked
+pub fn is_large(size: usize) -> bool {
+    CHUNKING.get().is_some_and(|chunking| size > chunking.above)
+}
+
+// Function to queue storing a large value as chunks, with a manifest under the key itself
+pub fn add_store(pipe: &mut redis::Pipeline, key: &str, stored: &[u8]) {
+    let size = CHUNKING.get().map_or(stored.len(), |chunking| chunking.size).max(1);
+    let mut manifest = MANIFEST_MARKER.to_vec();
+    manifest.extend(json!({"chunks": stored.len().div_ceil(size), "bytes": stored.len()}).to_string().into_bytes());
+    pipe.cmd("EVAL").arg(WRITE_SCRIPT).arg(2).arg(key).arg(chunk_prefix(key)).arg(manifest);
+    for chunk in stored.chunks(size) {
+        pipe.arg(chunk);
+    }
+    pipe.ignore();
+}
+
+// Function to queue dropping the chunks of a key's previous value, for writes and deletes replacing it
+pub fn remove(pipe: &mut redis::Pipeline, key: &str) {
+    if CHUNKING.get().is_some() {
+        pipe.cmd("EVAL").arg(DELETE_SCRIPT).arg(1).arg(chunk_prefix(key)).ignore();
+    }
+}
+
+// Function to reassemble a value read from a key if it is a manifest, passing other values through
+pub fn join(conn: &mut Storage, key: &str, stored: Option<Vec<u8>>) -> redis::RedisResult<Option<Vec<u8>>> {
+    if !stored.as_deref().is_some_and(|stored| stored.starts_with(MANIFEST_MARKER)) {
+        return Ok(stored);
+    }
+    let mut parts: Vec<Vec<u8>> = redis::cmd("EVAL").arg(READ_SCRIPT).arg(2).arg(key).arg(chunk_prefix(key)).query(conn)?;
+    if parts.is_empty() {
+        return Ok(None); // Deleted since the first read
+    }
+    let chunks = parts.split_off(1);
+    if !parts[0].starts_with(MANIFEST_MARKER) {
+        return Ok(parts.pop()); // Replaced by a small value since the first read
+    }
+    let manifest: Value = serde_json::from_slice(&parts[0][MANIFEST_MARKER.len()..]).unwrap_or_default();
+    let value = chunks.concat();
+    if manifest["chunks"].as_u64() != Some(chunks.len() as u64) || manifest["bytes"].as_u64() != Some(value.len() as u64) {
+        return Err(redis::RedisError::from((redis::ErrorKind::TypeError, "Incomplete chunked value", format!("{} has missing chunks", key))));
+    }
+    Ok(Some(value))
+}
@@ -0,0 +1,53 @@
+// Import necessary crates and modules
+#[cfg(feature = "zstd")]
+use rustredis::base64; // For carrying compressed payloads in JSON responses
+use serde_json::Value; // For response payloads
+use std::borrow::Cow; // For returning uncompressed values without copying them
+
+// Define the header marking a compressed value; a leading NUL never starts a JSON value
+const LZ4_MARKER: &[u8] = b"\0lz4";
+
+// Function to compress a value with LZ4 if it is larger than the threshold and compression pays off
+pub fn compress(value: &[u8], threshold: usize) -> Option<Vec<u8>> {
+    if value.len() <= threshold {
+        return None;
+    }
+    let mut compressed = LZ4_MARKER.to_vec();
+    compressed.extend(lz4_flex::compress_prepend_size(value));
+    (compressed.len() < value.len()).then_some(compressed)
+}
+
+// Function to undo `compress` on a stored value, passing values without the marker through unchanged
+pub fn decompress(stored: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    match stored.strip_prefix(LZ4_MARKER) {
+        Some(compressed) => lz4_flex::decompress_size_prepended(compressed)
+            .map(Cow::Owned)
+            .map_err(|e| format!("corrupt compressed value: {}", e)),
+        None => Ok(Cow::Borrowed(stored)),
+    }
+}
+
+// Define the zstd level of response payloads; low, as the proxy shares the device's CPU
+#[cfg(feature = "zstd")]
+const RESPONSE_LEVEL: i32 = 3;
+
+// Function to compress a response payload with zstd if its JSON is larger than the threshold and
+// compression pays off after base64; returns the encoded payload and the bytes it saves
+#[cfg(feature = "zstd")]
+pub fn pack_payload(data: &Value, threshold: usize) -> Option<(String, usize)> {
+    let json = serde_json::to_vec(data).ok()?;
+    if json.len() <= threshold {
+        return None;
+    }
+    let packed = base64::encode(&zstd::bulk::compress(&json, RESPONSE_LEVEL).ok()?);
+    (packed.len() < json.len()).then(|| {
+        let saved = json.len() - packed.len();
+        (packed, saved)
+    })
+}
+
+// Function to leave payloads uncompressed in proxies built without zstd (which never offer it)
+#[cfg(not(feature = "zstd"))]
+pub fn pack_payload(_data: &Value, _threshold: usize) -> Option<(String, usize)> {
+    None
+}
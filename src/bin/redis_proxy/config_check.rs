@@ -0,0 +1,163 @@
+// Import necessary crates and modules
+use super::router::backend_configs; // For checking backends, routes and replicas without connecting
+use super::{device, encryption, Args}; // For the parsed command line
+use rustredis::check::ConfigCheck; // For collecting and printing diagnostics
+use rustredis::hooks::Action as HookAction; // For checking the URLs of webhook hooks
+use rustredis::schema::{add_constraints, is_valid_key, read_schema_dir, VALID_PRODUCERS}; // For schemas and producer names
+use rustredis::storage::MEMORY_URL; // For backends needing no Redis
+
+// Function to check the URL of a backend or replica, which may name the in-memory store instead of Redis
+fn check_storage(check: &mut ConfigCheck, subject: &str, url: &str) {
+    if url == MEMORY_URL {
+        check.warn(subject, "in-memory store, its keys are lost when the proxy stops");
+    } else {
+        check.redis(subject, url);
+    }
+}
+
+// Function to record an error for a producer name the key grammar does not know
+fn check_producer(check: &mut ConfigCheck, subject: &str, producer: &str) {
+    if VALID_PRODUCERS.contains(&producer) {
+        check.ok(subject, format!("producer {}", producer));
+    } else {
+        check.error(subject, format!("unknown producer {}, expected one of {}", producer, VALID_PRODUCERS.join(", ")));
+    }
+}
+
+// Function to check everything the command line configures and exit with the diagnostics; Redis
+// backends, upstream proxies and HTTP sinks are only connected to with `connectivity`
+pub fn run(args: &Args, connectivity: bool) -> ! {
+    let mut check = ConfigCheck::new("redis_proxy", connectivity);
+
+    let mut schema_files = Default::default();
+    if let Some(ref dir) = args.schema_dir {
+        if let Some((active, shadow)) = check.check("--schema-dir", read_schema_dir(dir)) {
+            for base in active.keys().chain(shadow.keys()) {
+                if !is_valid_key(base) {
+                    check.warn("--schema-dir", format!("schema of {} applies to no valid key", base));
+                }
+            }
+            schema_files = active;
+        }
+    }
+    if !args.constraints.is_empty() {
+        check.check("--constraint", add_constraints(&mut schema_files, &args.constraints));
+    }
+    if args.device_file.is_some() || !args.device_fields.is_empty() {
+        check.check("--device-file", device::start(args.device_file.as_deref(), &args.device_fields, args.stamp_device));
+    } else if args.stamp_device {
+        check.error("--stamp-device", "needs --device-file or --device");
+    }
+    if let Some(ref key_file) = args.encryption_key_file {
+        check.check("--encryption-key-file", encryption::start(args.sensitive.clone(), key_file));
+    }
+    for pattern in &args.sensitive {
+        check.key_pattern("--sensitive", pattern);
+    }
+
+    // Listeners and their access restrictions
+    for listener in &args.listeners {
+        let subject = format!("--listen {}", listener.path);
+        if let Some(address) = listener.tcp_address() {
+            if listener.producers.is_none() && (listener.admin || listener.decrypt) {
+                check.warn(&subject, "TCP clients from any host may run admin actions or read sensitive values");
+            }
+            check.tcp(&subject, address);
+        } else if listener.pipe_name().is_none() {
+            match std::path::Path::new(&listener.path).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                Some(dir) if !dir.is_dir() => check.error(&subject, format!("directory {} does not exist", dir.display())),
+                _ => check.ok(&subject, "valid socket path"),
+            }
+        }
+        for producer in listener.producers.iter().flatten() {
+            check_producer(&mut check, &format!("{} producers", subject), producer);
+        }
+    }
+    for limit in &args.priority_limits {
+        check_producer(&mut check, "--priority-limit", &limit.producer);
+    }
+    for quota in &args.quotas {
+        check_producer(&mut check, "--quota", &quota.producer);
+    }
+
+    // Backends, routes and replicas
+    if let Some(backends) = check.check("--backend", backend_configs(&args.backends, &args.routes, &args.replicas)) {
+        for backend in &backends {
+            check_storage(&mut check, &format!("--backend {}", backend.name), &backend.url);
+        }
+    }
+    for replica in &args.replicas {
+        check_storage(&mut check, &format!("--replica {}", replica.backend), &replica.url);
+    }
+    for (i, policy) in args.redis_policies.iter().enumerate() {
+        let subject = format!("--redis-policy {}", policy.action);
+        if args.redis_policies[..i].iter().any(|earlier| earlier.action == policy.action) {
+            check.warn(&subject, "an earlier policy of the action applies instead");
+        } else if policy.hedge.is_some() && args.replicas.is_empty() {
+            check.warn(&subject, "hedged reads need a --replica to go to, reads are only retried");
+        } else {
+            check.ok(&subject, "valid policy");
+        }
+    }
+    for route in &args.routes {
+        check.key_pattern(&format!("--route {}", route.backend), &route.pattern);
+    }
+    for upstream in &args.upstreams {
+        check.key_pattern("--upstream", &upstream.pattern);
+        check.proxy("--upstream", &upstream.address.transport_address());
+    }
+    for webhook in &args.webhooks {
+        check.key_pattern("--webhook", &webhook.pattern);
+        check.http("--webhook", &webhook.url);
+    }
+    if let Some(ref endpoint) = args.otlp_endpoint {
+        check.http("--otlp-endpoint", endpoint);
+    }
+
+    // Key patterns and templates of the storage features
+    for policy in &args.publish_policies {
+        check.key_pattern("--publish", &policy.pattern);
+    }
+    for partition in &args.partitions {
+        check.key_pattern("--partition", &partition.pattern);
+    }
+    for rollup in &args.rollups {
+        check.key_pattern("--rollup", &rollup.pattern);
+    }
+    for index in &args.indexes {
+        check.key_pattern("--index", &index.pattern);
+        check.template("--index", &index.key, &[]);
+    }
+    for rule in &args.fanouts {
+        check.key_pattern("--fanout", &rule.pattern);
+        for template in rule.templates() {
+            check.template("--fanout", template, &[]);
+        }
+    }
+    for hook in &args.hooks {
+        check.key_pattern("--hook", &hook.pattern);
+        for template in hook.templates() {
+            check.template("--hook", template, &[]);
+        }
+        if let HookAction::Webhook { ref url } = hook.action {
+            check.http("--hook", url);
+        }
+    }
+    for search in &args.searches {
+        check.key("--search", &search.base);
+    }
+    for pattern in &args.json_storage {
+        check.key_pattern("--json-storage", pattern);
+    }
+    if let Some(ref path) = args.access_log {
+        match path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            Some(dir) if !dir.is_dir() => check.error("--access-log", format!("directory {} does not exist", dir.display())),
+            _ if args.access_log_sample == 0.0 && !args.access_log_failures => check.warn("--access-log", "samples no requests and not --access-log-failures, nothing is logged"),
+            _ => check.ok("--access-log", "valid path"),
+        }
+    }
+    if args.swap_error_margin < 0.0 {
+        check.error("--swap-error-margin", "every swap would be rolled back");
+    }
+    check.exit()
+}
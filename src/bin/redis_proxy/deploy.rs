@@ -0,0 +1,142 @@
+// Import necessary crates and modules
+use super::metrics::METRICS; // For the write and validation failure counts watched after a swap
+use super::router::Router; // For sampling stored values and announcing swaps
+use super::{encryption, publish_proxy_event, transfer}; // For skipping sensitive keys, proxy events and reading stored values
+use redis::Commands; // For scanning keys
+use rustredis::schema::{base_key, redact_message, rollback_schemas, schema_status, stage_schemas, swap_schemas, validate_staged_schema}; // For the schema slots
+use serde_json::{json, Value}; // For reports and events
+use std::collections::HashMap; // For schema sets by base key
+use std::sync::{Arc, OnceLock}; // For the watch settings shared with watcher threads
+use std::thread; // For watching a swap
+use std::time::Duration; // For the watch window
+
+// Define the writes whose schema validation failures make up the error rate watched after a swap
+const WRITE_ACTIONS: [&str; 4] = ["set", "sadd", "xadd", "patch"];
+
+// Define the fewest writes a watch window needs before a swap may be rolled back
+const MIN_WATCHED_WRITES: u64 = 20;
+
+// Define the most failing stored values listed when a set is staged
+const MAX_LISTED_FAILURES: usize = 10;
+
+// Define how swaps are watched
+struct Watch {
+    router: Arc<Router>, // For announcing rollbacks
+    window: Duration, // Time after a swap over which the error rate is measured
+    margin: f64, // Rise of the error rate over the one before the swap that rolls it back
+}
+
+// Define the watch settings, set at startup
+static WATCH: OnceLock<Watch> = OnceLock::new();
+
+// Function to watch every swap for `window`, rolling it back if the share of writes failing schema
+// validation rises by more than `margin`; a zero window disables automatic rollbacks
+pub fn start(router: &Arc<Router>, window: Duration, margin: f64) {
+    let _ = WATCH.set(Watch { router: Arc::clone(router), window, margin });
+}
+
+// Function to read the writes and schema validation failures counted so far
+fn counts() -> (u64, u64) {
+    let writes = METRICS.requests.lock().unwrap().iter().filter(|(action, _)| WRITE_ACTIONS.contains(&action.as_str())).map(|(_, count)| count).sum();
+    let failures = METRICS.validation_failures.lock().unwrap().get("schema").copied().unwrap_or(0);
+    (writes, failures)
+}
+
+// Function to return the share of writes that failed validation between two counts
+fn error_rate((writes, failures): (u64, u64)) -> f64 {
+    if writes == 0 { 0.0 } else { failures as f64 / writes as f64 }
+}
+
+// Function to publish a schema deployment event on the proxy events channel
+fn announce(router: &Router, event: Value) {
+    if let Ok(mut conn) = router.default_backend().connection() {
+        publish_proxy_event(&mut conn, event);
+    }
+}
+
+// Function to list the JSON values stored under a key as the schema would see them (values, set
+// members and stream entries); binary values and hashes are not validated
+fn stored_values(record: &Value) -> Vec<&Value> {
+    match record["type"].as_str() {
+        Some("string") => record.get("value").into_iter().collect(),
+        Some("set") => record["members"].as_array().map(|members| members.iter().collect()).unwrap_or_default(),
+        Some("stream") => record["entries"].as_array().map(|entries| entries.iter().map(|entry| &entry["value"]).collect()).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+// Function to stage a schema set and check up to `sample` stored keys per object type and backend
+// against it; returns the staged set's report
+pub fn stage(router: &Router, active: HashMap<String, Value>, shadow: HashMap<String, Value>, sample: usize) -> Result<Value, String> {
+    let mut bases: Vec<String> = active.keys().cloned().collect();
+    bases.sort();
+    let staged = stage_schemas(active, shadow)?;
+
+    let (mut checked, mut failing, mut failures) = (0, 0, Vec::new());
+    for base in &bases {
+        for backend in router.backends() {
+            let mut conn = backend.connection().map_err(|e| format!("Redis backend {} unavailable: {}", backend.name, e))?;
+            let keys: Vec<String> = conn.scan_match::<_, String>(format!("{}*", base)).map_err(|e| e.to_string())?
+                .filter(|key| base_key(key) == *base && !encryption::is_sensitive(key) && router.backend_for(key).name == backend.name)
+                .take(sample)
+                .collect();
+            for key in keys {
+                let Some(record) = transfer::read_record(&mut conn, &key).map_err(|e| format!("Failed to read {}: {}", key, e))? else { continue };
+                checked += 1;
+                let error = stored_values(&record).into_iter().find_map(|value| validate_staged_schema(&key, value).err().map(|err| redact_message(&key, value, &err)));
+                if let Some(error) = error {
+                    failing += 1;
+                    if failures.len() < MAX_LISTED_FAILURES {
+                        failures.push(json!({"key": key, "error": error}));
+                    }
+                }
+            }
+        }
+    }
+    Ok(json!({"staged": staged, "checked": checked, "failing": failing, "failures": failures}))
+}
+
+// Function to swap the staged schema set in and, unless disabled, watch it for a rollback
+pub fn swap(router: &Router) -> Result<Value, String> {
+    let before = counts();
+    let generation = swap_schemas()?;
+    announce(router, json!({"event": "schema-swap", "generation": generation}));
+    if let Some(watch) = WATCH.get().filter(|watch| !watch.window.is_zero()) {
+        thread::spawn(move || {
+            thread::sleep(watch.window);
+            watch_swap(watch, generation, before);
+        });
+    }
+    Ok(json!({"generation": generation, "watch_secs": WATCH.get().map_or(0, |watch| watch.window.as_secs())}))
+}
+
+// Function to roll a swap back if writes failed validation noticeably more often after it than before,
+// unless the schemas were swapped or rolled back again meanwhile
+fn watch_swap(watch: &Watch, generation: u64, before: (u64, u64)) {
+    let after = counts();
+    let watched = (after.0 - before.0, after.1 - before.1);
+    let (baseline, rate) = (error_rate(before), error_rate(watched));
+    if watched.0 < MIN_WATCHED_WRITES || rate <= baseline + watch.margin || schema_status().generation != generation {
+        return;
+    }
+    match rollback_schemas() {
+        Ok(rolled_back) => {
+            eprintln!("Rolled back schema generation {}: {:.1}% of writes failed validation, {:.1}% before", generation, rate * 100.0, baseline * 100.0);
+            announce(&watch.router, json!({"event": "schema-rollback", "generation": rolled_back, "swapped": generation, "error_rate": rate, "baseline": baseline}));
+        }
+        Err(err) => eprintln!("Failed to roll back schema generation {}: {}", generation, err),
+    }
+}
+
+// Function to bring back the schema set the last swap replaced
+pub fn rollback(router: &Router) -> Result<Value, String> {
+    let generation = rollback_schemas()?;
+    announce(router, json!({"event": "schema-rollback", "generation": generation}));
+    Ok(json!({"generation": generation}))
+}
+
+// Function to report the schema sets in use, for stats
+pub fn status() -> Value {
+    let status = schema_status();
+    json!({"generation": status.generation, "active": status.active, "staged": status.staged, "rollback": status.rollback})
+}
@@ -0,0 +1,58 @@
+// Import necessary crates and modules
+use serde_json::{Map, Value}; // For the identity object and the values it is stamped into
+use std::path::Path; // For locating the identity file
+use std::sync::OnceLock; // For the identity loaded at startup
+
+// Define the identity fields expected of every device
+const REQUIRED_FIELDS: [&str; 3] = ["serial", "model", "firmware"];
+
+// Define the identity of the device the proxy runs on, and whether it is stamped into values
+struct Device {
+    identity: Value, // {"serial", "model", "firmware", ...}
+    stamp: bool, // Inject it as `_device` into stored objects and published events
+}
+
+static DEVICE: OnceLock<Device> = OnceLock::new();
+
+// Function to parse an identity field given on the command line as FIELD=VALUE
+pub fn parse_field(spec: &str) -> Result<(String, String), String> {
+    let (field, value) = spec.split_once('=').ok_or("expected FIELD=VALUE")?;
+    if field.is_empty() {
+        return Err("missing field name".to_string());
+    }
+    Ok((field.to_string(), value.to_string()))
+}
+
+// Function to load the device identity from a JSON object file and/or FIELD=VALUE pairs (which win)
+pub fn start(file: Option<&Path>, fields: &[(String, String)], stamp: bool) -> Result<(), String> {
+    let mut identity = match file {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+            match serde_json::from_str(&text).map_err(|e| format!("invalid JSON in {}: {}", path.display(), e))? {
+                Value::Object(map) => map,
+                _ => return Err(format!("{} must hold a JSON object", path.display())),
+            }
+        }
+        None => Map::new(),
+    };
+    for (field, value) in fields {
+        identity.insert(field.clone(), Value::String(value.clone()));
+    }
+    if let Some(missing) = REQUIRED_FIELDS.iter().find(|field| !identity.contains_key(**field)) {
+        return Err(format!("device identity has no {} field", missing));
+    }
+    let _ = DEVICE.set(Device { identity: Value::Object(identity), stamp });
+    Ok(())
+}
+
+// Function to return the device identity, if one is configured
+pub fn identity() -> Option<&'static Value> {
+    DEVICE.get().map(|device| &device.identity)
+}
+
+// Function to stamp the device identity into a value if stamping is enabled (only JSON objects are stamped)
+pub fn stamp(value: &mut Value) {
+    if let (Some(device), Value::Object(map)) = (DEVICE.get().filter(|device| device.stamp), value) {
+        map.insert("_device".to_string(), device.identity.clone());
+    }
+}
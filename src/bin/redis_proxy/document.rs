@@ -0,0 +1,85 @@
+// Import necessary crates and modules
+use super::router::Router; // For checking the module on every backend
+use super::search; // For the object types stored as documents to be searchable
+use rustredis::glob::glob_match; // For matching keys against document patterns
+use rustredis::storage::Storage; // For the connection documents are read and patched through
+use serde_json::Value; // For paths and fragments
+use std::sync::OnceLock; // For the patterns enabled at startup
+
+// Define the key patterns stored as RedisJSON documents, empty when the module is missing
+static PATTERNS: OnceLock<Vec<String>> = OnceLock::new();
+
+// Function to check if a backend has all the given modules loaded (names as MODULE LIST reports them, lowercase)
+pub fn has_modules(conn: &mut Storage, wanted: &[&str]) -> redis::RedisResult<bool> {
+    let modules: Vec<Vec<redis::Value>> = redis::cmd("MODULE").arg("LIST").query(conn)?;
+    let names: Vec<String> = modules.iter()
+        .filter_map(|module| module.get(1))
+        .filter_map(|name| redis::from_redis_value::<String>(name).ok())
+        .map(|name| name.to_lowercase())
+        .collect();
+    Ok(wanted.iter().all(|module| names.iter().any(|name| name == module)))
+}
+
+// Function to store the keys matching the patterns as documents if every backend has RedisJSON;
+// otherwise they stay plain strings and patch and path reads are unavailable
+pub fn start(patterns: Vec<String>, router: &Router) -> redis::RedisResult<()> {
+    if patterns.is_empty() {
+        return Ok(());
+    }
+    for backend in router.backends() {
+        if !has_modules(&mut *backend.connection()?, &["rejson"])? {
+            eprintln!("Backend {} lacks the RedisJSON module, values stay strings", backend.name);
+            let _ = PATTERNS.set(Vec::new());
+            return Ok(());
+        }
+    }
+    let _ = PATTERNS.set(patterns);
+    Ok(())
+}
+
+// Function to check if the values of a key are stored as RedisJSON documents
+pub fn is_document(key: &str) -> bool {
+    PATTERNS.get().into_iter().flatten().any(|pattern| glob_match(pattern, key)) || search::is_indexed(key)
+}
+
+// Function to queue storing a JSON document under a key
+pub fn add_store(pipe: &mut redis::Pipeline, key: &str, document: &[u8]) {
+    pipe.cmd("JSON.SET").arg(key).arg("$").arg(document).ignore();
+}
+
+// Function to read the value of a key as JSON text, whether stored as a document or (written
+// before document storage was enabled) as a string
+pub fn read(conn: &mut Storage, key: &str) -> redis::RedisResult<Option<Vec<u8>>> {
+    match redis::cmd("JSON.GET").arg(key).query(conn) {
+        Err(err) if err.to_string().contains("WRONGTYPE") => redis::cmd("GET").arg(key).query(conn),
+        result => result,
+    }
+}
+
+// Function to split a path of the form $.field.subfield into its fields; only plain member
+// access is allowed, so a path always names one place schemas can describe
+pub fn parse_path(path: &str) -> Result<Vec<&str>, String> {
+    let fields: Vec<&str> = path.strip_prefix("$.").ok_or_else(|| format!("invalid path '{}', expected $.FIELD[.FIELD...]", path))?.split('.').collect();
+    let plain = |field: &&str| !field.is_empty() && field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !fields.iter().all(plain) {
+        return Err(format!("invalid path '{}', fields may only hold letters, digits, _ and -", path));
+    }
+    Ok(fields)
+}
+
+// Function to read the value at a path of a document, None if the key or the path is missing
+pub fn read_path(conn: &mut Storage, key: &str, path: &str) -> redis::RedisResult<Option<Value>> {
+    let text: Option<String> = redis::cmd("JSON.GET").arg(key).arg(path).query(conn)?;
+    // $ paths answer with the array of their matches, of which a plain path has at most one
+    Ok(text.and_then(|text| serde_json::from_str::<Vec<Value>>(&text).ok()).and_then(|matches| matches.into_iter().next()))
+}
+
+// Function to set the value at a path of a document in one transaction, returning the whole document after
+// the change; fails if the document does not exist, or the path's parent does not
+pub fn patch(conn: &mut Storage, key: &str, path: &str, value: &Value) -> redis::RedisResult<Option<Vec<u8>>> {
+    let (set, document): (Option<String>, Option<Vec<u8>>) = redis::pipe().atomic()
+        .cmd("JSON.SET").arg(key).arg(path).arg(value.to_string())
+        .cmd("JSON.GET").arg(key)
+        .query(conn)?;
+    Ok(set.and(document)) // JSON.SET answers nil when the path's parent is missing
+}
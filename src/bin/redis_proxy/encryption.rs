@@ -0,0 +1,41 @@
+// Import necessary crates and modules
+use rustredis::encryption::{is_encrypted, ValueCipher}; // For sealing values bound to their key
+use rustredis::glob::glob_match; // For matching sensitive key patterns
+use std::borrow::Cow; // For returning plaintext values without copying them
+use std::path::Path; // For locating the key file
+use std::sync::OnceLock; // For the encryption settings loaded at startup
+
+// Define which keys are encrypted and with what
+struct Sensitive {
+    patterns: Vec<String>, // Glob patterns of the keys whose values are encrypted
+    cipher: ValueCipher,
+}
+
+// Define the encryption settings, if any sensitive keys are configured
+static SENSITIVE: OnceLock<Sensitive> = OnceLock::new();
+
+// Function to enable encryption of the keys matching the patterns with the key from a file
+pub fn start(patterns: Vec<String>, key_file: &Path) -> Result<(), String> {
+    let cipher = ValueCipher::from_key_file(key_file)?;
+    let _ = SENSITIVE.set(Sensitive { patterns, cipher });
+    Ok(())
+}
+
+// Function to check if a key's values are encrypted
+pub fn is_sensitive(key: &str) -> bool {
+    SENSITIVE.get().is_some_and(|sensitive| sensitive.patterns.iter().any(|pattern| glob_match(pattern, key)))
+}
+
+// Function to encrypt a value of a sensitive key, bound to that key
+pub fn encrypt(key: &str, value: &[u8]) -> Vec<u8> {
+    SENSITIVE.get().expect("Only sensitive keys are encrypted").cipher.encrypt(key, value)
+}
+
+// Function to undo `encrypt` on a stored value of a key, passing values without the marker (written before encryption) through
+pub fn decrypt<'a>(key: &str, stored: &'a [u8]) -> Result<Cow<'a, [u8]>, String> {
+    if !is_encrypted(stored) {
+        return Ok(Cow::Borrowed(stored));
+    }
+    let sensitive = SENSITIVE.get().ok_or("value is encrypted but no encryption key is configured")?;
+    sensitive.cipher.decrypt(key, stored)
+}
@@ -0,0 +1,110 @@
+// Import necessary crates and modules
+use super::publish; // For announcing on extra channels with the configured pub/sub flavour
+use rustredis::glob::glob_match; // For matching keys against fan-out patterns
+use rustredis::keys::render_template; // For the derived keys, fields and channels of a key
+use std::sync::OnceLock; // For the global rule list
+
+// Define where a fan-out rule copies a write to
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    Copy { key: String }, // Another key holding the same value
+    Hash { key: String, field: String }, // A field of a hash holding the value
+    Channel { channel: String }, // Another channel receiving the key's event
+}
+
+// Define the configuration of one fan-out rule
+#[derive(Clone, Debug)]
+pub struct FanoutConfig {
+    pub pattern: String, // Key glob pattern of the writes fanned out
+    pub target: Target, // Templates with {key}, {producer}, {object}, {id} and {function} placeholders
+}
+
+impl FanoutConfig {
+    // Function to parse a fan-out specification of the form PATTERN=copy,key=TEMPLATE,
+    // PATTERN=hash,key=TEMPLATE,field=TEMPLATE or PATTERN=channel,channel=TEMPLATE
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (pattern, rest) = spec.split_once('=').ok_or("expected PATTERN=copy|hash|channel,OPTIONS")?;
+        let mut parts = rest.split(',');
+        let kind = parts.next().unwrap_or_default();
+        let (mut key, mut field, mut channel) = (None, None, None);
+        for option in parts {
+            match option.split_once('=') {
+                Some(("key", template)) if !template.is_empty() => key = Some(template.to_string()),
+                Some(("field", template)) if !template.is_empty() => field = Some(template.to_string()),
+                Some(("channel", template)) if !template.is_empty() => channel = Some(template.to_string()),
+                _ => return Err(format!("unknown fan-out option '{}'", option)),
+            }
+        }
+        let target = match (kind, key, field, channel) {
+            ("copy", Some(key), None, None) => Target::Copy { key },
+            ("hash", Some(key), Some(field), None) => Target::Hash { key, field },
+            ("channel", None, None, Some(channel)) => Target::Channel { channel },
+            ("copy", ..) => return Err(format!("copy fan-out takes key= only, in '{}'", spec)),
+            ("hash", ..) => return Err(format!("hash fan-out takes key= and field=, in '{}'", spec)),
+            ("channel", ..) => return Err(format!("channel fan-out takes channel= only, in '{}'", spec)),
+            _ => return Err(format!("invalid fan-out kind in '{}', expected copy, hash or channel", spec)),
+        };
+        Ok(FanoutConfig { pattern: pattern.to_string(), target })
+    }
+
+    // Function to list the templates of the rule, for checking them
+    pub fn templates(&self) -> Vec<&str> {
+        match self.target {
+            Target::Copy { ref key } => vec![key.as_str()],
+            Target::Hash { ref key, ref field } => vec![key.as_str(), field.as_str()],
+            Target::Channel { ref channel } => vec![channel.as_str()],
+        }
+    }
+}
+
+// Define the rules configured at startup
+static RULES: OnceLock<Vec<FanoutConfig>> = OnceLock::new();
+
+// Function to set the fan-out rules
+pub fn start(configs: Vec<FanoutConfig>) {
+    let _ = RULES.set(configs);
+}
+
+// Function to return the rules a key's writes are fanned out by (every matching rule)
+fn rules_of(key: &str) -> impl Iterator<Item = &'static FanoutConfig> + '_ {
+    RULES.get().into_iter().flatten().filter(move |rule| glob_match(&rule.pattern, key))
+}
+
+// Function to queue the fan-out of a value stored under a key: its copies, hash fields, and its
+// event (if the publish policy announces one) on the extra channels
+pub fn add(pipe: &mut redis::Pipeline, key: &str, stored: &[u8], publication: &Option<(String, String)>) {
+    for rule in rules_of(key) {
+        match rule.target {
+            Target::Copy { key: ref template } => {
+                pipe.set(render_template(template, key), stored).ignore();
+            }
+            Target::Hash { key: ref template, ref field } => {
+                pipe.hset(render_template(template, key), render_template(field, key), stored).ignore();
+            }
+            Target::Channel { ref channel } => {
+                if let Some((_, payload)) = publication {
+                    publish::add(pipe, &render_template(channel, key), payload);
+                }
+            }
+        }
+    }
+}
+
+// Function to queue the removal of a deleted key's copies and hash fields, and its event on the extra channels
+pub fn remove(pipe: &mut redis::Pipeline, key: &str, publication: &Option<(String, String)>) {
+    for rule in rules_of(key) {
+        match rule.target {
+            Target::Copy { key: ref template } => {
+                pipe.del(render_template(template, key)).ignore();
+            }
+            Target::Hash { key: ref template, ref field } => {
+                pipe.hdel(render_template(template, key), render_template(field, key)).ignore();
+            }
+            Target::Channel { ref channel } => {
+                if let Some((_, payload)) = publication {
+                    publish::add(pipe, &render_template(channel, key), payload);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+// Import necessary crates and modules
+use super::listener::{BoundListener, ListenerConfig}; // For adopting inherited sockets as configured listeners
+use super::supervisor::Supervisor; // For waiting on the clients of a draining proxy
+use serde_json::{json, Value}; // For the answer of the upgrade action
+use std::env; // For the socket activation variables and the command line to re-execute
+use std::io::{ErrorKind, Read, Write}; // For the readiness message of the new process
+use std::net::{TcpListener, ToSocketAddrs}; // For inherited TCP listeners
+use std::os::fd::OwnedFd; // For telling inherited Unix sockets from TCP ones
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd}; // For passing listening sockets by descriptor
+use std::os::unix::net::{UnixDatagram, UnixListener, UnixStream}; // For inherited sockets, readiness and systemd notifications
+use std::os::unix::process::{parent_id, CommandExt}; // For recognising a handover from the old process and placing descriptors
+use std::path::{Path, PathBuf}; // For matching inherited sockets to socket paths
+use std::process::{self, Command}; // For starting the new process
+use std::sync::atomic::{AtomicBool, Ordering}; // For the upgrade and drain state
+use std::sync::Mutex; // For the descriptors of the sockets listened on
+use std::thread; // For waiting on draining clients
+use std::time::{Duration, Instant}; // For readiness and drain deadlines
+
+// Define the first descriptor passed sockets are placed at (the sd_listen_fds convention)
+const LISTEN_FDS_START: RawFd = 3;
+
+// Define the name of the descriptor the new process reports readiness on
+const READY_NAME: &str = "proxy-handover-ready";
+
+// Define how long the new process may take to start listening before the upgrade is abandoned
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Define how often accept loops and the drain look at the drain state
+const DRAIN_CHECK_MS: i32 = 500;
+
+// Define whether an upgrade is starting the new process, and whether it took over the sockets
+static UPGRADING: AtomicBool = AtomicBool::new(false);
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+// Define the descriptors of the sockets this process listens on, passed on by an upgrade
+static LISTENING: Mutex<Vec<RawFd>> = Mutex::new(Vec::new());
+
+// Define the sockets passed to this process, by systemd (socket units or its fd store) or by the
+// proxy it replaces
+pub struct Inherited {
+    sockets: Vec<BoundListener>, // Listening sockets not yet adopted by a listener
+    ready: Option<UnixStream>, // Where the replaced proxy waits to hear this one listens
+}
+
+// Function to mark a descriptor close-on-exec, so processes the proxy starts do not inherit it
+fn close_on_exec(fd: RawFd) {
+    unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+}
+
+// Function to take the sockets passed with LISTEN_PID, LISTEN_FDS and LISTEN_FDNAMES, clearing the
+// variables so they are not passed on further
+pub fn inherited() -> Inherited {
+    let mut inherited = Inherited { sockets: Vec::new(), ready: None };
+    let (Ok(pid), Ok(fds)) = (env::var("LISTEN_PID"), env::var("LISTEN_FDS")) else { return inherited };
+    let names = env::var("LISTEN_FDNAMES").unwrap_or_default();
+    for variable in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        env::remove_var(variable);
+    }
+    let Ok(count) = fds.parse::<RawFd>() else { return inherited };
+    let (pid, names) = (pid.parse::<u32>().ok(), names.split(':').collect::<Vec<_>>());
+    let from_systemd = pid == Some(process::id());
+    let from_old_proxy = pid == Some(parent_id()) && names.last() == Some(&READY_NAME);
+    if !from_systemd && !from_old_proxy {
+        return inherited; // Meant for another process
+    }
+    for (i, fd) in (LISTEN_FDS_START..LISTEN_FDS_START + count).enumerate() {
+        close_on_exec(fd);
+        if names.get(i) == Some(&READY_NAME) {
+            inherited.ready = Some(unsafe { UnixStream::from_raw_fd(fd) });
+            continue;
+        }
+        let socket = unsafe { UnixListener::from_raw_fd(fd) };
+        if socket.local_addr().is_ok() {
+            inherited.sockets.push(BoundListener::Unix(socket));
+        } else {
+            inherited.sockets.push(BoundListener::Tcp(TcpListener::from(OwnedFd::from(socket))));
+        }
+    }
+    inherited
+}
+
+// Function to check if an inherited socket is the one a listener would bind
+fn is_socket_of(socket: &BoundListener, listener: &ListenerConfig) -> bool {
+    match (socket, listener.tcp_address()) {
+        (BoundListener::Unix(socket), None) => socket.local_addr().is_ok_and(|local| local.as_pathname() == Some(Path::new(&listener.path))),
+        (BoundListener::Tcp(socket), Some(address)) => socket.local_addr().is_ok_and(|local| {
+            address.to_socket_addrs().is_ok_and(|mut addresses| addresses.any(|address| address == local))
+        }),
+        _ => false,
+    }
+}
+
+impl Inherited {
+    // Function to take the inherited socket a listener would bind, if one was passed; its socket file
+    // and permissions are left as they are
+    pub fn adopt(&mut self, listener: &ListenerConfig) -> Option<BoundListener> {
+        let position = self.sockets.iter().position(|socket| is_socket_of(socket, listener))?;
+        Some(self.sockets.swap_remove(position))
+    }
+
+    // Function to close the sockets no listener adopted and tell the replaced proxy, if any, that
+    // this one accepts clients now
+    pub fn ready(mut self) {
+        if !self.sockets.is_empty() {
+            eprintln!("Warning: closing {} inherited sockets that match no --listen", self.sockets.len());
+        }
+        if let Some(ref mut ready) = self.ready {
+            if let Err(err) = ready.write_all(b"ready\n") {
+                eprintln!("Failed to report readiness to the replaced proxy: {}", err);
+            }
+        }
+    }
+}
+
+// Function to remember a socket this process listens on, for passing it to a new process
+pub fn listening(socket: &BoundListener) {
+    let fd = match socket {
+        BoundListener::Unix(socket) => socket.as_raw_fd(),
+        BoundListener::Tcp(socket) => socket.as_raw_fd(),
+    };
+    LISTENING.lock().unwrap().push(fd);
+}
+
+// Function to check if the sockets were handed to a new process
+pub fn draining() -> bool {
+    DRAINING.load(Ordering::Relaxed)
+}
+
+// Function to wait for a client on a listening socket; false once the socket was handed over, so the
+// accept loop ends without taking clients meant for the new process
+fn wait_for_client(fd: RawFd) -> bool {
+    let mut poll_fd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+    loop {
+        if draining() {
+            return false;
+        }
+        match unsafe { libc::poll(&mut poll_fd, 1, DRAIN_CHECK_MS) } {
+            0 => continue,
+            -1 if std::io::Error::last_os_error().kind() == ErrorKind::Interrupted => continue,
+            _ => return true, // A client waits, or an error accept will report
+        }
+    }
+}
+
+// Function to accept clients on a listening socket until it is handed over
+pub fn until_draining<S>(fd: RawFd, mut accept: impl FnMut() -> std::io::Result<S>) -> impl Iterator<Item = std::io::Result<S>> {
+    std::iter::from_fn(move || wait_for_client(fd).then(&mut accept))
+}
+
+// Function to return the binary to re-execute; on Linux a binary replaced by a package upgrade is
+// reported with a " (deleted)" suffix, and the new one is at the original path
+fn executable() -> std::io::Result<PathBuf> {
+    let exe = env::current_exe()?;
+    match exe.to_str().and_then(|path| path.strip_suffix(" (deleted)")) {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(exe),
+    }
+}
+
+// Function to place the passed descriptors at 3, 4, ... in the new process, between fork and exec;
+// they are first moved above the target range so none is overwritten before it is placed
+fn place_fds(fds: &[RawFd], moved: &mut [RawFd]) -> std::io::Result<()> {
+    let above = LISTEN_FDS_START + fds.len() as RawFd;
+    for (fd, moved) in fds.iter().zip(moved.iter_mut()) {
+        *moved = unsafe { libc::fcntl(*fd, libc::F_DUPFD_CLOEXEC, above) };
+        if *moved == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    for (target, moved) in (LISTEN_FDS_START..).zip(moved.iter()) {
+        if unsafe { libc::dup2(*moved, target) } == -1 { // The copy is not close-on-exec
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+// Function to tell systemd the new process is the service's main process now, so the old one
+// exiting does not stop the service (needs NotifyAccess=main or all in the unit)
+fn notify_main_pid(pid: u32) {
+    let Some(path) = env::var_os("NOTIFY_SOCKET") else { return };
+    let message = format!("MAINPID={}", pid);
+    let result = UnixDatagram::unbound().and_then(|socket| {
+        #[cfg(target_os = "linux")]
+        if let Some(name) = path.to_str().and_then(|path| path.strip_prefix('@')) {
+            use std::os::linux::net::SocketAddrExt;
+            let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            return socket.send_to_addr(message.as_bytes(), &address).map(|_| ());
+        }
+        socket.send_to(message.as_bytes(), &path).map(|_| ())
+    });
+    if let Err(err) = result {
+        eprintln!("Failed to notify systemd of the new main process {}: {}", pid, err);
+    }
+}
+
+// Function to start the new proxy: the binary at the same path with the same arguments, inheriting
+// the listening sockets, and wait until it accepts clients
+fn start_new_process() -> Result<u32, String> {
+    let exe = executable().map_err(|e| format!("Failed to find the proxy binary: {}", e))?;
+    let (mut ready, new_ready) = UnixStream::pair().map_err(|e| format!("Failed to create the readiness socket: {}", e))?;
+    let mut fds = LISTENING.lock().unwrap().clone();
+    fds.push(new_ready.as_raw_fd());
+    let mut names = vec!["listener"; fds.len() - 1];
+    names.push(READY_NAME);
+
+    let mut args = env::args_os();
+    let mut command = Command::new(&exe);
+    if let Some(arg0) = args.next() {
+        command.arg0(arg0); // Keeps the tool of multicall binaries
+    }
+    command.args(args)
+        .env("LISTEN_PID", process::id().to_string())
+        .env("LISTEN_FDS", fds.len().to_string())
+        .env("LISTEN_FDNAMES", names.join(":"));
+    let mut moved = vec![-1; fds.len()]; // Allocated before the fork
+    unsafe { command.pre_exec(move || place_fds(&fds, &mut moved)) };
+    let mut child = command.spawn().map_err(|e| format!("Failed to start {}: {}", exe.display(), e))?;
+    drop(new_ready); // Only the new process holds it now, so its exit ends the wait
+
+    let _ = ready.set_read_timeout(Some(READY_TIMEOUT));
+    let mut answer = [0; 16];
+    let failure = match ready.read(&mut answer) {
+        Ok(size) if size > 0 => return Ok(child.id()),
+        Ok(_) => "The new proxy exited before accepting clients, see its output".to_string(),
+        Err(err) => format!("The new proxy did not accept clients within {}s: {}", READY_TIMEOUT.as_secs(), err),
+    };
+    let _ = child.kill();
+    let _ = child.wait();
+    Err(failure)
+}
+
+// Function to hand the listening sockets to a new proxy process and start draining this one; the
+// sockets stay open throughout, so clients connecting meanwhile wait in the backlog instead of
+// failing. On any failure this proxy keeps serving as before
+pub fn upgrade(drain_timeout: Duration) -> Result<Value, String> {
+    if draining() || UPGRADING.swap(true, Ordering::SeqCst) {
+        return Err("An upgrade is already in progress".to_string());
+    }
+    match start_new_process() {
+        Ok(pid) => {
+            DRAINING.store(true, Ordering::SeqCst);
+            notify_main_pid(pid);
+            println!("Handed the listening sockets to process {}, draining", pid);
+            Ok(json!({"pid": pid, "drain_timeout_secs": drain_timeout.as_secs()}))
+        }
+        Err(err) => {
+            UPGRADING.store(false, Ordering::SeqCst);
+            Err(err)
+        }
+    }
+}
+
+// Function to wait, after the sockets were handed over, until the connected clients disconnect or
+// the drain timeout passes; clients still connected then are dropped with the process
+pub fn wait_drained(supervisor: &Supervisor, timeout: Duration) {
+    if !draining() {
+        return;
+    }
+    let started = Instant::now();
+    loop {
+        let running = supervisor.running();
+        if running == 0 {
+            println!("Drained all clients, exiting");
+            return;
+        }
+        if started.elapsed() >= timeout {
+            eprintln!("Warning: exiting after the drain timeout with {} clients still connected", running);
+            return;
+        }
+        thread::sleep(Duration::from_millis(DRAIN_CHECK_MS as u64));
+    }
+}
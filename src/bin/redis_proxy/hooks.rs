@@ -0,0 +1,68 @@
+// Import necessary crates and modules
+use super::{publish, webhook}; // For announcing on extra channels and notifying hook URLs
+use rustredis::glob::glob_match; // For matching keys against hook patterns
+use rustredis::hooks::{Action, Change, HookConfig, On}; // For the configured hooks and the changes they run on
+use rustredis::keys::render_template; // For the channels, counters and streams of a key
+use serde_json::{json, Value}; // For the payloads of hook events
+use std::sync::OnceLock; // For the global hook list
+
+// Define the hooks configured at startup
+static HOOKS: OnceLock<Vec<HookConfig>> = OnceLock::new();
+
+// Function to set the lifecycle hooks
+pub fn start(configs: Vec<HookConfig>) {
+    let _ = HOOKS.set(configs);
+}
+
+// Function to list the URLs of webhook hooks, for starting their delivery threads
+pub fn webhook_urls(configs: &[HookConfig]) -> Vec<String> {
+    let mut urls: Vec<String> = configs.iter().filter_map(|hook| match hook.action {
+        Action::Webhook { ref url } => Some(url.clone()),
+        _ => None,
+    }).collect();
+    urls.sort();
+    urls.dedup();
+    urls
+}
+
+// Function to return the hooks matching a key
+fn hooks_of(key: &str) -> impl Iterator<Item = &'static HookConfig> + '_ {
+    HOOKS.get().into_iter().flatten().filter(move |hook| glob_match(&hook.pattern, key))
+}
+
+// Function to check whether a write of the key has to find out if the key existed: some hook runs
+// only on creates, updates or deletes of it
+pub fn needs_existence(key: &str, deleting: bool) -> bool {
+    hooks_of(key).any(|hook| if deleting { hook.on == On::Delete } else { matches!(hook.on, On::Create | On::Update) })
+}
+
+// Function to queue the publications, counters and stream entries of the hooks running on a change
+// of a key, in the write's transaction; `value` is the value as events carry it (None when withheld)
+pub fn add(pipe: &mut redis::Pipeline, key: &str, change: Change, value: Option<&Value>) {
+    for hook in hooks_of(key).filter(|hook| hook.on.runs_on(change)) {
+        match hook.action {
+            Action::Publish { ref channel } => {
+                let payload = json!({"key": key, "action": change.action(), "value": value}).to_string();
+                publish::add(pipe, &render_template(channel, key), &payload);
+            }
+            Action::Incr { key: ref template } => {
+                pipe.incr(render_template(template, key), 1).ignore();
+            }
+            Action::Stream { key: ref template, maxlen } => {
+                let value = value.map_or_else(|| "null".to_string(), Value::to_string);
+                pipe.cmd("XADD").arg(render_template(template, key)).arg("MAXLEN").arg("~").arg(maxlen).arg("*")
+                    .arg("key").arg(key).arg("action").arg(change.action()).arg("value").arg(value).ignore();
+            }
+            Action::Webhook { .. } => {} // Notified once the transaction committed
+        }
+    }
+}
+
+// Function to notify the webhook hooks running on a committed change of a key
+pub fn notify(key: &str, change: Change, value: Option<&Value>) {
+    for hook in hooks_of(key).filter(|hook| hook.on.runs_on(change)) {
+        if let Action::Webhook { ref url } = hook.action {
+            webhook::notify_url(url, change.action(), key, value);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// Import necessary crates and modules
+use rustredis::glob::glob_match; // For matching keys against index patterns
+use rustredis::keys::render_template; // For the index key of a key
+use serde_json::Value; // For the field sorted sets are ordered by
+use std::sync::OnceLock; // For the global index list
+
+// Define the kinds of secondary index
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndexKind {
+    Set, // Set of the keys
+    Sorted(String), // Sorted set of the keys, scored by a numeric field of their value (dotted path)
+}
+
+// Define the configuration of one secondary index
+#[derive(Clone, Debug)]
+pub struct IndexConfig {
+    pub pattern: String, // Key glob pattern of the indexed objects
+    pub kind: IndexKind,
+    pub key: String, // Index key template with {producer}, {object}, {id} and {function} placeholders
+}
+
+impl IndexConfig {
+    // Function to parse an index specification of the form PATTERN=set,key=TEMPLATE or PATTERN=zset,key=TEMPLATE,field=PATH
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (pattern, rest) = spec.split_once('=').ok_or("expected PATTERN=set|zset,key=TEMPLATE[,field=PATH]")?;
+        let mut parts = rest.split(',');
+        let kind = parts.next().unwrap_or_default();
+        let (mut key, mut field) = (None, None);
+        for option in parts {
+            match option.split_once('=') {
+                Some(("key", template)) if !template.is_empty() => key = Some(template.to_string()),
+                Some(("field", path)) if !path.is_empty() => field = Some(path.to_string()),
+                _ => return Err(format!("unknown index option '{}'", option)),
+            }
+        }
+        let key = key.ok_or_else(|| format!("missing key= in index '{}'", spec))?;
+        let kind = match (kind, field) {
+            ("set", None) => IndexKind::Set,
+            ("zset", Some(field)) => IndexKind::Sorted(field),
+            ("zset", None) => return Err(format!("missing field= in sorted index '{}'", spec)),
+            ("set", Some(_)) => return Err(format!("field= only applies to zset indexes, in '{}'", spec)),
+            _ => return Err(format!("invalid index kind in '{}', expected set or zset", spec)),
+        };
+        Ok(IndexConfig { pattern: pattern.to_string(), kind, key })
+    }
+}
+
+// Define the indexes configured at startup
+static INDEXES: OnceLock<Vec<IndexConfig>> = OnceLock::new();
+
+// Function to set the secondary indexes
+pub fn start(configs: Vec<IndexConfig>) {
+    let _ = INDEXES.set(configs);
+}
+
+// Function to return the indexes a key belongs to (every matching index)
+fn indexes_of(key: &str) -> impl Iterator<Item = &'static IndexConfig> + '_ {
+    INDEXES.get().into_iter().flatten().filter(move |index| glob_match(&index.pattern, key))
+}
+
+// Function to queue the index updates of a value stored under a key: added to its set indexes, and
+// to its sorted indexes scored by their field (or removed from those when the field is not a number)
+pub fn add(pipe: &mut redis::Pipeline, key: &str, value: Option<&Value>) {
+    for index in indexes_of(key) {
+        let index_key = render_template(&index.key, key);
+        match index.kind {
+            IndexKind::Set => {
+                pipe.cmd("SADD").arg(index_key).arg(key).ignore();
+            }
+            IndexKind::Sorted(ref field) => {
+                let pointer = format!("/{}", field.replace('.', "/"));
+                match value.and_then(|value| value.pointer(&pointer)).and_then(Value::as_f64) {
+                    Some(score) => pipe.cmd("ZADD").arg(index_key).arg(score).arg(key).ignore(),
+                    None => pipe.cmd("ZREM").arg(index_key).arg(key).ignore(),
+                };
+            }
+        }
+    }
+}
+
+// Function to queue the removal of a deleted (or expired) key from its indexes
+pub fn remove(pipe: &mut redis::Pipeline, key: &str) {
+    for index in indexes_of(key) {
+        let command = if index.kind == IndexKind::Set { "SREM" } else { "ZREM" };
+        pipe.cmd(command).arg(render_template(&index.key, key)).arg(key).ignore();
+    }
+}
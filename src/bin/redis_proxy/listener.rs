@@ -0,0 +1,209 @@
+// Import necessary crates and modules
+use std::fs; // For file system operations
+use std::net::TcpListener; // For TCP listeners (e.g. a gateway proxy serving leaf proxies)
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt; // For setting socket file permissions
+#[cfg(unix)]
+use std::os::unix::net::UnixListener; // For Unix domain sockets
+
+// Define the configuration of one listening socket
+#[derive(Clone, Debug)]
+pub struct ListenerConfig {
+    pub path: String, // Path of the Unix socket, tcp:HOST:PORT for a TCP listener or pipe:NAME for a named pipe
+    pub mode: Option<u32>, // File permissions applied to the socket after binding
+    pub producers: Option<Vec<String>>, // Producers clients of this socket may write for (all if None)
+    pub admin: bool, // Whether clients of this socket may run admin actions (purge, memory, list-clients, export, import, schema deployment, chaos)
+    pub decrypt: bool, // Whether clients of this socket may read the values of sensitive keys
+}
+
+impl ListenerConfig {
+    // Function to create a listener with default permissions and no producer restriction
+    pub fn new(path: &str) -> Self {
+        ListenerConfig {
+            path: path.to_string(),
+            mode: None,
+            producers: None,
+            admin: false,
+            decrypt: false,
+        }
+    }
+
+    // Function to parse a listener specification of the form PATH[,mode=0660][,producers=A+B][,admin][,decrypt]
+    // (or tcp:HOST:PORT[,...] and pipe:NAME[,...] without a mode)
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut parts = spec.split(',');
+        let path = parts.next().filter(|p| !p.is_empty()).ok_or("missing socket path")?;
+        let mut config = ListenerConfig::new(path);
+
+        for option in parts {
+            match option.split_once('=') {
+                Some(("mode", mode)) => {
+                    config.mode = Some(u32::from_str_radix(mode, 8).map_err(|_| format!("invalid octal mode '{}'", mode))?);
+                }
+                Some(("producers", producers)) => {
+                    config.producers = Some(producers.split('+').map(str::to_string).collect());
+                }
+                None if option == "admin" => config.admin = true,
+                None if option == "decrypt" => config.decrypt = true,
+                _ => return Err(format!("unknown listener option '{}'", option)),
+            }
+        }
+        if (config.tcp_address().is_some() || config.pipe_name().is_some()) && config.mode.is_some() {
+            return Err("mode only applies to Unix socket listeners".to_string());
+        }
+        Ok(config)
+    }
+
+    // Function to check if clients of this listener may write for a producer
+    pub fn allows_producer(&self, producer: &str) -> bool {
+        self.producers.as_ref().is_none_or(|producers| producers.iter().any(|p| p == producer))
+    }
+
+    // Function to return the address of a TCP listener
+    pub fn tcp_address(&self) -> Option<&str> {
+        self.path.strip_prefix("tcp:")
+    }
+
+    // Function to return the name of a named pipe listener
+    pub fn pipe_name(&self) -> Option<&str> {
+        self.path.strip_prefix("pipe:")
+    }
+
+    // Function to bind the socket, replacing any stale socket file and applying permissions
+    pub fn bind(&self) -> std::io::Result<BoundListener> {
+        if let Some(address) = self.tcp_address() {
+            return TcpListener::bind(address).map(BoundListener::Tcp);
+        }
+        if let Some(name) = self.pipe_name() {
+            return bind_pipe(name);
+        }
+        self.bind_unix()
+    }
+
+    // Function to bind a Unix socket
+    #[cfg(unix)]
+    fn bind_unix(&self) -> std::io::Result<BoundListener> {
+        if fs::metadata(&self.path).is_ok() { // Check if socket file exists
+            fs::remove_file(&self.path)?; // Remove existing socket file
+        }
+
+        let listener = UnixListener::bind(&self.path)?; // Bind to the Unix socket path
+        if let Some(mode) = self.mode {
+            fs::set_permissions(&self.path, fs::Permissions::from_mode(mode))?;
+        }
+        Ok(BoundListener::Unix(listener))
+    }
+
+    // Function to reject Unix socket paths where they do not exist
+    #[cfg(not(unix))]
+    fn bind_unix(&self) -> std::io::Result<BoundListener> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, format!("Unix sockets are not available here, listen on pipe:NAME or tcp:HOST:PORT instead of {}", self.path)))
+    }
+}
+
+// Define a bound listening socket
+pub enum BoundListener {
+    #[cfg(unix)]
+    Unix(UnixListener),
+    Tcp(TcpListener), // Not protected by file permissions; restrict it with producers= where it matters
+    #[cfg(windows)]
+    Pipe(PipeListener), // Local clients only
+}
+
+// Function to reject named pipes where they do not exist
+#[cfg(not(windows))]
+fn bind_pipe(name: &str) -> std::io::Result<BoundListener> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, format!("named pipes are only available on Windows (pipe:{})", name)))
+}
+
+// Function to create the first instance of a named pipe, failing if another process owns the name
+#[cfg(windows)]
+fn bind_pipe(name: &str) -> std::io::Result<BoundListener> {
+    let name: Vec<u16> = format!(r"\\.\pipe\{}", name).encode_utf16().chain(Some(0)).collect();
+    let pending = pipe::create_instance(&name, true)?;
+    Ok(BoundListener::Pipe(PipeListener { name, pending }))
+}
+
+// Define a named pipe listener; one instance always waits for the next client
+#[cfg(windows)]
+pub struct PipeListener {
+    name: Vec<u16>, // NUL-terminated UTF-16 path of the pipe
+    pending: fs::File, // Instance clients connect to next
+}
+
+#[cfg(windows)]
+impl PipeListener {
+    // Function to wait for a client on the pending instance and create the next one
+    pub fn accept(&mut self) -> std::io::Result<fs::File> {
+        pipe::wait_for_client(&self.pending)?;
+        let next = pipe::create_instance(&self.name, false)?;
+        Ok(std::mem::replace(&mut self.pending, next))
+    }
+}
+
+// Function to check if the client of a named pipe hung up, discarding anything it sent
+#[cfg(windows)]
+pub fn pipe_client_gone(pipe: &mut fs::File) -> bool {
+    use std::io::Read;
+    match pipe::available(pipe) {
+        Ok(0) => false,
+        Ok(available) => {
+            let mut buffer = vec![0; available as usize];
+            pipe.read(&mut buffer).map_or(true, |read| read == 0) // Cannot block, the bytes are there
+        }
+        Err(_) => true, // ERROR_BROKEN_PIPE once the client closed its end
+    }
+}
+
+// Define the Win32 calls named pipes need
+#[cfg(windows)]
+mod pipe {
+    use std::fs::File; // For owning pipe handles
+    use std::io; // For Win32 errors
+    use std::os::windows::io::{AsRawHandle, FromRawHandle}; // For passing handles to Win32
+    use std::ptr; // For omitted optional arguments
+    use windows_sys::Win32::Foundation::{ERROR_PIPE_CONNECTED, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX};
+    use windows_sys::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PeekNamedPipe, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE,
+        PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+
+    // Define the buffer sizes of each pipe instance
+    const BUFFER_SIZE: u32 = 64 * 1024;
+
+    // Function to create a pipe instance; the first one also claims the name
+    pub fn create_instance(name: &[u16], first: bool) -> io::Result<File> {
+        let open_mode = PIPE_ACCESS_DUPLEX | if first { FILE_FLAG_FIRST_PIPE_INSTANCE } else { 0 };
+        let pipe_mode = PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS;
+        // SAFETY: name is NUL-terminated and outlives the call; a null security descriptor means the default one
+        let handle = unsafe { CreateNamedPipeW(name.as_ptr(), open_mode, pipe_mode, PIPE_UNLIMITED_INSTANCES, BUFFER_SIZE, BUFFER_SIZE, 0, ptr::null()) };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the handle is valid and owned by nothing else
+        Ok(unsafe { File::from_raw_handle(handle as _) })
+    }
+
+    // Function to block until a client connects to a pipe instance
+    pub fn wait_for_client(pipe: &File) -> io::Result<()> {
+        // SAFETY: the handle is a valid pipe instance for the duration of the call; no overlapped I/O
+        if unsafe { ConnectNamedPipe(pipe.as_raw_handle() as _, ptr::null_mut()) } == 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(ERROR_PIPE_CONNECTED as i32) {
+                return Err(err); // ERROR_PIPE_CONNECTED: the client connected before we waited
+            }
+        }
+        Ok(())
+    }
+
+    // Function to return how many bytes can be read from a pipe without blocking
+    pub fn available(pipe: &File) -> io::Result<u32> {
+        let mut available = 0;
+        // SAFETY: the handle is valid; with no buffer only the counters are written
+        if unsafe { PeekNamedPipe(pipe.as_raw_handle() as _, ptr::null_mut(), 0, ptr::null_mut(), &mut available, ptr::null_mut()) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(available)
+    }
+}
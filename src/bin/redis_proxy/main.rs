@@ -0,0 +1,2177 @@
+// Import necessary crates and modules
+mod access_log; // Sampled request log without values
+mod alert; // Alerts with acknowledgement and escalation
+mod call_policy; // Per-action timeouts, retries and hedged reads of Redis calls
+#[cfg(feature = "chaos")]
+mod chaos; // Injected latency, errors and dropped responses for resilience tests
+mod chunking; // Large values split across several keys
+mod compression; // Transparent compression of large values
+mod config_check; // The --check-config mode
+mod deploy; // Blue/green deployment of schema sets with rollback
+mod device; // Identity of the device the proxy runs on
+mod document; // RedisJSON storage with path-level reads and patches
+mod encryption; // Encryption at rest of sensitive values
+mod fanout; // Copies of writes to derived keys, hash fields and channels
+#[cfg(unix)]
+mod handover; // Listening socket handover to a new process on live upgrades
+mod hooks; // Lifecycle hooks run on creates, updates and deletes of keys
+mod index; // Secondary indexes maintained with writes
+mod listener; // Listening sockets and their per-socket defaults
+mod memory; // Redis memory used per producer namespace
+mod metrics; // Process-wide counters
+mod partition; // Time-bucketed keys of high-volume producers
+mod presence; // Heartbeat keys, offline and expired events
+mod publish; // Per-pattern policies for the events announcing writes
+mod purge; // Bulk deletion of a producer's keys by pattern
+mod quota; // Per-producer limits on keys and bytes
+mod registry; // Self-registration of producers in the reserved namespace
+mod rollup; // Periodic min/max/avg summaries of numeric writes
+mod router; // Routing of keys to Redis backends
+mod search; // RedisJSON storage and RediSearch indexes of object types
+mod shedding; // Priority admission of writes under overload
+mod snapshot; // Publication of current values at startup
+mod subscription; // Filtered event feeds for subscribed clients
+mod supervisor; // Panic isolation and health tracking of client handlers
+mod telemetry; // OpenTelemetry trace propagation and OTLP export
+mod timing; // Per-request timing breakdowns for debugging clients
+mod tombstone; // Soft-deleted values kept for a grace window
+mod transfer; // Export and import of namespaces as NDJSON records
+mod upstream; // Forwarding of namespaces to upstream proxies
+mod watch; // Batches of writes applied only if watched keys hold what the client expects
+mod webhook; // HTTP notifications of selected writes
+
+use access_log::AccessLogConfig; // For configuring the access log
+use call_policy::CallPolicy; // For configuring Redis call policies
+use fanout::FanoutConfig; // For configuring write fan-out
+use index::IndexConfig; // For configuring secondary indexes
+use listener::{BoundListener, ListenerConfig}; // For configuring listening sockets
+use metrics::METRICS; // For request, error and connection counters
+use partition::PartitionConfig; // For configuring time-partitioned keys
+use publish::{EventLog, PublishPolicy}; // For configuring how writes are announced
+use quota::QuotaConfig; // For configuring producer quotas
+use registry::Registration; // For reading producer registrations
+use rollup::RollupConfig; // For configuring rollups of high-frequency producers
+use router::{BackendConfig, ReplicaConfig, RouteConfig, Router}; // For routing keys to Redis backends
+use search::SearchConfig; // For configuring searchable object types
+use shedding::{Admission, PriorityLimit}; // For shedding low priority writes under overload
+use subscription::{Feed, Subscription}; // For streaming events to subscribed clients
+use supervisor::{HandlerHandle, Supervisor}; // For spawning supervised client handlers
+use telemetry::{Span, SpanKind}; // For tracing requests through the proxy
+use timing::Phase; // For timing breakdowns of debugging clients
+use tombstone::Restore; // For telling why a restore did nothing
+use transfer::ImportError; // For reporting rejected imports
+use watch::{Outcome, Prepared, Watched, Write as WatchedWrite}; // For conditional batches of writes
+use upstream::UpstreamConfig; // For configuring upstream proxies
+use webhook::WebhookConfig; // For configuring webhook sinks
+use rustredis::base64; // For binary values carried in JSON
+use rustredis::filter::Filter; // For parsing subscription filters
+use rustredis::framing::LineFramer; // For splitting the client's byte stream into requests
+use rustredis::hooks::{Change, HookConfig}; // For configuring and running lifecycle hooks
+use rustredis::schema::{add_constraints, base_key, is_valid_key, key_producer, load_schemas, normalize, pattern_producer, read_schema_dir, redact, redact_message, schema_for, validate_json_schema, validate_json_schema_at, validate_shadow_schema, FieldConstraint, VALID_PRODUCERS}; // For key and value validation
+use rustredis::storage::Storage; // For the connections requests run on
+use redis::Commands; // For Redis operations
+use serde::{Deserialize, Serialize}; // For serializing/deserializing JSON
+use serde_json::Value; // For working with JSON values
+use std::net::TcpStream; // For clients of TCP listeners
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd; // For watching listening sockets while accepting
+#[cfg(unix)]
+use std::os::unix::net::UnixStream; // For Unix domain sockets
+use std::io::{ErrorKind, Read, Write}; // For reading from and writing to streams
+use std::collections::HashMap; // For hash fields read with hgetall
+use std::sync::mpsc::RecvTimeoutError; // For telling a quiet feed from a finished one
+use std::sync::Arc; // For thread-safe reference counting
+use std::thread; // For running one accept loop per listener
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH}; // For proxy-side timestamps and timeouts
+use clap::Parser; // For command line argument parsing
+use lazy_static::lazy_static; // For defining static variables initialized at runtime
+
+// Define the default Unix socket path
+const SOCKET_PATH: &str = rustredis::client::DEFAULT_SOCKET_PATH;
+
+// Define the channel on which the proxy publishes its own events (warnings, lifecycle)
+const PROXY_EVENTS_CHANNEL: &str = "cs:_proxy:events";
+
+// Define the key (and channel) the proxy's own stats are pushed to with --stats-push-interval
+const STATS_KEY: &str = "cs:_proxy:stats";
+
+// Define how often idle connections are checked when timeouts or keepalives are enabled
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Redis Proxy Service
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Unix socket to listen on as PATH[,mode=0660][,producers=A+B][,admin][,decrypt], tcp:HOST:PORT[,...] for leaf
+    /// proxies on other hosts, or pipe:NAME[,...] for a Windows named pipe (repeatable, defaults to
+    /// /tmp/redis_proxy.sock, or pipe:redis_proxy on Windows)
+    #[arg(long = "listen", value_parser = ListenerConfig::parse)]
+    listeners: Vec<ListenerConfig>,
+
+    /// Inject a proxy-side `_received_at` timestamp (wall clock + monotonic) into stored objects
+    #[arg(long)]
+    stamp_received_at: bool,
+
+    /// JSON file holding the device identity (at least serial, model and firmware), reported in stats
+    #[arg(long)]
+    device_file: Option<std::path::PathBuf>,
+
+    /// Device identity field as FIELD=VALUE (repeatable, overrides the device file)
+    #[arg(long = "device", value_parser = device::parse_field)]
+    device_fields: Vec<(String, String)>,
+
+    /// Inject the device identity as `_device` into objects stored with set and xadd, alerts and proxy events,
+    /// for events replicated off the device
+    #[arg(long)]
+    stamp_device: bool,
+
+    /// Close client connections that stay silent for this many seconds
+    #[arg(long)]
+    idle_timeout: Option<u64>,
+
+    /// Send a ping to silent clients that negotiated the `keepalive` feature after this many seconds
+    #[arg(long)]
+    keepalive_interval: Option<u64>,
+
+    /// Upper bound on the approximate length of streams written with `xadd`
+    #[arg(long, default_value_t = 10000)]
+    stream_maxlen: usize,
+
+    /// OTLP/HTTP collector endpoint (e.g. http://127.0.0.1:4318) to export traces of requests carrying a `traceparent`
+    #[arg(long)]
+    otlp_endpoint: Option<String>,
+
+    /// How writes to keys matching a glob pattern are announced (repeatable, first match wins), as PATTERN=off or
+    /// PATTERN=[actions=set+xadd,][channel=TEMPLATE,][payload=TEMPLATE]; templates may use {key}, {action}, {value}
+    /// and {producer}, and the payload template runs to the end of the option. Subscribers of the proxy follow key
+    /// channels, so they miss events moved to other channels
+    #[arg(long = "publish", value_parser = PublishPolicy::parse)]
+    publish_policies: Vec<PublishPolicy>,
+
+    /// Publish events with SPUBLISH (Redis 7 sharded pub/sub), keeping each key's events on the cluster node that
+    /// holds the key; subscribe then takes an exact key instead of a pattern
+    #[arg(long)]
+    sharded_pubsub: bool,
+
+    /// Where events go: pub/sub channels, per-producer streams (cs:_events:<producer>, readable later through consumer
+    /// groups, see rustredis::event_log), or both
+    #[arg(long, value_enum, default_value_t = EventLog::Pubsub)]
+    event_log: EventLog,
+
+    /// Approximate number of events each producer's event log stream keeps
+    #[arg(long, default_value_t = 100000)]
+    event_log_maxlen: usize,
+
+    /// POST writes to keys matching a glob pattern to an http:// URL, as PATTERN=URL (repeatable)
+    #[arg(long = "webhook", value_parser = WebhookConfig::parse)]
+    webhooks: Vec<WebhookConfig>,
+
+    /// Delivery retries (with exponential backoff) before a webhook notification is dropped
+    #[arg(long, default_value_t = 5)]
+    webhook_retries: u32,
+
+    /// Directory of <producer>.<object>.json schemas overriding the built-in ones, and of <producer>.<object>.shadow.json
+    /// schemas that requests are also checked against without being rejected (see schema_gen)
+    #[arg(long)]
+    schema_dir: Option<std::path::PathBuf>,
+
+    /// Constrain a field of an object type instead of writing its schema, as
+    /// BASE_KEY=FIELD:TYPE[,min=N][,max=N][,enum=A|B|C][,optional] (repeatable), e.g.
+    /// cs:ModemWatcher:object2=signal_strength:integer,min=-120,max=0; TYPE is string, number, integer, boolean, object
+    /// or array, min and max bound numbers or the length of strings and arrays, and the fields of an object type
+    /// compile into one schema enforced like those of --schema-dir (a staged schema set replaces it)
+    #[arg(long = "constraint", value_parser = FieldConstraint::parse)]
+    constraints: Vec<FieldConstraint>,
+
+    /// Limit the keys and serialized value bytes a producer may occupy, as PRODUCER[,keys=N][,bytes=N] (repeatable)
+    #[arg(long = "quota", value_parser = QuotaConfig::parse)]
+    quotas: Vec<QuotaConfig>,
+
+    /// Forward requests on keys matching a glob pattern to another proxy once validated locally, as
+    /// PATTERN=unix:PATH, PATTERN=tcp:HOST:PORT or PATTERN=pipe:NAME (repeatable, first match wins)
+    #[arg(long = "upstream", value_parser = UpstreamConfig::parse)]
+    upstreams: Vec<UpstreamConfig>,
+
+    /// Redis instance keys can be routed to, as NAME=URL (repeatable); `default=URL` replaces redis://127.0.0.1/.
+    /// Append ?tunnel=socks5://HOST:PORT or ?tunnel=ssh://[USER@]HOST to reach it through a jump host; memory:// keeps
+    /// the keys in the proxy's memory instead, for trying it out without Redis (no subscriptions, scripts or modules)
+    #[arg(long = "backend", value_parser = BackendConfig::parse)]
+    backends: Vec<BackendConfig>,
+
+    /// Send keys matching a glob pattern to a backend, as PATTERN=NAME (repeatable, first match wins, others go to default)
+    #[arg(long = "route", value_parser = RouteConfig::parse)]
+    routes: Vec<RouteConfig>,
+
+    /// Read replica of a backend, as BACKEND=URL (repeatable); get, smembers and hgetall are served by fresh replicas
+    #[arg(long = "replica", value_parser = ReplicaConfig::parse)]
+    replicas: Vec<ReplicaConfig>,
+
+    /// Largest replication offset distance (bytes) behind the master at which a replica still serves reads
+    #[arg(long, default_value_t = 1024 * 1024)]
+    max_replica_lag_bytes: u64,
+
+    /// Timeout, retries and hedging of an action's Redis calls, as ACTION[,timeout_ms=N][,retries=N][,backoff_ms=N][,hedge_ms=N]
+    /// (repeatable; ACTION * covers actions without their own). Writes are only retried when no
+    /// connection could be made; reads also after timeouts and dropped connections, and hedged reads
+    /// are sent to another node of the backend once hedge_ms passed without a reply
+    #[arg(long = "redis-policy", value_parser = CallPolicy::parse)]
+    redis_policies: Vec<CallPolicy>,
+
+    /// Seconds between health checks of the Redis backends (and replica lag checks)
+    #[arg(long, default_value_t = 10)]
+    backend_check_interval: u64,
+
+    /// Largest binary value (decoded `value_b64`) accepted
+    #[arg(long, default_value_t = 1024 * 1024)]
+    max_binary_bytes: usize,
+
+    /// Store values written with `set` that are larger than this many bytes LZ4-compressed (behind a marker
+    /// header, decompressed again by `get`); clients reading Redis directly see the compressed form
+    #[arg(long)]
+    compress_above: Option<usize>,
+
+    /// Send response payloads (and export records) whose JSON is larger than this many bytes zstd-compressed, to
+    /// clients that negotiated compression:zstd in hello (proxies built with the zstd feature only)
+    #[arg(long, default_value_t = 1024)]
+    compress_responses_above: usize,
+
+    /// Split values written with `set` that are still larger than this many bytes (after compression) across
+    /// cs:_chunks:KEY:N keys, with a manifest under the key that `get` reassembles them from; their events and
+    /// webhooks carry only the size
+    #[arg(long)]
+    chunk_above: Option<usize>,
+
+    /// Bytes per chunk of a value split with --chunk-above
+    #[arg(long, default_value_t = 262144)]
+    chunk_size: usize,
+
+    /// Encrypt the values of keys matching a glob pattern with AES-256-GCM (repeatable); only sockets with the
+    /// `decrypt` option may get them, and only set, get and del are allowed on them
+    #[arg(long = "sensitive", requires = "encryption_key_file")]
+    sensitive: Vec<String>,
+
+    /// File holding the 32 byte encryption key of sensitive keys, raw or base64 encoded
+    #[arg(long)]
+    encryption_key_file: Option<std::path::PathBuf>,
+
+    /// Seconds a heartbeat keeps a producer key online when the client gives no ttl
+    #[arg(long, default_value_t = 30)]
+    heartbeat_ttl: u64,
+
+    /// Seconds a register keeps a producer listed as live in cs:_registry when the client gives no ttl
+    #[arg(long, default_value_t = 60)]
+    registration_ttl: u64,
+
+    /// Publish `offline` on a key's channel when its heartbeats lapse (enables expired-key notifications in Redis)
+    #[arg(long)]
+    presence_watcher: bool,
+
+    /// Publish `expired` on a key's channel when a producer key expires, releasing its quota (enables
+    /// expired-key notifications in Redis); publish policies apply to the action `expired`
+    #[arg(long)]
+    expired_events: bool,
+
+    /// Seconds a soft-deleted value can be restored when the client gives no ttl
+    #[arg(long, default_value_t = 86400)]
+    soft_delete_grace: u64,
+
+    /// Seconds an alert is kept when the client gives no ttl
+    #[arg(long, default_value_t = 3600)]
+    alert_ttl: u64,
+
+    /// Republish an unacknowledged alert as an `escalation` event this many seconds after it was raised (repeatable)
+    #[arg(long = "alert-escalation")]
+    alert_escalations: Vec<u64>,
+
+    /// Most keys a single purge deletes; clients may only ask for fewer
+    #[arg(long, default_value_t = 10000)]
+    purge_max_keys: u64,
+
+    /// Once listening, publish a `snapshot` event with the current value of every key, so consumers can bootstrap state
+    #[arg(long)]
+    startup_snapshot: bool,
+
+    /// Publish the startup snapshot as {"key", "action", "value"} objects on this channel instead of each key's channel
+    #[arg(long, requires = "startup_snapshot")]
+    snapshot_channel: Option<String>,
+
+    /// Seconds to wait after startup before publishing the snapshot, for consumers started alongside the proxy to subscribe
+    #[arg(long, default_value_t = 2)]
+    snapshot_delay: u64,
+
+    /// Summarize the numeric values written to keys matching a glob pattern every SECS seconds into
+    /// cs:_rollup:<key> (min/max/avg/count per field), as PATTERN=SECS[,only]; `only` skips storing raw values (repeatable)
+    #[arg(long = "rollup", value_parser = RollupConfig::parse)]
+    rollups: Vec<RollupConfig>,
+
+    /// Store set and xadd on keys matching a glob pattern under <key>:<hour or day bucket>, with <key>:latest
+    /// naming the current bucket for get, as PATTERN=hour|day[,ttl=SECS]; buckets expire SECS after they end (repeatable)
+    #[arg(long = "partition", value_parser = PartitionConfig::parse)]
+    partitions: Vec<PartitionConfig>,
+
+    /// Maintain a secondary index of the keys matching a glob pattern, updated in the same transaction as
+    /// their set and del: PATTERN=set,key=TEMPLATE for a set of the keys, or PATTERN=zset,key=TEMPLATE,field=PATH
+    /// for a sorted set scored by a numeric field (e.g. cs:DiskMonitor:disk:*=zset,key=cs:_index:{producer}:{object}:usage,field=usage);
+    /// TEMPLATE takes {producer}, {object}, {id} and {function}, and must route to the same backend (repeatable)
+    #[arg(long = "index", value_parser = IndexConfig::parse)]
+    indexes: Vec<IndexConfig>,
+
+    /// Copy the set and del of the keys matching a glob pattern to derived keys in the same transaction, so
+    /// consumers get the layouts they need without producers knowing them: PATTERN=copy,key=TEMPLATE for a key
+    /// holding the same value (e.g. cs:Psmon:object1:*=copy,key=cs:Psmon:object1:latest), PATTERN=hash,key=TEMPLATE,field=TEMPLATE
+    /// for a hash field (e.g. key=cs:_by_id:{producer}:{object},field={id}), or PATTERN=channel,channel=TEMPLATE
+    /// for the key's event on another channel; templates take {key}, {producer}, {object}, {id} and {function},
+    /// and keys must route to the same backend (repeatable)
+    #[arg(long = "fanout", value_parser = FanoutConfig::parse)]
+    fanouts: Vec<FanoutConfig>,
+
+    /// Run a lifecycle hook on the sets and dels of the keys matching a glob pattern, as PATTERN=ON:ACTION,OPTIONS
+    /// where ON is write, create, update or delete (of an existing key) and ACTION is publish,channel=TEMPLATE
+    /// ({"key", "action", "value"} on a channel), incr,key=TEMPLATE (a counter, e.g. cs:Modem:*:reset=write:incr,key=cs:_counters:{id}:resets),
+    /// stream,key=TEMPLATE[,maxlen=N] (an entry in a capped stream) or webhook,url=URL; all but webhooks run in the
+    /// write's transaction and their keys must route to the same backend. Writes of watch-and-apply run write and
+    /// delete hooks, the latter whether or not the key existed (repeatable)
+    #[arg(long = "hook", value_parser = HookConfig::parse)]
+    hooks: Vec<HookConfig>,
+
+    /// Store the values of an object type as RedisJSON documents with a RediSearch index, for the search action,
+    /// as cs:PRODUCER:OBJECT[=FIELD:numeric|tag|text[+...]] (fields default to those of its schema); ignored
+    /// with a warning unless every backend has both modules. Sensitive and binary values stay strings (repeatable)
+    #[arg(long = "search", value_parser = SearchConfig::parse)]
+    searches: Vec<SearchConfig>,
+
+    /// Store the values of keys matching a glob pattern as RedisJSON documents, so `patch` sets one path
+    /// and `get` may read one; ignored with a warning unless every backend has the module (repeatable)
+    #[arg(long = "json-storage")]
+    json_storage: Vec<String>,
+
+    /// Count the proxy as overloaded while this many writes execute at once: higher `priority` writes go first
+    /// and writes below --shed-below are rejected
+    #[arg(long)]
+    max_in_flight: Option<usize>,
+
+    /// Priority (0-9) below which writes are shed instead of queued while the proxy is overloaded
+    #[arg(long, default_value_t = shedding::DEFAULT_PRIORITY, requires = "max_in_flight")]
+    shed_below: u8,
+
+    /// Highest priority a producer's writes may claim, as PRODUCER=N (repeatable); others are capped at the default of 5
+    #[arg(long = "priority-limit", value_parser = PriorityLimit::parse)]
+    priority_limits: Vec<PriorityLimit>,
+
+    /// Seconds between supervisor reports on client handler health
+    #[arg(long, default_value_t = 60)]
+    supervisor_interval: u64,
+
+    /// Seconds a client handler may spend on a single request before the supervisor flags it as stuck
+    #[arg(long, default_value_t = 30)]
+    stall_threshold: u64,
+
+    /// Seconds a proxy that handed its sockets to a new process (upgrade action) keeps serving its connected clients before exiting
+    #[arg(long, default_value_t = 60)]
+    drain_timeout: u64,
+
+    /// Every this many seconds, store the stats report under cs:_proxy:stats and publish it on the channel of the
+    /// same name, for devices without a metrics scraper; the key expires if the proxy stops pushing
+    #[arg(long)]
+    stats_push_interval: Option<u64>,
+
+    /// Offer the debug:timing hello feature, with which every response carries a timing breakdown
+    /// (parse_us, validate_us, redis_us, total_us) for developers checking their latency budget on-device
+    #[arg(long)]
+    debug_timing: bool,
+
+    /// Append a line per sampled request (listener, action, key, value size, status, latency; never values)
+    /// to this file, for debugging on devices where logging every request would wear out flash
+    #[arg(long)]
+    access_log: Option<std::path::PathBuf>,
+
+    /// Share of requests written to the access log, from 0 to 1
+    #[arg(long, default_value_t = 0.01, value_parser = access_log::parse_sample, requires = "access_log")]
+    access_log_sample: f64,
+
+    /// Write every failed request to the access log, besides the sampled ones
+    #[arg(long, requires = "access_log")]
+    access_log_failures: bool,
+
+    /// Size in bytes at which the access log is moved to PATH.1 (replacing the previous one) and started anew
+    #[arg(long, default_value_t = 4 * 1024 * 1024, requires = "access_log")]
+    access_log_max_bytes: u64,
+
+    /// Seconds after a swap-schemas during which the share of writes failing validation is watched; the swap is
+    /// rolled back if it rises by more than --swap-error-margin (0 disables automatic rollbacks)
+    #[arg(long, default_value_t = 300)]
+    swap_watch: u64,
+
+    /// Rise of the share of writes failing validation after a swap, over the share before it, that rolls the swap back
+    #[arg(long, default_value_t = 0.05)]
+    swap_error_margin: f64,
+
+    /// Check the schemas, listeners, access restrictions, backends and other settings, print the diagnostics
+    /// as JSON and exit (status 1 if any is invalid)
+    #[arg(long)]
+    check_config: bool,
+
+    /// With --check-config, also connect to the Redis backends, upstream proxies and HTTP sinks
+    #[arg(long, requires = "check_config")]
+    check_connectivity: bool,
+}
+
+// Define the actions counted by name in stats (anything else is counted as unknown)
+const ACTIONS: [&str; 32] = ["hello", "ping", "stats", "subscribe", "get", "smembers", "hgetall", "set", "del", "soft-delete", "restore", "sadd", "srem", "xadd", "heartbeat", "alert", "ack", "purge", "memory", "list-clients", "export", "import", "stage-schemas", "swap-schemas", "rollback-schemas", "search", "patch", "chaos", "register", "registry", "upgrade", "watch-and-apply"];
+
+// Define the actions that only read, which may be served by replicas and are not forwarded to webhooks
+const READ_ACTIONS: [&str; 3] = ["get", "smembers", "hgetall"];
+
+// Define the protocol version spoken by this proxy (legacy clients that never say hello are version 0)
+const PROTOCOL_VERSION: u64 = 1;
+
+// Define the structure of incoming requests
+#[derive(Deserialize)]
+struct Request {
+    action: String, // The action to perform (hello, ping, stats, subscribe, get, smembers, hgetall, set, del, soft-delete, restore, sadd, srem, xadd, heartbeat, alert, ack, purge, memory, list-clients, export, import, stage-schemas, swap-schemas, rollback-schemas, search, patch, chaos, register, registry, upgrade, watch-and-apply)
+    #[serde(default)]
+    key: String, // The Redis key (not needed for hello, ping, stats, subscribe, purge, memory, register, registry and watch-and-apply)
+    value: Option<Value>, // The value to store (optional), or the producer's registration (register)
+    value_b64: Option<String>, // Opaque binary value to store instead, base64 encoded; no schema applies
+    protocol_version: Option<u64>, // Protocol version declared by the client (hello only)
+    features: Option<Vec<String>>, // Features requested by the client (hello only)
+    traceparent: Option<String>, // W3C trace context of the client's span (optional)
+    maxlen: Option<usize>, // Approximate length cap of the stream (xadd only)
+    #[serde(default)]
+    validate: bool, // Check the stored value against the key's current schema (get only)
+    #[serde(default)]
+    binary: bool, // Return the stored value as `value_b64` even if it is text (get only)
+    ttl: Option<u64>, // Seconds until the producer counts as offline without another heartbeat (heartbeat), the alert expires (alert), the soft-deleted value can no longer be restored (soft-delete) or the registration lapses (register)
+    severity: Option<String>, // Severity of the alert: info, warning or critical (alert only)
+    pattern: Option<String>, // Glob pattern of the keys to watch (subscribe), delete within one producer's namespace (purge) or measure (memory, default cs:*)
+    filter: Option<String>, // Condition events must meet to be forwarded, e.g. `value.usage > 90` (subscribe only)
+    #[serde(default)]
+    dry_run: bool, // Only count and list the matching keys (purge only)
+    limit: Option<u64>, // Most keys to delete, below the proxy's cap (purge), or to measure per namespace (memory)
+    top: Option<usize>, // Largest keys listed per namespace (memory only)
+    path: Option<String>, // Path within a document as $.FIELD[.FIELD...] to set (patch) or read (get, optional)
+    query: Option<String>, // RediSearch query over the object type given as key, e.g. `@usage:[90 +inf]` (search only)
+    records: Option<Vec<Value>>, // Records as export sends them, to validate and load (import only)
+    schemas: Option<serde_json::Map<String, Value>>, // Complete set of enforced schemas by base key (stage-schemas only)
+    shadow_schemas: Option<serde_json::Map<String, Value>>, // Shadow schemas of the staged set by base key (stage-schemas only)
+    #[cfg(feature = "chaos")]
+    faults: Option<serde_json::Map<String, Value>>, // Faults to inject by action or *, as {latency_ms, latency, error, drop} (chaos only)
+    #[cfg(feature = "chaos")]
+    seed: Option<u64>, // Seed of the draws deciding which requests faults hit (chaos only)
+    watch: Option<Vec<Value>>, // Keys as {key, sha1} whose stored values must still hash to sha1 (null: must not exist) for the writes to apply (watch-and-apply only)
+    writes: Option<Vec<Value>>, // Writes as {action: set, key, value} or {action: del, key}, applied together or not at all (watch-and-apply only)
+    priority: Option<u8>, // Importance of a write under overload, 0 (shed first) to 9 (e.g. alarms); defaults to 5
+    timeout_ms: Option<u64>, // Latency budget of the request; Redis calls still running when it runs out are abandoned with DEADLINE_EXCEEDED
+}
+
+// Define the structure of responses sent back to clients (and read back from upstream proxies)
+#[derive(Serialize, Deserialize)]
+struct Response {
+    status: String, // Status of the request (ok or error)
+    message: String, // Additional message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>, // Action specific payload (omitted for plain acknowledgements)
+}
+
+// Define the per-connection state negotiated through the hello action
+struct Session {
+    protocol_version: u64, // Negotiated protocol version (0 until the client says hello)
+    features: Vec<String>, // Features agreed on with the client
+    listener: Arc<ListenerConfig>, // Listener the client connected through
+    feed: Option<Feed>, // Events to stream once the subscribe response is sent
+    export: Option<String>, // Pattern of the keys to stream once the export response is sent
+    handler: HandlerHandle, // Reports the connection's traffic to the supervisor
+}
+
+impl Session {
+    // Function to create the session of a legacy client that has not said hello
+    fn new(listener: Arc<ListenerConfig>, handler: HandlerHandle) -> Self {
+        Session {
+            protocol_version: 0,
+            features: Vec::new(),
+            listener,
+            feed: None,
+            export: None,
+            handler,
+        }
+    }
+
+    // Function to check if a feature was negotiated for this connection
+    fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+// Define static variables that are initialized lazily
+lazy_static! {
+    static ref PROXY_START: Instant = Instant::now(); // Reference point for monotonic timestamps
+    static ref SUPPORTED_FEATURES: Vec<&'static str> = vec!["framing:newline", "encoding:json", "keepalive", "debug:timing", "compression:zstd"]; // Features offered in hello (debug:timing only with --debug-timing, compression:zstd only built with zstd)
+}
+
+// Function to build the proxy-side reception timestamp
+fn received_at() -> Value {
+    let wall = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos(); // Wall clock time in nanoseconds since the epoch
+    let monotonic = PROXY_START.elapsed().as_nanos(); // Nanoseconds since proxy start, never goes backwards
+    serde_json::json!({
+        "wall": wall as u64,
+        "monotonic": monotonic as u64
+    })
+}
+
+// Function to stamp a value with the reception timestamp (only JSON objects are stamped)
+fn stamp_received_at(value: &mut Value) {
+    if let Value::Object(map) = value {
+        map.insert("_received_at".to_string(), received_at());
+    }
+}
+
+// Function to build a response without payload
+fn response(status: &str, message: &str) -> Response {
+    Response {
+        status: status.to_string(),
+        message: message.to_string(),
+        data: None,
+    }
+}
+
+// Function to build a response carrying a payload
+fn data_response(message: &str, data: Value) -> Response {
+    Response {
+        status: "ok".to_string(),
+        message: message.to_string(),
+        data: Some(data),
+    }
+}
+
+impl Response {
+    // Function to serialize the response for the wire
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+// Function to serialize a response for a client, with its timing breakdown if any, sending a large
+// payload as zstd-compressed base64 in data_zstd if the client negotiated compression:zstd
+fn encode_response(response: &Response, timing: Option<Value>, session: &Session, args: &Args) -> String {
+    let packed = response.data.as_ref()
+        .filter(|_| session.has_feature("compression:zstd"))
+        .and_then(|data| compression::pack_payload(data, args.compress_responses_above));
+    if packed.is_none() && timing.is_none() {
+        return response.to_json();
+    }
+    let mut wire = serde_json::to_value(response).unwrap();
+    if let Some((packed, saved)) = packed {
+        metrics::incr(&METRICS.responses_compressed);
+        metrics::add(&METRICS.response_saved_bytes, saved as u64);
+        wire.as_object_mut().unwrap().remove("data");
+        wire["data_zstd"] = Value::String(packed);
+    }
+    if let Some(timing) = timing {
+        wire["timing"] = timing;
+    }
+    wire.to_string()
+}
+
+// Function to negotiate the protocol version and features with a client
+fn handle_hello(args: &Args, session: &mut Session, req: &Request) -> Response {
+    let supported: Vec<&str> = SUPPORTED_FEATURES.iter().copied()
+        .filter(|f| *f != "debug:timing" || args.debug_timing)
+        .filter(|f| *f != "compression:zstd" || cfg!(feature = "zstd"))
+        .collect();
+    let requested_version = req.protocol_version.unwrap_or(PROTOCOL_VERSION);
+    session.protocol_version = requested_version.min(PROTOCOL_VERSION); // Speak the highest version both sides know
+    session.features = req.features.as_deref().unwrap_or_default().iter()
+        .filter(|f| supported.contains(&f.as_str())) // Keep only the features we support
+        .cloned()
+        .collect();
+
+    data_response("Hello", serde_json::json!({
+        "protocol_version": session.protocol_version,
+        "features": session.features,
+        "supported_features": supported
+    }))
+}
+
+// Function to wrap a binary value for JSON payloads and events
+fn binary_json(bytes: &[u8]) -> Value {
+    serde_json::json!({"value_b64": base64::encode(bytes)})
+}
+
+// Function to parse a stored value as JSON, keeping it as a string if it is not (legacy writes) and wrapping binary values
+fn stored_json(stored: &[u8]) -> Value {
+    match std::str::from_utf8(stored) {
+        Ok(text) => serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string())),
+        Err(_) => binary_json(stored),
+    }
+}
+
+// Function to build the payload of a get, checking the stored value against the key's current schema if asked
+fn get_payload(key: &str, stored: Option<&[u8]>, validate: bool, binary: bool) -> Value {
+    let Some(stored) = stored else {
+        return serde_json::json!({"found": false, "value": null});
+    };
+    if binary || std::str::from_utf8(stored).is_err() { // Binary values have no schema to check
+        return serde_json::json!({"found": true, "value_b64": base64::encode(stored)});
+    }
+    let value = stored_json(stored);
+    if !validate {
+        return serde_json::json!({"found": true, "value": value});
+    }
+    let (schema_valid, schema_error) = match schema_for(key) {
+        Some(_) => match validate_json_schema(key, &value) {
+            Ok(()) => (Some(true), None),
+            Err(err) => (Some(false), Some(err)),
+        },
+        None => (None, None), // Nothing to check against
+    };
+    serde_json::json!({
+        "found": true,
+        "value": value,
+        "schema_valid": schema_valid,
+        "schema_error": schema_error,
+        "normalized": normalize(key, &value)
+    })
+}
+
+// Function to report the proxy's health: uptime, request and failure counters, clients and Redis state
+fn handle_stats(router: &Router) -> Response {
+    data_response("Stats", stats_report(router))
+}
+
+// Function to build the health report shared by the stats action and the stats push
+fn stats_report(router: &Router) -> Value {
+    let last_error = METRICS.last_error.lock().unwrap().as_ref().map(|error| serde_json::json!({
+        "action": error.action,
+        "message": error.message,
+        "at": error.at.duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }));
+    serde_json::json!({
+        "uptime_secs": PROXY_START.elapsed().as_secs(),
+        "device": device::identity(),
+        "requests": *METRICS.requests.lock().unwrap(),
+        "validation_failures": *METRICS.validation_failures.lock().unwrap(),
+        "shadow_schema_failures": *METRICS.shadow_failures.lock().unwrap(),
+        "clients": {
+            "connected": metrics::get(&METRICS.connections_active),
+            "accepted": metrics::get(&METRICS.connections_accepted),
+            "handler_panics": metrics::get(&METRICS.handler_panics),
+            "bytes_received": metrics::get(&METRICS.bytes_received),
+            "bytes_sent": metrics::get(&METRICS.bytes_sent),
+            "blocked_write_ms": metrics::get(&METRICS.blocked_write_ms),
+            "responses_compressed": metrics::get(&METRICS.responses_compressed),
+            "response_saved_bytes": metrics::get(&METRICS.response_saved_bytes)
+        },
+        "redis": {
+            "connect_failures": metrics::get(&METRICS.redis_connect_failures),
+            "errors": metrics::get(&METRICS.redis_errors),
+            "values_compressed": metrics::get(&METRICS.values_compressed),
+            "compression_saved_bytes": metrics::get(&METRICS.compression_saved_bytes),
+            "deadlines_exceeded": metrics::get(&METRICS.deadlines_exceeded),
+            "timeouts": metrics::get(&METRICS.redis_timeouts),
+            "retries": metrics::get(&METRICS.redis_retries),
+            "hedged_reads": metrics::get(&METRICS.hedged_reads),
+            "hedges_won": metrics::get(&METRICS.hedges_won),
+            "policies": call_policy::summary(),
+            "backends": router.summary()
+        },
+        "upstream": {
+            "relayed": metrics::get(&METRICS.requests_relayed),
+            "failures": metrics::get(&METRICS.upstream_failures)
+        },
+        "last_error": last_error,
+        "quotas": quota::summary(),
+        "access_log": access_log::summary(),
+        "schemas": deploy::status(),
+        "load_shedding": shedding::summary().map(|mut summary| {
+            summary["shed"] = serde_json::json!(*METRICS.writes_shed.lock().unwrap());
+            summary["delayed"] = serde_json::json!(metrics::get(&METRICS.writes_delayed));
+            summary
+        })
+    })
+}
+
+// Function to start pushing the stats report to Redis every interval
+fn start_stats_push(router: &Arc<Router>, interval: Duration) {
+    let router = Arc::clone(router);
+    let expiry = (interval.as_secs() * 3).max(1); // A few missed pushes before the report disappears
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        let mut report = stats_report(&router);
+        report["pushed_at"] = serde_json::json!(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs());
+        let result = router.backend_for(STATS_KEY).connection().and_then(|mut conn| {
+            conn.set_ex::<_, _, ()>(STATS_KEY, report.to_string(), expiry)?;
+            publish::send(&mut *conn, STATS_KEY, &report.to_string())
+        });
+        if let Err(err) = result {
+            eprintln!("Failed to push stats: {}", err);
+        }
+    });
+}
+
+// Function to count a rejected request
+fn validation_failure(reason: &'static str) {
+    metrics::incr_keyed(&METRICS.validation_failures, reason);
+    access_log::reject(reason);
+}
+
+// Function to subscribe the client to the events of the keys matching a pattern, optionally filtered
+fn handle_subscribe(router: &Router, session: &mut Session, req: &Request) -> Response {
+    let pattern = req.pattern.clone().unwrap_or_else(|| "cs:*".to_string());
+    if session.listener.producers.is_some() { // Restricted sockets only see their own producers' events
+        if let Some(producer) = pattern_producer(&pattern).filter(|p| !session.listener.allows_producer(p)) {
+            validation_failure("producer_not_allowed");
+            return response("error", &format!("Producer {} not allowed on this socket", producer));
+        }
+        if pattern_producer(&pattern).is_none() {
+            validation_failure("invalid_pattern");
+            return response("error", "Subscriptions on this socket must stay within a producer namespace (cs:<producer>:...)");
+        }
+    }
+    if publish::sharded() && pattern.contains(['*', '?', '[']) { // Sharded channels cannot be matched by pattern
+        validation_failure("invalid_pattern");
+        return response("error", "With sharded pub/sub, subscribe to an exact key rather than a pattern");
+    }
+    let filter = match req.filter.as_deref().map(Filter::parse).transpose() {
+        Ok(filter) => filter,
+        Err(err) => {
+            validation_failure("invalid_filter");
+            return response("error", &format!("Invalid filter: {}", err));
+        }
+    };
+
+    match subscription::start(router, Subscription { pattern: pattern.clone(), filter }) {
+        Ok(feed) => {
+            session.feed = Some(feed);
+            session.handler.set_subscription(&pattern);
+            data_response("Subscribed", serde_json::json!({"pattern": pattern, "filter": req.filter}))
+        }
+        Err(err) => {
+            metrics::incr(&METRICS.redis_connect_failures);
+            response("error", &format!("Subscription failed: {}", err))
+        }
+    }
+}
+
+// Define the socket types clients connect through
+trait ClientStream: Read + Write + Send + 'static {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()>;
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()>;
+
+    // Function to check if a subscribed client hung up; anything it sends is ignored
+    fn client_gone(&mut self) -> bool {
+        let mut buffer = [0; 1024];
+        let _ = self.set_nonblocking(true);
+        let gone = match self.read(&mut buffer) {
+            Ok(0) => true,
+            Ok(_) => false,
+            Err(err) => err.kind() != ErrorKind::WouldBlock,
+        };
+        let _ = self.set_nonblocking(false);
+        gone
+    }
+}
+
+#[cfg(unix)]
+impl ClientStream for UnixStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+impl ClientStream for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+// Named pipe clients have no read timeouts, so idle timeouts and keepalive pings do not apply to them
+#[cfg(windows)]
+impl ClientStream for std::fs::File {
+    fn set_read_timeout(&self, _timeout: Option<Duration>) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn set_nonblocking(&self, _nonblocking: bool) -> std::io::Result<()> {
+        Err(std::io::Error::from(ErrorKind::Unsupported))
+    }
+
+    fn client_gone(&mut self) -> bool {
+        listener::pipe_client_gone(self)
+    }
+}
+
+// Function to stream a subscription's events to the client until it hangs up or the feed ends
+fn stream_events(stream: &mut impl ClientStream, feed: Feed, session: &Session) {
+    loop {
+        match feed.recv_timeout(IDLE_CHECK_INTERVAL) {
+            Ok(event) => {
+                session.handler.set_queued_events(feed.queued()); // Events still waiting show how far the client lags
+                let action = event["action"].as_str().unwrap_or_default().to_string();
+                let mut line = Response { status: "event".to_string(), message: action, data: Some(event) }.to_json();
+                if session.has_feature("framing:newline") {
+                    line.push('\n');
+                }
+                if session.handler.write(stream, line.as_bytes()).is_err() {
+                    return; // Client is gone
+                }
+            }
+            Err(RecvTimeoutError::Timeout) if !stream.client_gone() => session.handler.set_queued_events(feed.queued()),
+            Err(_) => return, // Client hung up, or every backend subscription ended and the client should resubscribe
+        }
+    }
+}
+
+// Function to delete the keys matching a pattern within one producer's namespace, on admin sockets only
+fn handle_purge(router: &Router, args: &Args, session: &Session, req: &Request) -> Response {
+    if !session.listener.admin {
+        validation_failure("admin_only");
+        return response("error", "Purge is only allowed on admin sockets");
+    }
+    let pattern = req.pattern.as_deref().unwrap_or_default();
+    let Some(producer) = pattern_producer(pattern) else {
+        validation_failure("invalid_pattern");
+        return response("error", "Purge pattern must stay within a producer namespace (cs:<producer>:...)");
+    };
+    if !session.listener.allows_producer(producer) {
+        validation_failure("producer_not_allowed");
+        return response("error", &format!("Producer {} not allowed on this socket", producer));
+    }
+
+    let cap = req.limit.unwrap_or(args.purge_max_keys).min(args.purge_max_keys);
+    eprintln!("Purge of {} started (dry run: {}, cap: {})", pattern, req.dry_run, cap);
+    let mut events = router.default_backend().connection().ok();
+    let result = purge::run(router, pattern, req.dry_run, cap, |progress| {
+        if let Some(ref mut conn) = events { // Let operators follow long purges
+            publish_proxy_event(conn, serde_json::json!({"event": "purge_progress", "pattern": pattern, "progress": progress.to_json()}));
+        }
+    });
+    match result {
+        Ok(done) => {
+            eprintln!("Purge of {} finished: {} matched, {} deleted", pattern, done.matched, done.deleted);
+            data_response(if req.dry_run { "Dry run completed" } else { "Purge completed" }, done.to_json())
+        }
+        Err(err) => {
+            metrics::incr(&METRICS.redis_errors);
+            response("error", &format!("Purge of {} failed: {}", pattern, err))
+        }
+    }
+}
+
+// Function to start an export of the keys matching a pattern (default cs:*) or to import records, on admin sockets only
+fn handle_transfer(router: &Router, args: &Args, session: &mut Session, req: &Request) -> Response {
+    if !session.listener.admin {
+        validation_failure("admin_only");
+        return response("error", &format!("The {} action is only allowed on admin sockets", req.action));
+    }
+    if req.action == "export" {
+        if !session.has_feature("framing:newline") {
+            validation_failure("invalid_request");
+            return response("error", "Export streams NDJSON records, say hello with the framing:newline feature first");
+        }
+        let pattern = req.pattern.clone().unwrap_or_else(|| "cs:*".to_string());
+        session.export = Some(pattern.clone());
+        return data_response("Export started", serde_json::json!({"pattern": pattern}));
+    }
+
+    let Some(records) = req.records.as_deref() else {
+        validation_failure("invalid_request");
+        return response("error", "Import needs records");
+    };
+    let check_key = |key: &str| {
+        if !is_valid_key(key) {
+            return Err("invalid key format".to_string());
+        }
+        if key_producer(key).is_some_and(|producer| !session.listener.allows_producer(producer)) {
+            return Err("producer not allowed on this socket".to_string());
+        }
+        if upstream::for_key(key).is_some() {
+            return Err("namespace is owned by an upstream proxy".to_string());
+        }
+        Ok(())
+    };
+    match transfer::import(router, records, &check_key, args.compress_above, args.stream_maxlen) {
+        Ok(written) => {
+            if let Ok(mut conn) = router.default_backend().connection() { // Consumers may want to reload after a restore
+                publish_proxy_event(&mut conn, serde_json::json!({"event": "import", "keys": written}));
+            }
+            data_response("Import completed", serde_json::json!({"imported": written}))
+        }
+        Err(ImportError::Invalid(errors)) => {
+            validation_failure("import");
+            Response { status: "error".to_string(), message: format!("{} records failed validation, nothing was imported", errors.len()), data: Some(serde_json::json!({"errors": errors})) }
+        }
+        Err(ImportError::Failed { written, error }) => {
+            metrics::incr(&METRICS.redis_errors);
+            Response { status: "error".to_string(), message: format!("Import stopped after {} keys: {}", written, error), data: Some(serde_json::json!({"imported": written})) }
+        }
+    }
+}
+
+// Function to stream the records of an export followed by a summary line; false once the client is gone
+fn stream_export(stream: &mut impl ClientStream, router: &Router, args: &Args, session: &Session, pattern: &str) -> bool {
+    let mut gone = false;
+    let result = transfer::export(router, pattern, |producer| session.listener.allows_producer(producer), session.listener.decrypt, |record| {
+        let key = record["key"].as_str().unwrap_or_default().to_string();
+        let mut line = encode_response(&Response { status: "record".to_string(), message: key, data: Some(record) }, None, session, args);
+        line.push('\n');
+        session.handler.write(stream, line.as_bytes()).inspect_err(|_| gone = true)
+    });
+    if gone {
+        return false;
+    }
+    let mut summary = match result {
+        Ok(summary) => data_response("Export completed", serde_json::json!({"exported": summary.exported, "skipped": summary.skipped})),
+        Err(err) => {
+            metrics::incr(&METRICS.redis_errors);
+            response("error", &format!("Export of {} failed: {}", pattern, err))
+        }
+    }.to_json();
+    summary.push('\n');
+    session.handler.write(stream, summary.as_bytes()).is_ok()
+}
+
+// Function to report the Redis memory used by each producer namespace, on admin sockets only
+fn handle_memory(router: &Router, session: &Session, req: &Request) -> Response {
+    if !session.listener.admin {
+        validation_failure("admin_only");
+        return response("error", "Memory reports are only allowed on admin sockets");
+    }
+    let pattern = req.pattern.as_deref().unwrap_or("cs:*");
+    if let Some(producer) = pattern_producer(pattern).filter(|p| !session.listener.allows_producer(p)) {
+        validation_failure("producer_not_allowed");
+        return response("error", &format!("Producer {} not allowed on this socket", producer));
+    }
+    match memory::report(router, pattern, |producer| session.listener.allows_producer(producer), req.limit.unwrap_or(memory::DEFAULT_SAMPLE), req.top.unwrap_or(memory::DEFAULT_TOP)) {
+        Ok(report) => data_response("Memory report", report),
+        Err(err) => {
+            metrics::incr(&METRICS.redis_errors);
+            response("error", &format!("Memory report for {} failed: {}", pattern, err))
+        }
+    }
+}
+
+// Function to stage a schema set, checking a sample of stored values against it (limit keys per object type,
+// default 100), swap the staged set in, or roll the last swap back, on admin sockets only
+fn handle_schemas(router: &Router, session: &Session, req: &Request) -> Response {
+    if !session.listener.admin {
+        validation_failure("admin_only");
+        return response("error", &format!("The {} action is only allowed on admin sockets", req.action));
+    }
+    let result = match req.action.as_str() {
+        "stage-schemas" => {
+            let Some(ref schemas) = req.schemas else {
+                validation_failure("invalid_request");
+                return response("error", "Staging needs the complete schema set as schemas");
+            };
+            let set = |schemas: &serde_json::Map<String, Value>| -> HashMap<String, Value> { schemas.iter().map(|(base, schema)| (base.clone(), schema.clone())).collect() };
+            let shadow = req.shadow_schemas.as_ref().map(set).unwrap_or_default();
+            deploy::stage(router, set(schemas), shadow, req.limit.unwrap_or(100) as usize).map(|report| ("Schemas staged", report))
+        }
+        "swap-schemas" => deploy::swap(router).map(|report| ("Schemas swapped", report)),
+        "rollback-schemas" => deploy::rollback(router).map(|report| ("Schemas rolled back", report)),
+        _ => {
+            validation_failure("invalid_action");
+            return response("error", "Invalid action");
+        }
+    };
+    match result {
+        Ok((message, report)) => data_response(message, report),
+        Err(err) => {
+            validation_failure("schema_deployment");
+            response("error", &err)
+        }
+    }
+}
+
+// Function to register a producer for ttl seconds (default --registration-ttl), or list the live
+// registrations of the producers the socket allows
+fn handle_registry(router: &Router, args: &Args, session: &Session, req: &Request) -> Response {
+    if req.action == "registry" {
+        return match registry::live(router, |producer| session.listener.allows_producer(producer)) {
+            Ok(registrations) => data_response("Registered producers", registrations),
+            Err(err) => {
+                metrics::incr(&METRICS.redis_errors);
+                response("error", &err.to_string())
+            }
+        };
+    }
+    let registration = match req.value.as_ref().ok_or_else(|| "Registering needs the registration as value".to_string()).and_then(Registration::parse) {
+        Ok(registration) => registration,
+        Err(err) => {
+            validation_failure("invalid_registration");
+            return response("error", &err);
+        }
+    };
+    if !VALID_PRODUCERS.contains(&registration.name.as_str()) {
+        validation_failure("invalid_registration");
+        return response("error", &format!("Unknown producer {}", registration.name));
+    }
+    if !session.listener.allows_producer(&registration.name) {
+        validation_failure("producer_not_allowed");
+        return response("error", &format!("Producer {} not allowed on this socket", registration.name));
+    }
+    match registry::register(router, registration, req.ttl.unwrap_or(args.registration_ttl).max(1)) {
+        Ok(record) => data_response("Producer registered", record),
+        Err(err) => {
+            metrics::incr(&METRICS.redis_errors);
+            response("error", &err.to_string())
+        }
+    }
+}
+
+// Function to replace the injected faults (or report them if none are given), on admin sockets of
+// proxies built with the chaos feature only
+fn handle_chaos(session: &Session, req: &Request) -> Response {
+    if !session.listener.admin {
+        validation_failure("admin_only");
+        return response("error", "The chaos action is only allowed on admin sockets");
+    }
+    #[cfg(feature = "chaos")]
+    {
+        let result = match req.faults {
+            Some(ref faults) => chaos::configure(faults, req.seed),
+            None => Ok(chaos::report()),
+        };
+        match result {
+            Ok(report) => data_response("Faults in effect", report),
+            Err(err) => {
+                validation_failure("invalid_faults");
+                response("error", &err)
+            }
+        }
+    }
+    #[cfg(not(feature = "chaos"))]
+    {
+        let _ = req;
+        validation_failure("invalid_action");
+        response("error", "The chaos action needs a proxy built with the chaos feature")
+    }
+}
+
+// Function to start a new proxy process from the binary on disk, handing it the listening sockets,
+// and drain this one (admin sockets only)
+fn handle_upgrade(args: &Args, session: &Session) -> Response {
+    if !session.listener.admin {
+        validation_failure("admin_only");
+        return response("error", "The upgrade action is only allowed on admin sockets");
+    }
+    #[cfg(unix)]
+    {
+        match handover::upgrade(Duration::from_secs(args.drain_timeout)) {
+            Ok(upgrade) => data_response("New proxy accepting clients, draining this one", upgrade),
+            Err(err) => response("error", &err),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = args;
+        validation_failure("invalid_action");
+        response("error", "The upgrade action needs Unix socket handover, restart the service instead")
+    }
+}
+
+// Function to apply a batch of sets and dels only if every watched key still holds what the client
+// expects (the SHA-1 of its stored bytes, as reported by an earlier call), for optimistic concurrency
+// across keys; the check and the writes run as one script. Keys must share a backend, and documents,
+// partitioned, sensitive and upstream keys and values large enough to be chunked are refused
+fn handle_watch_and_apply(router: &Router, args: &Args, session: &Session, req: &Request) -> Response {
+    let parsed = req.watch.as_deref().unwrap_or_default().iter().map(Watched::parse).collect::<Result<Vec<_>, _>>()
+        .and_then(|watched| Ok((watched, req.writes.as_deref().unwrap_or_default().iter().map(WatchedWrite::parse).collect::<Result<Vec<_>, _>>()?)));
+    let (watched, writes) = match parsed {
+        Ok((watched, writes)) if watched.is_empty() && writes.is_empty() => {
+            validation_failure("invalid_request");
+            return response("error", "Watch-and-apply needs watched keys or writes");
+        }
+        Ok((watched, writes)) if watched.len() + writes.len() > watch::MAX_KEYS => {
+            validation_failure("invalid_request");
+            return response("error", &format!("Watch-and-apply takes at most {} watched keys and writes", watch::MAX_KEYS));
+        }
+        Ok(parsed) => parsed,
+        Err(err) => {
+            validation_failure("invalid_request");
+            return response("error", &err);
+        }
+    };
+
+    let keys: Vec<&str> = watched.iter().map(|watch| watch.key.as_str()).chain(writes.iter().map(WatchedWrite::key)).collect();
+    let backend = router.backend_for(keys[0]);
+    for key in &keys {
+        if !is_valid_key(key) {
+            validation_failure("invalid_key");
+            return response("error", &format!("Invalid key format: {}", key));
+        }
+        if let Some(producer) = key_producer(key).filter(|producer| !session.listener.allows_producer(producer)) {
+            validation_failure("producer_not_allowed");
+            return response("error", &format!("Producer {} not allowed on this socket", producer));
+        }
+        if upstream::for_key(key).is_some() || document::is_document(key) || partition::is_partitioned(key) || encryption::is_sensitive(key) {
+            validation_failure("unsupported_key");
+            return response("error", &format!("Key {} is owned upstream, a document, partitioned or sensitive, watch-and-apply cannot cover it", key));
+        }
+        if router.backend_for(key).name != backend.name {
+            validation_failure("cross_backend");
+            return response("error", &format!("Key {} is on backend {}, all keys of a watch-and-apply must be on {}", key, router.backend_for(key).name, backend.name));
+        }
+    }
+
+    // Validate and encode the writes as set and del would
+    let mut prepared = Vec::new();
+    for write in writes {
+        let (key, stored, announced, event) = match write {
+            WatchedWrite::Set { key, mut value } => {
+                if let Err(err) = validate_json_schema(&key, &value) {
+                    validation_failure("schema");
+                    return response("error", &redact_message(&key, &value, &err));
+                }
+                if args.stamp_received_at {
+                    stamp_received_at(&mut value);
+                }
+                device::stamp(&mut value);
+                let mut stored = value.to_string().into_bytes();
+                if let Some(compressed) = args.compress_above.and_then(|above| compression::compress(&stored, above)) {
+                    metrics::incr(&METRICS.values_compressed);
+                    metrics::add(&METRICS.compression_saved_bytes, (stored.len() - compressed.len()) as u64);
+                    stored = compressed;
+                }
+                if chunking::is_large(stored.len()) {
+                    validation_failure("value_too_large");
+                    return response("error", &format!("Value of {} would be split into chunks, set it on its own", key));
+                }
+                let announced = redact(&key, &value);
+                let event = format!("set: {}", announced);
+                (key, Some(stored), Some(announced), event)
+            }
+            WatchedWrite::Del { key } => (key, None, None, "del".to_string()),
+        };
+        let action = if stored.is_some() { "set" } else { "del" };
+        let publication = publish::publication(action, &key, &event, announced.as_ref());
+        prepared.push(Prepared { key, stored, announced, publication });
+    }
+
+    // Hold the quotas of the producers written for, in name order so concurrent batches can't deadlock
+    let producers: std::collections::BTreeSet<&str> = prepared.iter().filter_map(|write| key_producer(&write.key)).collect();
+    let mut usages: Vec<_> = producers.into_iter().filter_map(|producer| quota::lock(producer).map(|usage| (producer, usage))).collect();
+    for write in &prepared {
+        let usage = usages.iter().find(|(producer, _)| key_producer(&write.key) == Some(*producer));
+        if let (Some((_, usage)), Some(stored)) = (usage, write.stored.as_ref()) {
+            if let Err(err) = usage.check("set", &write.key, stored.len() as u64) {
+                validation_failure("quota");
+                return response("error", &err);
+            }
+        }
+    }
+
+    let mut conn = match backend.connection() {
+        Ok(conn) => conn,
+        Err(err) => {
+            metrics::incr(&METRICS.redis_connect_failures);
+            return response("error", &format!("Redis backend {} unavailable: {}", backend.name, err));
+        }
+    };
+    match watch::apply(&mut conn, &watched, &prepared) {
+        Ok(Outcome::Applied { hashes }) => {
+            for write in &prepared {
+                let action = if write.stored.is_some() { "set" } else { "del" };
+                if let Some((_, usage)) = usages.iter_mut().find(|(producer, _)| key_producer(&write.key) == Some(*producer)) {
+                    usage.record(action, &write.key, write.stored.as_ref().map_or(0, |stored| stored.len() as u64), 0, args.stream_maxlen);
+                }
+                webhook::notify(action, &write.key, write.announced.as_ref());
+                hooks::notify(&write.key, write.change(), write.announced.as_ref());
+            }
+            drop(usages);
+            if let Err(err) = watch::update_derived(&mut conn, &prepared) {
+                metrics::incr(&METRICS.redis_errors);
+                return response("error", &format!("Writes applied, but updating their indexes, copies or events failed: {}", err));
+            }
+            data_response("Writes applied", serde_json::json!({"applied": true, "hashes": hashes}))
+        }
+        Ok(Outcome::Failed { key, expected, actual, hashes }) => Response {
+            status: "error".to_string(),
+            message: format!("Watched key {} does not hold the expected value, nothing was written", key),
+            data: Some(serde_json::json!({"applied": false, "failed": key, "expected": expected, "actual": actual, "hashes": hashes})),
+        },
+        Err(err) => {
+            metrics::incr(&METRICS.redis_errors);
+            response("error", &err.to_string())
+        }
+    }
+}
+
+// Function to search the documents of an object type, returning at most `limit` of them (default 10)
+fn handle_search(router: &Router, req: &Request) -> Response {
+    let Some(query) = req.query.as_deref() else {
+        validation_failure("invalid_request");
+        return response("error", "Search needs a query");
+    };
+    match search::search(router, &req.key, query, req.limit.unwrap_or(10)) {
+        Ok(results) => data_response("Search completed", results),
+        Err(err) => {
+            metrics::incr(&METRICS.redis_errors);
+            response("error", &err)
+        }
+    }
+}
+
+// Function to set one path of a document, validated against the part of the schema describing it
+fn handle_patch(router: &Router, req: &Request) -> Response {
+    if !document::is_document(&req.key) || encryption::is_sensitive(&req.key) { // Sensitive values stay encrypted strings
+        validation_failure("invalid_action");
+        return response("error", &format!("Key {} is not stored as a document, patch is unavailable", req.key));
+    }
+    let (Some(path), Some(value)) = (req.path.as_deref(), req.value.as_ref()) else {
+        validation_failure("invalid_request");
+        return response("error", "Patch needs a path and a value");
+    };
+    let fields = match document::parse_path(path) {
+        Ok(fields) => fields,
+        Err(err) => {
+            validation_failure("invalid_path");
+            return response("error", &err);
+        }
+    };
+    if let Err(err) = validate_json_schema_at(&req.key, &fields, value) {
+        validation_failure("schema");
+        return response("error", &redact_message(&req.key, value, &err));
+    }
+
+    let backend = router.backend_for(&req.key);
+    let mut conn = match backend.connection() {
+        Ok(conn) => conn,
+        Err(err) => {
+            metrics::incr(&METRICS.redis_connect_failures);
+            return response("error", &format!("Redis backend {} unavailable: {}", backend.name, err));
+        }
+    };
+    // The quota is settled from the document's size after the patch, which is only known then
+    let mut usage = key_producer(&req.key).and_then(quota::lock);
+    let patched = partition::read_target(&mut conn, &req.key).and_then(|target| document::patch(&mut conn, &target, path, value));
+    let document = match patched {
+        Ok(Some(document)) => document,
+        Ok(None) => return response("error", &format!("Path {} has no parent in {}", path, req.key)),
+        Err(err) => {
+            metrics::incr(&METRICS.redis_errors);
+            return response("error", &err.to_string());
+        }
+    };
+    if let Some(ref mut usage) = usage {
+        usage.record("set", &req.key, document.len() as u64, 0, 0);
+    }
+    drop(usage);
+
+    // Announce the patched fragment, as much of it as the key's redaction rules show
+    let shown = redact(&req.key, &stored_json(&document));
+    let fragment = shown.pointer(&format!("/{}", fields.join("/"))).cloned().unwrap_or(Value::Null);
+    let mut pipe = redis::pipe();
+    index::add(&mut pipe, &req.key, Some(&shown));
+    if let Err(err) = pipe.query::<()>(&mut *conn) {
+        eprintln!("Failed to update the indexes of {}: {}", req.key, err);
+    }
+    let event = serde_json::json!({"path": path, "value": fragment});
+    let publication = publish::publication("patch", &req.key, &format!("patch: {}", event), Some(&event));
+    if let Err(err) = publish_event(&mut conn, &publication) {
+        eprintln!("Failed to publish patch event for {}: {}", req.key, err);
+    }
+    webhook::notify("patch", &req.key, Some(&event));
+    response("ok", "Action completed successfully")
+}
+
+// Function to relay a request as received to the upstream proxy owning its namespace, returning its response
+fn relay(upstream: usize, raw: &str) -> Response {
+    metrics::incr(&METRICS.requests_relayed);
+    match upstream::relay(upstream, raw) {
+        Ok(line) => serde_json::from_str(&line).unwrap_or_else(|_| response("error", "Invalid response from upstream proxy")),
+        Err(err) => {
+            metrics::incr(&METRICS.upstream_failures);
+            response("error", &err)
+        }
+    }
+}
+
+// Function to raise or acknowledge the alert of a producer key
+fn handle_alert(router: &Router, args: &Args, req: &Request) -> Response {
+    let severity = req.severity.as_deref().unwrap_or("warning");
+    if req.action == "alert" && !alert::SEVERITIES.contains(&severity) {
+        validation_failure("invalid_severity");
+        return response("error", &format!("Invalid severity {}, expected one of {}", severity, alert::SEVERITIES.join(", ")));
+    }
+    let backend = router.backend_for(&alert::alert_key(&req.key));
+    let mut conn = match backend.connection() {
+        Ok(conn) => conn,
+        Err(err) => {
+            metrics::incr(&METRICS.redis_connect_failures);
+            return response("error", &format!("Redis backend {} unavailable: {}", backend.name, err));
+        }
+    };
+    let result = match req.action.as_str() {
+        "alert" => alert::raise(&mut conn, &req.key, severity, req.value.as_ref(), req.ttl.unwrap_or(args.alert_ttl).max(1))
+            .map(|record| data_response("Alert raised", record)),
+        _ => alert::ack(&mut conn, &req.key)
+            .map(|acked| data_response(if acked { "Alert acknowledged" } else { "No alert to acknowledge" }, serde_json::json!({"acked": acked}))),
+    };
+    result.unwrap_or_else(|err| {
+        metrics::incr(&METRICS.redis_errors);
+        response("error", &err.to_string())
+    })
+}
+
+// Function to measure the value a request sends, for the access log
+fn value_bytes(req: &Request) -> Option<usize> {
+    match (&req.value, &req.value_b64) {
+        (Some(value), _) => serde_json::to_string(value).ok().map(|json| json.len()),
+        (None, Some(encoded)) => Some(encoded.len() / 4 * 3), // Decoded size, give or take the padding
+        (None, None) => None,
+    }
+}
+
+// Function to handle an individual request
+fn handle_request(router: &Router, args: &Args, session: &mut Session, data: &str) -> String {
+    let started = Instant::now();
+    let timed = session.has_feature("debug:timing"); // Responses carry where the request's time went
+    if timed {
+        timing::begin();
+    }
+    let request: Result<Request, _> = serde_json::from_str(data); // Deserialize JSON request
+    timing::record(Phase::Parse, started.elapsed());
+    if let Ok(req) = request {
+        // Continue the client's trace if it sent one
+        let mut span = req.traceparent.as_deref().and_then(|tp| telemetry::request_span(tp, &req.action, &req.key));
+        let action = req.action.clone();
+        let logged = access_log::enabled().then(|| (req.key.clone(), value_bytes(&req))); // Key and value size for the access log
+        let response = process_request(router, args, session, req, data, span.as_ref());
+        if response.status == "error" {
+            metrics::record_error(&action, &response.message);
+            if let Some(ref mut span) = span {
+                span.set_error(&response.message);
+            }
+        }
+        if let Some((key, value_bytes)) = logged {
+            access_log::record(access_log::Entry {
+                listener: &session.listener.path,
+                action: &action,
+                key: &key,
+                value_bytes,
+                status: &response.status,
+                message: &response.message,
+                took: started.elapsed(),
+            });
+        }
+        #[cfg(feature = "chaos")]
+        if chaos::drop_response(&action) {
+            return String::new(); // Processed, but the client never hears back
+        }
+        let breakdown = timed.then(|| timing::finish(started.elapsed()))
+            .filter(|_| session.has_feature("debug:timing")); // Not if the request was a hello that dropped the feature
+        encode_response(&response, breakdown, session, args)
+    } else {
+        // Return error if request format is invalid
+        validation_failure("invalid_request");
+        metrics::record_error("", "Invalid request format");
+        let response = response("error", "Invalid request format");
+        access_log::record(access_log::Entry {
+            listener: &session.listener.path,
+            action: "",
+            key: "",
+            value_bytes: None,
+            status: &response.status,
+            message: &response.message,
+            took: started.elapsed(),
+        });
+        response.to_json()
+    }
+}
+
+// Function to answer request lines the way a client of a plain socket gets them answered, with a
+// fresh in-memory store as the only backend; the request fuzz target drives the dispatch through it
+#[cfg(fuzzing)]
+pub fn handle_in_memory(requests: &[String]) -> Vec<String> {
+    let args = Args::parse_from(["redis_proxy", "--backend", &format!("{}={}", router::DEFAULT_BACKEND, rustredis::storage::MEMORY_URL)]);
+    let router = Router::new(&args.backends, args.routes.clone(), &args.replicas, args.max_replica_lag_bytes).expect("An in-memory default backend is a valid configuration");
+    let mut session = Session::new(Arc::new(ListenerConfig::new("memory")), Supervisor::new().register());
+    requests.iter()
+        .filter(|data| !is_pong(data.trim()))
+        .map(|data| handle_request(&router, &args, &mut session, data.trim()))
+        .collect()
+}
+
+// Function to run a Redis call inside a child span of the request span
+fn traced_redis<T>(trace: Option<&Span>, action: &str, call: impl FnOnce() -> redis::RedisResult<T>) -> redis::RedisResult<T> {
+    let mut span = trace.map(|t| t.child(&format!("redis.{}", action), SpanKind::Client));
+    let started = Instant::now();
+    let result = call();
+    timing::record(Phase::Redis, started.elapsed());
+    if let (Some(span), Err(err)) = (span.as_mut(), &result) {
+        span.set_error(&err.to_string());
+    }
+    result
+}
+
+// Function to validate and execute a parsed request
+fn process_request(router: &Router, args: &Args, session: &mut Session, mut req: Request, raw: &str, trace: Option<&Span>) -> Response {
+    let deadline = req.timeout_ms.map(|ms| Instant::now() + Duration::from_millis(ms)); // Covers validation and overload waits too
+    let counted = ACTIONS.iter().find(|a| **a == req.action).copied().unwrap_or("unknown");
+    metrics::incr_keyed(&METRICS.requests, counted.to_string());
+
+    if req.action == "hello" { // Handshake does not touch Redis
+        return handle_hello(args, session, &req);
+    }
+
+    if req.action == "ping" { // Application-level liveness check from the client
+        return response("ok", "pong");
+    }
+
+    if req.action == "stats" { // Health report for device tooling
+        return handle_stats(router);
+    }
+
+    if req.action == "subscribe" { // Turns the connection into an event stream once answered
+        return handle_subscribe(router, session, &req);
+    }
+
+    if req.action == "purge" { // Admin action over many keys, checked separately
+        return handle_purge(router, args, session, &req);
+    }
+
+    if req.action == "export" || req.action == "import" { // Admin actions over many keys, checked separately
+        return handle_transfer(router, args, session, &req);
+    }
+
+    if req.action.ends_with("-schemas") { // Admin deployment of validation rules, checked separately
+        return handle_schemas(router, session, &req);
+    }
+
+    if req.action == "register" || req.action == "registry" { // Producer inventory, kept outside the producers' keys
+        return handle_registry(router, args, session, &req);
+    }
+
+    if req.action == "chaos" { // Admin fault injection of test builds, checked separately
+        return handle_chaos(session, &req);
+    }
+
+    if req.action == "upgrade" { // Admin re-exec of the proxy, checked separately
+        return handle_upgrade(args, session);
+    }
+
+    if req.action == "watch-and-apply" { // Writes to several keys, each checked separately
+        return handle_watch_and_apply(router, args, session, &req);
+    }
+
+    if req.action == "memory" { // Admin report scanning every backend
+        return handle_memory(router, session, &req);
+    }
+
+    if req.action == "list-clients" { // Admin view of every connection, for spotting slow consumers
+        if !session.listener.admin {
+            validation_failure("admin_only");
+            return response("error", "Listing clients is only allowed on admin sockets");
+        }
+        return data_response("Clients listed", session.handler.clients());
+    }
+
+    let mut validate_span = trace.map(|t| t.child("proxy.validate", SpanKind::Internal));
+    let validation_started = Instant::now();
+    if !is_valid_key(&req.key) { // Validate key format
+        validation_failure("invalid_key");
+        return response("error", "Invalid key format");
+    }
+
+    if let Some(producer) = key_producer(&req.key) { // Enforce the listener's producer restriction
+        if !session.listener.allows_producer(producer) {
+            validation_failure("producer_not_allowed");
+            return response("error", &format!("Producer {} not allowed on this socket", producer));
+        }
+    }
+
+    let upstream = upstream::for_key(&req.key); // Namespace owned by another proxy
+    if req.action == "alert" || req.action == "ack" { // Alerts carry their own payload, not the key's value
+        return match upstream {
+            Some(upstream) => relay(upstream, raw),
+            None => handle_alert(router, args, &req),
+        };
+    }
+
+    if req.action == "search" { // Queries the object type's index instead of one key
+        return match upstream {
+            Some(upstream) => relay(upstream, raw),
+            None => handle_search(router, &req),
+        };
+    }
+
+    if req.action == "patch" { // Validates the fragment against its part of the schema, not the whole value
+        return match upstream {
+            Some(upstream) => relay(upstream, raw),
+            None => handle_patch(router, &req),
+        };
+    }
+    if req.action == "get" && req.path.is_some() && !document::is_document(&req.key) {
+        validation_failure("invalid_path");
+        return response("error", &format!("Key {} is not stored as a document, paths are unavailable", req.key));
+    }
+
+    if let Some(ref value) = req.value { // If value exists, validate against schema
+        if let Err(err) = validate_json_schema(&req.key, value) {
+            let err = redact_message(&req.key, value, &err); // Errors end up in stats and traces
+            validation_failure("schema");
+            if let Some(ref mut span) = validate_span {
+                span.set_error(&err);
+            }
+            return response("error", &err);
+        }
+        if let Err(err) = validate_shadow_schema(&req.key, value) { // Accepted, but record what a stricter schema would reject
+            metrics::incr_keyed(&METRICS.shadow_failures, base_key(&req.key));
+            eprintln!("Audit: shadow schema would reject {} on {}: {}", req.action, req.key, redact_message(&req.key, value, &err));
+        }
+    }
+    let sensitive = encryption::is_sensitive(&req.key);
+    if sensitive && req.action == "get" && !session.listener.decrypt {
+        validation_failure("not_authorized");
+        return response("error", &format!("Key {} is sensitive and this socket may not read it", req.key));
+    }
+    if sensitive && matches!(req.action.as_str(), "sadd" | "srem" | "xadd" | "smembers" | "hgetall") {
+        validation_failure("sensitive_key");
+        return response("error", &format!("Key {} is sensitive, only set, get and del are allowed", req.key));
+    }
+    if matches!(req.action.as_str(), "soft-delete" | "restore") && partition::is_partitioned(&req.key) {
+        validation_failure("partitioned_key");
+        return response("error", &format!("Key {} is partitioned, its buckets cannot be soft-deleted", req.key));
+    }
+
+    // Binary values are stored as raw bytes and skip schema validation
+    let binary = match req.value_b64.as_deref() {
+        None => None,
+        Some(_) if req.value.is_some() => {
+            validation_failure("invalid_request");
+            return response("error", "Send either value or value_b64, not both");
+        }
+        Some(text) => match base64::decode(text) {
+            Ok(bytes) if bytes.len() > args.max_binary_bytes => {
+                validation_failure("binary_too_large");
+                return response("error", &format!("Binary value of {} bytes exceeds the limit of {}", bytes.len(), args.max_binary_bytes));
+            }
+            Ok(bytes) => Some(bytes),
+            Err(err) => {
+                validation_failure("invalid_base64");
+                return response("error", &format!("Invalid value_b64: {}", err));
+            }
+        },
+    };
+    drop(validate_span); // Validation finished
+    timing::record(Phase::Validate, validation_started.elapsed());
+
+    if let Some(upstream) = upstream { // Validated here, stored by the proxy that owns the namespace
+        return relay(upstream, raw);
+    }
+
+    if args.stamp_received_at && req.action == "set" { // Stamp stored objects after validation
+        if let Some(ref mut value) = req.value {
+            stamp_received_at(value);
+        }
+    }
+    if matches!(req.action.as_str(), "set" | "xadd") { // Set members stay unstamped so srem can match them
+        if let Some(ref mut value) = req.value {
+            device::stamp(value);
+        }
+    }
+
+    // Summarized writes feed their key's rollup, and skip Redis entirely if only summaries are kept
+    if matches!(req.action.as_str(), "set" | "xadd") && binary.is_none() && !sensitive {
+        if let Some(value) = req.value.as_ref() {
+            if rollup::record(&req.key, value) == Some(true) {
+                return response("ok", "Sample added to rollup");
+            }
+        }
+    }
+
+    let event_value = binary.as_deref().map(binary_json).or_else(|| req.value.as_ref().map(|value| redact(&req.key, value))); // Value as events and webhooks carry it
+    let document = req.action == "set" && binary.is_none() && !sensitive && document::is_document(&req.key); // Stored with JSON.SET
+    let mut stored = binary.unwrap_or_else(|| req.value.as_ref().unwrap_or(&Value::Null).to_string().into_bytes()); // Bytes as stored
+    if let Some(compressed) = args.compress_above.filter(|_| req.action == "set" && !document).and_then(|above| compression::compress(&stored, above)) {
+        metrics::incr(&METRICS.values_compressed);
+        metrics::add(&METRICS.compression_saved_bytes, (stored.len() - compressed.len()) as u64);
+        stored = compressed;
+    }
+    if sensitive && req.action == "set" { // Encrypt last, ciphertext does not compress
+        stored = encryption::encrypt(&req.key, &stored);
+    }
+    let chunked = req.action == "set" && !document && !partition::is_partitioned(&req.key) && chunking::is_large(stored.len()); // Split across chunk keys
+    let chunk_summary = chunked.then(|| serde_json::json!({"chunked": true, "bytes": stored.len()}));
+    let announced = chunk_summary.as_ref().or(event_value.as_ref()); // Chunked values are too large for events and webhooks
+    let event = match announced { // Values of sensitive keys stay out of events
+        Some(shown) if !sensitive => format!("{}: {}", req.action, shown),
+        None if !sensitive => format!("{}: null", req.action),
+        _ => req.action.clone(),
+    };
+    let default_event = if matches!(req.action.as_str(), "del" | "soft-delete") { req.action.as_str() } else { event.as_str() };
+    let publication = publish::publication(&req.action, &req.key, default_event, announced.filter(|_| !sensitive));
+    let maxlen = req.maxlen.unwrap_or(args.stream_maxlen).min(args.stream_maxlen); // Clients may only cap tighter (xadd only)
+
+    // Under overload, writes wait for those of higher priority, and low priority ones are shed
+    let reading = READ_ACTIONS.contains(&req.action.as_str());
+    if req.priority.is_some_and(|priority| priority > shedding::MAX_PRIORITY) {
+        validation_failure("invalid_priority");
+        return response("error", &format!("Priority must be between 0 and {}", shedding::MAX_PRIORITY));
+    }
+    let _permit = if reading {
+        None
+    } else {
+        let priority = shedding::effective_priority(key_producer(&req.key), req.priority);
+        match shedding::admit(priority) {
+            Admission::Admitted(permit) => Some(permit),
+            Admission::Waited(permit) => {
+                metrics::incr(&METRICS.writes_delayed);
+                Some(permit)
+            }
+            Admission::Shed => {
+                metrics::incr_keyed(&METRICS.writes_shed, key_producer(&req.key).unwrap_or_default().to_string());
+                return response("error", &format!("Proxy overloaded, {} of priority {} shed", req.action, priority));
+            }
+        }
+    };
+
+    // Hold the producer's quota until the write's effect is recorded, so concurrent writes can't overshoot it
+    let mut usage = key_producer(&req.key).filter(|_| !reading).and_then(quota::lock);
+    if let Some(ref usage) = usage {
+        if matches!(req.action.as_str(), "set" | "sadd" | "xadd" | "restore") {
+            let size = if req.action == "restore" { 0 } else { stored.len() as u64 }; // Restores only need room for the key
+            if let Err(err) = usage.check(&req.action, &req.key, size) {
+                validation_failure("quota");
+                return response("error", &err);
+            }
+        }
+    }
+    let mut changed = 0; // Set members added or removed
+    let mut lifecycle = None; // Change the lifecycle hooks of a set or del ran on
+
+    // Borrow a connection to the backend the key is routed to (or to one of its replicas for reads)
+    let policy = call_policy::for_action(&req.action);
+    let backend = router.backend_for(&req.key);
+    let mut conn = match call_policy::connect(backend, reading, policy, deadline) {
+        Ok(conn) => conn,
+        Err(err) => {
+            metrics::incr(&METRICS.redis_connect_failures);
+            return response("error", &format!("Redis backend {} unavailable: {}", backend.name, err));
+        }
+    };
+    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        metrics::incr(&METRICS.deadlines_exceeded);
+        return response("error", "DEADLINE_EXCEEDED: timeout_ms ran out before Redis was called");
+    }
+    if let Err(err) = call_policy::bound(&mut conn, policy, deadline) {
+        return response("error", &format!("Failed to apply timeout_ms: {}", err));
+    }
+    #[cfg(feature = "chaos")]
+    if let Some(err) = chaos::before_redis(&req.action) {
+        metrics::incr(&METRICS.redis_errors);
+        return response("error", &err);
+    }
+    let redis_client: &mut Storage = &mut conn;
+    let partition = if matches!(req.action.as_str(), "set" | "xadd") { partition::for_write(&req.key) } else { None }; // Bucket of a partitioned write
+    let target = partition.as_ref().map_or(req.key.as_str(), |partition| partition.key.as_str()); // Key the write is stored under
+
+    // Match the action and perform corresponding Redis command
+    let result: redis::RedisResult<Option<Value>> = match req.action.as_str() {
+        // Reads run under their call policy, which may hedge them on other threads, so they own what they read
+        "get" if req.path.is_some() => {
+            let (key, path) = (req.key.clone(), req.path.clone().unwrap_or_default());
+            traced_redis(trace, "get", || call_policy::read(backend, &mut conn, policy, deadline, move |conn| document::parse_path(&path)
+                .map_err(|err| redis::RedisError::from((redis::ErrorKind::InvalidClientConfig, "Invalid path", err)))
+                .and_then(|_| partition::read_target(conn, &key))
+                .and_then(|target| document::read_path(conn, &target, &path))))
+                .map(|value| Some(serde_json::json!({"found": value.is_some(), "path": req.path, "value": value})))
+        },
+        "get" => {
+            let key = req.key.clone();
+            traced_redis(trace, "get", || call_policy::read(backend, &mut conn, policy, deadline, move |conn| partition::read_target(conn, &key)
+                .and_then(|target| if document::is_document(&key) {
+                    document::read(conn, &target)
+                } else {
+                    conn.get::<&str, Option<Vec<u8>>>(&target).and_then(|stored| chunking::join(conn, &target, stored))
+                })))
+                .and_then(|stored| {
+                    let unreadable = |err: String| redis::RedisError::from((redis::ErrorKind::TypeError, "Unreadable stored value", err));
+                    let plain = stored.as_deref().map(|stored| encryption::decrypt(&req.key, stored)).transpose().map_err(unreadable)?;
+                    let value = plain.as_deref().map(compression::decompress).transpose().map_err(unreadable)?;
+                    Ok(Some(get_payload(&req.key, value.as_deref(), req.validate, req.binary)))
+                })
+        },
+        "smembers" => {
+            let key = req.key.clone();
+            traced_redis(trace, "smembers", || call_policy::read(backend, &mut conn, policy, deadline, move |conn| conn.smembers::<&str, Vec<Vec<u8>>>(&key)))
+                .map(|members| Some(serde_json::json!({"members": members.iter().map(|m| stored_json(m)).collect::<Vec<_>>()})))
+        },
+        "hgetall" => {
+            let key = req.key.clone();
+            traced_redis(trace, "hgetall", || call_policy::read(backend, &mut conn, policy, deadline, move |conn| conn.hgetall::<&str, HashMap<String, Vec<u8>>>(&key)))
+                .map(|fields| Some(serde_json::json!({"fields": fields.iter().map(|(f, v)| (f.clone(), stored_json(v))).collect::<serde_json::Map<_, _>>()})))
+        },
+        "set" => {
+            // Function to queue the write with everything derived from it
+            let queue = |pipe: &mut redis::Pipeline, change: Change| {
+                if document {
+                    document::add_store(pipe, target, &stored);
+                } else if chunked {
+                    chunking::add_store(pipe, target, &stored);
+                } else {
+                    chunking::remove(pipe, target); // Chunks of a large value it replaces
+                    pipe.set(target, &stored).ignore();
+                }
+                index::add(pipe, &req.key, event_value.as_ref().filter(|_| !sensitive)); // Sensitive values are not scored
+                fanout::add(pipe, &req.key, &stored, &publication);
+                hooks::add(pipe, &req.key, change, announced.filter(|_| !sensitive));
+            };
+            traced_redis(trace, "set", || if hooks::needs_existence(&req.key, false) {
+                // Create and update hooks need to know if the key existed; watching it keeps that true until the write
+                redis::transaction(redis_client, &[target], |conn, pipe| {
+                    let change = if conn.exists::<_, bool>(target)? { Change::Updated } else { Change::Created };
+                    queue(pipe, change);
+                    pipe.query::<Option<()>>(conn).map(|committed| committed.map(|_| change))
+                })
+            } else {
+                let mut pipe = redis::pipe();
+                pipe.atomic();
+                queue(&mut pipe, Change::Written);
+                pipe.query::<()>(redis_client).map(|_| Change::Written)
+            }
+                .and_then(|change| partition.as_ref().map_or(Ok(()), |partition| partition::mark(redis_client, partition)).map(|_| change))
+                .and_then(|change| publish_event(redis_client, &publication).map(|_| change)))
+                .map(|change| {
+                    lifecycle = Some(change);
+                    partition.as_ref().map(|partition| serde_json::json!({"partition": partition.key}))
+                })
+        },
+        "del" => {
+            let targets = partition::delete_targets(&req.key);
+            // Function to queue the delete with everything derived from it
+            let queue = |pipe: &mut redis::Pipeline, change: Option<Change>| {
+                pipe.del(&targets).ignore();
+                chunking::remove(pipe, &req.key);
+                index::remove(pipe, &req.key);
+                fanout::remove(pipe, &req.key, &publication);
+                if let Some(change) = change {
+                    hooks::add(pipe, &req.key, change, None);
+                }
+            };
+            traced_redis(trace, "del", || if hooks::needs_existence(&req.key, true) {
+                // Delete hooks only run if the key existed; watching it keeps that true until the delete
+                redis::transaction(redis_client, &targets, |conn, pipe| {
+                    let change = (conn.exists::<_, u64>(&targets)? > 0).then_some(Change::Deleted);
+                    queue(pipe, change);
+                    pipe.query::<Option<()>>(conn).map(|committed| committed.map(|_| change))
+                })
+            } else {
+                let mut pipe = redis::pipe();
+                pipe.atomic();
+                queue(&mut pipe, None);
+                pipe.query::<()>(redis_client).map(|_| None)
+            }
+                .and_then(|change| publish_event(redis_client, &publication).map(|_| change)))
+                .map(|change| {
+                    lifecycle = change;
+                    None
+                })
+        },
+        "soft-delete" => {
+            let grace = req.ttl.unwrap_or(args.soft_delete_grace).max(1);
+            traced_redis(trace, "soft-delete", || tombstone::soft_delete(redis_client, &req.key, grace)
+                .and_then(|deleted| if deleted { publish_event(redis_client, &publication).map(|_| deleted) } else { Ok(deleted) }))
+                .map(|deleted| Some(serde_json::json!({"deleted": deleted, "grace_secs": grace})))
+        },
+        "restore" => match traced_redis(trace, "restore", || tombstone::restore(redis_client, &req.key)) {
+            Ok(Restore::Missing) => return response("error", &format!("Key {} has no soft-deleted value, or its grace window has passed", req.key)),
+            Ok(Restore::Conflict) => return response("error", &format!("Key {} was written since it was soft-deleted, delete it before restoring", req.key)),
+            Ok(Restore::Restored) => traced_redis(trace, "restore", || {
+                let value = if sensitive { None } else { restored_value(redis_client, &req.key)? }; // Values of sensitive keys stay out of indexes and events
+                let mut pipe = redis::pipe();
+                index::add(&mut pipe, &req.key, value.as_ref());
+                pipe.query::<()>(redis_client)?;
+                if let Some(ref mut usage) = usage {
+                    usage.remeasure(redis_client, &req.key)?;
+                }
+                let event = match value {
+                    Some(ref value) => format!("restore: {}", value),
+                    None => "restore".to_string(),
+                };
+                publish_event(redis_client, &publish::publication("restore", &req.key, &event, value.as_ref()))
+            })
+                .map(|_| None),
+            Err(err) => Err(err),
+        },
+        "sadd" => {
+            traced_redis(trace, "sadd", || redis_client.sadd::<&str, &[u8], u64>(&req.key, &stored)
+                .and_then(|added| publish_event(redis_client, &publication).map(|_| added)))
+                .map(|added| { changed = added; None })
+        },
+        "srem" => {
+            traced_redis(trace, "srem", || redis_client.srem::<&str, &[u8], u64>(&req.key, &stored)
+                .and_then(|removed| publish_event(redis_client, &publication).map(|_| removed)))
+                .map(|removed| { changed = removed; None })
+        },
+        "heartbeat" => {
+            let ttl = req.ttl.unwrap_or(args.heartbeat_ttl).max(1);
+            let stamp = received_at().to_string();
+            // SET ... GET returns the previous heartbeat, so a missing one means the producer just came online
+            traced_redis(trace, "heartbeat", || redis::cmd("SET").arg(presence::presence_key(&req.key)).arg(&stamp)
+                .arg("EX").arg(ttl).arg("GET").query::<Option<String>>(redis_client)
+                .and_then(|previous| match previous {
+                    Some(_) => Ok(false),
+                    None => publish::send(redis_client, &req.key, &format!("online: {}", stamp)).map(|_| true),
+                }))
+                .map(|online| Some(serde_json::json!({"ttl": ttl, "came_online": online})))
+        },
+        "xadd" => {
+            traced_redis(trace, "xadd", || redis::cmd("XADD").arg(target).arg("MAXLEN").arg("~").arg(maxlen)
+                .arg("*").arg("data").arg(&stored).query::<String>(redis_client)
+                .and_then(|id| partition.as_ref().map_or(Ok(()), |partition| partition::mark(redis_client, partition)).map(|_| id))
+                .and_then(|id| publish_event(redis_client, &publication).map(|_| id)))
+                .map(|id| match partition {
+                    Some(ref partition) => Some(serde_json::json!({"id": id, "partition": partition.key})),
+                    None => Some(serde_json::json!({"id": id})),
+                })
+        },
+        _ => {
+            validation_failure("invalid_action");
+            return response("error", "Invalid action"); // Handle invalid actions
+        },
+    };
+
+    // Return success or error response based on Redis operation result
+    match result {
+        Ok(data) => {
+            if let Some(ref mut usage) = usage {
+                usage.record(&req.action, &req.key, stored.len() as u64, changed, maxlen);
+            }
+            drop(usage); // Let other writes of the producer proceed
+            if !reading && req.action != "heartbeat" { // Heartbeats are too frequent to forward
+                webhook::notify(&req.action, &req.key, announced.filter(|_| !sensitive)); // Forward to matching webhook sinks
+            }
+            if let Some(change) = lifecycle {
+                hooks::notify(&req.key, change, announced.filter(|_| !sensitive));
+            }
+            match data {
+                Some(data) => data_response("Action completed successfully", data),
+                None => response("ok", "Action completed successfully"),
+            }
+        },
+        Err(err) if err.is_timeout() && (deadline.is_some() || policy.is_some_and(|policy| policy.timeout.is_some())) => {
+            conn.discard(); // Its reply may still come
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) || policy.is_none_or(|policy| policy.timeout.is_none()) {
+                metrics::incr(&METRICS.deadlines_exceeded);
+                response("error", &format!("DEADLINE_EXCEEDED: Redis did not answer within timeout_ms, the {} may still take effect", req.action))
+            } else {
+                metrics::incr(&METRICS.redis_timeouts);
+                response("error", &format!("TIMEOUT: Redis did not answer within the call policy's timeout_ms, the {} may still take effect", req.action))
+            }
+        },
+        Err(err) => {
+            metrics::incr(&METRICS.redis_errors);
+            response("error", &err.to_string())
+        },
+    }
+}
+
+// Function to read the value of a restored key for its indexes and event (None for sets and streams)
+fn restored_value(conn: &mut Storage, key: &str) -> redis::RedisResult<Option<Value>> {
+    let stored = if document::is_document(key) {
+        document::read(conn, key)?
+    } else if redis::cmd("TYPE").arg(key).query::<String>(conn)? == "string" {
+        let stored = conn.get::<&str, Option<Vec<u8>>>(key)?;
+        chunking::join(conn, key, stored)?
+    } else {
+        None
+    };
+    Ok(stored.as_deref().and_then(|stored| compression::decompress(stored).ok()).map(|plain| stored_json(&plain)))
+}
+
+// Function to announce a write as its publish policy says (nothing for silent writes)
+fn publish_event(conn: &mut Storage, publication: &Option<(String, String)>) -> redis::RedisResult<()> {
+    match publication {
+        Some((channel, payload)) => publish::send(conn, channel, payload),
+        None => Ok(()),
+    }
+}
+
+// Function to check if a message is a client's answer to a keepalive ping (answers get no response)
+fn is_pong(data: &str) -> bool {
+    data.contains("pong") && serde_json::from_str::<Request>(data).is_ok_and(|req| req.action == "pong")
+}
+
+// Function to publish a proxy event, ignoring failures since events are best effort
+fn publish_proxy_event(conn: &mut Storage, mut event: Value) {
+    device::stamp(&mut event);
+    let _ = publish::send(conn, PROXY_EVENTS_CHANNEL, &event.to_string());
+}
+
+// Function to handle client connections
+fn handle_client(mut stream: impl ClientStream, router: Arc<Router>, args: Arc<Args>, listener: Arc<ListenerConfig>, handle: &HandlerHandle) {
+    let mut framer = LineFramer::new(); // Incoming data not yet handled as requests
+    handle.set_listener(&listener.path);
+    let mut session = Session::new(listener, handle.clone()); // Legacy session until the client says hello
+    let idle_timeout = args.idle_timeout.map(Duration::from_secs);
+    let keepalive_interval = args.keepalive_interval.map(Duration::from_secs);
+    let mut last_activity = Instant::now(); // Time of the last message received from the client
+    let mut ping_sent = false; // Whether a keepalive ping is waiting for its pong
+
+    if idle_timeout.is_some() || keepalive_interval.is_some() {
+        stream.set_read_timeout(Some(IDLE_CHECK_INTERVAL)).expect("Failed to set read timeout"); // Wake up periodically to check for idleness
+    }
+
+    loop {
+        let mut temp_buffer = [0; 1024]; // Temporary buffer to read data in chunks
+        match stream.read(&mut temp_buffer) {
+            Ok(0) => break, // Connection closed by client
+            Ok(size) => {
+                handle.record_read(size);
+                last_activity = Instant::now();
+                ping_sent = false;
+                framer.push(&temp_buffer[..size]); // Append new data to the buffer
+                while let Some(line) = framer.next_message() { // Handle every complete (newline-delimited) message
+                    if let Ok(data) = String::from_utf8(line) {
+                        if is_pong(data.trim()) {
+                            continue; // Keepalive answer, nothing to reply
+                        }
+                        handle.begin_request();
+                        let mut response = handle_request(&router, &args, &mut session, data.trim()); // Process the request
+                        handle.end_request();
+                        if response.is_empty() {
+                            continue; // Dropped by fault injection
+                        }
+                        if session.has_feature("framing:newline") {
+                            response.push('\n'); // Newline-terminate responses for clients that negotiated it
+                        }
+                        if let Err(err) = handle.write(&mut stream, response.as_bytes()) { // Send response
+                            eprintln!("Failed to write to client: {}", err);
+                            return;
+                        }
+                        if let Some(pattern) = session.export.take() { // Records follow the response, then a summary line
+                            if !stream_export(&mut stream, &router, &args, &session, &pattern) {
+                                return;
+                            }
+                        }
+                        if let Some(feed) = session.feed.take() { // Subscribed clients only receive events from now on
+                            stream_events(&mut stream, feed, &session);
+                            return;
+                        }
+                    }
+                }
+            }
+            Err(ref err) if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut => {
+                let idle = last_activity.elapsed();
+                if idle_timeout.is_some_and(|timeout| idle >= timeout) {
+                    eprintln!("Warning: closing client connection idle for {:.0?}", idle);
+                    if let Ok(mut conn) = router.default_backend().connection() {
+                        publish_proxy_event(&mut conn, serde_json::json!({
+                            "event": "idle_timeout",
+                            "idle_secs": idle.as_secs()
+                        }));
+                    }
+                    break;
+                }
+                if !ping_sent && session.has_feature("keepalive") && keepalive_interval.is_some_and(|interval| idle >= interval) {
+                    let mut ping = response("ping", "keepalive").to_json();
+                    ping.push('\n');
+                    if handle.write(&mut stream, ping.as_bytes()).is_err() {
+                        break; // Client is gone
+                    }
+                    ping_sent = true;
+                }
+            }
+            Err(err) => {
+                eprintln!("Failed to read from client: {}", err);
+                break;
+            }
+        }
+    }
+}
+
+
+// Function to accept connections on one listener, handing each to a supervised handler thread; on
+// Unix the loop ends once an upgrade handed the socket to a new process
+fn serve(socket_listener: BoundListener, listener: Arc<ListenerConfig>, router: Arc<Router>, args: Arc<Args>, supervisor: Arc<Supervisor>) {
+    let nodelay = |stream: std::io::Result<TcpStream>| {
+        stream.inspect(|stream| { let _ = stream.set_nodelay(true); }) // Responses are small and latency matters
+    };
+    match socket_listener {
+        #[cfg(unix)]
+        BoundListener::Unix(socket_listener) => {
+            let incoming = handover::until_draining(socket_listener.as_raw_fd(), || socket_listener.accept().map(|(stream, _)| stream));
+            accept(incoming, listener, router, args, supervisor)
+        }
+        #[cfg(unix)]
+        BoundListener::Tcp(socket_listener) => {
+            let incoming = handover::until_draining(socket_listener.as_raw_fd(), || socket_listener.accept().map(|(stream, _)| stream));
+            accept(incoming.map(nodelay), listener, router, args, supervisor)
+        }
+        #[cfg(not(unix))]
+        BoundListener::Tcp(socket_listener) => accept(socket_listener.incoming().map(nodelay), listener, router, args, supervisor),
+        #[cfg(windows)]
+        BoundListener::Pipe(mut socket_listener) => accept(std::iter::from_fn(move || Some(socket_listener.accept())), listener, router, args, supervisor),
+    }
+}
+
+// Function to run the accept loop of one listener over its incoming connections
+fn accept<S: ClientStream>(incoming: impl Iterator<Item = std::io::Result<S>>, listener: Arc<ListenerConfig>, router: Arc<Router>, args: Arc<Args>, supervisor: Arc<Supervisor>) {
+    // Loop to accept incoming connections
+    for stream in incoming {
+        match stream {
+            Ok(socket) => {
+                let router_clone = Arc::clone(&router); // Clone the Redis backends for the new thread
+                let args_clone = Arc::clone(&args); // Clone the arguments for the new thread
+                let listener_clone = Arc::clone(&listener); // Clone the listener defaults for the new thread
+                supervisor.spawn(move |handle| handle_client(socket, router_clone, args_clone, listener_clone, handle)); // Spawn a supervised thread to handle the client
+            }
+            Err(err) => eprintln!("Connection failed: {}", err), // Print error if connection fails
+        }
+    }
+}
+
+// Main function to start the proxy service
+pub fn main() -> std::io::Result<()> {
+    let args = Arc::new(Args::parse_from(rustredis::multicall::args())); // Parse command line arguments
+    if args.check_config {
+        config_check::run(&args, args.check_connectivity);
+    }
+    lazy_static::initialize(&PROXY_START); // Start the monotonic clock
+
+    if args.schema_dir.is_some() || !args.constraints.is_empty() {
+        // A broken schema must not go unnoticed
+        let (mut active, shadow) = match args.schema_dir {
+            Some(ref dir) => read_schema_dir(dir).map_err(std::io::Error::other)?,
+            None => Default::default(),
+        };
+        add_constraints(&mut active, &args.constraints).map_err(std::io::Error::other)?;
+        let count = load_schemas(active, shadow).map_err(std::io::Error::other)?;
+        match args.schema_dir {
+            Some(ref dir) => println!("Loaded {} schemas from {} and {} field constraints", count, dir.display(), args.constraints.len()),
+            None => println!("Loaded {} schemas from {} field constraints", count, args.constraints.len()),
+        }
+    }
+
+    if args.device_file.is_some() || !args.device_fields.is_empty() {
+        device::start(args.device_file.as_deref(), &args.device_fields, args.stamp_device).map_err(std::io::Error::other)?;
+    } else if args.stamp_device {
+        return Err(std::io::Error::other("--stamp-device needs --device-file or --device"));
+    }
+
+    if let Some(ref key_file) = args.encryption_key_file {
+        encryption::start(args.sensitive.clone(), key_file).map_err(std::io::Error::other)?; // Refuse to store sensitive values in plaintext
+    }
+
+    if let Some(ref endpoint) = args.otlp_endpoint {
+        telemetry::start_exporter(endpoint); // Export spans of traced requests
+    }
+    if let Some(ref path) = args.access_log {
+        let config = AccessLogConfig { path: path.clone(), sample: args.access_log_sample, failures: args.access_log_failures, max_bytes: args.access_log_max_bytes };
+        access_log::start(config).map_err(|e| std::io::Error::other(format!("Failed to open access log {}: {}", path.display(), e)))?;
+    }
+    #[cfg(feature = "chaos")]
+    eprintln!("Warning: built with the chaos feature, admin sockets can inject faults into requests");
+    webhook::start(args.webhooks.clone(), hooks::webhook_urls(&args.hooks), args.webhook_retries); // Start webhook delivery threads
+    upstream::start(args.upstreams.clone());
+    publish::start(args.publish_policies.clone());
+    partition::start(args.partitions.clone());
+    if let Some(above) = args.chunk_above {
+        chunking::start(above, args.chunk_size);
+    }
+    index::start(args.indexes.clone());
+    fanout::start(args.fanouts.clone());
+    hooks::start(args.hooks.clone());
+    publish::use_sharded(args.sharded_pubsub);
+    publish::use_event_log(args.event_log, args.event_log_maxlen);
+
+    let router = Arc::new(Router::new(&args.backends, args.routes.clone(), &args.replicas, args.max_replica_lag_bytes).map_err(std::io::Error::other)?); // Redis backends shared by all handlers
+    deploy::start(&router, Duration::from_secs(args.swap_watch), args.swap_error_margin);
+    call_policy::start(args.redis_policies.clone());
+    router.start_health_checks(Duration::from_secs(args.backend_check_interval));
+    if args.presence_watcher || args.expired_events {
+        presence::start_watcher(&router, presence::Watch { offline: args.presence_watcher, expired: args.expired_events });
+    }
+    search::start(args.searches.clone(), &router).expect("Failed to create search indexes");
+    document::start(args.json_storage.clone(), &router).expect("Failed to check for the RedisJSON module");
+    if !args.quotas.is_empty() { // Measure what producers with a quota already occupy
+        quota::start(args.quotas.clone(), &router).expect("Failed to measure producer quota usage");
+    }
+    if !args.alert_escalations.is_empty() {
+        alert::start_escalation(&router, args.alert_escalations.clone());
+    }
+    if !args.rollups.is_empty() {
+        rollup::start(args.rollups.clone(), &router);
+    }
+    if let Some(max_in_flight) = args.max_in_flight {
+        shedding::start(max_in_flight, args.shed_below, args.priority_limits.clone());
+    }
+    if let Some(interval) = args.stats_push_interval {
+        start_stats_push(&router, Duration::from_secs(interval));
+    }
+    let supervisor = Supervisor::new(); // Registry of running client handlers shared by all listeners
+    supervisor.start_monitor(Duration::from_secs(args.supervisor_interval), Duration::from_secs(args.stall_threshold));
+
+    let mut listeners = args.listeners.clone();
+    if listeners.is_empty() {
+        listeners.push(ListenerConfig::new(SOCKET_PATH)); // Single default socket when none configured
+    }
+
+    // Bind every socket up front so a bad listener fails startup instead of running half configured;
+    // sockets passed by systemd or by the proxy this one replaces are used as they are
+    #[cfg(unix)]
+    let mut inherited = handover::inherited();
+    let mut bound = Vec::new();
+    for listener in listeners {
+        #[cfg(unix)]
+        let adopted = inherited.adopt(&listener);
+        #[cfg(not(unix))]
+        let adopted = None;
+        let socket_listener = match adopted {
+            Some(socket_listener) => {
+                println!("Listening on {} (inherited)", listener.path);
+                socket_listener
+            }
+            None => {
+                let socket_listener = listener.bind()?;
+                println!("Listening on {}", listener.path);
+                socket_listener
+            }
+        };
+        #[cfg(unix)]
+        handover::listening(&socket_listener);
+        bound.push((socket_listener, Arc::new(listener)));
+    }
+
+    // Start one accept loop per listener, all sharing the same Redis backends and supervisor
+    let mut accept_threads = Vec::new();
+    for (socket_listener, listener) in bound {
+        let (router, args, supervisor) = (Arc::clone(&router), Arc::clone(&args), Arc::clone(&supervisor));
+        accept_threads.push(thread::spawn(move || serve(socket_listener, listener, router, args, supervisor)));
+    }
+    println!("Redis Proxy Service Started. Waiting for connections...");
+    #[cfg(unix)]
+    inherited.ready();
+
+    if args.startup_snapshot {
+        let (router, args) = (Arc::clone(&router), Arc::clone(&args));
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(args.snapshot_delay));
+            match snapshot::publish(&router, args.snapshot_channel.as_deref()) {
+                Ok(count) => println!("Published startup snapshot of {} keys", count),
+                Err(err) => eprintln!("Startup snapshot failed: {}", err),
+            }
+        });
+    }
+
+    for accept_thread in accept_threads {
+        accept_thread.join().expect("Listener thread panicked");
+    }
+    #[cfg(unix)]
+    handover::wait_drained(&supervisor, Duration::from_secs(args.drain_timeout));
+
+    Ok(()) // Return Ok to indicate successful execution
+}
@@ -0,0 +1,88 @@
+// Import necessary crates and modules
+use super::router::Router; // For scanning every backend keys may be routed to
+use redis::Commands; // For scanning keys
+use rustredis::schema::key_producer; // For grouping keys by producer namespace
+use serde_json::{json, Value}; // For the report
+use std::collections::BTreeMap; // For namespaces in name order
+
+// Define how many keys per namespace are measured unless the request asks otherwise
+pub const DEFAULT_SAMPLE: u64 = 200;
+
+// Define how many of the largest sampled keys are listed per namespace unless the request asks otherwise
+pub const DEFAULT_TOP: usize = 5;
+
+// Define the figures of one producer namespace
+#[derive(Default)]
+struct Namespace {
+    keys: Vec<(usize, String)>, // Keys of the namespace, by backend index
+    sampled: u64, // Keys measured
+    sampled_bytes: u64, // Their total MEMORY USAGE
+    largest: Vec<(u64, String)>, // Largest measured keys, biggest first
+}
+
+// Function to pick `count` keys spread evenly over the namespace's keys
+fn spread<T>(items: &[T], count: u64) -> impl Iterator<Item = &T> {
+    let count = usize::try_from(count).unwrap_or(usize::MAX).min(items.len());
+    let step = items.len().checked_div(count).unwrap_or(1);
+    items.iter().step_by(step.max(1)).take(count)
+}
+
+// Function to report Redis memory per producer namespace: keys matching `pattern` are counted, up
+// to `sample` of each namespace measured with MEMORY USAGE, and the namespace total estimated
+// from their average, largest namespaces first. Namespaces `allowed` rejects are left out (keys of
+// no producer count as _other)
+pub fn report(router: &Router, pattern: &str, allowed: impl Fn(&str) -> bool, sample: u64, top: usize) -> redis::RedisResult<Value> {
+    let backends = router.backends();
+    let mut namespaces: BTreeMap<String, Namespace> = BTreeMap::new();
+    for (index, backend) in backends.iter().enumerate() {
+        let mut conn = backend.connection()?;
+        let names: Vec<String> = conn.scan_match::<_, String>(pattern)?.collect();
+        for name in names {
+            if router.backend_for(&name).name != backend.name {
+                continue; // Left over from a routing change, not counted twice
+            }
+            let producer = key_producer(&name).unwrap_or("_other");
+            if !allowed(producer) {
+                continue; // Another producer's namespace, hidden from restricted sockets
+            }
+            namespaces.entry(producer.to_string()).or_default().keys.push((index, name));
+        }
+    }
+
+    for namespace in namespaces.values_mut() {
+        // Measure the sample in one pipeline per backend
+        let mut by_backend: BTreeMap<usize, Vec<&String>> = BTreeMap::new();
+        for (index, name) in spread(&namespace.keys, sample) {
+            by_backend.entry(*index).or_default().push(name);
+        }
+        let mut measured = Vec::new();
+        for (index, names) in by_backend {
+            let mut pipe = redis::pipe();
+            for name in &names {
+                pipe.cmd("MEMORY").arg("USAGE").arg(name.as_str()).arg("SAMPLES").arg(0);
+            }
+            let sizes: Vec<Option<u64>> = pipe.query(&mut *backends[index].connection()?)?;
+            measured.extend(names.into_iter().zip(sizes).filter_map(|(name, size)| size.map(|size| (size, name.clone())))); // None if deleted meanwhile
+        }
+        namespace.sampled = measured.len() as u64;
+        namespace.sampled_bytes = measured.iter().map(|(size, _)| size).sum();
+        measured.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+        measured.truncate(top);
+        namespace.largest = measured;
+    }
+
+    let mut rows: Vec<Value> = namespaces.into_iter().map(|(producer, namespace)| {
+        let average = namespace.sampled_bytes.checked_div(namespace.sampled).unwrap_or(0);
+        json!({
+            "producer": producer,
+            "keys": namespace.keys.len(),
+            "sampled": namespace.sampled,
+            "average_bytes": average,
+            "estimated_bytes": average * namespace.keys.len() as u64,
+            "top": namespace.largest.iter().map(|(size, key)| json!({"key": key, "bytes": size})).collect::<Vec<_>>(),
+        })
+    }).collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row["estimated_bytes"].as_u64().unwrap_or(0)));
+    let total: u64 = rows.iter().filter_map(|row| row["estimated_bytes"].as_u64()).sum();
+    Ok(json!({"pattern": pattern, "estimated_bytes": total, "namespaces": rows}))
+}
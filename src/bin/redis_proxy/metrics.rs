@@ -0,0 +1,111 @@
+// Import necessary crates and modules
+use std::collections::BTreeMap; // For counters keyed by name, listed in order
+use std::sync::atomic::{AtomicU64, Ordering}; // For lock-free counters shared between threads
+use std::sync::Mutex; // For the keyed counters and the last error
+use std::time::SystemTime; // For timestamping the last error
+
+// Define the most recent error returned to a client
+pub struct LastError {
+    pub action: String, // Action of the failed request (empty if it could not be parsed)
+    pub message: String, // Error message sent to the client
+    pub at: SystemTime, // When the error was returned
+}
+
+// Define the process-wide proxy counters
+pub struct Metrics {
+    pub connections_accepted: AtomicU64, // Client connections accepted since start
+    pub connections_active: AtomicU64, // Client handlers currently running
+    pub handler_panics: AtomicU64, // Client handlers that terminated with a panic
+    pub redis_connect_failures: AtomicU64, // Requests that could not get a connection to their Redis backend
+    pub redis_errors: AtomicU64, // Redis commands that failed
+    pub values_compressed: AtomicU64, // Values stored compressed
+    pub requests_relayed: AtomicU64, // Requests forwarded to upstream proxies
+    pub upstream_failures: AtomicU64, // Forwarded requests no upstream proxy answered
+    pub compression_saved_bytes: AtomicU64, // Bytes of Redis memory compression saved on writes (not net of later overwrites)
+    pub bytes_received: AtomicU64, // Bytes received from clients
+    pub bytes_sent: AtomicU64, // Bytes of responses and events written to clients
+    pub blocked_write_ms: AtomicU64, // Milliseconds spent waiting for clients to take writes
+    pub responses_compressed: AtomicU64, // Responses and export records sent with a zstd-compressed payload
+    pub response_saved_bytes: AtomicU64, // Bytes response compression kept off the client links
+    pub deadlines_exceeded: AtomicU64, // Requests answered DEADLINE_EXCEEDED because Redis took longer than their timeout_ms
+    pub redis_timeouts: AtomicU64, // Requests answered TIMEOUT because Redis took longer than their action's call policy allows
+    pub redis_retries: AtomicU64, // Connects and reads attempted again under a call policy
+    pub hedged_reads: AtomicU64, // Slow reads also sent to another node of their backend
+    pub hedges_won: AtomicU64, // Hedged reads answered by the other node first
+    pub requests: Mutex<BTreeMap<String, u64>>, // Requests by action
+    pub validation_failures: Mutex<BTreeMap<&'static str, u64>>, // Rejected requests by reason
+    pub shadow_failures: Mutex<BTreeMap<String, u64>>, // Accepted values their shadow schema would reject, by base key
+    pub writes_shed: Mutex<BTreeMap<String, u64>>, // Writes rejected under overload, by producer
+    pub writes_delayed: AtomicU64, // Writes that waited for higher priority writes under overload
+    pub last_error: Mutex<Option<LastError>>, // Most recent error response
+}
+
+impl Metrics {
+    // Function to create a zeroed set of counters
+    const fn new() -> Self {
+        Metrics {
+            connections_accepted: AtomicU64::new(0),
+            connections_active: AtomicU64::new(0),
+            handler_panics: AtomicU64::new(0),
+            redis_connect_failures: AtomicU64::new(0),
+            redis_errors: AtomicU64::new(0),
+            values_compressed: AtomicU64::new(0),
+            requests_relayed: AtomicU64::new(0),
+            upstream_failures: AtomicU64::new(0),
+            compression_saved_bytes: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            blocked_write_ms: AtomicU64::new(0),
+            responses_compressed: AtomicU64::new(0),
+            response_saved_bytes: AtomicU64::new(0),
+            deadlines_exceeded: AtomicU64::new(0),
+            redis_timeouts: AtomicU64::new(0),
+            redis_retries: AtomicU64::new(0),
+            hedged_reads: AtomicU64::new(0),
+            hedges_won: AtomicU64::new(0),
+            requests: Mutex::new(BTreeMap::new()),
+            validation_failures: Mutex::new(BTreeMap::new()),
+            shadow_failures: Mutex::new(BTreeMap::new()),
+            writes_shed: Mutex::new(BTreeMap::new()),
+            writes_delayed: AtomicU64::new(0),
+            last_error: Mutex::new(None),
+        }
+    }
+}
+
+// Function to increment a counter by one
+pub fn incr(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+// Function to increase a counter by an amount
+pub fn add(counter: &AtomicU64, amount: u64) {
+    counter.fetch_add(amount, Ordering::Relaxed);
+}
+
+// Function to decrement a counter by one
+pub fn decr(counter: &AtomicU64) {
+    counter.fetch_sub(1, Ordering::Relaxed);
+}
+
+// Function to read the current value of a counter
+pub fn get(counter: &AtomicU64) -> u64 {
+    counter.load(Ordering::Relaxed)
+}
+
+// Function to increment the counter of one name by one
+pub fn incr_keyed<K: Ord>(counters: &Mutex<BTreeMap<K, u64>>, name: K) {
+    *counters.lock().unwrap().entry(name).or_insert(0) += 1;
+}
+
+// Function to remember the most recent error response
+pub fn record_error(action: &str, message: &str) {
+    *METRICS.last_error.lock().unwrap() = Some(LastError {
+        action: action.to_string(),
+        message: message.to_string(),
+        at: SystemTime::now(),
+    });
+}
+
+// Define the global metrics instance shared by all listeners and handlers
+pub static METRICS: Metrics = Metrics::new();
@@ -0,0 +1,161 @@
+// Import necessary crates and modules
+use rustredis::glob::glob_match; // For matching keys against partition patterns
+use rustredis::storage::Storage; // For the connection partitions are marked through
+use std::sync::OnceLock; // For the global partition list
+use std::time::{SystemTime, UNIX_EPOCH}; // For the bucket of a write
+
+// Define the suffix of the key pointing at a partitioned key's latest bucket
+const LATEST_SUFFIX: &str = ":latest";
+
+// Define the length of one time bucket
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Granularity {
+    Hour, // Buckets like :2024-06-01T12
+    Day, // Buckets like :2024-06-01
+}
+
+impl Granularity {
+    // Function to return the length of a bucket in seconds
+    fn secs(self) -> u64 {
+        match self {
+            Granularity::Hour => 3600,
+            Granularity::Day => 86400,
+        }
+    }
+}
+
+// Define the configuration of one partitioned key pattern
+#[derive(Clone, Debug)]
+pub struct PartitionConfig {
+    pub pattern: String, // Key glob pattern of the partitioned writes
+    pub granularity: Granularity,
+    pub ttl: Option<u64>, // Seconds a bucket is kept after it ends
+}
+
+impl PartitionConfig {
+    // Function to parse a partition specification of the form PATTERN=hour|day[,ttl=SECS]
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (pattern, rest) = spec.split_once('=').ok_or("expected PATTERN=hour|day[,ttl=SECS]")?;
+        let mut parts = rest.split(',');
+        let granularity = match parts.next() {
+            Some("hour") => Granularity::Hour,
+            Some("day") => Granularity::Day,
+            _ => return Err(format!("invalid partition granularity in '{}', expected hour or day", spec)),
+        };
+        let mut config = PartitionConfig { pattern: pattern.to_string(), granularity, ttl: None };
+        for option in parts {
+            match option.split_once('=') {
+                Some(("ttl", secs)) => config.ttl = Some(secs.parse().map_err(|_| format!("invalid partition ttl '{}'", secs))?),
+                _ => return Err(format!("unknown partition option '{}'", option)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+// Define where a partitioned write goes
+pub struct Partition {
+    pub key: String, // Key of the current bucket (<key>:<bucket>)
+    latest: String, // Key pointing at the current bucket
+    expire_at: Option<u64>, // Unix time the bucket (and the pointer, unless written again) expires
+}
+
+// Define the partitions configured at startup
+static PARTITIONS: OnceLock<Vec<PartitionConfig>> = OnceLock::new();
+
+// Function to set the partitioned key patterns
+pub fn start(configs: Vec<PartitionConfig>) {
+    let _ = PARTITIONS.set(configs);
+}
+
+// Function to find the partition configuration of a key (first match wins)
+fn config_for(key: &str) -> Option<&'static PartitionConfig> {
+    PARTITIONS.get()?.iter().find(|config| glob_match(&config.pattern, key))
+}
+
+// Function to return the key pointing at the latest bucket of a partitioned key
+fn latest_key(key: &str) -> String {
+    format!("{}{}", key, LATEST_SUFFIX)
+}
+
+// Function to check if a key is partitioned into time buckets
+pub fn is_partitioned(key: &str) -> bool {
+    config_for(key).is_some()
+}
+
+// Function to check if a key is the latest pointer of a partitioned key
+pub fn is_latest_pointer(key: &str) -> bool {
+    key.strip_suffix(LATEST_SUFFIX).is_some_and(|key| config_for(key).is_some())
+}
+
+// Function to return the partitioned key a bucket key (<key>:<bucket>) belongs to, or the key itself
+pub fn owner_key(key: &str) -> &str {
+    let Some((owner, bucket)) = key.rsplit_once(':') else { return key };
+    let is_bucket = |config: &PartitionConfig| match config.granularity {
+        Granularity::Day => bucket.len() == 10,
+        Granularity::Hour => bucket.len() == 13 && bucket.as_bytes()[10] == b'T',
+    };
+    let digits = bucket.bytes().enumerate().all(|(i, b)| if i == 4 || i == 7 { b == b'-' } else { i == 10 || b.is_ascii_digit() });
+    if digits && config_for(owner).is_some_and(is_bucket) { owner } else { key }
+}
+
+// Function to format the start of a bucket as 2024-06-01T12 (hour) or 2024-06-01 (day), in UTC
+fn bucket_name(start: u64, granularity: Granularity) -> String {
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let days = (start / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    match granularity {
+        Granularity::Day => format!("{:04}-{:02}-{:02}", year, month, day),
+        Granularity::Hour => format!("{:04}-{:02}-{:02}T{:02}", year, month, day, start % 86400 / 3600),
+    }
+}
+
+// Function to return the bucket a write on a key goes to now, if the key is partitioned
+pub fn for_write(key: &str) -> Option<Partition> {
+    let config = config_for(key)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let length = config.granularity.secs();
+    let start = now - now % length;
+    Some(Partition {
+        key: format!("{}:{}", key, bucket_name(start, config.granularity)),
+        latest: latest_key(key),
+        expire_at: config.ttl.map(|ttl| start + length + ttl),
+    })
+}
+
+// Function to point the key's latest pointer at the bucket just written and apply the bucket's expiry
+pub fn mark(conn: &mut Storage, partition: &Partition) -> redis::RedisResult<()> {
+    let mut pipe = redis::pipe();
+    pipe.cmd("SET").arg(&partition.latest).arg(&partition.key).ignore();
+    if let Some(expire_at) = partition.expire_at {
+        pipe.cmd("EXPIREAT").arg(&partition.key).arg(expire_at).ignore();
+        pipe.cmd("EXPIREAT").arg(&partition.latest).arg(expire_at).ignore();
+    }
+    pipe.query(conn)
+}
+
+// Function to return the key a read of a key goes to: the latest bucket of a partitioned key
+pub fn read_target(conn: &mut Storage, key: &str) -> redis::RedisResult<String> {
+    if config_for(key).is_none() {
+        return Ok(key.to_string());
+    }
+    let latest: Option<String> = redis::cmd("GET").arg(latest_key(key)).query(conn)?;
+    Ok(latest.unwrap_or_else(|| key.to_string())) // Nothing written yet (or expired)
+}
+
+// Function to return the keys a deletion of a key removes: also the latest pointer of a partitioned key,
+// whose buckets are left to their expiry
+pub fn delete_targets(key: &str) -> Vec<String> {
+    match config_for(key) {
+        Some(_) => vec![key.to_string(), latest_key(key)],
+        None => vec![key.to_string()],
+    }
+}
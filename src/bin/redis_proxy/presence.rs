@@ -0,0 +1,108 @@
+// Import necessary crates and modules
+use super::index; // For removing expired keys from their indexes
+use super::partition; // For telling latest pointers from the keys they point at
+use super::publish; // For announcing offline and expired keys with the configured pub/sub flavour
+use super::quota; // For releasing the quota occupied by expired keys
+use super::router::Router; // For watching every backend and publishing on the key's backend
+use rustredis::schema::key_producer; // For the namespace of expired keys
+use std::sync::Arc; // For sharing the router with watcher threads
+use std::thread; // For one watcher thread per backend
+use std::time::Duration; // For the resubscribe delay
+
+// Define the namespace heartbeat keys are kept in (cs:<producer>:... -> cs:_presence:<producer>:...)
+const PRESENCE_PREFIX: &str = "cs:_presence:";
+
+// Define the channel pattern on which Redis announces expired keys
+const EXPIRED_EVENTS: &str = "__keyevent@*__:expired";
+
+// Define how long a watcher waits before resubscribing after losing its connection
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+// Function to return the heartbeat key of a producer key
+pub fn presence_key(key: &str) -> String {
+    format!("{}{}", PRESENCE_PREFIX, key.trim_start_matches("cs:"))
+}
+
+// Function to return the producer key of a heartbeat key, if it is one
+fn producer_key(presence_key: &str) -> Option<String> {
+    presence_key.strip_prefix(PRESENCE_PREFIX).map(|rest| format!("cs:{}", rest))
+}
+
+// Function to enable the expired-key notifications the watcher relies on, keeping the classes already enabled
+fn enable_expired_events(conn: &mut redis::Connection) -> redis::RedisResult<()> {
+    let (_, mut flags): (String, String) = redis::cmd("CONFIG").arg("GET").arg("notify-keyspace-events").query(conn)?;
+    if !flags.contains('E') {
+        flags.push('E');
+    }
+    if !flags.contains('x') && !flags.contains('A') {
+        flags.push('x');
+    }
+    redis::cmd("CONFIG").arg("SET").arg("notify-keyspace-events").arg(flags).query(conn)
+}
+
+// Define which expirations the watchers announce
+#[derive(Clone, Copy)]
+pub struct Watch {
+    pub offline: bool, // Lapsed heartbeats, as `offline` on the producer key's channel
+    pub expired: bool, // Expired producer keys, as `expired` on their own channel
+}
+
+// Function to announce an event on a key's channel, on the backend owning the key like the writes to it
+fn announce(router: &Router, key: &str, channel: &str, payload: &str) {
+    let result = router.backend_for(key).connection().and_then(|mut conn| publish::send(&mut *conn, channel, payload));
+    if let Err(err) = result {
+        eprintln!("Failed to publish {} event for {}: {}", payload, key, err);
+    }
+}
+
+// Function to handle the expiry of a key in a producer namespace: remove it from its indexes,
+// release its quota and publish the `expired` tombstone (subject to the publish policies, like a del)
+fn expire(router: &Router, key: &str) {
+    let Some(producer) = key_producer(key).filter(|producer| !producer.starts_with('_')) else { return }; // Proxy-internal keys
+    if partition::is_latest_pointer(key) {
+        return; // Expires along with the bucket it points at, which is announced
+    }
+    let mut pipe = redis::pipe();
+    index::remove(&mut pipe, key);
+    if let Err(err) = router.backend_for(key).connection().and_then(|mut conn| pipe.query::<()>(&mut *conn)) {
+        eprintln!("Failed to remove expired key {} from its indexes: {}", key, err);
+    }
+    if let Some(ref mut usage) = quota::lock(producer) {
+        usage.record("del", key, 0, 0, 0);
+    }
+    if let Some((channel, payload)) = publish::publication("expired", key, "expired", None) {
+        announce(router, key, &channel, &payload);
+    }
+}
+
+// Function to watch one backend for lapsed heartbeats and expired keys until its connection fails
+fn watch(router: &Router, backend: &str, watching: Watch) -> redis::RedisResult<()> {
+    let backend = router.backends().iter().find(|b| b.name == backend).expect("Watchers only watch known backends");
+    let mut conn = backend.dedicated_connection()?;
+    enable_expired_events(&mut conn)?;
+    let mut pubsub = conn.as_pubsub();
+    pubsub.psubscribe(EXPIRED_EVENTS)?;
+    loop {
+        let expired: String = pubsub.get_message()?.get_payload()?;
+        match producer_key(&expired) {
+            Some(key) if watching.offline => announce(router, &key, &key, "offline"), // On the producer key's channel
+            Some(_) => {}
+            None if watching.expired => expire(router, &expired),
+            None => {}
+        }
+    }
+}
+
+// Function to start one thread per backend publishing `offline` events when heartbeats lapse
+// and/or `expired` events when producer keys expire
+pub fn start_watcher(router: &Arc<Router>, watching: Watch) {
+    for backend in router.backends() {
+        let (router, name) = (Arc::clone(router), backend.name.clone());
+        thread::spawn(move || loop {
+            if let Err(err) = watch(&router, &name, watching) {
+                eprintln!("Presence watcher for backend {} stopped: {}", name, err);
+            }
+            thread::sleep(RESUBSCRIBE_DELAY);
+        });
+    }
+}
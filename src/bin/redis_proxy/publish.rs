@@ -0,0 +1,131 @@
+// Import necessary crates and modules
+use rustredis::event_log::stream_key; // For naming the producers' event log streams
+use rustredis::glob::glob_match; // For matching keys against policy patterns
+use rustredis::schema::key_producer; // For the {producer} placeholder
+use serde_json::Value; // For the {value} placeholder
+use std::sync::atomic::{AtomicBool, Ordering}; // For the pub/sub flavour chosen at startup
+use std::sync::OnceLock; // For the policies configured at startup
+
+// Define how the writes to keys matching a pattern are announced
+#[derive(Clone, Debug)]
+pub struct PublishPolicy {
+    pub pattern: String, // Key glob pattern the policy applies to
+    pub actions: Option<Vec<String>>, // Actions the policy applies to (all writes if None)
+    pub silent: bool, // Publish nothing
+    pub channel: Option<String>, // Channel template (the key if None)
+    pub payload: Option<String>, // Payload template (`<action>: <value>` if None)
+}
+
+impl PublishPolicy {
+    // Function to parse a policy of the form PATTERN=off or PATTERN=[actions=A+B,][channel=TEMPLATE,][payload=TEMPLATE]
+    // (the payload comes last and runs to the end, so JSON templates may contain commas)
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (pattern, rest) = spec.split_once('=').ok_or("expected PATTERN=off or PATTERN=OPTIONS")?;
+        let mut policy = PublishPolicy { pattern: pattern.to_string(), actions: None, silent: false, channel: None, payload: None };
+        let mut rest = rest;
+        while !rest.is_empty() {
+            if let Some(payload) = rest.strip_prefix("payload=") {
+                policy.payload = Some(payload.to_string());
+                break;
+            }
+            let (option, tail) = rest.split_once(',').unwrap_or((rest, ""));
+            match option.split_once('=') {
+                None if option == "off" => policy.silent = true,
+                Some(("actions", actions)) => policy.actions = Some(actions.split('+').map(str::to_string).collect()),
+                Some(("channel", channel)) if !channel.is_empty() => policy.channel = Some(channel.to_string()),
+                _ => return Err(format!("unknown publish option '{}'", option)),
+            }
+            rest = tail;
+        }
+        if !policy.silent && policy.channel.is_none() && policy.payload.is_none() {
+            return Err(format!("publish policy for {} changes nothing, expected off, channel= or payload=", pattern));
+        }
+        Ok(policy)
+    }
+}
+
+// Define where events go
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum EventLog {
+    Pubsub, // Published on pub/sub channels, lost for consumers not listening
+    Streams, // Appended to per-producer streams, readable later and through consumer groups
+    Both,
+}
+
+// Define the event log settings chosen at startup (pub/sub only if unset)
+static EVENT_LOG: OnceLock<(EventLog, usize)> = OnceLock::new();
+
+// Function to choose where events go, and roughly how many events each producer's stream keeps
+pub fn use_event_log(event_log: EventLog, maxlen: usize) {
+    let _ = EVENT_LOG.set((event_log, maxlen));
+}
+
+// Define whether events go out through sharded pub/sub (SPUBLISH, Redis 7)
+static SHARDED: AtomicBool = AtomicBool::new(false);
+
+// Function to publish events with SPUBLISH from now on: in a Redis 7 cluster a sharded channel
+// lives on the node owning its slot, and a key's channel shares the key's slot, so each event
+// stays on the node that stored the write instead of crossing the cluster bus
+pub fn use_sharded(sharded: bool) {
+    SHARDED.store(sharded, Ordering::Relaxed);
+}
+
+// Function to check whether events go out through sharded pub/sub
+pub fn sharded() -> bool {
+    SHARDED.load(Ordering::Relaxed)
+}
+
+// Function to queue the commands announcing an event on a channel: a publication with the
+// configured pub/sub flavour and/or an entry in the event log stream of the channel's producer
+pub fn add(pipe: &mut redis::Pipeline, channel: &str, payload: &str) {
+    let (event_log, maxlen) = EVENT_LOG.get().copied().unwrap_or((EventLog::Pubsub, 0));
+    if event_log != EventLog::Streams {
+        pipe.cmd(if sharded() { "SPUBLISH" } else { "PUBLISH" }).arg(channel).arg(payload).ignore();
+    }
+    if event_log != EventLog::Pubsub {
+        let stream = stream_key(key_producer(channel).unwrap_or("_proxy"));
+        pipe.cmd("XADD").arg(stream).arg("MAXLEN").arg("~").arg(maxlen).arg("*").arg("channel").arg(channel).arg("event").arg(payload).ignore();
+    }
+}
+
+// Function to announce an event on a channel as configured
+pub fn send(conn: &mut impl redis::ConnectionLike, channel: &str, payload: &str) -> redis::RedisResult<()> {
+    let mut pipe = redis::pipe();
+    add(&mut pipe, channel, payload);
+    pipe.query(conn)
+}
+
+// Define the policies configured at startup
+static POLICIES: OnceLock<Vec<PublishPolicy>> = OnceLock::new();
+
+// Function to register the publish policies (first matching policy wins)
+pub fn start(policies: Vec<PublishPolicy>) {
+    let _ = POLICIES.set(policies);
+}
+
+// Function to fill the placeholders {key}, {action}, {value} and {producer} of a template
+fn render(template: &str, action: &str, key: &str, value: &str) -> String {
+    template
+        .replace("{key}", key)
+        .replace("{action}", action)
+        .replace("{producer}", key_producer(key).unwrap_or_default())
+        .replace("{value}", value)
+}
+
+// Function to work out the channel and payload announcing a write, None if it is to stay silent;
+// `event` is the default payload and `value` what {value} stands for (null when withheld)
+pub fn publication(action: &str, key: &str, event: &str, value: Option<&Value>) -> Option<(String, String)> {
+    let policy = POLICIES.get().into_iter().flatten().find(|policy| {
+        glob_match(&policy.pattern, key) && policy.actions.as_ref().is_none_or(|actions| actions.iter().any(|a| a == action))
+    });
+    let Some(policy) = policy else {
+        return Some((key.to_string(), event.to_string()));
+    };
+    if policy.silent {
+        return None;
+    }
+    let value = value.map_or_else(|| "null".to_string(), Value::to_string);
+    let channel = policy.channel.as_deref().map_or_else(|| key.to_string(), |template| render(template, action, key, &value));
+    let payload = policy.payload.as_deref().map_or_else(|| event.to_string(), |template| render(template, action, key, &value));
+    Some((channel, payload))
+}
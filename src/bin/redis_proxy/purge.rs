@@ -0,0 +1,84 @@
+// Import necessary crates and modules
+use super::chunking; // For deleting the chunks of large values
+use super::index; // For removing deleted keys from their indexes
+use super::publish; // For announcing deletions with the configured pub/sub flavour
+use super::quota; // For releasing the quota occupied by deleted keys
+use super::router::Router; // For scanning every backend keys may be routed to
+use redis::Commands; // For scanning keys
+use rustredis::schema::pattern_producer; // For the namespace a pattern stays within
+use rustredis::storage::Storage; // For the connection batches are deleted through
+use serde_json::{json, Value}; // For progress reports and results
+
+// Define how many keys are deleted per round trip
+const BATCH_SIZE: usize = 500;
+
+// Define how many matching keys are listed in the result
+const SAMPLE_SIZE: usize = 10;
+
+// Define the outcome of a purge (or of its progress so far)
+#[derive(Default)]
+pub struct Progress {
+    pub matched: u64, // Keys matching the pattern found so far
+    pub deleted: u64, // Keys deleted so far (always 0 on a dry run)
+    pub capped: bool, // Whether the purge stopped at its cap with matching keys left
+    pub sample: Vec<String>, // First matching keys, to check a pattern before running it for real
+}
+
+impl Progress {
+    // Function to serialize the progress for responses and events
+    pub fn to_json(&self) -> Value {
+        json!({"matched": self.matched, "deleted": self.deleted, "capped": self.capped, "sample": self.sample})
+    }
+}
+
+// Function to delete one batch of keys, announcing each deletion like a del does
+fn delete_batch(conn: &mut Storage, producer: &str, keys: &[String]) -> redis::RedisResult<()> {
+    let mut pipe = redis::pipe();
+    for key in keys {
+        pipe.del(key).ignore();
+        chunking::remove(&mut pipe, key);
+        index::remove(&mut pipe, key);
+        publish::add(&mut pipe, key, "del");
+    }
+    // Hold the producer's quota while the batch is deleted, like single writes do
+    let mut usage = quota::lock(producer);
+    pipe.query::<()>(conn)?;
+    if let Some(ref mut usage) = usage {
+        keys.iter().for_each(|key| usage.record("del", key, 0, 0, 0));
+    }
+    Ok(())
+}
+
+// Function to delete (or with `dry_run` only count) the keys matching a pattern on every backend, at most `cap` of them
+pub fn run(router: &Router, pattern: &str, dry_run: bool, cap: u64, mut progress: impl FnMut(&Progress)) -> redis::RedisResult<Progress> {
+    let producer = pattern_producer(pattern).expect("Purge patterns are checked before running");
+    let mut done = Progress::default();
+    for backend in router.backends() {
+        // Collect the matches first; deleting while a SCAN is in progress may skip keys
+        let mut conn = backend.connection()?;
+        let names: Vec<String> = conn.scan_match::<_, String>(pattern)?.collect();
+        let names: Vec<String> = names.into_iter().filter(|name| router.backend_for(name).name == backend.name).collect();
+
+        for batch in names.chunks(BATCH_SIZE) {
+            if done.matched == cap {
+                done.capped = true; // Keys are left over; run the purge again to continue
+                return Ok(done);
+            }
+            let take = batch.len().min(usize::try_from(cap - done.matched).unwrap_or(usize::MAX)); // usize is 32 bits on ARM routers
+            done.capped = take < batch.len();
+            let batch = &batch[..take];
+            let room = SAMPLE_SIZE.saturating_sub(done.sample.len());
+            done.sample.extend(batch.iter().take(room).cloned());
+            if !dry_run {
+                delete_batch(&mut conn, producer, batch)?;
+                done.deleted += batch.len() as u64;
+            }
+            done.matched += batch.len() as u64;
+            progress(&done);
+            if done.capped {
+                return Ok(done);
+            }
+        }
+    }
+    Ok(done)
+}
@@ -0,0 +1,180 @@
+// Import necessary crates and modules
+use super::router::Router; // For finding the backends a producer's keys live on
+use redis::Commands; // For scanning a producer's keys
+use rustredis::storage::Storage; // For measuring keys on their backend
+use serde_json::{json, Value}; // For usage summaries
+use std::collections::HashMap; // For usage by producer and by key
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError}; // For the global usage table
+
+// Define the configuration of one producer quota
+#[derive(Clone, Debug)]
+pub struct QuotaConfig {
+    pub producer: String, // Producer whose cs:<producer>:* keys are limited
+    pub max_keys: Option<u64>, // Most keys the producer may occupy
+    pub max_bytes: Option<u64>, // Most serialized value bytes the producer may occupy
+}
+
+impl QuotaConfig {
+    // Function to parse a quota specification of the form PRODUCER[,keys=N][,bytes=N]
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut parts = spec.split(',');
+        let producer = parts.next().filter(|p| !p.is_empty()).ok_or("missing producer")?;
+        let mut config = QuotaConfig { producer: producer.to_string(), max_keys: None, max_bytes: None };
+
+        for option in parts {
+            let limit = |value: &str| value.parse::<u64>().map_err(|_| format!("invalid limit '{}'", value));
+            match option.split_once('=') {
+                Some(("keys", keys)) => config.max_keys = Some(limit(keys)?),
+                Some(("bytes", bytes)) => config.max_bytes = Some(limit(bytes)?),
+                _ => return Err(format!("unknown quota option '{}'", option)),
+            }
+        }
+        if config.max_keys.is_none() && config.max_bytes.is_none() {
+            return Err(format!("quota for {} sets no limit, expected keys=N and/or bytes=N", producer));
+        }
+        Ok(config)
+    }
+}
+
+// Define what one key occupies: serialized bytes of its values and their number (set members, stream entries)
+#[derive(Clone, Copy, Default)]
+struct KeyUsage {
+    bytes: u64,
+    entries: u64,
+}
+
+// Define the tracked usage of one producer's namespace
+pub struct Usage {
+    config: QuotaConfig, // Limits of the producer
+    keys: HashMap<String, KeyUsage>, // Occupied keys
+    bytes: u64, // Total bytes over all keys
+}
+
+// Define the usage of every producer with a quota
+static QUOTAS: OnceLock<HashMap<String, Mutex<Usage>>> = OnceLock::new();
+
+// Function to measure one existing key
+fn measure(conn: &mut Storage, key: &str) -> redis::RedisResult<KeyUsage> {
+    let kind: String = redis::cmd("TYPE").arg(key).query(conn)?;
+    Ok(match kind.as_str() {
+        "string" => KeyUsage { bytes: redis::cmd("STRLEN").arg(key).query(conn)?, entries: 1 },
+        "set" => {
+            let members: Vec<String> = redis::cmd("SMEMBERS").arg(key).query(conn)?;
+            KeyUsage { bytes: members.iter().map(|m| m.len() as u64).sum(), entries: members.len() as u64 }
+        }
+        "stream" => {
+            let entries: Vec<(String, Vec<String>)> = redis::cmd("XRANGE").arg(key).arg("-").arg("+").query(conn)?;
+            let bytes = entries.iter().flat_map(|(_, fields)| fields.iter().skip(1).step_by(2)).map(|v| v.len() as u64).sum();
+            KeyUsage { bytes, entries: entries.len() as u64 }
+        }
+        _ => KeyUsage { bytes: 0, entries: 1 }, // Written outside the proxy; occupies a key but no tracked bytes
+    })
+}
+
+// Function to measure the keys a producer already occupies on every backend they are routed to
+fn load(router: &Router, config: QuotaConfig) -> redis::RedisResult<Usage> {
+    let pattern = format!("cs:{}:*", config.producer);
+    let mut usage = Usage { config, keys: HashMap::new(), bytes: 0 };
+    for backend in router.backends() {
+        let mut conn = backend.connection()?;
+        let names: Vec<String> = conn.scan_match::<_, String>(&pattern)?.collect();
+        for name in names.into_iter().filter(|name| router.backend_for(name).name == backend.name) {
+            let key = measure(&mut conn, &name)?;
+            usage.bytes += key.bytes;
+            usage.keys.insert(name, key);
+        }
+    }
+    Ok(usage)
+}
+
+// Function to measure the current usage of every producer with a quota
+pub fn start(configs: Vec<QuotaConfig>, router: &Router) -> redis::RedisResult<()> {
+    let mut quotas = HashMap::new();
+    for config in configs {
+        let usage = load(router, config)?;
+        println!(
+            "Quota for {}: {} keys, {} bytes in use",
+            usage.config.producer, usage.keys.len(), usage.bytes
+        );
+        quotas.insert(usage.config.producer.clone(), Mutex::new(usage));
+    }
+    let _ = QUOTAS.set(quotas);
+    Ok(())
+}
+
+// Function to lock the usage of a producer with a quota; writes hold it until their effect is recorded.
+// A handler that panicked mid-write poisons the lock, but at worst left that write unrecorded, so the
+// usage stays usable rather than locking the producer out until a restart
+pub fn lock(producer: &str) -> Option<MutexGuard<'static, Usage>> {
+    QUOTAS.get()?.get(producer).map(|usage| usage.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+// Function to summarize the usage and limits of every producer with a quota
+pub fn summary() -> Value {
+    let Some(quotas) = QUOTAS.get() else { return json!({}) };
+    quotas.iter().map(|(producer, usage)| {
+        let usage = usage.lock().unwrap_or_else(PoisonError::into_inner);
+        (producer.clone(), json!({
+            "keys": usage.keys.len(),
+            "max_keys": usage.config.max_keys,
+            "bytes": usage.bytes,
+            "max_bytes": usage.config.max_bytes
+        }))
+    }).collect::<serde_json::Map<_, _>>().into()
+}
+
+impl Usage {
+    // Function to check if a write adding `size` bytes to a key (replacing its value for set) stays within the limits
+    pub fn check(&self, action: &str, key: &str, size: u64) -> Result<(), String> {
+        let current = self.keys.get(key);
+        if let Some(max_keys) = self.config.max_keys.filter(|max| current.is_none() && self.keys.len() as u64 >= *max) {
+            return Err(format!(
+                "Quota exceeded for producer {}: {} keys in use (limit {})",
+                self.config.producer, self.keys.len(), max_keys
+            ));
+        }
+        let replaced = if action == "set" { current.map_or(0, |k| k.bytes) } else { 0 };
+        let projected = self.bytes - replaced + size;
+        if let Some(max_bytes) = self.config.max_bytes.filter(|max| size > replaced && projected > *max) {
+            return Err(format!(
+                "Quota exceeded for producer {}: write would use {} bytes (limit {})",
+                self.config.producer, projected, max_bytes
+            ));
+        }
+        Ok(())
+    }
+
+    // Function to record a key that came back without a write (restored from its tombstone), measuring it
+    pub fn remeasure(&mut self, conn: &mut Storage, key: &str) -> redis::RedisResult<()> {
+        let after = measure(conn, key)?;
+        let before = self.keys.insert(key.to_string(), after).unwrap_or_default();
+        self.bytes = self.bytes - before.bytes + after.bytes;
+        Ok(())
+    }
+
+    // Function to record the effect of a successful write; `changed` is the number of set members added or removed
+    pub fn record(&mut self, action: &str, key: &str, size: u64, changed: u64, maxlen: usize) {
+        let before = self.keys.get(key).copied().unwrap_or_default();
+        let after = match action {
+            "set" => KeyUsage { bytes: size, entries: 1 },
+            "del" | "soft-delete" => KeyUsage::default(),
+            "sadd" => KeyUsage { bytes: before.bytes + size * changed, entries: before.entries + changed },
+            "srem" => KeyUsage { bytes: before.bytes.saturating_sub(size * changed), entries: before.entries.saturating_sub(changed) },
+            "xadd" => {
+                let (bytes, entries) = (before.bytes + size, before.entries + 1);
+                if entries > maxlen as u64 { // Redis trims the oldest entries; assume they were of average size
+                    KeyUsage { bytes: bytes * maxlen as u64 / entries, entries: maxlen as u64 }
+                } else {
+                    KeyUsage { bytes, entries }
+                }
+            }
+            _ => before,
+        };
+        self.bytes = self.bytes - before.bytes + after.bytes;
+        if after.entries == 0 {
+            self.keys.remove(key); // Redis drops empty sets and deleted keys
+        } else {
+            self.keys.insert(key.to_string(), after);
+        }
+    }
+}
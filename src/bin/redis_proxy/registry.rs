@@ -0,0 +1,101 @@
+// Import necessary crates and modules
+use super::publish_proxy_event; // For announcing new producers and version changes
+use super::router::Router; // For storing registrations on the backend of the registry key
+use redis::Commands; // For the registry hash
+use rustredis::schema::SYSTEM_PREFIX; // For the registry key in the reserved namespace
+use serde_json::{json, Value}; // For registration records
+use std::collections::HashMap; // For the registry hash
+use std::time::{SystemTime, UNIX_EPOCH}; // For registration timestamps
+
+// Define the name of the hash producers register in (under SYSTEM_PREFIX), one field per producer
+const REGISTRY_NAME: &str = "registry";
+
+// Define a producer's announcement of itself
+pub struct Registration {
+    pub name: String, // Producer name, as in its keys
+    version: String, // Version of the producer software
+    schema_versions: Value, // Versions of the schemas the producer writes with, by base key
+    pid: Option<u64>, // Process id on the device
+}
+
+impl Registration {
+    // Function to read a registration from the value of a register request:
+    // {"name": ..., "version": ..., "schema_versions": {...}, "pid": N}
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        let name = value["name"].as_str().filter(|name| !name.is_empty()).ok_or("Registration needs the producer's name")?;
+        let version = value["version"].as_str().filter(|version| !version.is_empty()).ok_or("Registration needs the producer's version")?;
+        let schema_versions = value.get("schema_versions").cloned().unwrap_or_else(|| json!({}));
+        if !schema_versions.is_object() {
+            return Err("schema_versions must map base keys to versions".to_string());
+        }
+        let pid = match value.get("pid").filter(|pid| !pid.is_null()) {
+            Some(pid) => Some(pid.as_u64().ok_or("pid must be a process id")?),
+            None => None,
+        };
+        Ok(Registration { name: name.to_string(), version: version.to_string(), schema_versions, pid })
+    }
+}
+
+// Function to return the key of the registry hash
+fn registry_key() -> String {
+    format!("{}{}", SYSTEM_PREFIX, REGISTRY_NAME)
+}
+
+// Function to return the current Unix time in seconds
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+// Function to check if a stored registration is still within its TTL
+fn is_live(record: &Value, now: u64) -> bool {
+    record["expires_at"].as_u64().is_some_and(|expires_at| expires_at > now)
+}
+
+// Function to record a registration for `ttl` seconds, announcing producers that newly registered
+// or restarted (another pid) or changed version on the proxy events channel
+pub fn register(router: &Router, registration: Registration, ttl: u64) -> redis::RedisResult<Value> {
+    let key = registry_key();
+    let mut conn = router.backend_for(&key).connection()?;
+    let now = now();
+    let previous = conn.hget::<_, _, Option<String>>(&key, &registration.name)?
+        .and_then(|record| serde_json::from_str::<Value>(&record).ok())
+        .filter(|record| is_live(record, now));
+    let changed = previous.as_ref().is_none_or(|previous| previous["version"] != registration.version.as_str() || previous["pid"] != json!(registration.pid));
+    let registered_at = match previous {
+        Some(ref previous) if !changed => previous["registered_at"].as_u64().unwrap_or(now),
+        _ => now,
+    };
+    let record = json!({
+        "name": registration.name,
+        "version": registration.version,
+        "schema_versions": registration.schema_versions,
+        "pid": registration.pid,
+        "registered_at": registered_at,
+        "last_seen": now,
+        "expires_at": now + ttl
+    });
+    conn.hset::<_, _, _, ()>(&key, &registration.name, record.to_string())?;
+    if changed {
+        publish_proxy_event(&mut conn, json!({"event": "producer-registered", "producer": registration.name, "version": registration.version, "pid": registration.pid}));
+    }
+    Ok(record)
+}
+
+// Function to list the registrations of the producers `allowed` sees: the live ones, and the names
+// of those whose registration lapsed (kept until they register again, as producers are few)
+pub fn live(router: &Router, allowed: impl Fn(&str) -> bool) -> redis::RedisResult<Value> {
+    let key = registry_key();
+    let mut conn = router.backend_for(&key).connection()?;
+    let records: HashMap<String, String> = conn.hgetall(&key)?;
+    let now = now();
+    let (mut live, mut lapsed) = (Vec::new(), Vec::new());
+    for (name, record) in records.into_iter().filter(|(name, _)| allowed(name)) {
+        match serde_json::from_str::<Value>(&record) {
+            Ok(record) if is_live(&record, now) => live.push(record),
+            _ => lapsed.push(name),
+        }
+    }
+    live.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    lapsed.sort();
+    Ok(json!({"producers": live, "lapsed": lapsed}))
+}
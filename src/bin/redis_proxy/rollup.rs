@@ -0,0 +1,162 @@
+// Import necessary crates and modules
+use super::publish; // For announcing summaries with the configured pub/sub flavour
+use super::router::Router; // For writing summaries to the backend of the rollup key
+use redis::Commands; // For storing and publishing summaries
+use rustredis::glob::glob_match; // For matching keys against rollup patterns
+use serde_json::{json, Map, Value}; // For reading samples and building summaries
+use std::collections::{BTreeMap, HashMap}; // For the windows by key and the statistics by field
+use std::sync::{Arc, Mutex, OnceLock}; // For the global rollup table shared with the flusher
+use std::thread; // For the flusher thread
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH}; // For windows and their timestamps
+
+// Define the namespace summaries are kept in (cs:<producer>:... -> cs:_rollup:<producer>:...)
+const ROLLUP_PREFIX: &str = "cs:_rollup:";
+
+// Define how often the flusher checks for windows that are over
+const FLUSH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Define the configuration of one rollup
+#[derive(Clone, Debug)]
+pub struct RollupConfig {
+    pub pattern: String, // Key glob pattern selecting the writes to summarize
+    pub interval: Duration, // Length of one summary window
+    pub only: bool, // Keep only the summaries, not the raw latest value
+}
+
+impl RollupConfig {
+    // Function to parse a rollup specification of the form PATTERN=SECS[,only]
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (pattern, rest) = spec.split_once('=').ok_or("expected PATTERN=SECS[,only]")?;
+        let mut parts = rest.split(',');
+        let secs = parts.next().and_then(|secs| secs.parse::<u64>().ok()).filter(|secs| *secs > 0)
+            .ok_or_else(|| format!("invalid rollup interval in '{}', expected seconds", spec))?;
+        let mut config = RollupConfig { pattern: pattern.to_string(), interval: Duration::from_secs(secs), only: false };
+        for option in parts {
+            match option {
+                "only" => config.only = true,
+                _ => return Err(format!("unknown rollup option '{}'", option)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+// Define the statistics of one numeric field over a window
+#[derive(Clone, Copy)]
+struct FieldStats {
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: u64,
+}
+
+impl FieldStats {
+    // Function to add a sample
+    fn add(&mut self, sample: f64) {
+        self.min = self.min.min(sample);
+        self.max = self.max.max(sample);
+        self.sum += sample;
+        self.count += 1;
+    }
+}
+
+// Define the samples of one key accumulated in the current window
+struct Window {
+    started: SystemTime, // When the first sample of the window arrived
+    samples: u64, // Writes summarized, numeric or not
+    fields: BTreeMap<String, FieldStats>, // Statistics by field (`value` for bare numbers)
+}
+
+// Define a running rollup: its configuration and the windows of the keys it matched
+struct Rollup {
+    config: RollupConfig,
+    windows: Mutex<HashMap<String, Window>>, // Open windows by key
+    next_flush: Mutex<Instant>, // End of the current window
+}
+
+// Define the rollups started at startup
+static ROLLUPS: OnceLock<Vec<Rollup>> = OnceLock::new();
+
+// Function to return the key a key's summaries are written to
+pub fn rollup_key(key: &str) -> String {
+    format!("{}{}", ROLLUP_PREFIX, key.trim_start_matches("cs:"))
+}
+
+// Function to pick out the numeric samples of a value: a bare number, or the numeric top-level fields of an object
+fn numeric_fields(value: &Value) -> Vec<(&str, f64)> {
+    match value {
+        Value::Number(n) => n.as_f64().map(|n| vec![("value", n)]).unwrap_or_default(),
+        Value::Object(map) => map.iter().filter_map(|(field, v)| v.as_f64().map(|n| (field.as_str(), n))).collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Function to add a written value to the window of the first rollup matching its key; returns
+// whether that rollup keeps only summaries (the raw write is then skipped), None if none matched
+pub fn record(key: &str, value: &Value) -> Option<bool> {
+    let rollup = ROLLUPS.get()?.iter().find(|rollup| glob_match(&rollup.config.pattern, key))?;
+    let mut windows = rollup.windows.lock().unwrap();
+    let window = windows.entry(key.to_string()).or_insert_with(|| Window { started: SystemTime::now(), samples: 0, fields: BTreeMap::new() });
+    window.samples += 1;
+    for (field, sample) in numeric_fields(value) {
+        window.fields.entry(field.to_string())
+            .or_insert(FieldStats { min: sample, max: sample, sum: 0.0, count: 0 })
+            .add(sample);
+    }
+    Some(rollup.config.only)
+}
+
+// Function to build the summary of a closed window
+fn summary(window: &Window, interval: Duration) -> Value {
+    let millis = |at: SystemTime| at.duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+    let fields: Map<String, Value> = window.fields.iter().map(|(field, stats)| (field.clone(), json!({
+        "min": stats.min,
+        "max": stats.max,
+        "avg": stats.sum / stats.count as f64,
+        "count": stats.count
+    }))).collect();
+    json!({
+        "window_secs": interval.as_secs(),
+        "start": millis(window.started),
+        "end": millis(SystemTime::now()),
+        "samples": window.samples,
+        "fields": fields
+    })
+}
+
+// Function to write the summaries of every window of a rollup and start new ones
+fn flush(router: &Router, rollup: &Rollup) {
+    let windows = std::mem::take(&mut *rollup.windows.lock().unwrap()); // New samples open new windows meanwhile
+    for (key, window) in windows {
+        let (target, summary) = (rollup_key(&key), summary(&window, rollup.config.interval));
+        let result = router.backend_for(&target).connection().and_then(|mut conn| {
+            conn.set::<&str, String, ()>(&target, summary.to_string())?;
+            publish::send(&mut *conn, &target, &format!("rollup: {}", summary))
+        });
+        if let Err(err) = result {
+            eprintln!("Failed to write rollup of {}: {}", key, err);
+        }
+    }
+}
+
+// Function to start summarizing the keys matching the configured patterns, written out by a flusher thread
+pub fn start(configs: Vec<RollupConfig>, router: &Arc<Router>) {
+    let now = Instant::now();
+    let rollups = configs.into_iter()
+        .map(|config| Rollup { next_flush: Mutex::new(now + config.interval), config, windows: Mutex::new(HashMap::new()) })
+        .collect();
+    let _ = ROLLUPS.set(rollups);
+
+    let router = Arc::clone(router);
+    thread::spawn(move || loop {
+        thread::sleep(FLUSH_CHECK_INTERVAL);
+        for rollup in ROLLUPS.get().into_iter().flatten() {
+            let mut next_flush = rollup.next_flush.lock().unwrap();
+            if Instant::now() >= *next_flush {
+                *next_flush += rollup.config.interval;
+                drop(next_flush);
+                flush(&router, rollup);
+            }
+        }
+    });
+}
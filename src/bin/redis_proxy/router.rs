@@ -0,0 +1,404 @@
+// Import necessary crates and modules
+use redis::ConnectionLike; // For checking whether a returned connection is still open
+use rustredis::glob::glob_match; // For matching keys against route patterns
+use rustredis::storage::{MemoryStore, Storage, MEMORY_URL}; // For connections to Redis or to an in-memory store
+use serde_json::{json, Value}; // For backend summaries
+use std::ops::{Deref, DerefMut}; // For using pooled connections as plain connections
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering}; // For health flags, counters and replica rotation
+use std::sync::{Arc, Mutex}; // For the idle connection pools
+use std::thread; // For the health check thread
+use std::time::Duration; // For the health check interval
+
+// Define the name of the backend keys go to when no route matches
+pub const DEFAULT_BACKEND: &str = "default";
+
+// Define the URL of the default backend unless --backend overrides it
+const DEFAULT_BACKEND_URL: &str = "redis://127.0.0.1/";
+
+// Define how many idle connections each backend keeps for reuse
+const MAX_IDLE_CONNECTIONS: usize = 16;
+
+// Define the configuration of one Redis backend
+#[derive(Clone, Debug)]
+pub struct BackendConfig {
+    pub name: String, // Name routes refer to
+    pub url: String, // redis:// URL of the instance, or memory:// for an in-memory store
+}
+
+// Function to check a backend or replica URL: memory:// or one the Redis client accepts
+fn check_url(url: &str) -> redis::RedisResult<()> {
+    if url != MEMORY_URL {
+        redis::Client::open(url)?;
+    }
+    Ok(())
+}
+
+impl BackendConfig {
+    // Function to parse a backend specification of the form NAME=URL
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (name, url) = spec.split_once('=').ok_or("expected NAME=URL")?;
+        check_url(url).map_err(|e| format!("invalid backend URL '{}': {}", url, e))?;
+        Ok(BackendConfig { name: name.to_string(), url: url.to_string() })
+    }
+}
+
+// Define the configuration of one route
+#[derive(Clone, Debug)]
+pub struct RouteConfig {
+    pub pattern: String, // Key glob pattern
+    pub backend: String, // Backend the matching keys live on
+}
+
+impl RouteConfig {
+    // Function to parse a route specification of the form PATTERN=BACKEND
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (pattern, backend) = spec.split_once('=').ok_or("expected PATTERN=BACKEND")?;
+        Ok(RouteConfig { pattern: pattern.to_string(), backend: backend.to_string() })
+    }
+}
+
+// Define the configuration of one read replica
+#[derive(Clone, Debug)]
+pub struct ReplicaConfig {
+    pub backend: String, // Backend the replica copies
+    pub url: String, // redis:// URL of the replica
+}
+
+impl ReplicaConfig {
+    // Function to parse a replica specification of the form BACKEND=URL
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (backend, url) = spec.split_once('=').ok_or("expected BACKEND=URL")?;
+        check_url(url).map_err(|e| format!("invalid replica URL '{}': {}", url, e))?;
+        Ok(ReplicaConfig { backend: backend.to_string(), url: url.to_string() })
+    }
+}
+
+// Define a read replica of a backend and how far it lags behind
+struct Replica {
+    pool: Backend, // Connections to the replica
+    fresh: AtomicBool, // Whether the last check found it linked and within the allowed lag
+    lag_bytes: AtomicU64, // Replication offset distance to the master at the last check
+}
+
+// Function to read one field of an INFO reply
+fn info_field<'a>(info: &'a str, name: &str) -> Option<&'a str> {
+    info.lines().find_map(|line| line.strip_prefix(name)?.strip_prefix(':')).map(str::trim)
+}
+
+// Function to read the INFO replication section of an instance
+fn replication_info(backend: &Backend) -> redis::RedisResult<String> {
+    let mut conn = backend.connection()?;
+    redis::cmd("INFO").arg("replication").query(&mut *conn)
+}
+
+// Define how a backend opens new connections
+enum Opener {
+    Redis(redis::Client), // Connects to a Redis server
+    Memory(MemoryStore), // Connects to the backend's in-memory store
+}
+
+// Define one Redis backend: its client, idle connections and health
+pub struct Backend {
+    pub name: String, // Name routes refer to
+    address: String, // Shown in logs and stats (the URL without credentials)
+    opener: Opener, // Opens new connections
+    idle: Mutex<Vec<Storage>>, // Connections returned by finished requests
+    healthy: AtomicBool, // Cleared on connection failures, set again by successful connects and health checks
+    failures: AtomicU64, // Connection failures since start
+    last_error: Mutex<Option<String>>, // Most recent connection failure
+    replicas: Vec<Replica>, // Read replicas, tried in turn for read actions
+    next_replica: AtomicUsize, // Rotates reads over the replicas
+}
+
+// Define a connection borrowed from a backend's pool, returned to it when dropped
+pub struct PooledConnection<'a> {
+    backend: &'a Backend, // Pool the connection goes back to
+    conn: Option<Storage>, // Set until dropped, unless taken out
+    deadline: bool, // Whether timeouts were set on the connection, to be cleared before it is reused
+    discard: bool, // Whether the connection must not be reused
+}
+
+impl PooledConnection<'_> {
+    // Function to bound how long each Redis call on the connection may block
+    pub fn set_deadline(&mut self, timeout: Duration) -> redis::RedisResult<()> {
+        let conn = self.conn.as_mut().unwrap();
+        conn.set_read_timeout(Some(timeout))?;
+        conn.set_write_timeout(Some(timeout))?;
+        self.deadline = true;
+        Ok(())
+    }
+
+    // Function to close the connection instead of returning it to the pool, e.g. after a timed out
+    // call whose reply may still arrive and would be read as the answer to the next call
+    pub fn discard(&mut self) {
+        self.discard = true;
+    }
+
+    // Function to take the connection out, e.g. to call Redis on another thread; nothing returns to
+    // the pool unless it is put back
+    pub fn take(&mut self) -> Option<Storage> {
+        self.conn.take()
+    }
+
+    // Function to put back a connection taken out
+    pub fn put_back(&mut self, conn: Storage) {
+        self.conn = Some(conn);
+    }
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Storage;
+
+    fn deref(&self) -> &Storage {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Storage {
+        self.conn.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else {
+            return;
+        };
+        if self.discard {
+            return;
+        }
+        if self.deadline && (conn.set_read_timeout(None).is_err() || conn.set_write_timeout(None).is_err()) {
+            return;
+        }
+        if !conn.is_open() {
+            self.backend.mark_failed("connection lost"); // Broken connections are not reused
+            return;
+        }
+        let mut idle = self.backend.idle.lock().unwrap();
+        if idle.len() < MAX_IDLE_CONNECTIONS {
+            idle.push(conn);
+        }
+    }
+}
+
+impl Backend {
+    // Function to create a backend without connecting yet
+    fn new(config: &BackendConfig, replicas: Vec<Backend>) -> Self {
+        let (opener, address) = if config.url == MEMORY_URL {
+            (Opener::Memory(MemoryStore::new()), "memory".to_string())
+        } else {
+            let client = rustredis::tunnel::open(&config.url).expect("Backend URL was checked when parsing, failing here means its tunnel could not start");
+            let address = client.get_connection_info().addr.to_string();
+            (Opener::Redis(client), address)
+        };
+        Backend {
+            name: config.name.clone(),
+            address,
+            opener,
+            idle: Mutex::new(Vec::new()),
+            healthy: AtomicBool::new(true),
+            failures: AtomicU64::new(0),
+            last_error: Mutex::new(None),
+            replicas: replicas.into_iter().map(|pool| Replica { pool, fresh: AtomicBool::new(false), lag_bytes: AtomicU64::new(0) }).collect(),
+            next_replica: AtomicUsize::new(0),
+        }
+    }
+
+    // Function to borrow an idle connection, or open a new one
+    pub fn connection(&self) -> redis::RedisResult<PooledConnection<'_>> {
+        let idle = self.idle.lock().unwrap().pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => match self.open() {
+                Ok(conn) => {
+                    self.mark_healthy();
+                    conn
+                }
+                Err(err) => {
+                    self.mark_failed(&err.to_string());
+                    return Err(err);
+                }
+            },
+        };
+        Ok(PooledConnection { backend: self, conn: Some(conn), deadline: false, discard: false })
+    }
+
+    // Function to open a new connection for the pool
+    fn open(&self) -> redis::RedisResult<Storage> {
+        match self.opener {
+            Opener::Redis(ref client) => client.get_connection().map(Storage::Redis),
+            Opener::Memory(ref store) => Ok(Storage::Memory(store.connection())),
+        }
+    }
+
+    // Function to open a connection outside the pool, for subscriptions
+    pub fn dedicated_connection(&self) -> redis::RedisResult<redis::Connection> {
+        match self.opener {
+            Opener::Redis(ref client) => client.get_connection(),
+            Opener::Memory(_) => Err(redis::RedisError::from((redis::ErrorKind::InvalidClientConfig, "Subscriptions need a Redis backend, the in-memory store has none"))),
+        }
+    }
+
+    // Function to borrow a connection for a read: from a fresh replica if there is one, else from the master
+    pub fn read_connection(&self) -> redis::RedisResult<PooledConnection<'_>> {
+        let start = self.next_replica.fetch_add(1, Ordering::Relaxed);
+        for offset in 0..self.replicas.len() {
+            let replica = &self.replicas[(start + offset) % self.replicas.len()];
+            if replica.fresh.load(Ordering::Relaxed) {
+                if let Ok(conn) = replica.pool.connection() {
+                    return Ok(conn);
+                }
+            }
+        }
+        self.connection()
+    }
+
+    // Function to borrow a connection for hedging a slow read: from a fresh replica or the master,
+    // other than the node the read went to (None if the backend has no other node)
+    pub fn hedge_connection(&self, read: &PooledConnection) -> Option<PooledConnection<'_>> {
+        let replicas = self.replicas.iter().filter(|replica| replica.fresh.load(Ordering::Relaxed)).map(|replica| &replica.pool);
+        replicas.chain(std::iter::once(self))
+            .filter(|node| !std::ptr::eq(*node, read.backend))
+            .find_map(|node| node.connection().ok())
+    }
+
+    // Function to record a connection failure, logging the change if the backend was healthy
+    fn mark_failed(&self, error: &str) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        *self.last_error.lock().unwrap() = Some(error.to_string());
+        if self.healthy.swap(false, Ordering::Relaxed) {
+            eprintln!("Warning: Redis backend {} ({}) is unhealthy: {}", self.name, self.address, error);
+        }
+    }
+
+    // Function to record a working connection, logging the change if the backend was unhealthy
+    fn mark_healthy(&self) {
+        if !self.healthy.swap(true, Ordering::Relaxed) {
+            println!("Redis backend {} ({}) recovered", self.name, self.address);
+        }
+    }
+
+    // Function to check the backend with a PING, and how far each replica lags behind it
+    fn check(&self, max_lag_bytes: u64) {
+        let result = self.connection().and_then(|mut conn| redis::cmd("PING").query::<String>(&mut *conn));
+        match result {
+            Ok(_) => self.mark_healthy(),
+            Err(err) => self.mark_failed(&err.to_string()),
+        }
+        if self.replicas.is_empty() {
+            return;
+        }
+        let master_offset = replication_info(self).ok()
+            .and_then(|info| info_field(&info, "master_repl_offset").and_then(|o| o.parse::<u64>().ok()));
+        for replica in &self.replicas {
+            // A replica is only read from while linked to the master and close enough to its offset
+            let lag = replication_info(&replica.pool).ok().and_then(|info| {
+                if info_field(&info, "master_link_status") != Some("up") {
+                    return None;
+                }
+                let offset = info_field(&info, "slave_repl_offset")?.parse::<u64>().ok()?;
+                Some(master_offset?.saturating_sub(offset))
+            });
+            replica.lag_bytes.store(lag.unwrap_or(u64::MAX), Ordering::Relaxed);
+            let fresh = lag.is_some_and(|lag| lag <= max_lag_bytes);
+            if replica.fresh.swap(fresh, Ordering::Relaxed) != fresh {
+                println!(
+                    "Replica {} of backend {} {} reads",
+                    replica.pool.address, self.name, if fresh { "now serves" } else { "is stale or unlinked, no longer serves" }
+                );
+            }
+        }
+    }
+}
+
+// Define the router picking the backend of each key
+pub struct Router {
+    backends: Vec<Backend>, // All backends, the default one included
+    routes: Vec<RouteConfig>, // Checked in order, the first matching pattern wins
+    max_lag_bytes: u64, // Largest replication offset distance at which replicas still serve reads
+}
+
+// Function to check the backends, routes and replicas against each other without connecting to anything;
+// returns the backends with the default one added if it was not configured
+pub fn backend_configs(backends: &[BackendConfig], routes: &[RouteConfig], replicas: &[ReplicaConfig]) -> Result<Vec<BackendConfig>, String> {
+    let mut configs = backends.to_vec();
+    if !configs.iter().any(|b| b.name == DEFAULT_BACKEND) {
+        configs.insert(0, BackendConfig { name: DEFAULT_BACKEND.to_string(), url: DEFAULT_BACKEND_URL.to_string() });
+    }
+    for (index, config) in configs.iter().enumerate() {
+        if configs[..index].iter().any(|b| b.name == config.name) {
+            return Err(format!("backend {} is defined twice", config.name));
+        }
+    }
+    if let Some(route) = routes.iter().find(|r| !configs.iter().any(|b| b.name == r.backend)) {
+        return Err(format!("route {} refers to unknown backend {}", route.pattern, route.backend));
+    }
+    if let Some(replica) = replicas.iter().find(|r| !configs.iter().any(|b| b.name == r.backend)) {
+        return Err(format!("replica {} refers to unknown backend {}", replica.url, replica.backend));
+    }
+    Ok(configs)
+}
+
+impl Router {
+    // Function to build the router; the default backend is added unless configured explicitly
+    pub fn new(backends: &[BackendConfig], routes: Vec<RouteConfig>, replicas: &[ReplicaConfig], max_lag_bytes: u64) -> Result<Self, String> {
+        let configs = backend_configs(backends, &routes, replicas)?;
+        let backends = configs.iter().map(|config| {
+            let replicas = replicas.iter().filter(|r| r.backend == config.name).enumerate()
+                .map(|(index, r)| Backend::new(&BackendConfig { name: format!("{}/replica{}", config.name, index), url: r.url.clone() }, Vec::new()))
+                .collect();
+            Backend::new(config, replicas)
+        }).collect();
+        Ok(Router { backends, routes, max_lag_bytes })
+    }
+
+    // Function to return the backend a key lives on
+    pub fn backend_for(&self, key: &str) -> &Backend {
+        let name = self.routes.iter()
+            .find(|route| glob_match(&route.pattern, key))
+            .map_or(DEFAULT_BACKEND, |route| route.backend.as_str());
+        self.backend(name)
+    }
+
+    // Function to return the backend keys without a matching route go to
+    pub fn default_backend(&self) -> &Backend {
+        self.backend(DEFAULT_BACKEND)
+    }
+
+    // Function to return a backend by name (routes were checked against the backends when built)
+    fn backend(&self, name: &str) -> &Backend {
+        self.backends.iter().find(|b| b.name == name).expect("Routes only name known backends")
+    }
+
+    // Function to return all backends
+    pub fn backends(&self) -> &[Backend] {
+        &self.backends
+    }
+
+    // Function to summarize the health and pool of every backend
+    pub fn summary(&self) -> Value {
+        self.backends.iter().map(|backend| (backend.name.clone(), json!({
+            "address": backend.address,
+            "healthy": backend.healthy.load(Ordering::Relaxed),
+            "idle_connections": backend.idle.lock().unwrap().len(),
+            "connection_failures": backend.failures.load(Ordering::Relaxed),
+            "last_error": *backend.last_error.lock().unwrap(),
+            "replicas": backend.replicas.iter().map(|replica| json!({
+                "address": replica.pool.address,
+                "fresh": replica.fresh.load(Ordering::Relaxed),
+                "lag_bytes": replica.lag_bytes.load(Ordering::Relaxed),
+                "healthy": replica.pool.healthy.load(Ordering::Relaxed)
+            })).collect::<Vec<_>>()
+        }))).collect::<serde_json::Map<_, _>>().into()
+    }
+
+    // Function to start the background thread that periodically checks every backend
+    pub fn start_health_checks(self: &Arc<Self>, interval: Duration) {
+        let router = Arc::clone(self);
+        thread::spawn(move || loop {
+            router.backends.iter().for_each(|backend| backend.check(router.max_lag_bytes));
+            thread::sleep(interval); // Checked once right away so replicas serve reads soon after startup
+        });
+    }
+}
@@ -0,0 +1,150 @@
+// Import necessary crates and modules
+use super::document; // For checking the modules are loaded
+use super::router::Router; // For creating the indexes on, and searching, every backend
+use rustredis::schema::{base_key, schema_for}; // For the object type of a key and the fields of its schema
+use rustredis::storage::Storage; // For the connection indexes are created through
+use serde_json::{json, Value}; // For documents and search results
+use std::sync::OnceLock; // For the object types enabled at startup
+
+// Define the prefix of the search index names (rustredis:<producer>:<object>)
+const INDEX_PREFIX: &str = "rustredis:";
+
+// Define how a field of the stored documents is indexed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldType {
+    Numeric, // Range queries, e.g. @usage:[90 +inf]
+    Tag, // Exact matches, e.g. @status:{offline}
+    Text, // Full-text matches
+}
+
+impl FieldType {
+    // Function to name the type in FT.CREATE
+    fn as_str(self) -> &'static str {
+        match self {
+            FieldType::Numeric => "NUMERIC",
+            FieldType::Tag => "TAG",
+            FieldType::Text => "TEXT",
+        }
+    }
+}
+
+// Define an object type stored as RedisJSON documents with a search index
+#[derive(Clone, Debug)]
+pub struct SearchConfig {
+    pub base: String, // Object type as cs:<producer>:<object>; its keys are indexed
+    pub fields: Vec<(String, FieldType)>, // Indexed top-level fields; those of the object's schema if empty
+}
+
+impl SearchConfig {
+    // Function to parse a specification of the form cs:PRODUCER:OBJECT[=FIELD:numeric|tag|text[+FIELD:TYPE...]]
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (base, fields) = spec.split_once('=').map_or((spec, None), |(base, fields)| (base, Some(fields)));
+        if base.split(':').count() != 3 || !base.starts_with("cs:") {
+            return Err(format!("invalid object type '{}', expected cs:PRODUCER:OBJECT", base));
+        }
+        let fields = fields.into_iter().flat_map(|fields| fields.split('+')).map(|field| {
+            let (name, kind) = field.split_once(':').ok_or_else(|| format!("expected FIELD:TYPE, got '{}'", field))?;
+            let kind = match kind {
+                "numeric" => FieldType::Numeric,
+                "tag" => FieldType::Tag,
+                "text" => FieldType::Text,
+                _ => return Err(format!("invalid field type '{}', expected numeric, tag or text", kind)),
+            };
+            Ok((name.to_string(), kind))
+        }).collect::<Result<_, String>>()?;
+        Ok(SearchConfig { base: base.to_string(), fields })
+    }
+
+    // Function to return the indexed fields: the configured ones, or the top-level properties of the object's schema
+    fn fields(&self) -> Vec<(String, FieldType)> {
+        if !self.fields.is_empty() {
+            return self.fields.clone();
+        }
+        let schema = schema_for(&self.base);
+        let properties = schema.as_deref().and_then(|schema| schema["properties"].as_object());
+        properties.into_iter().flatten().filter_map(|(name, property)| match property["type"].as_str() {
+            Some("number" | "integer") => Some((name.clone(), FieldType::Numeric)),
+            Some("string" | "boolean") => Some((name.clone(), FieldType::Tag)),
+            _ => None, // Nested objects and arrays are not indexed
+        }).collect()
+    }
+
+    // Function to name the search index of the object type
+    fn index_name(&self) -> String {
+        format!("{}{}", INDEX_PREFIX, self.base.trim_start_matches("cs:"))
+    }
+}
+
+// Define the object types stored as documents, empty when the modules are missing
+static SEARCH: OnceLock<Vec<SearchConfig>> = OnceLock::new();
+
+// Function to create the search index of an object type, keeping an existing one
+fn create_index(conn: &mut Storage, config: &SearchConfig) -> redis::RedisResult<()> {
+    let mut create = redis::cmd("FT.CREATE");
+    create.arg(config.index_name()).arg("ON").arg("JSON").arg("PREFIX").arg(1).arg(&config.base).arg("SCHEMA");
+    for (name, kind) in config.fields() {
+        create.arg(format!("$.{}", name)).arg("AS").arg(name).arg(kind.as_str());
+    }
+    match create.query::<()>(conn) {
+        Err(err) if err.to_string().contains("already exists") => Ok(()),
+        result => result,
+    }
+}
+
+// Function to enable document storage and search for the configured object types if every
+// backend has the modules; otherwise values stay plain strings and search is unavailable
+pub fn start(configs: Vec<SearchConfig>, router: &Router) -> redis::RedisResult<()> {
+    if configs.is_empty() {
+        return Ok(());
+    }
+    for backend in router.backends() {
+        let mut conn = backend.connection()?;
+        if !document::has_modules(&mut conn, &["search", "rejson"])? {
+            eprintln!("Backend {} lacks the RediSearch or RedisJSON module, search is disabled", backend.name);
+            let _ = SEARCH.set(Vec::new());
+            return Ok(());
+        }
+        for config in &configs {
+            create_index(&mut conn, config)?;
+        }
+    }
+    println!("Search enabled for {}", configs.iter().map(|config| config.base.as_str()).collect::<Vec<_>>().join(", "));
+    let _ = SEARCH.set(configs);
+    Ok(())
+}
+
+// Function to find the searchable object type of a key
+fn config_for(key: &str) -> Option<&'static SearchConfig> {
+    let base = base_key(key);
+    SEARCH.get()?.iter().find(|config| config.base == base)
+}
+
+// Function to check if a key belongs to a searchable object type
+pub fn is_indexed(key: &str) -> bool {
+    config_for(key).is_some()
+}
+
+// Function to run a RediSearch query over an object type on every backend, returning the
+// total number of matches and up to `limit` matching keys with their values
+pub fn search(router: &Router, base: &str, query: &str, limit: u64) -> Result<Value, String> {
+    let config = config_for(base).ok_or_else(|| format!("Search is not enabled for {}", base_key(base)))?;
+    let (mut total, mut results) = (0, Vec::new());
+    for backend in router.backends() {
+        let mut conn = backend.connection().map_err(|e| format!("Redis backend {} unavailable: {}", backend.name, e))?;
+        let reply: Vec<redis::Value> = redis::cmd("FT.SEARCH").arg(config.index_name()).arg(query).arg("LIMIT").arg(0).arg(limit)
+            .query(&mut *conn)
+            .map_err(|e| format!("Search failed on backend {}: {}", backend.name, e))?;
+        let Some((count, hits)) = reply.split_first() else { continue };
+        total += redis::from_redis_value::<u64>(count).unwrap_or(0);
+        // Hits come as key, [path, document] pairs
+        for hit in hits.chunks(2) {
+            let [key, fields] = hit else { continue };
+            let key: String = redis::from_redis_value(key).unwrap_or_default();
+            let fields: Vec<String> = redis::from_redis_value(fields).unwrap_or_default();
+            let document = fields.get(1).and_then(|text| serde_json::from_str(text).ok()).unwrap_or(Value::Null);
+            results.push(json!({"key": key, "value": document}));
+        }
+    }
+    results.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+    Ok(json!({"total": total, "results": results}))
+}
@@ -0,0 +1,130 @@
+// Import necessary crates and modules
+use std::cmp::Reverse; // For serving equal priorities in arrival order
+use std::collections::{BinaryHeap, HashMap}; // For the waiting writes and the per-producer limits
+use std::sync::{Condvar, Mutex, OnceLock}; // For the global admission gate
+
+// Define the priority of writes that give none (0 is the lowest, MAX_PRIORITY the highest)
+pub const DEFAULT_PRIORITY: u8 = 5;
+
+// Define the highest priority a request may ask for
+pub const MAX_PRIORITY: u8 = 9;
+
+// Define the highest priority one producer's writes may claim
+#[derive(Clone, Debug)]
+pub struct PriorityLimit {
+    pub producer: String, // Producer whose cs:<producer>:* writes are bounded
+    pub max: u8, // Highest priority they get, whatever they ask for
+}
+
+impl PriorityLimit {
+    // Function to parse a limit specification of the form PRODUCER=N
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (producer, max) = spec.split_once('=').ok_or("expected PRODUCER=N")?;
+        let max = max.parse::<u8>().ok().filter(|max| *max <= MAX_PRIORITY)
+            .ok_or_else(|| format!("invalid priority '{}', expected 0 to {}", max, MAX_PRIORITY))?;
+        Ok(PriorityLimit { producer: producer.to_string(), max })
+    }
+}
+
+// Define the writes currently executing and those waiting for a slot
+#[derive(Default)]
+struct GateState {
+    in_flight: usize, // Writes being executed
+    waiting: BinaryHeap<(u8, Reverse<u64>)>, // Waiting writes by priority, then ticket (oldest first)
+    next_ticket: u64,
+}
+
+// Define the admission gate writes pass before touching Redis
+struct Gate {
+    max_in_flight: usize, // Writes executed at once before the proxy counts as overloaded
+    shed_below: u8, // Writes below this priority are rejected while overloaded instead of waiting
+    limits: HashMap<String, u8>, // Highest priority by producer
+    state: Mutex<GateState>,
+    freed: Condvar, // Signalled when a write finishes
+}
+
+// Define the gate, if load shedding is enabled
+static GATE: OnceLock<Gate> = OnceLock::new();
+
+// Function to enable load shedding of writes beyond a number executing at once
+pub fn start(max_in_flight: usize, shed_below: u8, limits: Vec<PriorityLimit>) {
+    let _ = GATE.set(Gate {
+        max_in_flight: max_in_flight.max(1),
+        shed_below,
+        limits: limits.into_iter().map(|limit| (limit.producer, limit.max)).collect(),
+        state: Mutex::new(GateState::default()),
+        freed: Condvar::new(),
+    });
+}
+
+// Function to work out the priority of a write: what it asks for (or the default), bounded by its
+// producer's limit; producers without a configured limit cannot rise above the default
+pub fn effective_priority(producer: Option<&str>, requested: Option<u8>) -> u8 {
+    let limit = GATE.get()
+        .and_then(|gate| producer.and_then(|producer| gate.limits.get(producer)))
+        .copied()
+        .unwrap_or(DEFAULT_PRIORITY);
+    requested.unwrap_or(DEFAULT_PRIORITY).min(limit)
+}
+
+// Define a write's slot, released when dropped
+pub struct Permit {
+    gate: Option<&'static Gate>, // None when load shedding is disabled
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(gate) = self.gate {
+            gate.state.lock().unwrap().in_flight -= 1;
+            gate.freed.notify_all(); // The waiting write of highest priority takes the slot
+        }
+    }
+}
+
+// Define what admission did to a write
+pub enum Admission {
+    Admitted(Permit), // Free to execute; holds its slot until dropped
+    Waited(Permit), // Executes after waiting for higher or earlier writes
+    Shed, // Rejected because the proxy is overloaded and the write's priority is low
+}
+
+// Function to admit a write of some priority: straight away while the proxy has capacity, after
+// the waiting writes of higher priority once it is overloaded, or not at all if it is below the
+// shedding threshold
+pub fn admit(priority: u8) -> Admission {
+    let Some(gate) = GATE.get() else {
+        return Admission::Admitted(Permit { gate: None });
+    };
+    let mut state = gate.state.lock().unwrap();
+    if state.in_flight < gate.max_in_flight && state.waiting.is_empty() {
+        state.in_flight += 1;
+        return Admission::Admitted(Permit { gate: Some(gate) });
+    }
+    if priority < gate.shed_below {
+        return Admission::Shed;
+    }
+
+    let turn = (priority, Reverse(state.next_ticket));
+    state.next_ticket += 1;
+    state.waiting.push(turn);
+    while state.in_flight >= gate.max_in_flight || state.waiting.peek() != Some(&turn) {
+        state = gate.freed.wait(state).unwrap();
+    }
+    state.waiting.pop();
+    state.in_flight += 1;
+    drop(state);
+    gate.freed.notify_all(); // The next waiting write may fit as well
+    Admission::Waited(Permit { gate: Some(gate) })
+}
+
+// Function to summarize the gate for stats: writes executing and waiting, and the limits
+pub fn summary() -> Option<serde_json::Value> {
+    let gate = GATE.get()?;
+    let state = gate.state.lock().unwrap();
+    Some(serde_json::json!({
+        "in_flight": state.in_flight,
+        "waiting": state.waiting.len(),
+        "max_in_flight": gate.max_in_flight,
+        "shed_below": gate.shed_below
+    }))
+}
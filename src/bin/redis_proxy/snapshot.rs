@@ -0,0 +1,52 @@
+// Import necessary crates and modules
+use super::publish; // For announcing values with the configured pub/sub flavour
+use super::router::Router; // For scanning every backend
+use super::{chunking, compression, encryption, publish_proxy_event, stored_json}; // For reading values as get returns them
+use redis::Commands; // For scanning and reading keys
+use rustredis::schema::{is_system_key, redact}; // For skipping bookkeeping keys and masking fields like events do
+use rustredis::storage::Storage; // For reading current values
+use serde_json::{json, Value}; // For snapshot events
+use std::collections::HashMap; // For hash fields
+
+// Function to read the value of a key as the snapshot carries it, or None for keys left out
+fn current_value(conn: &mut Storage, key: &str) -> redis::RedisResult<Option<Value>> {
+    if encryption::is_sensitive(key) {
+        return Ok(None); // Never published in the clear
+    }
+    let kind: String = redis::cmd("TYPE").arg(key).query(conn)?;
+    let value = match kind.as_str() {
+        "string" => {
+            let stored: Option<Vec<u8>> = conn.get(key)?;
+            let Some(stored) = chunking::join(conn, key, stored)? else { return Ok(None) };
+            match compression::decompress(&stored) {
+                Ok(plain) => stored_json(&plain),
+                Err(_) => return Ok(None),
+            }
+        }
+        "set" => Value::Array(conn.smembers::<_, Vec<Vec<u8>>>(key)?.iter().map(|m| stored_json(m)).collect()),
+        "hash" => Value::Object(conn.hgetall::<_, HashMap<String, Vec<u8>>>(key)?.iter().map(|(f, v)| (f.clone(), stored_json(v))).collect()),
+        _ => return Ok(None), // Streams keep their own history for late readers
+    };
+    Ok(Some(redact(key, &value)))
+}
+
+// Function to publish the current value of every key in the namespace, on each key's channel or on one channel
+pub fn publish(router: &Router, channel: Option<&str>) -> redis::RedisResult<u64> {
+    let mut published = 0;
+    for backend in router.backends() {
+        let mut conn = backend.connection()?;
+        let names: Vec<String> = conn.scan_match::<_, String>("cs:*")?.collect();
+        for name in names.iter().filter(|name| !is_system_key(name) && router.backend_for(name).name == backend.name) {
+            let Some(value) = current_value(&mut conn, name)? else { continue };
+            match channel {
+                Some(channel) => publish::send(&mut *conn, channel, &json!({"key": name, "action": "snapshot", "value": value}).to_string())?,
+                None => publish::send(&mut *conn, name, &format!("snapshot: {}", value))?,
+            }
+            published += 1;
+        }
+    }
+    if let Ok(mut conn) = router.default_backend().connection() { // Tells consumers the bootstrap is complete
+        publish_proxy_event(&mut conn, json!({"event": "snapshot_complete", "keys": published, "channel": channel}));
+    }
+    Ok(published)
+}
@@ -0,0 +1,135 @@
+// Import necessary crates and modules
+use super::publish; // For following sharded channels when events are published with SPUBLISH
+use super::router::Router; // For subscribing on every backend keys may be routed to
+use rustredis::events::parse_event; // For structuring events before they are filtered
+use rustredis::filter::Filter; // For dropping events the subscriber is not interested in
+use serde_json::Value; // For structured events
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering}; // For stopping the backend threads and counting queued events
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError}; // For handing events to the client handler
+use std::sync::Arc; // For sharing the stop flag
+use std::thread; // For one subscriber thread per backend
+use std::time::Duration; // For bounding waits
+
+// Define how often backend threads check if the client is still subscribed
+const STOP_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Define what a client subscribed to
+pub struct Subscription {
+    pub pattern: String, // Glob pattern of the key channels
+    pub filter: Option<Filter>, // Condition events must meet to be forwarded
+}
+
+// Define the events of a subscription as they arrive from the backends; dropping it unsubscribes
+pub struct Feed {
+    events: Receiver<Value>,
+    stop: Arc<AtomicBool>,
+    queued: Arc<AtomicUsize>, // Events received from the backends and not yet taken by the client handler
+}
+
+impl Feed {
+    // Function to wait for the next event that passed the filter
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Value, RecvTimeoutError> {
+        let event = self.events.recv_timeout(timeout)?;
+        self.queued.fetch_sub(1, Ordering::Relaxed);
+        Ok(event)
+    }
+
+    // Function to return how many events wait for the client handler, a sign of a slow subscriber
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+}
+
+// Define the sending end of a feed, counting the events it queues
+struct Outbox {
+    sender: mpsc::Sender<Value>, // Hands events to the client handler
+    queued: Arc<AtomicUsize>, // Shared with the feed, which counts events taken
+}
+
+impl Outbox {
+    // Function to queue an event, false once the feed is dropped
+    fn send(&self, event: Value) -> bool {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        self.sender.send(event).is_ok()
+    }
+}
+
+impl Drop for Feed {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// Function to forward the filtered events of one backend until the feed is dropped or the connection fails
+fn forward(mut conn: redis::Connection, subscription: &Subscription, events: Outbox, stop: &AtomicBool) -> redis::RedisResult<()> {
+    let mut pubsub = conn.as_pubsub();
+    pubsub.psubscribe(&subscription.pattern)?;
+    pubsub.set_read_timeout(Some(STOP_CHECK_INTERVAL))?;
+    while !stop.load(Ordering::Relaxed) {
+        let message = match pubsub.get_message() {
+            Ok(message) => message,
+            Err(err) if err.is_timeout() => continue,
+            Err(err) => return Err(err),
+        };
+        let event = parse_event(message.get_channel_name(), &message.get_payload::<String>()?);
+        // Filtering here keeps low-power subscribers asleep through traffic they don't care about
+        if subscription.filter.as_ref().is_none_or(|filter| filter.matches(&event)) && !events.send(event) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Function to forward the filtered events of one sharded channel (the subscription's exact key),
+// read as raw ["smessage", channel, payload] pushes since the client's PubSub has no SSUBSCRIBE
+fn forward_sharded(mut conn: redis::Connection, subscription: &Subscription, events: Outbox, stop: &AtomicBool) -> redis::RedisResult<()> {
+    redis::cmd("SSUBSCRIBE").arg(&subscription.pattern).query::<()>(&mut conn)?;
+    conn.set_read_timeout(Some(STOP_CHECK_INTERVAL))?;
+    while !stop.load(Ordering::Relaxed) {
+        let parts: Vec<String> = match conn.recv_response() {
+            Ok(reply) => redis::from_redis_value(&reply)?,
+            Err(err) if err.is_timeout() => continue,
+            Err(err) => return Err(err),
+        };
+        let [kind, channel, payload] = parts.as_slice() else { continue };
+        if kind != "smessage" {
+            continue;
+        }
+        let event = parse_event(channel, payload);
+        if subscription.filter.as_ref().is_none_or(|filter| filter.matches(&event)) && !events.send(event) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Function to subscribe on every backend (only the key's own backend for a sharded channel); the
+// feed disconnects once no backend is left forwarding
+pub fn start(router: &Router, subscription: Subscription) -> redis::RedisResult<Feed> {
+    let (sender, events) = mpsc::channel();
+    let feed = Feed { events, stop: Arc::new(AtomicBool::new(false)), queued: Arc::default() }; // Stops threads already started if a backend fails
+    let subscription = Arc::new(subscription);
+    if publish::sharded() {
+        let backend = router.backend_for(&subscription.pattern);
+        let conn = backend.dedicated_connection()?;
+        let (stop, name) = (Arc::clone(&feed.stop), backend.name.clone());
+        let outbox = Outbox { sender, queued: Arc::clone(&feed.queued) };
+        thread::spawn(move || {
+            if let Err(err) = forward_sharded(conn, &subscription, outbox, &stop) {
+                eprintln!("Sharded subscription to {} on backend {} ended: {}", subscription.pattern, name, err);
+            }
+        });
+        return Ok(feed);
+    }
+    for backend in router.backends() {
+        let conn = backend.dedicated_connection()?; // Fail the subscribe itself if a backend is down
+        let (subscription, stop, name) = (Arc::clone(&subscription), Arc::clone(&feed.stop), backend.name.clone());
+        let outbox = Outbox { sender: sender.clone(), queued: Arc::clone(&feed.queued) };
+        thread::spawn(move || {
+            if let Err(err) = forward(conn, &subscription, outbox, &stop) {
+                eprintln!("Subscription to {} on backend {} ended: {}", subscription.pattern, name, err);
+            }
+        });
+    }
+    Ok(feed)
+}
@@ -0,0 +1,234 @@
+// Import necessary crates and modules
+use super::metrics::{self, METRICS}; // For connection and panic counters
+use serde_json::{json, Value}; // For the client list of the admin socket
+use std::any::Any; // For inspecting panic payloads
+use std::collections::BTreeMap; // For tracking live handlers by id, listed in connection order
+use std::panic::{self, AssertUnwindSafe}; // For isolating panics in client handlers
+use std::sync::atomic::{AtomicU64, Ordering}; // For allocating handler ids
+use std::sync::{Arc, Mutex}; // For sharing the handler registry between threads
+use std::thread; // For spawning handler and supervisor threads
+use std::time::{Duration, Instant}; // For tracking handler activity
+
+// Define the health information kept for each running client handler
+struct HandlerStatus {
+    started: Instant, // When the handler was spawned
+    busy_since: Option<Instant>, // When the request currently being processed started
+    requests: u64, // Requests processed by the handler
+    listener: String, // Socket the client connected through
+    bytes_in: u64, // Bytes received from the client
+    bytes_out: u64, // Bytes of responses and events written to the client
+    last_activity: Instant, // Last data received from or written to the client
+    writing_since: Option<Instant>, // When the write currently waiting for the client started
+    blocked_write: Duration, // Total time spent waiting for the client to take writes
+    longest_write: Duration, // Longest single write
+    subscription: Option<String>, // Pattern of a subscribed client
+    queued_events: usize, // Events of a subscribed client waiting to be written
+}
+
+// Define the supervisor that owns the registry of running client handlers
+pub struct Supervisor {
+    next_id: AtomicU64, // Id given to the next spawned handler
+    handlers: Mutex<BTreeMap<u64, HandlerStatus>>, // Running handlers by id
+}
+
+// Define the handle given to a client handler to report its activity
+#[derive(Clone)]
+pub struct HandlerHandle {
+    id: u64, // Id of the handler in the registry
+    supervisor: Arc<Supervisor>, // Supervisor owning the registry
+}
+
+impl HandlerHandle {
+    // Function to update the handler's status
+    fn update(&self, change: impl FnOnce(&mut HandlerStatus)) {
+        if let Some(status) = self.supervisor.handlers.lock().unwrap().get_mut(&self.id) {
+            change(status);
+        }
+    }
+
+    // Function to mark the start of a request
+    pub fn begin_request(&self) {
+        self.update(|status| status.busy_since = Some(Instant::now()));
+    }
+
+    // Function to mark the end of a request
+    pub fn end_request(&self) {
+        self.update(|status| {
+            status.busy_since = None;
+            status.requests += 1;
+        });
+    }
+
+    // Function to name the socket the client connected through
+    pub fn set_listener(&self, listener: &str) {
+        self.update(|status| status.listener = listener.to_string());
+    }
+
+    // Function to count data received from the client
+    pub fn record_read(&self, bytes: usize) {
+        metrics::add(&METRICS.bytes_received, bytes as u64);
+        self.update(|status| {
+            status.bytes_in += bytes as u64;
+            status.last_activity = Instant::now();
+        });
+    }
+
+    // Function to write to the client, timing how long the client takes to accept the data
+    pub fn write(&self, stream: &mut impl std::io::Write, data: &[u8]) -> std::io::Result<()> {
+        let started = Instant::now();
+        self.update(|status| status.writing_since = Some(started));
+        let result = stream.write_all(data);
+        let took = started.elapsed();
+        metrics::add(&METRICS.bytes_sent, data.len() as u64);
+        metrics::add(&METRICS.blocked_write_ms, took.as_millis() as u64);
+        self.update(|status| {
+            status.writing_since = None;
+            status.bytes_out += data.len() as u64;
+            status.blocked_write += took;
+            status.longest_write = status.longest_write.max(took);
+            status.last_activity = Instant::now();
+        });
+        result
+    }
+
+    // Function to record the pattern a subscribed client listens to
+    pub fn set_subscription(&self, pattern: &str) {
+        self.update(|status| status.subscription = Some(pattern.to_string()));
+    }
+
+    // Function to record how many events wait to be written to a subscribed client
+    pub fn set_queued_events(&self, queued_events: usize) {
+        self.update(|status| status.queued_events = queued_events);
+    }
+
+    // Function to list the running handlers, for the admin socket
+    pub fn clients(&self) -> Value {
+        self.supervisor.clients(self.id)
+    }
+}
+
+// Function to extract a readable message from a panic payload
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+impl Supervisor {
+    // Function to create an empty supervisor
+    pub fn new() -> Arc<Self> {
+        Arc::new(Supervisor {
+            next_id: AtomicU64::new(1),
+            handlers: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    // Function to add a handler to the registry, returning the handle it reports its activity through
+    pub fn register(self: &Arc<Self>) -> HandlerHandle {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.handlers.lock().unwrap().insert(id, HandlerStatus {
+            started: Instant::now(),
+            busy_since: None,
+            requests: 0,
+            listener: String::new(),
+            bytes_in: 0,
+            bytes_out: 0,
+            last_activity: Instant::now(),
+            writing_since: None,
+            blocked_write: Duration::ZERO,
+            longest_write: Duration::ZERO,
+            subscription: None,
+            queued_events: 0,
+        });
+        HandlerHandle { id, supervisor: Arc::clone(self) }
+    }
+
+    // Function to spawn a client handler whose panics are contained to its own connection
+    pub fn spawn<F>(self: &Arc<Self>, handler: F)
+    where
+        F: FnOnce(&HandlerHandle) + Send + 'static,
+    {
+        let handle = self.register();
+        metrics::incr(&METRICS.connections_accepted);
+        metrics::incr(&METRICS.connections_active);
+        thread::spawn(move || {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| handler(&handle))) {
+                metrics::incr(&METRICS.handler_panics);
+                eprintln!("Client handler {} panicked: {}", handle.id, panic_message(payload.as_ref()));
+            }
+            handle.supervisor.handlers.lock().unwrap().remove(&handle.id); // Forget the handler whatever the outcome
+            metrics::decr(&METRICS.connections_active);
+        });
+    }
+
+    // Function to count the running handlers
+    pub fn running(&self) -> usize {
+        self.handlers.lock().unwrap().len()
+    }
+
+    // Function to describe every running handler, marking the one asking
+    fn clients(&self, own_id: u64) -> Value {
+        let handlers = self.handlers.lock().unwrap();
+        let millis = |duration: Duration| duration.as_millis() as u64;
+        handlers.iter().map(|(id, status)| json!({
+            "id": id,
+            "self": *id == own_id,
+            "listener": status.listener,
+            "up_secs": status.started.elapsed().as_secs(),
+            "requests": status.requests,
+            "bytes_in": status.bytes_in,
+            "bytes_out": status.bytes_out,
+            "idle_ms": millis(status.last_activity.elapsed()),
+            "busy_ms": status.busy_since.map(|since| millis(since.elapsed())),
+            "writing_ms": status.writing_since.map(|since| millis(since.elapsed())),
+            "blocked_write_ms": millis(status.blocked_write),
+            "longest_write_ms": millis(status.longest_write),
+            "subscription": status.subscription,
+            "queued_events": status.queued_events,
+        })).collect::<Vec<_>>().into()
+    }
+
+    // Function to log the health of all running handlers, flagging those stuck in a request or on a slow client
+    fn report(&self, stall_threshold: Duration) {
+        let handlers = self.handlers.lock().unwrap();
+        println!(
+            "Supervisor: {} active handlers, {} accepted, {} panicked",
+            handlers.len(),
+            metrics::get(&METRICS.connections_accepted),
+            metrics::get(&METRICS.handler_panics)
+        );
+        for (id, status) in handlers.iter() {
+            if let Some(busy_since) = status.busy_since {
+                let busy = busy_since.elapsed();
+                if busy >= stall_threshold {
+                    eprintln!(
+                        "Warning: client handler {} (up {:.0?}, {} requests) stuck in a request for {:.0?}",
+                        id, status.started.elapsed(), status.requests, busy
+                    );
+                }
+            }
+            if let Some(writing_since) = status.writing_since {
+                let blocked = writing_since.elapsed();
+                if blocked >= stall_threshold {
+                    eprintln!(
+                        "Warning: client handler {} on {} blocked writing to a slow client for {:.0?} ({} events queued)",
+                        id, status.listener, blocked, status.queued_events
+                    );
+                }
+            }
+        }
+    }
+
+    // Function to start the background thread that periodically reports handler health
+    pub fn start_monitor(self: &Arc<Self>, interval: Duration, stall_threshold: Duration) {
+        let supervisor = Arc::clone(self);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            supervisor.report(stall_threshold);
+        });
+    }
+}
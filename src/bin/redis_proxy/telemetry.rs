@@ -0,0 +1,191 @@
+// Import necessary crates and modules
+use rustredis::http; // For posting OTLP payloads
+use serde_json::{json, Value}; // For building OTLP/JSON payloads
+use std::collections::hash_map::RandomState; // For randomly seeded id generation
+use std::hash::{BuildHasher, Hasher}; // For hashing id seeds
+use std::sync::atomic::{AtomicU64, Ordering}; // For making every id seed unique
+use std::sync::mpsc::{self, RecvTimeoutError, Sender}; // For handing finished spans to the exporter
+use std::sync::{Mutex, OnceLock}; // For the global exporter handle
+use std::thread; // For the exporter thread
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH}; // For span timestamps and batching
+
+// Define the service name reported in exported traces
+const SERVICE_NAME: &str = "redis_proxy";
+
+// Define the maximum number of spans sent in one OTLP request
+const EXPORT_BATCH_SIZE: usize = 256;
+
+// Define how long finished spans may wait before being exported
+const EXPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+// Define the OTLP span kinds used by the proxy
+#[derive(Clone, Copy)]
+pub enum SpanKind {
+    Internal = 1, // Work inside the proxy (validation)
+    Server = 2, // Handling of a client request
+    Client = 3, // Calls to Redis
+}
+
+// Define a span that is exported when dropped
+pub struct Span {
+    trace_id: String, // 32 hex digit trace id shared with the client
+    span_id: String, // 16 hex digit id of this span
+    parent_span_id: String, // Id of the parent span (the client's span for request spans)
+    name: String, // Operation name
+    kind: SpanKind, // OTLP span kind
+    start: u64, // Start time in nanoseconds since the epoch
+    attributes: Vec<(String, String)>, // String attributes attached to the span
+    error: Option<String>, // Error message if the operation failed
+}
+
+// Define the sender side of the exporter thread, set once at startup
+static EXPORTER: OnceLock<Mutex<Sender<Value>>> = OnceLock::new();
+
+// Define the source of unique id seeds
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// Function to get the current time in nanoseconds since the epoch
+fn now_nanos() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64
+}
+
+// Function to generate a random 64-bit id (never zero, which W3C reserves as invalid)
+fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher(); // Randomly keyed per call
+    hasher.write_u64(ID_COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.write_u64(now_nanos());
+    hasher.finish().max(1)
+}
+
+// Function to check that a string is lowercase hex of the given length and not all zeros
+fn is_valid_id(id: &str, len: usize) -> bool {
+    id.len() == len
+        && id.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        && id.bytes().any(|b| b != b'0')
+}
+
+// Function to parse a W3C traceparent header into trace id, parent span id and sampled flag
+pub fn parse_traceparent(traceparent: &str) -> Option<(String, String, bool)> {
+    let parts: Vec<&str> = traceparent.trim().split('-').collect();
+    if parts.len() != 4 || parts[0] != "00" || !is_valid_id(parts[1], 32) || !is_valid_id(parts[2], 16) {
+        return None;
+    }
+    let flags = u8::from_str_radix(parts[3], 16).ok()?;
+    Some((parts[1].to_string(), parts[2].to_string(), flags & 1 == 1))
+}
+
+// Function to start the OTLP/HTTP exporter posting to the given collector endpoint
+pub fn start_exporter(endpoint: &str) {
+    let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+    let (sender, receiver) = mpsc::channel::<Value>();
+    if EXPORTER.set(Mutex::new(sender)).is_err() {
+        return; // Already started
+    }
+
+    thread::spawn(move || {
+        let mut batch = Vec::new();
+        let mut last_flush = Instant::now();
+        loop {
+            match receiver.recv_timeout(EXPORT_INTERVAL) {
+                Ok(span) => batch.push(span),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if !batch.is_empty() && (batch.len() >= EXPORT_BATCH_SIZE || last_flush.elapsed() >= EXPORT_INTERVAL) {
+                export(&url, std::mem::take(&mut batch));
+                last_flush = Instant::now();
+            }
+        }
+    });
+}
+
+// Function to send a batch of spans to the collector, dropping them on failure
+fn export(url: &str, spans: Vec<Value>) {
+    let payload = json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [{"key": "service.name", "value": {"stringValue": SERVICE_NAME}}]
+            },
+            "scopeSpans": [{
+                "scope": {"name": SERVICE_NAME},
+                "spans": spans
+            }]
+        }]
+    });
+    match http::post_json(url, &payload.to_string()) {
+        Ok(status) if (200..300).contains(&status) => {}
+        Ok(status) => eprintln!("OTLP export to {} rejected with status {}", url, status),
+        Err(err) => eprintln!("OTLP export to {} failed: {}", url, err),
+    }
+}
+
+// Function to start the span of a client request continuing the client's trace, if tracing is enabled and sampled
+pub fn request_span(traceparent: &str, action: &str, key: &str) -> Option<Span> {
+    EXPORTER.get()?;
+    let (trace_id, parent_span_id, sampled) = parse_traceparent(traceparent)?;
+    if !sampled {
+        return None;
+    }
+    let mut span = Span::new(trace_id, parent_span_id, &format!("proxy.{}", action), SpanKind::Server);
+    span.set_attribute("redis.key", key);
+    Some(span)
+}
+
+impl Span {
+    // Function to create a span starting now
+    fn new(trace_id: String, parent_span_id: String, name: &str, kind: SpanKind) -> Self {
+        Span {
+            trace_id,
+            span_id: format!("{:016x}", random_u64()),
+            parent_span_id,
+            name: name.to_string(),
+            kind,
+            start: now_nanos(),
+            attributes: Vec::new(),
+            error: None,
+        }
+    }
+
+    // Function to start a child span of this span
+    pub fn child(&self, name: &str, kind: SpanKind) -> Span {
+        Span::new(self.trace_id.clone(), self.span_id.clone(), name, kind)
+    }
+
+    // Function to attach a string attribute
+    pub fn set_attribute(&mut self, key: &str, value: &str) {
+        self.attributes.push((key.to_string(), value.to_string()));
+    }
+
+    // Function to mark the span as failed
+    pub fn set_error(&mut self, message: &str) {
+        self.error = Some(message.to_string());
+    }
+}
+
+impl Drop for Span {
+    // Function to finish the span and hand it to the exporter
+    fn drop(&mut self) {
+        let Some(exporter) = EXPORTER.get() else {
+            return;
+        };
+        let status = match &self.error {
+            Some(message) => json!({"code": 2, "message": message}),
+            None => json!({"code": 1}),
+        };
+        let attributes: Vec<Value> = self.attributes.iter()
+            .map(|(key, value)| json!({"key": key, "value": {"stringValue": value}}))
+            .collect();
+        let span = json!({
+            "traceId": self.trace_id,
+            "spanId": self.span_id,
+            "parentSpanId": self.parent_span_id,
+            "name": self.name,
+            "kind": self.kind as u8,
+            "startTimeUnixNano": self.start.to_string(),
+            "endTimeUnixNano": now_nanos().to_string(),
+            "attributes": attributes,
+            "status": status
+        });
+        let _ = exporter.lock().unwrap().send(span); // Exporter gone means tracing is shutting down
+    }
+}
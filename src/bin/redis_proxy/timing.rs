@@ -0,0 +1,55 @@
+// Import necessary crates and modules
+use serde_json::{json, Value}; // For the breakdown added to responses
+use std::cell::RefCell; // For the breakdown of the request a handler thread is processing
+use std::time::Duration; // For phase durations
+
+// Define the timed phases of a request
+#[derive(Clone, Copy, Debug)]
+pub enum Phase {
+    Parse, // Deserializing the request
+    Validate, // Checking the key, producer and schema
+    Redis, // Redis calls made for the request
+}
+
+// Define the time spent so far in each phase of a request
+#[derive(Default)]
+struct Breakdown {
+    parse: Duration,
+    validate: Duration,
+    redis: Duration,
+}
+
+thread_local! {
+    // Breakdown of the request the handler thread is processing, if its client negotiated debug:timing
+    static CURRENT: RefCell<Option<Breakdown>> = const { RefCell::new(None) };
+}
+
+// Function to start timing the request about to be processed on this thread
+pub fn begin() {
+    CURRENT.with(|current| *current.borrow_mut() = Some(Breakdown::default()));
+}
+
+// Function to add time spent in a phase to the current request's breakdown (nothing unless it is timed)
+pub fn record(phase: Phase, took: Duration) {
+    CURRENT.with(|current| {
+        if let Some(ref mut breakdown) = *current.borrow_mut() {
+            match phase {
+                Phase::Parse => breakdown.parse += took,
+                Phase::Validate => breakdown.validate += took,
+                Phase::Redis => breakdown.redis += took,
+            }
+        }
+    });
+}
+
+// Function to end timing the current request, returning its breakdown in microseconds
+pub fn finish(total: Duration) -> Value {
+    let breakdown = CURRENT.with(|current| current.borrow_mut().take()).unwrap_or_default();
+    let micros = |duration: Duration| duration.as_micros() as u64;
+    json!({
+        "parse_us": micros(breakdown.parse),
+        "validate_us": micros(breakdown.validate),
+        "redis_us": micros(breakdown.redis),
+        "total_us": micros(total)
+    })
+}
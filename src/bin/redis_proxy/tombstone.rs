@@ -0,0 +1,60 @@
+// Import necessary crates and modules
+use super::index; // For dropping the index entries of soft-deleted keys
+use rustredis::storage::Storage; // For the connection tombstones are moved through
+
+// Define the prefix of the keys soft-deleted values wait under until restored or expired
+const TOMBSTONE_PREFIX: &str = "cs:_tombstone:";
+
+// Define the script moving a key to its tombstone, keeping an expiry of its own if it comes sooner
+// than the grace window; returns 0 if there was nothing to delete
+const SOFT_DELETE_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 0 then return 0 end
+local ttl = redis.call('PTTL', KEYS[1])
+redis.call('RENAME', KEYS[1], KEYS[2])
+if ttl < 0 or ttl > tonumber(ARGV[1]) * 1000 then redis.call('EXPIRE', KEYS[2], ARGV[1]) end
+return 1
+";
+
+// Define the script moving a tombstone back to its key; returns 0 if there is no tombstone and -1 if
+// the key was written again meanwhile
+const RESTORE_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[2]) == 0 then return 0 end
+if redis.call('EXISTS', KEYS[1]) == 1 then return -1 end
+redis.call('RENAME', KEYS[2], KEYS[1])
+redis.call('PERSIST', KEYS[1])
+return 1
+";
+
+// Define the outcome of a restore
+#[derive(Debug, PartialEq)]
+pub enum Restore {
+    Restored, // The key holds its soft-deleted value again
+    Missing, // Nothing was soft-deleted, or the grace window has passed
+    Conflict, // The key was written again since it was soft-deleted
+}
+
+// Function to return the key a soft-deleted key's value waits under
+pub fn tombstone_key(key: &str) -> String {
+    format!("{}{}", TOMBSTONE_PREFIX, key)
+}
+
+// Function to move a key to its tombstone for `grace_secs`, dropping it from its indexes; false if
+// the key did not exist
+pub fn soft_delete(conn: &mut Storage, key: &str, grace_secs: u64) -> redis::RedisResult<bool> {
+    let mut pipe = redis::pipe();
+    pipe.atomic().cmd("EVAL").arg(SOFT_DELETE_SCRIPT).arg(2).arg(key).arg(tombstone_key(key)).arg(grace_secs);
+    index::remove(&mut pipe, key);
+    let (moved,): (i64,) = pipe.query(conn)?;
+    Ok(moved == 1)
+}
+
+// Function to move a key's tombstone back, without the grace window's expiry; the caller re-adds
+// it to its indexes once it has read the value
+pub fn restore(conn: &mut Storage, key: &str) -> redis::RedisResult<Restore> {
+    let outcome: i64 = redis::cmd("EVAL").arg(RESTORE_SCRIPT).arg(2).arg(key).arg(tombstone_key(key)).query(conn)?;
+    Ok(match outcome {
+        1 => Restore::Restored,
+        -1 => Restore::Conflict,
+        _ => Restore::Missing,
+    })
+}
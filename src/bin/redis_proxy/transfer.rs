@@ -0,0 +1,261 @@
+// Import necessary crates and modules
+use super::router::Router; // For scanning and writing every backend
+use super::{chunking, compression, document, encryption, index, partition, quota, stored_json}; // For reading and storing values as get and set do
+use redis::Commands; // For scanning and reading keys
+use rustredis::base64; // For binary values
+use rustredis::schema::{is_system_key, key_producer, validate_json_schema}; // For skipping bookkeeping keys and validating imported values
+use rustredis::storage::Storage; // For reading and writing records
+use serde_json::{json, Map, Value}; // For records
+use std::collections::HashMap; // For hash fields
+
+// Define the counts reported at the end of an export
+#[derive(Default)]
+pub struct ExportSummary {
+    pub exported: u64, // Records sent
+    pub skipped: u64, // Keys left out: of producers or sensitive values the socket may not read, or of other types
+}
+
+// Define why an import wrote nothing, or stopped partway
+pub enum ImportError {
+    Invalid(Vec<String>), // Records failing validation; nothing was written
+    Failed { written: u64, error: String }, // Redis or quota failure after `written` keys were stored
+}
+
+// Define the stored form of an imported key
+enum Content {
+    Value { stored: Vec<u8>, document: bool, value: Option<Value> }, // Bytes as set stores them, and the value for indexes
+    Members(Vec<Vec<u8>>), // Set members as sadd stores them
+    Fields(Vec<(String, Vec<u8>)>), // Hash fields
+    Entries(Vec<Vec<u8>>), // Stream entry values as xadd stores them
+}
+
+// Define a validated record ready to be written
+struct Parsed {
+    key: String,
+    content: Content,
+    ttl: Option<u64>, // Seconds the key had left when exported
+}
+
+impl Parsed {
+    // Function to return the bytes the key occupies, for quota checks
+    fn size(&self) -> u64 {
+        match self.content {
+            Content::Value { ref stored, .. } => stored.len() as u64,
+            Content::Members(ref values) | Content::Entries(ref values) => values.iter().map(|v| v.len() as u64).sum(),
+            Content::Fields(ref fields) => fields.iter().map(|(_, v)| v.len() as u64).sum(),
+        }
+    }
+}
+
+// Function to read one key as an export record ({"key", "type", "value" or "value_b64" | "members" | "fields" |
+// "entries", "ttl"}), or None if it is gone or of a type not exported
+pub fn read_record(conn: &mut Storage, key: &str) -> redis::RedisResult<Option<Value>> {
+    let unreadable = |err: String| redis::RedisError::from((redis::ErrorKind::TypeError, "Unreadable stored value", err));
+    let kind: String = redis::cmd("TYPE").arg(key).query(conn)?;
+    let mut record = Map::new();
+    record.insert("key".to_string(), json!(key));
+    match kind.as_str() {
+        "string" | "ReJSON-RL" => {
+            let stored = if kind == "string" {
+                let stored = conn.get::<_, Option<Vec<u8>>>(key)?;
+                chunking::join(conn, key, stored)?
+            } else {
+                document::read(conn, key)?
+            };
+            let Some(stored) = stored else { return Ok(None) };
+            let plain = encryption::decrypt(partition::owner_key(key), &stored).map_err(unreadable)?; // Buckets hold values of their partitioned key
+            let plain = compression::decompress(&plain).map_err(unreadable)?;
+            record.insert("type".to_string(), json!("string"));
+            match std::str::from_utf8(&plain) {
+                Ok(_) => record.insert("value".to_string(), stored_json(&plain)),
+                Err(_) => record.insert("value_b64".to_string(), json!(base64::encode(&plain))),
+            };
+        }
+        "set" => {
+            let members: Vec<Vec<u8>> = conn.smembers(key)?;
+            record.insert("type".to_string(), json!("set"));
+            record.insert("members".to_string(), members.iter().map(|m| stored_json(m)).collect());
+        }
+        "hash" => {
+            let fields: HashMap<String, Vec<u8>> = conn.hgetall(key)?;
+            record.insert("type".to_string(), json!("hash"));
+            record.insert("fields".to_string(), Value::Object(fields.iter().map(|(f, v)| (f.clone(), stored_json(v))).collect()));
+        }
+        "stream" => {
+            let entries: Vec<(String, Vec<Vec<u8>>)> = redis::cmd("XRANGE").arg(key).arg("-").arg("+").query(conn)?;
+            let entries: Vec<Value> = entries.iter().map(|(id, fields)| {
+                let value = fields.chunks(2).find(|field| field[0] == b"data").and_then(|field| field.get(1)).map_or(Value::Null, |v| stored_json(v));
+                json!({"id": id, "value": value})
+            }).collect();
+            record.insert("type".to_string(), json!("stream"));
+            record.insert("entries".to_string(), entries.into());
+        }
+        _ => return Ok(None), // Gone meanwhile, or a type the proxy never writes
+    }
+    let ttl: i64 = conn.ttl(key)?;
+    if ttl > 0 {
+        record.insert("ttl".to_string(), json!(ttl));
+    }
+    Ok(Some(Value::Object(record)))
+}
+
+// Function to send every key matching a pattern to `emit` as a record, leaving out internal keys, keys of
+// producers `allowed` refuses and sensitive keys unless `decrypt`
+pub fn export(
+    router: &Router,
+    pattern: &str,
+    allowed: impl Fn(&str) -> bool,
+    decrypt: bool,
+    mut emit: impl FnMut(Value) -> std::io::Result<()>,
+) -> Result<ExportSummary, String> {
+    let mut summary = ExportSummary::default();
+    for backend in router.backends() {
+        let mut conn = backend.connection().map_err(|e| format!("Redis backend {} unavailable: {}", backend.name, e))?;
+        let mut names: Vec<String> = conn.scan_match::<_, String>(pattern).map_err(|e| e.to_string())?.collect();
+        names.sort();
+        for name in names.iter().filter(|name| !is_system_key(name) && router.backend_for(name).name == backend.name) {
+            if !key_producer(name).is_some_and(&allowed) || (encryption::is_sensitive(name) && !decrypt) {
+                summary.skipped += 1;
+                continue;
+            }
+            match read_record(&mut conn, name).map_err(|e| format!("Failed to read {}: {}", name, e))? {
+                Some(record) => {
+                    emit(record).map_err(|e| format!("Client went away: {}", e))?;
+                    summary.exported += 1;
+                }
+                None => summary.skipped += 1,
+            }
+        }
+    }
+    Ok(summary)
+}
+
+// Function to serialize a value as sadd and xadd store it
+fn stored_bytes(value: &Value) -> Vec<u8> {
+    value.to_string().into_bytes()
+}
+
+// Function to check a record against its key's schema and turn it into what set, sadd or xadd would store
+fn parse_record(record: &Value, check_key: &dyn Fn(&str) -> Result<(), String>, compress_above: Option<usize>) -> Result<Parsed, String> {
+    let key = record["key"].as_str().ok_or("record without a key")?;
+    check_key(key)?;
+    let sensitive = encryption::is_sensitive(key);
+    let validated = |value: &Value| validate_json_schema(key, value).map(|_| stored_bytes(value));
+    let list = |field: &str| record[field].as_array().ok_or_else(|| format!("{} record without {}", record["type"], field));
+    let content = match record["type"].as_str().unwrap_or("string") {
+        "string" => {
+            let (mut stored, value) = match (record.get("value"), record["value_b64"].as_str()) {
+                (Some(value), None) => (validated(value)?, Some(value.clone())),
+                (None, Some(text)) => (base64::decode(text).map_err(|e| format!("invalid value_b64: {}", e))?, None),
+                _ => return Err("string record needs either value or value_b64".to_string()),
+            };
+            let document = value.is_some() && !sensitive && document::is_document(key);
+            if let Some(compressed) = compress_above.filter(|_| !document).and_then(|above| compression::compress(&stored, above)) {
+                stored = compressed;
+            }
+            if sensitive {
+                stored = encryption::encrypt(partition::owner_key(key), &stored);
+            }
+            Content::Value { stored, document, value: value.filter(|_| !sensitive) }
+        }
+        _ if sensitive => return Err("sensitive keys only hold plain values".to_string()),
+        "set" => Content::Members(list("members")?.iter().map(validated).collect::<Result<_, _>>()?),
+        "stream" => Content::Entries(list("entries")?.iter().map(|entry| validated(&entry["value"])).collect::<Result<_, _>>()?),
+        "hash" => {
+            let fields = record["fields"].as_object().ok_or("hash record without fields")?;
+            Content::Fields(fields.iter().map(|(field, value)| {
+                let stored = match value {
+                    Value::String(text) => text.clone().into_bytes(), // Hash fields are written outside the proxy, as plain text
+                    other => stored_bytes(other),
+                };
+                (field.clone(), stored)
+            }).collect())
+        }
+        other => return Err(format!("unsupported record type {}", other)),
+    };
+    Ok(Parsed { key: key.to_string(), content, ttl: record["ttl"].as_u64() })
+}
+
+// Function to replace one key with an imported record, keeping its indexes and quota up to date
+fn write(conn: &mut Storage, parsed: &Parsed, stream_maxlen: usize) -> Result<(), String> {
+    // Hold the producer's quota while the key is replaced, like single writes do
+    let mut usage = key_producer(&parsed.key).and_then(quota::lock);
+    if let Some(ref usage) = usage {
+        usage.check("set", &parsed.key, parsed.size())?;
+    }
+    let key = parsed.key.as_str();
+    let mut pipe = redis::pipe();
+    pipe.atomic().del(key).ignore();
+    chunking::remove(&mut pipe, key);
+    match parsed.content {
+        Content::Value { ref stored, document, ref value } => {
+            if document {
+                document::add_store(&mut pipe, key, stored);
+            } else if chunking::is_large(stored.len()) {
+                chunking::add_store(&mut pipe, key, stored);
+            } else {
+                pipe.set(key, stored).ignore();
+            }
+            index::add(&mut pipe, key, value.as_ref());
+        }
+        Content::Members(ref members) => {
+            if !members.is_empty() {
+                pipe.sadd(key, members).ignore();
+            }
+            index::add(&mut pipe, key, None);
+        }
+        Content::Fields(ref fields) => {
+            if !fields.is_empty() {
+                pipe.hset_multiple(key, fields).ignore();
+            }
+        }
+        Content::Entries(ref entries) => {
+            for entry in entries {
+                pipe.cmd("XADD").arg(key).arg("MAXLEN").arg("~").arg(stream_maxlen).arg("*").arg("data").arg(entry).ignore();
+            }
+        }
+    }
+    if let Some(ttl) = parsed.ttl {
+        pipe.expire(key, ttl as i64).ignore();
+    }
+    pipe.query::<()>(conn).map_err(|e| format!("Failed to write {}: {}", key, e))?;
+    if let Some(ref mut usage) = usage {
+        usage.remeasure(conn, key).map_err(|e| format!("Failed to measure {}: {}", key, e))?;
+    }
+    Ok(())
+}
+
+// Function to validate every record and, only if all pass, replace their keys; `check_key` refuses keys the
+// client may not write. Returns the number of keys written
+pub fn import(
+    router: &Router,
+    records: &[Value],
+    check_key: &dyn Fn(&str) -> Result<(), String>,
+    compress_above: Option<usize>,
+    stream_maxlen: usize,
+) -> Result<u64, ImportError> {
+    let mut parsed = Vec::new();
+    let mut invalid = Vec::new();
+    for (position, record) in records.iter().enumerate() {
+        match parse_record(record, check_key, compress_above) {
+            Ok(record) => parsed.push(record),
+            Err(err) => invalid.push(format!("record {} ({}): {}", position, record["key"].as_str().unwrap_or("no key"), err)),
+        }
+    }
+    if !invalid.is_empty() {
+        return Err(ImportError::Invalid(invalid));
+    }
+
+    let mut written = 0;
+    for record in &parsed {
+        let backend = router.backend_for(&record.key);
+        let result = backend.connection()
+            .map_err(|e| format!("Redis backend {} unavailable: {}", backend.name, e))
+            .and_then(|mut conn| write(&mut conn, record, stream_maxlen));
+        if let Err(error) = result {
+            return Err(ImportError::Failed { written, error });
+        }
+        written += 1;
+    }
+    Ok(written)
+}
@@ -0,0 +1,119 @@
+// Import necessary crates and modules
+use rustredis::glob::glob_match; // For matching keys against forwarded namespaces
+use rustredis::transport::Transport; // For reaching upstream proxies over any IPC
+use std::io::{BufRead, BufReader, Read, Write}; // For line-based relaying
+use std::sync::{Mutex, OnceLock}; // For the global upstream list and their idle connections
+use std::time::Duration; // For bounding how long a relayed request may take
+
+// Define how long the upstream proxy may take to answer a relayed request
+const RELAY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Define how an upstream proxy is reached
+#[derive(Clone, Debug)]
+pub enum UpstreamAddress {
+    Unix(String), // Path of its Unix socket
+    Tcp(String), // HOST:PORT of its TCP listener
+    Pipe(String), // Name of its Windows named pipe
+}
+
+impl UpstreamAddress {
+    // Function to return the address in the form Transport connects to
+    pub fn transport_address(&self) -> String {
+        match self {
+            UpstreamAddress::Unix(path) => path.clone(),
+            UpstreamAddress::Tcp(host_port) => format!("tcp:{}", host_port),
+            UpstreamAddress::Pipe(name) => format!("pipe:{}", name),
+        }
+    }
+}
+
+// Define the configuration of one upstream proxy
+#[derive(Clone, Debug)]
+pub struct UpstreamConfig {
+    pub pattern: String, // Key glob pattern of the namespace forwarded to it
+    pub address: UpstreamAddress,
+}
+
+impl UpstreamConfig {
+    // Function to parse an upstream specification of the form PATTERN=unix:PATH, PATTERN=tcp:HOST:PORT or PATTERN=pipe:NAME
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (pattern, address) = spec.split_once('=').ok_or("expected PATTERN=unix:PATH, PATTERN=tcp:HOST:PORT or PATTERN=pipe:NAME")?;
+        let address = match address.split_once(':') {
+            Some(("unix", path)) if !path.is_empty() => UpstreamAddress::Unix(path.to_string()),
+            Some(("tcp", host_port)) if host_port.contains(':') => UpstreamAddress::Tcp(host_port.to_string()),
+            Some(("pipe", name)) if !name.is_empty() => UpstreamAddress::Pipe(name.to_string()),
+            _ => return Err(format!("invalid upstream address '{}', expected unix:PATH, tcp:HOST:PORT or pipe:NAME", address)),
+        };
+        Ok(UpstreamConfig { pattern: pattern.to_string(), address })
+    }
+}
+
+// Define a connection to an upstream proxy that negotiated newline-framed responses
+struct Connection {
+    reader: BufReader<Box<dyn Read + Send>>,
+    writer: Box<dyn Write + Send>,
+}
+
+impl Connection {
+    // Function to connect and say hello, so responses come back one per line
+    fn open(address: &UpstreamAddress) -> std::io::Result<Self> {
+        let stream = Transport::connect(&address.transport_address())?;
+        stream.set_read_timeout(Some(RELAY_TIMEOUT))?;
+        let (reader, writer): (Box<dyn Read + Send>, Box<dyn Write + Send>) = (Box::new(stream.try_clone()?), Box::new(stream));
+        let mut connection = Connection { reader: BufReader::new(reader), writer };
+        let hello = serde_json::json!({"action": "hello", "protocol_version": super::PROTOCOL_VERSION, "features": ["framing:newline"]});
+        connection.exchange(&hello.to_string())?;
+        Ok(connection)
+    }
+
+    // Function to send one request line and read the response line
+    fn exchange(&mut self, request: &str) -> std::io::Result<String> {
+        self.writer.write_all(format!("{}\n", request).as_bytes())?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "upstream proxy closed the connection"));
+        }
+        Ok(line.trim_end().to_string())
+    }
+}
+
+// Define a running upstream: its configuration and the connections not in use
+struct Upstream {
+    config: UpstreamConfig,
+    idle: Mutex<Vec<Connection>>, // Connections returned after a successful relay
+}
+
+// Define the upstreams configured at startup
+static UPSTREAMS: OnceLock<Vec<Upstream>> = OnceLock::new();
+
+// Function to register the upstream proxies namespaces are forwarded to
+pub fn start(configs: Vec<UpstreamConfig>) {
+    let _ = UPSTREAMS.set(configs.into_iter().map(|config| Upstream { config, idle: Mutex::new(Vec::new()) }).collect());
+}
+
+// Function to return the index of the upstream a key is forwarded to (first matching pattern), if any
+pub fn for_key(key: &str) -> Option<usize> {
+    UPSTREAMS.get()?.iter().position(|upstream| glob_match(&upstream.config.pattern, key))
+}
+
+// Function to relay a request line to an upstream proxy and return its response line; a stale
+// pooled connection is replaced once before giving up
+pub fn relay(index: usize, request: &str) -> Result<String, String> {
+    let upstream = &UPSTREAMS.get().expect("relay without upstreams")[index];
+    let mut pooled = upstream.idle.lock().unwrap().pop();
+    let mut last_error = String::new();
+    for _ in 0..2 {
+        let connection = match pooled.take() {
+            Some(connection) => Ok(connection),
+            None => Connection::open(&upstream.config.address),
+        };
+        match connection.and_then(|mut connection| connection.exchange(request).map(|line| (connection, line))) {
+            Ok((connection, line)) => {
+                upstream.idle.lock().unwrap().push(connection);
+                return Ok(line);
+            }
+            Err(err) => last_error = err.to_string(),
+        }
+    }
+    Err(format!("upstream proxy {:?} unavailable: {}", upstream.config.address, last_error))
+}
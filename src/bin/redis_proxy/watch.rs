@@ -0,0 +1,183 @@
+// Import necessary crates and modules
+use super::{chunking, fanout, hooks, index, publish_event}; // For the derived data, hooks and events of applied writes
+use rustredis::hooks::Change; // For the lifecycle hooks of applied writes
+use rustredis::storage::Storage; // For the connection batches are applied through
+use serde_json::{json, Value}; // For expectations, writes and reported hashes
+
+// Define the most keys one watch-and-apply may watch and write, keeping the script's run short
+pub const MAX_KEYS: usize = 64;
+
+// Define the script checking every expectation and applying the writes only if all hold; Redis runs
+// it atomically. ARGV holds the number of watched keys, their expectations (* for none, empty for
+// absent, else a SHA-1) and then the writes (set VALUE or del) of the remaining KEYS
+const SCRIPT: &str = r#"
+local watched = tonumber(ARGV[1])
+local function hashes()
+  local found = {}
+  for i = 1, watched do
+    local stored = redis.call('GET', KEYS[i])
+    found[i] = stored and redis.sha1hex(stored) or ''
+  end
+  return found
+end
+local before = hashes()
+for i = 1, watched do
+  local expected = ARGV[i + 1]
+  if expected ~= '*' and expected ~= before[i] then
+    return {i, before}
+  end
+end
+local arg = watched + 2
+for i = watched + 1, #KEYS do
+  if ARGV[arg] == 'set' then
+    redis.call('SET', KEYS[i], ARGV[arg + 1])
+    arg = arg + 2
+  else
+    redis.call('DEL', KEYS[i])
+    arg = arg + 1
+  end
+end
+return {0, hashes()}
+"#;
+
+// Define what a watched key must hold for the writes to be applied
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expected {
+    Any, // Only reported
+    Absent, // The key must not exist
+    Sha1(String), // Lowercase hex SHA-1 of the stored bytes, as an earlier watch-and-apply reported it
+}
+
+// Define a key watched by a watch-and-apply
+pub struct Watched {
+    pub key: String,
+    expected: Expected,
+}
+
+impl Watched {
+    // Function to parse {"key": ..., "sha1": HEX} (null for a key that must not exist); without sha1
+    // the key's hash is only reported
+    pub fn parse(entry: &Value) -> Result<Self, String> {
+        let key = entry["key"].as_str().filter(|key| !key.is_empty()).ok_or("Every watched key needs a key")?;
+        let expected = match entry.get("sha1") {
+            None => Expected::Any,
+            Some(Value::Null) => Expected::Absent,
+            Some(Value::String(hash)) if hash.len() == 40 && hash.bytes().all(|b| b.is_ascii_hexdigit()) => Expected::Sha1(hash.to_ascii_lowercase()),
+            Some(other) => return Err(format!("Invalid sha1 {} of {}, expected 40 hex digits or null", other, key)),
+        };
+        Ok(Watched { key: key.to_string(), expected })
+    }
+
+    // Function to return the expectation as the script takes it
+    fn argument(&self) -> &str {
+        match self.expected {
+            Expected::Any => "*",
+            Expected::Absent => "",
+            Expected::Sha1(ref hash) => hash,
+        }
+    }
+}
+
+// Define a write of a watch-and-apply
+pub enum Write {
+    Set { key: String, value: Value },
+    Del { key: String },
+}
+
+impl Write {
+    // Function to parse {"action": "set", "key": ..., "value": ...} or {"action": "del", "key": ...}
+    pub fn parse(entry: &Value) -> Result<Self, String> {
+        let key = entry["key"].as_str().filter(|key| !key.is_empty()).ok_or("Every write needs a key")?.to_string();
+        match (entry["action"].as_str(), entry.get("value")) {
+            (Some("set"), Some(value)) => Ok(Write::Set { key, value: value.clone() }),
+            (Some("set"), None) => Err(format!("The set of {} needs a value", key)),
+            (Some("del"), None) => Ok(Write::Del { key }),
+            (Some("del"), Some(_)) => Err(format!("The del of {} takes no value", key)),
+            _ => Err(format!("Invalid write of {}, only set and del can be applied", key)),
+        }
+    }
+
+    // Function to return the key written
+    pub fn key(&self) -> &str {
+        match self {
+            Write::Set { key, .. } | Write::Del { key } => key,
+        }
+    }
+}
+
+// Define a write ready for the script, with what announces it
+pub struct Prepared {
+    pub key: String,
+    pub stored: Option<Vec<u8>>, // Bytes to store, None to delete
+    pub announced: Option<Value>, // Value as indexes, events and webhooks carry it
+    pub publication: Option<(String, String)>, // Channel and payload of the event, None if silent
+}
+
+impl Prepared {
+    // Function to return the change the lifecycle hooks of the write run on; the script does not
+    // tell creates from updates, nor deletes of absent keys from others
+    pub fn change(&self) -> Change {
+        if self.stored.is_some() { Change::Written } else { Change::Deleted }
+    }
+}
+
+// Define the outcome of a watch-and-apply
+pub enum Outcome {
+    Applied { hashes: Value }, // Hashes of the watched keys after the writes
+    Failed { key: String, expected: Value, actual: Value, hashes: Value }, // First expectation that did not hold; nothing was written
+}
+
+// Function to report a hash as JSON, null for an absent key
+fn hash_json(hash: &str) -> Value {
+    if hash.is_empty() { Value::Null } else { Value::String(hash.to_string()) }
+}
+
+// Function to check the expectations and apply the writes in one script run
+pub fn apply(conn: &mut Storage, watched: &[Watched], writes: &[Prepared]) -> redis::RedisResult<Outcome> {
+    let script = redis::Script::new(SCRIPT);
+    let mut invocation = script.prepare_invoke();
+    invocation.arg(watched.len());
+    for watch in watched {
+        invocation.key(&watch.key).arg(watch.argument());
+    }
+    for write in writes {
+        invocation.key(&write.key);
+        match write.stored {
+            Some(ref stored) => invocation.arg("set").arg(stored.as_slice()),
+            None => invocation.arg("del"),
+        };
+    }
+    let (failed, hashes): (usize, Vec<String>) = invocation.invoke(conn)?;
+    let reported: serde_json::Map<String, Value> = watched.iter().zip(&hashes).map(|(watch, hash)| (watch.key.clone(), hash_json(hash))).collect();
+    if failed == 0 {
+        return Ok(Outcome::Applied { hashes: reported.into() });
+    }
+    let watch = &watched[failed - 1];
+    let expected = match watch.expected {
+        Expected::Sha1(ref hash) => json!(hash),
+        _ => Value::Null,
+    };
+    Ok(Outcome::Failed { key: watch.key.clone(), expected, actual: hash_json(&hashes[failed - 1]), hashes: reported.into() })
+}
+
+// Function to bring the chunks, indexes, fan-out copies and hooks of applied writes up to date and announce them
+pub fn update_derived(conn: &mut Storage, writes: &[Prepared]) -> redis::RedisResult<()> {
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    for write in writes {
+        chunking::remove(&mut pipe, &write.key); // Chunks of a large value the write replaced
+        match write.stored {
+            Some(ref stored) => {
+                index::add(&mut pipe, &write.key, write.announced.as_ref());
+                fanout::add(&mut pipe, &write.key, stored, &write.publication);
+            }
+            None => {
+                index::remove(&mut pipe, &write.key);
+                fanout::remove(&mut pipe, &write.key, &write.publication);
+            }
+        }
+        hooks::add(&mut pipe, &write.key, write.change(), write.announced.as_ref());
+    }
+    pipe.query::<()>(conn)?;
+    writes.iter().try_for_each(|write| publish_event(conn, &write.publication))
+}
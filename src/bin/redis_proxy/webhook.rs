@@ -0,0 +1,119 @@
+// Import necessary crates and modules
+use rustredis::glob::glob_match; // For matching keys against sink patterns
+use rustredis::http; // For posting notifications
+use serde_json::{json, Value}; // For building notification payloads
+use std::sync::mpsc::{self, SyncSender, TrySendError}; // For queueing notifications per sink
+use std::sync::OnceLock; // For the global sink list
+use std::thread; // For delivery threads
+use std::time::{Duration, SystemTime, UNIX_EPOCH}; // For backoff and timestamps
+
+// Define how many notifications may wait per sink before new ones are dropped
+const QUEUE_CAPACITY: usize = 1024;
+
+// Define the first retry delay, doubled after each failed attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+// Define the longest delay between two retries
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// Define the configuration of one webhook sink
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    pub pattern: String, // Key glob pattern selecting the writes to forward
+    pub url: String, // http:// URL the notifications are POSTed to
+}
+
+impl WebhookConfig {
+    // Function to parse a sink specification of the form PATTERN=URL
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (pattern, url) = spec.split_once('=').ok_or("expected PATTERN=URL")?;
+        if !url.starts_with("http://") {
+            return Err(format!("unsupported webhook URL '{}', only http:// is supported", url));
+        }
+        Ok(WebhookConfig {
+            pattern: pattern.to_string(),
+            url: url.to_string(),
+        })
+    }
+}
+
+// Define a running sink: its configuration and the queue feeding its delivery thread
+struct Sink {
+    config: WebhookConfig, // Pattern and URL of the sink
+    hook: bool, // Only notified by lifecycle hooks, not by every matching write
+    queue: SyncSender<String>, // Serialized notifications waiting for delivery
+}
+
+// Define the sinks started at startup
+static SINKS: OnceLock<Vec<Sink>> = OnceLock::new();
+
+// Function to start one delivery thread per configured sink and per URL of the lifecycle hooks
+pub fn start(configs: Vec<WebhookConfig>, hook_urls: Vec<String>, max_retries: u32) {
+    let hooks = hook_urls.into_iter().map(|url| (WebhookConfig { pattern: String::new(), url }, true));
+    let sinks = configs.into_iter().map(|config| (config, false)).chain(hooks).map(|(config, hook)| {
+        let (queue, receiver) = mpsc::sync_channel::<String>(QUEUE_CAPACITY);
+        let url = config.url.clone();
+        thread::spawn(move || {
+            for body in receiver {
+                deliver(&url, &body, max_retries);
+            }
+        });
+        Sink { config, hook, queue }
+    }).collect();
+    let _ = SINKS.set(sinks);
+}
+
+// Function to POST one notification, retrying with exponential backoff
+fn deliver(url: &str, body: &str, max_retries: u32) {
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 0..=max_retries {
+        let error = match http::post_json(url, body) {
+            Ok(status) if (200..300).contains(&status) => return,
+            Ok(status) => format!("status {}", status),
+            Err(err) => err,
+        };
+        if attempt == max_retries {
+            eprintln!("Webhook {} failed after {} attempts, dropping notification: {}", url, attempt + 1, error);
+            return;
+        }
+        eprintln!("Webhook {} failed ({}), retrying in {:?}", url, error, backoff);
+        thread::sleep(backoff);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+// Function to build the body of a notification
+fn body(action: &str, key: &str, value: Option<&Value>) -> String {
+    json!({
+        "action": action,
+        "key": key,
+        "value": value,
+        "timestamp": SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64
+    }).to_string()
+}
+
+// Function to queue a notification on a sink, dropping it if the sink is backed up
+fn queue(sink: &Sink, key: &str, body: String) {
+    if let Err(TrySendError::Full(_)) = sink.queue.try_send(body) {
+        eprintln!("Webhook {} queue full, dropping notification for {}", sink.config.url, key);
+    }
+}
+
+// Function to queue a notification of a completed write to every sink whose pattern matches the key
+pub fn notify(action: &str, key: &str, value: Option<&Value>) {
+    let Some(sinks) = SINKS.get() else {
+        return;
+    };
+    let mut notification = None; // Built lazily, most writes match no sink
+    for sink in sinks.iter().filter(|sink| !sink.hook && glob_match(&sink.config.pattern, key)) {
+        let notification = notification.get_or_insert_with(|| body(action, key, value));
+        queue(sink, key, notification.clone());
+    }
+}
+
+// Function to queue a notification of a lifecycle hook on the sink of its URL
+pub fn notify_url(url: &str, action: &str, key: &str, value: Option<&Value>) {
+    if let Some(sink) = SINKS.get().into_iter().flatten().find(|sink| sink.hook && sink.config.url == url) {
+        queue(sink, key, body(action, key, value));
+    }
+}
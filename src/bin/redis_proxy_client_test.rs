@@ -1,8 +1,10 @@
-use std::os::unix::net::UnixStream;
+use rustredis::transport::Transport;
 use std::io::{Write, Read};
 use std::thread;
 use std::time::Duration;
 use clap::Parser;
+use rustredis::client::new_traceparent;
+use rustredis::rng::{self, Rng};
 use serde_json::json;
 
 /// Redis Proxy Test Client
@@ -15,26 +17,38 @@ struct Args {
     /// Number of requests per second
     #[arg(long)]
     rate: u64,
+
+    /// Attach a W3C `traceparent` to every request so the proxy traces it
+    #[arg(long)]
+    trace: bool,
+
+    /// Seed of the generated trace ids; runs with the same seed send identical requests
+    #[arg(long)]
+    seed: Option<u64>,
 }
 
-fn main() {
-    let args = Args::parse();
+pub fn main() {
+    let args = Args::parse_from(rustredis::multicall::args());
 
-    // Define the Unix socket path used by the Redis Proxy
-    let socket_path = "/tmp/redis_proxy.sock";
+    // Define the address of the Redis Proxy (its Unix socket, or its named pipe on Windows)
+    let socket_path = rustredis::client::DEFAULT_SOCKET_PATH;
 
     // Connect to the Redis Proxy
-    let mut stream = UnixStream::connect(&socket_path)
+    let mut stream = Transport::connect(socket_path)
         .expect("Failed to connect to Redis Proxy");
 
     println!("Connected to Redis Proxy. Sending {} requests per second to key: {}", args.rate, args.key);
 
+    let seed = args.seed.unwrap_or_else(rng::entropy_seed);
+    println!("Seed: {}", seed);
+    let mut rng = Rng::new(seed);
+
     let interval = Duration::from_secs_f64(1.0 / args.rate as f64);
     let mut usage_value = 1; // Initialize usage value
 
     loop {
         // Constructing a JSON request matching the schema
-        let request = json!({
+        let mut request = json!({
             "action": "set",
             "key": args.key,
             "value": {
@@ -43,12 +57,17 @@ fn main() {
                 "usage": usage_value
             }
         });
+        if args.trace {
+            let traceparent = new_traceparent(&mut rng);
+            println!("Sending request with traceparent {}", traceparent);
+            request["traceparent"] = json!(traceparent);
+        }
 
         // Increment usage value and reset if it exceeds 10000
         usage_value = if usage_value >= 10000 { 1 } else { usage_value + 1 };
 
         // Send request
-        let request_str = format!("{}\n", request.to_string());
+        let request_str = format!("{}\n", request);
         stream.write_all(request_str.as_bytes())
             .expect("Failed to send request");
 
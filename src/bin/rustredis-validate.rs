@@ -0,0 +1,184 @@
+// Backfill validator.
+//
+// Scans the cs:* keys already in Redis and checks them against the current schemas (the built-in
+// ones, or those of --schema-dir), for cleaning up data written before the proxy enforced them.
+// String values, set members and stream entries (their `data` field) are checked; keys outside
+// the key grammar are reported too. Depending on --action, non-conforming entries are then:
+//
+//   report      listed only (the default)
+//   fix         replaced by their normalized form (undeclared properties dropped) if that conforms;
+//               strings only, keeping their TTL
+//   delete      deleted (the key for strings, the member or entry for sets and streams)
+//   quarantine  moved to cs:_quarantine:<key> for later inspection; strings only
+//
+// Example:  rustredis-validate --schema-dir schemas --action fix
+//
+// Values the proxy stored compressed, encrypted or as binary cannot be checked and are counted as opaque.
+
+use clap::{Parser, ValueEnum};
+use redis::Commands;
+use rustredis::schema::{is_system_key, is_valid_key, load_schema_dir, normalize, schema_for, validate_json_schema};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+// Define the namespace quarantined values are moved to
+const QUARANTINE_PREFIX: &str = "cs:_quarantine:";
+
+// Define what happens to non-conforming entries
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Action {
+    Report,
+    Fix,
+    Delete,
+    Quarantine,
+}
+
+/// Validate the cs:* data in Redis against the producer schemas, optionally repairing it
+#[derive(Parser)]
+#[command(name = "rustredis-validate", author, version, about)]
+struct Args {
+    /// Redis instance to scan
+    #[arg(long, default_value = "redis://127.0.0.1/")]
+    redis_url: String,
+
+    /// Glob pattern of the keys to scan
+    #[arg(long, default_value = "cs:*")]
+    pattern: String,
+
+    /// Directory of <producer>.<object>.json schemas overriding the built-in ones
+    #[arg(long)]
+    schema_dir: Option<PathBuf>,
+
+    /// What to do with non-conforming entries
+    #[arg(long, value_enum, default_value_t = Action::Report)]
+    action: Action,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+// Define the outcome of the scan
+#[derive(Default)]
+struct Report {
+    scanned: u64, // Keys looked at
+    valid: u64, // Entries conforming to their schema (or without one)
+    opaque: u64, // Entries that could not be checked
+    invalid: Vec<Value>, // Non-conforming entries and what was done with them
+}
+
+// Function to parse a stored value for checking; None for compressed, encrypted and binary values
+fn parse_stored(stored: &[u8]) -> Option<Value> {
+    if stored.first() == Some(&0) {
+        return None; // Proxy marker header of a compressed or encrypted value
+    }
+    let text = std::str::from_utf8(stored).ok()?;
+    Some(serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))) // Legacy plain strings
+}
+
+// Function to check a value against the key's schema
+fn check(key: &str, stored: &[u8]) -> Option<Result<(), String>> {
+    parse_stored(stored).map(|value| validate_json_schema(key, &value))
+}
+
+// Function to repair or remove a non-conforming string value; returns what was done
+fn handle_string(conn: &mut redis::Connection, key: &str, stored: &[u8], action: Action) -> redis::RedisResult<&'static str> {
+    match action {
+        Action::Report => Ok("reported"),
+        Action::Delete => conn.del::<&str, ()>(key).map(|_| "deleted"),
+        Action::Quarantine => redis::cmd("RENAME").arg(key).arg(format!("{}{}", QUARANTINE_PREFIX, key.trim_start_matches("cs:")))
+            .query::<()>(conn).map(|_| "quarantined"),
+        Action::Fix => {
+            let normalized = parse_stored(stored).map(|value| normalize(key, &value));
+            match normalized.filter(|value| validate_json_schema(key, value).is_ok()) {
+                Some(value) => redis::cmd("SET").arg(key).arg(value.to_string()).arg("KEEPTTL").query::<()>(conn).map(|_| "fixed"),
+                None => Ok("unfixable"),
+            }
+        }
+    }
+}
+
+// Define a stream entry as XRANGE returns it: its id and its field-value pairs
+type StreamEntry = (String, Vec<(String, Vec<u8>)>);
+
+// Function to check every value of one key, acting on the non-conforming ones
+fn validate_key(conn: &mut redis::Connection, key: &str, action: Action, report: &mut Report) -> redis::RedisResult<()> {
+    report.scanned += 1;
+    if !is_valid_key(key) {
+        report.invalid.push(json!({"key": key, "error": "key does not match the key grammar", "outcome": "reported"}));
+        return Ok(());
+    }
+    if schema_for(key).is_none() {
+        report.valid += 1; // Keys without a schema accept any value
+        return Ok(());
+    }
+
+    let kind: String = redis::cmd("TYPE").arg(key).query(conn)?;
+    let entries: Vec<(Option<String>, Vec<u8>)> = match kind.as_str() {
+        "string" => conn.get::<&str, Option<Vec<u8>>>(key)?.into_iter().map(|value| (None, value)).collect(),
+        "set" => conn.smembers::<&str, Vec<Vec<u8>>>(key)?.into_iter().map(|member| (None, member)).collect(),
+        "stream" => {
+            let entries: Vec<StreamEntry> = redis::cmd("XRANGE").arg(key).arg("-").arg("+").query(conn)?;
+            entries.into_iter()
+                .filter_map(|(id, fields)| fields.into_iter().find(|(field, _)| field == "data").map(|(_, data)| (Some(id), data)))
+                .collect()
+        }
+        _ => return Ok(()), // Hashes and others are not written through schemas
+    };
+
+    for (id, stored) in entries {
+        let error = match check(key, &stored) {
+            None => {
+                report.opaque += 1;
+                continue;
+            }
+            Some(Ok(())) => {
+                report.valid += 1;
+                continue;
+            }
+            Some(Err(error)) => error,
+        };
+        let outcome = match (kind.as_str(), action) {
+            ("string", _) => handle_string(conn, key, &stored, action)?,
+            (_, Action::Delete) if kind == "set" => conn.srem::<&str, &[u8], ()>(key, &stored).map(|_| "deleted")?,
+            (_, Action::Delete) => conn.xdel::<&str, &str, ()>(key, &[id.as_deref().unwrap_or_default()]).map(|_| "deleted")?,
+            (_, Action::Report) => "reported",
+            _ => "unsupported", // Fixing and quarantining apply to strings only
+        };
+        report.invalid.push(json!({"key": key, "entry": id, "error": error, "outcome": outcome}));
+    }
+    Ok(())
+}
+
+fn main() {
+    let args = Args::parse();
+    if let Some(ref dir) = args.schema_dir {
+        if let Err(err) = load_schema_dir(dir) {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    }
+    let client = rustredis::tunnel::open(&args.redis_url).expect("Invalid Redis URL");
+    let mut conn = client.get_connection().expect("Failed to connect to Redis");
+    let keys: Vec<String> = conn.scan_match::<_, String>(&args.pattern).expect("Failed to scan keys").collect();
+
+    let mut report = Report::default();
+    for key in keys.iter().filter(|key| !is_system_key(key)) { // Proxy bookkeeping keys have no schema
+        if let Err(err) = validate_key(&mut conn, key, args.action, &mut report) {
+            eprintln!("Failed to validate {}: {}", key, err);
+        }
+    }
+
+    if args.json {
+        println!("{}", json!({"scanned": report.scanned, "valid": report.valid, "opaque": report.opaque, "invalid": report.invalid}));
+    } else {
+        for entry in &report.invalid {
+            let at = entry["entry"].as_str().map(|id| format!(" (entry {})", id)).unwrap_or_default();
+            println!("{}{}: {} [{}]", entry["key"].as_str().unwrap_or_default(), at, entry["error"].as_str().unwrap_or_default(), entry["outcome"].as_str().unwrap_or_default());
+        }
+        println!("{} keys scanned: {} entries valid, {} invalid, {} opaque", report.scanned, report.valid, report.invalid.len(), report.opaque);
+    }
+    if !report.invalid.is_empty() && args.action == Action::Report {
+        std::process::exit(1); // Lets scripts tell clean data from dirty
+    }
+}
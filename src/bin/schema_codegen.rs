@@ -0,0 +1,183 @@
+// Typed producer bindings generator.
+//
+// Turns the JSON schemas of producer objects into Rust structs with serde derives, plus a
+// `TypedProducers` extension trait of ProxyClient with one `set_<producer>_<object>` method per
+// schema, so producers get compile-time checked payloads. The library's own bindings for the
+// built-in schemas are regenerated with:
+//
+//   schema_codegen --out src/typed.rs
+//
+// Deployments with their own schemas generate bindings for their producer crates with:
+//
+//   schema_codegen --schema-dir schemas --crate-path rustredis --out src/typed.rs
+//
+// Properties become fields (number: f64, integer: i64, string: String, boolean: bool, arrays of
+// those: Vec<_>, anything else: serde_json::Value); properties that are not required are Options.
+
+use clap::Parser;
+use rustredis::schema::{read_schema_dir, SCHEMAS};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+// Define the words that need a raw identifier as field names
+const KEYWORDS: [&str; 50] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn", "else",
+    "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match",
+    "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static", "struct", "super", "trait", "true",
+    "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Generate typed Rust bindings for producer objects from their JSON schemas
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Directory of <producer>.<object>.json schemas to generate bindings for instead of the built-in ones
+    #[arg(long)]
+    schema_dir: Option<PathBuf>,
+
+    /// Path of the rustredis crate in the generated code ("crate" inside the library itself)
+    #[arg(long, default_value = "crate")]
+    crate_path: String,
+
+    /// File to write the bindings to (printed if omitted)
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+// Function to turn a name into UpperCamelCase (DiskUsage + object1 -> DiskUsageObject1)
+fn camel_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| part[..1].to_ascii_uppercase() + &part[1..])
+        .collect()
+}
+
+// Function to turn a name into snake_case (DiskUsage -> disk_usage, signal-strength -> signal_strength)
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            snake.push(c);
+        } else if !snake.ends_with('_') {
+            snake.push('_');
+        }
+    }
+    if snake.starts_with(|c: char| c.is_ascii_digit()) {
+        snake.insert(0, '_');
+    }
+    if KEYWORDS.contains(&snake.as_str()) {
+        snake.insert_str(0, "r#");
+    }
+    snake
+}
+
+// Function to pick the Rust type of a property schema
+fn rust_type(schema: &Value) -> String {
+    match schema["type"].as_str() {
+        Some("number") => "f64".to_string(),
+        Some("integer") => "i64".to_string(),
+        Some("string") => "String".to_string(),
+        Some("boolean") => "bool".to_string(),
+        Some("array") => format!("Vec<{}>", rust_type(&schema["items"])),
+        _ => "serde_json::Value".to_string(), // Objects, unions and untyped values
+    }
+}
+
+// Function to write the struct, trait impl and setter of one producer object
+fn generate_object(code: &mut String, base_key: &str, schema: &Value) -> (String, String) {
+    let mut parts = base_key.split(':').skip(1);
+    let (producer, object) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let name = camel_case(&format!("{}_{}", producer, object));
+    let required: Vec<&str> = schema["required"].as_array().map(|r| r.iter().filter_map(Value::as_str).collect()).unwrap_or_default();
+    let properties = schema["properties"].as_object().cloned().unwrap_or_default();
+
+    writeln!(code, "/// Value of `{}`.", base_key).unwrap();
+    writeln!(code, "#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]").unwrap();
+    writeln!(code, "pub struct {} {{", name).unwrap();
+    for (property, property_schema) in &properties {
+        let field = snake_case(property);
+        if field.trim_start_matches("r#") != property {
+            writeln!(code, "    #[serde(rename = {:?})]", property).unwrap();
+        }
+        if required.contains(&property.as_str()) {
+            writeln!(code, "    pub {}: {},", field, rust_type(property_schema)).unwrap();
+        } else {
+            writeln!(code, "    #[serde(default, skip_serializing_if = \"Option::is_none\")]").unwrap();
+            writeln!(code, "    pub {}: Option<{}>,", field, rust_type(property_schema)).unwrap();
+        }
+    }
+    writeln!(code, "}}\n").unwrap();
+    writeln!(code, "impl ProducerObject for {} {{", name).unwrap();
+    writeln!(code, "    const BASE_KEY: &'static str = {:?};", base_key).unwrap();
+    writeln!(code, "}}\n").unwrap();
+    (name, format!("set_{}_{}", snake_case(producer), snake_case(object)))
+}
+
+// Function to generate the bindings of a set of schemas
+fn generate(schemas: &BTreeMap<String, Value>, crate_path: &str, source: &str) -> String {
+    let mut code = String::new();
+    writeln!(code, "//! Payload types of the producer objects with a {}.", source).unwrap();
+    writeln!(code, "//!").unwrap();
+    writeln!(code, "//! Generated by `schema_codegen`; do not edit by hand.\n").unwrap();
+    writeln!(code, "use {}::client::{{ClientError, ProducerObject, ProxyClient}};", crate_path).unwrap();
+    writeln!(code, "use serde::{{Deserialize, Serialize}};\n").unwrap();
+
+    let setters: Vec<(String, String, &String)> = schemas.iter()
+        .map(|(base_key, schema)| {
+            let (name, setter) = generate_object(&mut code, base_key, schema);
+            (name, setter, base_key)
+        })
+        .collect();
+
+    writeln!(code, "/// Typed setters of the producer objects, checked against their schemas at compile time.").unwrap();
+    writeln!(code, "pub trait TypedProducers {{").unwrap();
+    for (name, setter, base_key) in &setters {
+        writeln!(code, "    /// Stores a `{}` value (under `{}:<id>` with an id).", base_key, base_key).unwrap();
+        writeln!(code, "    fn {}(&mut self, id: Option<&str>, value: &{}) -> Result<(), ClientError>;", setter, name).unwrap();
+    }
+    writeln!(code, "}}\n").unwrap();
+    writeln!(code, "impl TypedProducers for ProxyClient {{").unwrap();
+    for (i, (name, setter, _)) in setters.iter().enumerate() {
+        if i > 0 {
+            writeln!(code).unwrap();
+        }
+        writeln!(code, "    fn {}(&mut self, id: Option<&str>, value: &{}) -> Result<(), ClientError> {{", setter, name).unwrap();
+        writeln!(code, "        self.set_object(id, value)").unwrap();
+        writeln!(code, "    }}").unwrap();
+    }
+    writeln!(code, "}}").unwrap();
+    code
+}
+
+fn main() {
+    let args = Args::parse();
+    let (schemas, source): (BTreeMap<String, Value>, _) = match args.schema_dir {
+        Some(ref dir) => {
+            let (active, _) = read_schema_dir(dir).unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            });
+            (active.into_iter().collect(), format!("schema in {}", dir.display()))
+        }
+        None => (SCHEMAS.iter().map(|(key, schema)| (key.to_string(), schema.clone())).collect(), "built-in schema".to_string()),
+    };
+
+    let code = generate(&schemas, &args.crate_path, &source);
+    match args.out {
+        Some(ref path) => {
+            if let Err(err) = std::fs::write(path, code) {
+                eprintln!("Failed to write {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+            println!("Bindings for {} producer objects written to {}", schemas.len(), path.display());
+        }
+        None => print!("{}", code),
+    }
+}
@@ -0,0 +1,174 @@
+// Draft schema generator.
+//
+// Reads sample payloads of a producer object (JSON files, or newline-delimited JSON on stdin
+// with `-`) and writes a draft JSON Schema inferred from them into the schema directory the
+// proxy loads with --schema-dir. Properties present in every sample become required; values
+// seen with several types get a list of types. Example:
+//
+//   schema_gen --key cs:DiskUsage:object1 samples/*.json
+//
+// writes schemas/DiskUsage.object1.json, to be reviewed and tightened before deployment. With
+// --shadow the draft is written as DiskUsage.object1.shadow.json, which the proxy only checks
+// requests against and reports on, so its effect can be assessed before it is enforced.
+
+use clap::Parser;
+use rustredis::schema::{base_key, schema_file_name};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+
+/// Infer a draft JSON Schema for a producer object from sample payloads
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Base key the schema governs (cs:<producer>:<object>)
+    #[arg(long)]
+    key: String,
+
+    /// Sample files, each one JSON payload or newline-delimited payloads ("-" reads stdin)
+    #[arg(required = true)]
+    samples: Vec<String>,
+
+    /// Directory the draft schema is written to
+    #[arg(long, default_value = "schemas")]
+    schema_dir: PathBuf,
+
+    /// Replace an existing schema file
+    #[arg(long)]
+    force: bool,
+
+    /// Write the draft as the key's shadow schema, checked by the proxy without rejecting requests
+    #[arg(long)]
+    shadow: bool,
+
+    /// Print the schema instead of writing it
+    #[arg(long)]
+    stdout: bool,
+}
+
+// Define what was observed at one position of the samples
+#[derive(Default)]
+struct Shape {
+    types: BTreeSet<&'static str>, // JSON Schema types seen
+    objects: u64, // Number of objects seen, to tell which properties are always present
+    properties: BTreeMap<String, (u64, Shape)>, // Times each property was present, and its shape
+    items: Option<Box<Shape>>, // Shape of array elements
+}
+
+impl Shape {
+    // Function to merge one observed value into the shape
+    fn add(&mut self, value: &Value) {
+        let kind = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(items) => {
+                let shape = self.items.get_or_insert_with(Box::default);
+                items.iter().for_each(|item| shape.add(item));
+                "array"
+            }
+            Value::Object(map) => {
+                self.objects += 1;
+                for (name, field) in map {
+                    let (count, shape) = self.properties.entry(name.clone()).or_default();
+                    *count += 1;
+                    shape.add(field);
+                }
+                "object"
+            }
+        };
+        self.types.insert(kind);
+    }
+
+    // Function to turn the shape into a schema
+    fn schema(&self) -> Value {
+        let mut types = self.types.clone();
+        if types.contains("number") {
+            types.remove("integer"); // Integers are numbers too
+        }
+        let mut schema = Map::new();
+        match types.len() {
+            0 => {} // Only empty arrays seen; anything goes
+            1 => { schema.insert("type".to_string(), json!(types.first())); }
+            _ => { schema.insert("type".to_string(), json!(types)); }
+        }
+        if types.contains("object") {
+            let properties: Map<String, Value> = self.properties.iter().map(|(name, (_, shape))| (name.clone(), shape.schema())).collect();
+            let required: Vec<&String> = self.properties.iter().filter(|(_, (count, _))| *count == self.objects).map(|(name, _)| name).collect();
+            schema.insert("properties".to_string(), Value::Object(properties));
+            schema.insert("required".to_string(), json!(required));
+        }
+        if let Some(ref items) = self.items {
+            schema.insert("items".to_string(), items.schema());
+        }
+        Value::Object(schema)
+    }
+}
+
+// Function to read the payloads of one sample source
+fn read_samples(source: &str) -> Result<Vec<Value>, String> {
+    let text = if source == "-" {
+        io::stdin().lock().lines().collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?.join("\n")
+    } else {
+        std::fs::read_to_string(source).map_err(|e| format!("failed to read {}: {}", source, e))?
+    };
+    // A file holding one (possibly pretty-printed) payload, or one payload per line
+    if let Ok(value) = serde_json::from_str(&text) {
+        return Ok(vec![value]);
+    }
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(|e| format!("invalid JSON in {}: {}", source, e)))
+        .collect()
+}
+
+fn main() {
+    let args = Args::parse();
+    let key = base_key(&args.key);
+    if !key.starts_with("cs:") || key.split(':').count() != 3 {
+        eprintln!("--key must be a base key of the form cs:<producer>:<object>");
+        std::process::exit(2);
+    }
+
+    let mut shape = Shape::default();
+    let mut count = 0;
+    for source in &args.samples {
+        let samples = read_samples(source).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        });
+        for sample in &samples {
+            shape.add(sample);
+        }
+        count += samples.len();
+    }
+
+    let mut schema = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": key,
+        "description": format!("Draft inferred from {} sample payloads; review before deploying", count),
+    });
+    if let (Value::Object(schema), Value::Object(inferred)) = (&mut schema, shape.schema()) {
+        schema.extend(inferred);
+    }
+    let text = format!("{}\n", serde_json::to_string_pretty(&schema).unwrap());
+
+    if args.stdout {
+        print!("{}", text);
+        return;
+    }
+    let path = args.schema_dir.join(schema_file_name(&key, args.shadow));
+    if path.exists() && !args.force {
+        eprintln!("{} already exists, use --force to replace it", path.display());
+        std::process::exit(1);
+    }
+    let written = std::fs::create_dir_all(&args.schema_dir).and_then(|_| std::fs::write(&path, text));
+    if let Err(err) = written {
+        eprintln!("Failed to write {}: {}", path.display(), err);
+        std::process::exit(1);
+    }
+    println!("Draft schema for {} from {} samples written to {}", key, count, path.display());
+}
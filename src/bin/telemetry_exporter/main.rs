@@ -0,0 +1,293 @@
+// Telemetry exporter: periodically writes the streams and hashes maintained by the monitors
+// as NDJSON batches (optionally gzip-compressed) to a directory or an S3-compatible bucket.
+//
+// Stream entries are exported once: the last exported id of every stream is kept in a
+// checkpoint file that only advances after a batch was stored. Hashes are exported as a
+// full snapshot in every batch. Compression runs in-process, so no gzip command is needed.
+
+// Import necessary crates and modules
+mod s3; // S3-compatible uploads
+
+use clap::Parser; // For command line argument parsing
+use flate2::write::GzEncoder; // For compressing batches
+use flate2::Compression; // For the compression level
+use redis::streams::StreamRangeReply; // For reading stream entries
+use redis::Commands; // For Redis operations
+use rustredis::check::ConfigCheck; // For the --check-config mode
+use s3::S3Config; // For the bucket destination
+use serde::Deserialize; // For deserializing the configuration file
+use serde_json::{json, Value}; // For building records
+use std::collections::{BTreeMap, HashMap}; // For checkpoints and hash snapshots
+use std::fs; // For the configuration, checkpoint and directory destination
+use std::io::{self, Write}; // For feeding the encoder
+use std::path::Path; // For building file names
+use std::thread; // For waiting between batches
+use std::time::{Duration, SystemTime, UNIX_EPOCH}; // For the export interval and batch names
+
+// Define how many stream entries are read per XRANGE call
+const XRANGE_COUNT: usize = 1000;
+
+/// Export monitor streams and hashes as NDJSON batches
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Path of the JSON configuration file
+    #[arg(long)]
+    config: String,
+
+    /// Export one batch and exit instead of running periodically
+    #[arg(long)]
+    once: bool,
+
+    /// Check the configuration file and checkpoint, print the diagnostics as JSON and exit (status 1 if invalid)
+    #[arg(long)]
+    check_config: bool,
+
+    /// With --check-config, also connect to Redis and the bucket's service
+    #[arg(long, requires = "check_config")]
+    check_connectivity: bool,
+}
+
+// Define the exporter configuration
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default = "default_redis_url")]
+    redis_url: String, // Redis holding the telemetry
+    #[serde(default)]
+    streams: Vec<String>, // Key patterns of the streams to export
+    #[serde(default)]
+    hashes: Vec<String>, // Key patterns of the hashes to snapshot
+    #[serde(default = "default_interval_secs")]
+    interval_secs: u64, // Time between two batches
+    #[serde(default)]
+    format: Format, // Batch file format
+    #[serde(default = "default_compress")]
+    compress: bool, // Whether batches are gzip-compressed
+    #[serde(default)]
+    prefix: String, // Prepended to batch names, e.g. "site-a/"
+    checkpoint_path: String, // File keeping the last exported id of every stream
+    destination: Destination, // Where batches are written
+}
+
+// Define the batch file formats
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    #[default]
+    Ndjson, // One JSON record per line
+}
+
+// Define where batches are written
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Destination {
+    Directory {
+        path: String, // Directory receiving the batch files
+    },
+    S3(S3Config),
+}
+
+fn default_redis_url() -> String {
+    "redis://127.0.0.1/".to_string()
+}
+
+fn default_interval_secs() -> u64 {
+    300
+}
+
+fn default_compress() -> bool {
+    true
+}
+
+// Function to decode a stored value as JSON, keeping it as a string otherwise
+fn decode(raw: String) -> Value {
+    serde_json::from_str(&raw).unwrap_or(Value::String(raw))
+}
+
+// Function to list the keys of a given Redis type matching any of the patterns
+fn matching_keys(conn: &mut redis::Connection, patterns: &[String], kind: &str) -> redis::RedisResult<Vec<String>> {
+    let mut keys = Vec::new();
+    for pattern in patterns {
+        let found: Vec<String> = conn.scan_match(pattern)?.collect();
+        for key in found {
+            let key_type: String = conn.key_type(&key)?;
+            if key_type == kind && !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    keys.sort();
+    Ok(keys)
+}
+
+// Function to collect the records of one batch and the checkpoint to store once it is written
+fn collect(conn: &mut redis::Connection, config: &Config, checkpoint: &BTreeMap<String, String>) -> redis::RedisResult<(Vec<Value>, BTreeMap<String, String>)> {
+    let mut records = Vec::new();
+    let mut next_checkpoint = checkpoint.clone();
+
+    for key in matching_keys(conn, &config.streams, "stream")? {
+        loop {
+            // "(" makes the start exclusive so the last exported entry is not repeated
+            let start = next_checkpoint.get(&key).map(|id| format!("({}", id)).unwrap_or_else(|| "-".to_string());
+            let reply: StreamRangeReply = conn.xrange_count(&key, start, "+", XRANGE_COUNT)?;
+            let count = reply.ids.len();
+            for entry in reply.ids {
+                let fields: BTreeMap<String, Value> = entry.map.iter()
+                    .map(|(field, value)| (field.clone(), decode(redis::from_redis_value(value).unwrap_or_default())))
+                    .collect();
+                let timestamp_ms: u64 = entry.id.split('-').next().and_then(|ms| ms.parse().ok()).unwrap_or_default();
+                records.push(json!({"type": "stream", "key": key, "id": entry.id, "timestamp_ms": timestamp_ms, "fields": fields}));
+                next_checkpoint.insert(key.clone(), entry.id);
+            }
+            if count < XRANGE_COUNT {
+                break;
+            }
+        }
+    }
+
+    let snapshot_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+    for key in matching_keys(conn, &config.hashes, "hash")? {
+        let fields: HashMap<String, String> = conn.hgetall(&key)?;
+        let fields: BTreeMap<String, Value> = fields.into_iter().map(|(field, value)| (field, decode(value))).collect();
+        records.push(json!({"type": "hash", "key": key, "timestamp_ms": snapshot_ms, "fields": fields}));
+    }
+
+    Ok((records, next_checkpoint))
+}
+
+// Function to compress data in the gzip format
+fn gzip(data: Vec<u8>) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(data.len() / 4), Compression::default());
+    encoder.write_all(&data)?;
+    encoder.finish()
+}
+
+// Function to encode, compress and store one batch
+fn write_batch(config: &Config, records: &[Value]) -> Result<String, String> {
+    let (mut body, extension, mut content_type) = match config.format {
+        Format::Ndjson => {
+            let lines: String = records.iter().map(|record| format!("{}\n", record)).collect();
+            (lines.into_bytes(), "ndjson", "application/x-ndjson")
+        }
+    };
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let mut file_name = format!("{}telemetry-{}.{}", config.prefix, stamp, extension);
+    if config.compress {
+        body = gzip(body).map_err(|e| format!("compress batch: {}", e))?;
+        file_name.push_str(".gz");
+        content_type = "application/gzip";
+    }
+
+    match &config.destination {
+        Destination::Directory { path } => {
+            let target = Path::new(path).join(&file_name);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("create {}: {}", parent.display(), e))?;
+            }
+            // Write under a temporary name so readers never see a partial batch
+            let partial = target.with_extension("partial");
+            fs::write(&partial, &body).and_then(|_| fs::rename(&partial, &target)).map_err(|e| format!("write {}: {}", target.display(), e))?;
+            Ok(target.display().to_string())
+        }
+        Destination::S3(bucket) => {
+            s3::put_object(bucket, &file_name, content_type, &body)?;
+            Ok(format!("s3://{}/{}", bucket.bucket, file_name))
+        }
+    }
+}
+
+// Function to load the checkpoint, starting from scratch if there is none
+fn load_checkpoint(path: &str) -> BTreeMap<String, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).expect("Invalid checkpoint file"),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+        Err(err) => panic!("Failed to read checkpoint {}: {}", path, err),
+    }
+}
+
+// Function to replace the checkpoint atomically
+fn save_checkpoint(path: &str, checkpoint: &BTreeMap<String, String>) -> io::Result<()> {
+    let partial = format!("{}.partial", path);
+    fs::write(&partial, serde_json::to_string_pretty(checkpoint).unwrap())?;
+    fs::rename(&partial, path)
+}
+
+// Function to export one batch, advancing the checkpoint only once it is stored
+fn export(conn: &mut redis::Connection, config: &Config, checkpoint: &mut BTreeMap<String, String>) -> Result<(), String> {
+    let (records, next_checkpoint) = collect(conn, config, checkpoint).map_err(|e| format!("read from Redis: {}", e))?;
+    if records.is_empty() {
+        println!("Nothing to export");
+        return Ok(());
+    }
+    let location = write_batch(config, &records)?;
+    save_checkpoint(&config.checkpoint_path, &next_checkpoint).map_err(|e| format!("save checkpoint: {}", e))?;
+    *checkpoint = next_checkpoint;
+    println!("Exported {} records to {}", records.len(), location);
+    Ok(())
+}
+
+// Function to check the configuration file and checkpoint, then exit with the diagnostics
+fn check_config(args: &Args) -> ! {
+    let mut check = ConfigCheck::new("telemetry_exporter", args.check_connectivity);
+    let Some(config) = check.parse_json::<Config>(&args.config) else { check.exit() };
+    check.redis("redis_url", &config.redis_url);
+    if config.streams.is_empty() && config.hashes.is_empty() {
+        check.warn("streams", "no stream or hash patterns, every batch is empty");
+    }
+    for (i, pattern) in config.streams.iter().enumerate() {
+        check.key_pattern(&format!("streams[{}]", i), pattern);
+    }
+    for (i, pattern) in config.hashes.iter().enumerate() {
+        check.key_pattern(&format!("hashes[{}]", i), pattern);
+    }
+    if config.interval_secs == 0 {
+        check.warn("interval_secs", "batches are exported back to back");
+    }
+    let checkpoint = match fs::read_to_string(&config.checkpoint_path) {
+        Ok(text) => serde_json::from_str::<BTreeMap<String, String>>(&text).map(drop).map_err(|e| format!("invalid checkpoint file: {}", e)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()), // Created by the first export
+        Err(err) => Err(format!("failed to read {}: {}", config.checkpoint_path, err)),
+    };
+    check.check("checkpoint_path", checkpoint);
+    match config.destination {
+        Destination::Directory { ref path } if Path::new(path).is_dir() => check.ok("destination.path", "directory exists"),
+        Destination::Directory { ref path } => check.error("destination.path", format!("{} is not a directory", path)),
+        Destination::S3(ref s3) => {
+            check.http("destination.endpoint", &s3.endpoint);
+            if s3.bucket.is_empty() {
+                check.error("destination.bucket", "no bucket");
+            }
+            if s3.access_key.is_empty() || s3.secret_key.is_empty() {
+                check.error("destination.access_key", "access_key and secret_key are required");
+            }
+        }
+    }
+    check.exit()
+}
+
+fn main() {
+    let args = Args::parse();
+    if args.check_config {
+        check_config(&args);
+    }
+    let config_text = fs::read_to_string(&args.config).expect("Failed to read configuration file");
+    let config: Config = serde_json::from_str(&config_text).expect("Invalid configuration file");
+
+    let client = rustredis::tunnel::open(&config.redis_url).expect("Failed to create Redis client");
+    let mut conn = client.get_connection().expect("Failed to connect to Redis");
+    let mut checkpoint = load_checkpoint(&config.checkpoint_path);
+
+    loop {
+        // A failed batch is retried with the same entries at the next interval
+        if let Err(err) = export(&mut conn, &config, &mut checkpoint) {
+            eprintln!("Export failed: {}", err);
+            if args.once {
+                std::process::exit(1);
+            }
+        }
+        if args.once {
+            break;
+        }
+        thread::sleep(Duration::from_secs(config.interval_secs));
+    }
+}
@@ -0,0 +1,167 @@
+// Minimal S3-compatible object upload: path-style PUT signed with AWS Signature Version 4
+
+// Import necessary crates and modules
+use rustredis::http; // For sending the request
+use serde::Deserialize; // For deserializing the endpoint configuration
+use std::time::{SystemTime, UNIX_EPOCH}; // For the request timestamp
+
+// Define an S3-compatible bucket to upload to
+#[derive(Deserialize)]
+pub struct S3Config {
+    pub endpoint: String, // Base http:// URL of the service, e.g. http://minio:9000
+    pub bucket: String, // Bucket receiving the batches
+    #[serde(default = "default_region")]
+    pub region: String, // Region used in the signature scope
+    pub access_key: String, // Access key id
+    pub secret_key: String, // Secret access key
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+// Function to upload an object, failing unless the service answers 2xx
+pub fn put_object(config: &S3Config, key: &str, content_type: &str, body: &[u8]) -> Result<(), String> {
+    let endpoint = config.endpoint.trim_end_matches('/');
+    let host = endpoint.strip_prefix("http://").ok_or_else(|| format!("unsupported endpoint '{}', only http:// is supported", endpoint))?;
+    let path = format!("/{}/{}", uri_encode(&config.bucket), uri_encode(key));
+
+    let (date, timestamp) = amz_date(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs());
+    let payload_hash = hex(&sha256(body));
+    let canonical_request = format!(
+        "PUT\n{}\n\ncontent-type:{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\ncontent-type;host;x-amz-content-sha256;x-amz-date\n{}",
+        path, content_type, host, payload_hash, timestamp, payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, config.region);
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", timestamp, scope, hex(&sha256(canonical_request.as_bytes())));
+
+    let mut signing_key = hmac_sha256(format!("AWS4{}", config.secret_key).as_bytes(), date.as_bytes());
+    for part in [config.region.as_str(), "s3", "aws4_request"] {
+        signing_key = hmac_sha256(&signing_key, part.as_bytes());
+    }
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=content-type;host;x-amz-content-sha256;x-amz-date, Signature={}",
+        config.access_key, scope, hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()))
+    );
+
+    let headers = [
+        ("Content-Type", content_type),
+        ("x-amz-content-sha256", payload_hash.as_str()),
+        ("x-amz-date", timestamp.as_str()),
+        ("Authorization", authorization.as_str()),
+    ];
+    match http::put(&format!("{}{}", endpoint, path), &headers, body)? {
+        200..=299 => Ok(()),
+        status => Err(format!("{} answered {} for {}", host, status, path)),
+    }
+}
+
+// Function to percent-encode a URI component as SigV4 requires, keeping '/' in object keys
+fn uri_encode(text: &str) -> String {
+    text.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+        b'/' => "/".to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+// Function to format a Unix time as the SigV4 date (YYYYMMDD) and timestamp (YYYYMMDDTHHMMSSZ)
+fn amz_date(secs: u64) -> (String, String) {
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+    let time = secs % 86400;
+    let timestamp = format!("{}T{:02}{:02}{:02}Z", date, time / 3600, time % 3600 / 60, time % 60);
+    (date, timestamp)
+}
+
+// Function to format bytes as lowercase hex
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Function to compute an HMAC-SHA256
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > 64 {
+        block[..32].copy_from_slice(&sha256(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner: Vec<u8> = block.iter().map(|b| b ^ 0x36).chain(message.iter().copied()).collect();
+    let outer: Vec<u8> = block.iter().map(|b| b ^ 0x5c).chain(sha256(&inner)).collect();
+    sha256(&outer)
+}
+
+// Define the SHA-256 round constants
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// Function to compute a SHA-256 digest
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (i, word) in h.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
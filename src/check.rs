@@ -0,0 +1,233 @@
+// Import necessary crates and modules
+use crate::client::ProxyClient; // For reaching the proxy
+use serde::de::DeserializeOwned; // For parsing configuration files
+use serde::Serialize; // For the report
+use serde_json::{json, Value}; // For the report
+use std::net::{TcpStream, ToSocketAddrs}; // For reaching TCP services
+use std::time::Duration; // For connection timeouts
+
+// Define how long a connectivity check waits for a TCP connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Severity of a diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Ok, // Checked and valid
+    Warning, // Accepted, but probably not what was meant
+    Error, // The tool would refuse to start, or fail once running
+}
+
+/// One finding of a configuration check.
+#[derive(Clone, Debug, Serialize)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub subject: String, // What was checked, e.g. "redis_url" or "topics[2].key_pattern"
+    pub message: String,
+}
+
+/// Diagnostics collected by a tool's `--check-config` mode.
+///
+/// Checks never stop at the first problem, so one run lists everything wrong with a
+/// configuration. Connections to Redis, the proxy and other services are only attempted when
+/// `connectivity` is set; otherwise addresses are only checked for syntax. [`ConfigCheck::exit`]
+/// prints the report as JSON and exits with status 1 if any check failed.
+pub struct ConfigCheck {
+    tool: &'static str, // Binary the configuration belongs to
+    connectivity: bool, // Whether to connect to the configured services
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ConfigCheck {
+    /// Starts the check of a tool's configuration.
+    pub fn new(tool: &'static str, connectivity: bool) -> Self {
+        ConfigCheck { tool, connectivity, diagnostics: Vec::new() }
+    }
+
+    /// Records a passed check.
+    pub fn ok(&mut self, subject: &str, message: impl Into<String>) {
+        self.push(Level::Ok, subject, message.into());
+    }
+
+    /// Records a suspicious but accepted setting.
+    pub fn warn(&mut self, subject: &str, message: impl Into<String>) {
+        self.push(Level::Warning, subject, message.into());
+    }
+
+    /// Records a failed check.
+    pub fn error(&mut self, subject: &str, message: impl Into<String>) {
+        self.push(Level::Error, subject, message.into());
+    }
+
+    // Function to append a diagnostic
+    fn push(&mut self, level: Level, subject: &str, message: String) {
+        self.diagnostics.push(Diagnostic { level, subject: subject.to_string(), message });
+    }
+
+    /// Records the outcome of a validation, returning its value if it passed.
+    pub fn check<T>(&mut self, subject: &str, result: Result<T, String>) -> Option<T> {
+        match result {
+            Ok(value) => {
+                self.ok(subject, "valid");
+                Some(value)
+            }
+            Err(err) => {
+                self.error(subject, err);
+                None
+            }
+        }
+    }
+
+    /// Reads and parses a JSON configuration file.
+    pub fn parse_json<T: DeserializeOwned>(&mut self, path: &str) -> Option<T> {
+        let parsed = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path, e))
+            .and_then(|text| serde_json::from_str(&text).map_err(|e| format!("invalid configuration: {}", e)));
+        self.check(path, parsed)
+    }
+
+    /// Returns true if connections to the configured services are checked.
+    pub fn connectivity(&self) -> bool {
+        self.connectivity
+    }
+
+    /// Returns true if any check failed.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.level == Level::Error)
+    }
+
+    /// Checks a Redis URL and its tunnel, and with connectivity checks that the server answers PING.
+    pub fn redis(&mut self, subject: &str, url: &str) {
+        if let Err(err) = redis::Client::open(url).map(drop).map_err(|e| e.to_string()).and_then(|_| crate::tunnel::check(url)) {
+            self.error(subject, format!("invalid Redis URL {}: {}", url, err));
+            return;
+        }
+        if !self.connectivity {
+            self.ok(subject, "valid Redis URL");
+            return;
+        }
+        let pong = crate::tunnel::open(url)
+            .and_then(|client| client.get_connection())
+            .and_then(|mut conn| redis::cmd("PING").query::<String>(&mut conn));
+        match pong {
+            Ok(_) => self.ok(subject, format!("{} answers PING", url)),
+            Err(err) => self.error(subject, format!("{} unreachable: {}", url, err)),
+        }
+    }
+
+    /// Checks a HOST:PORT address, and with connectivity checks that it accepts connections.
+    pub fn tcp(&mut self, subject: &str, address: &str) {
+        if !address.rsplit_once(':').is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok()) {
+            self.error(subject, format!("expected HOST:PORT, got '{}'", address));
+            return;
+        }
+        if !self.connectivity {
+            self.ok(subject, "valid address");
+            return;
+        }
+        let connected = address.to_socket_addrs()
+            .and_then(|mut addrs| addrs.next().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no address")))
+            .and_then(|addr| TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT));
+        match connected {
+            Ok(_) => self.ok(subject, format!("{} accepts connections", address)),
+            Err(err) => self.error(subject, format!("{} unreachable: {}", address, err)),
+        }
+    }
+
+    /// Checks an http:// URL, and with connectivity checks that its host accepts connections.
+    pub fn http(&mut self, subject: &str, url: &str) {
+        let Some(rest) = url.strip_prefix("http://") else {
+            self.error(subject, format!("unsupported URL '{}', only http:// is supported", url));
+            return;
+        };
+        let host = rest.split('/').next().unwrap_or_default();
+        let address = if host.contains(':') { host.to_string() } else { format!("{}:80", host) };
+        self.tcp(subject, &address);
+    }
+
+    /// Checks a proxy address, and with connectivity checks that the proxy completes the handshake.
+    pub fn proxy(&mut self, subject: &str, address: &str) {
+        if address.is_empty() {
+            self.error(subject, "empty proxy address");
+            return;
+        }
+        if !self.connectivity {
+            self.ok(subject, "valid address");
+            return;
+        }
+        match ProxyClient::connect(address) {
+            Ok(_) => self.ok(subject, format!("proxy at {} answers", address)),
+            Err(err) => self.error(subject, format!("proxy at {} unreachable: {}", address, err)),
+        }
+    }
+
+    /// Checks a key against the key grammar; placeholders (`{...}`) are checked as if filled in,
+    /// and only warned about since what they are filled with is not known here.
+    pub fn key(&mut self, subject: &str, key: &str) {
+        let templated = key.contains('{');
+        let filled = if templated { fill_placeholders(key) } else { key.to_string() };
+        #[cfg(feature = "proxy")]
+        let valid = crate::schema::is_valid_key(&filled);
+        #[cfg(not(feature = "proxy"))]
+        let valid = filled.starts_with("cs:") && filled.split(':').count() >= 3;
+        match (valid, templated) {
+            (true, _) => self.ok(subject, "valid key"),
+            (false, true) => self.warn(subject, format!("'{}' may not give a valid cs:<producer>:<object>[:<id>][:<function>] key", key)),
+            (false, false) => self.error(subject, format!("'{}' is not a valid cs:<producer>:<object>[:<id>][:<function>] key", key)),
+        }
+    }
+
+    /// Checks that a glob pattern can match keys of the cs: namespace.
+    pub fn key_pattern(&mut self, subject: &str, pattern: &str) {
+        if pattern.is_empty() {
+            self.error(subject, "empty key pattern");
+        } else if !pattern.starts_with("cs:") && !pattern.starts_with(['*', '?', '[']) {
+            self.warn(subject, format!("'{}' matches no key of the cs: namespace", pattern));
+        } else {
+            self.ok(subject, "valid key pattern");
+        }
+    }
+
+    /// Checks that a topic template only uses the key placeholders, plus the `extra` ones.
+    pub fn template(&mut self, subject: &str, template: &str, extra: &[&str]) {
+        let known = ["key", "producer", "object", "id", "function"];
+        let unknown: Vec<&str> = placeholders(template).filter(|name| !known.contains(name) && !extra.contains(name)).collect();
+        if unknown.is_empty() {
+            self.ok(subject, "valid template");
+        } else {
+            self.warn(subject, format!("unknown placeholders {{{}}} are left as they are", unknown.join("}, {")));
+        }
+    }
+
+    /// Returns the report: the tool, whether every check passed, the error and warning counts and the diagnostics.
+    pub fn report(&self) -> Value {
+        let count = |level: Level| self.diagnostics.iter().filter(|d| d.level == level).count();
+        json!({
+            "tool": self.tool,
+            "ok": !self.has_errors(),
+            "errors": count(Level::Error),
+            "warnings": count(Level::Warning),
+            "diagnostics": self.diagnostics,
+        })
+    }
+
+    /// Prints the report as JSON and exits, with status 1 if any check failed.
+    pub fn exit(self) -> ! {
+        println!("{}", serde_json::to_string_pretty(&self.report()).unwrap_or_default());
+        std::process::exit(if self.has_errors() { 1 } else { 0 })
+    }
+}
+
+// Function to list the names of a template's {...} placeholders
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template.split('{').skip(1).filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+}
+
+// Function to fill a template's placeholders with a sample id, for checking it against the key grammar
+fn fill_placeholders(template: &str) -> String {
+    let mut filled = template.to_string();
+    for name in placeholders(template) {
+        filled = filled.replace(&format!("{{{}}}", name), "0");
+    }
+    filled
+}
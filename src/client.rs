@@ -0,0 +1,452 @@
+// Import necessary crates and modules
+use crate::base64; // For binary values
+use crate::rng::Rng; // For the ids of new traces
+use serde::{Deserialize, Serialize}; // For deserializing proxy responses and serializing typed payloads
+use serde_json::{json, Value}; // For building requests
+use std::fmt; // For displaying errors
+use std::io::{self, BufRead, BufReader, Write}; // For line-based socket I/O
+use crate::transport::Transport; // For connecting to the proxy
+
+/// Default address of the proxy: its Unix socket, or its named pipe on Windows.
+#[cfg(not(windows))]
+pub const DEFAULT_SOCKET_PATH: &str = "/tmp/redis_proxy.sock";
+/// Default address of the proxy: its Unix socket, or its named pipe on Windows.
+#[cfg(windows)]
+pub const DEFAULT_SOCKET_PATH: &str = "pipe:redis_proxy";
+
+/// Protocol version requested in the `hello` handshake.
+const PROTOCOL_VERSION: u64 = 1;
+
+// Function to build the handshake asking for newline-framed responses
+pub(crate) fn hello_request() -> Value {
+    json!({
+        "action": "hello",
+        "protocol_version": PROTOCOL_VERSION,
+        "features": ["framing:newline"]
+    })
+}
+
+// Function to build the blocking client's handshake, which also takes zstd-compressed payloads when
+// built with the zstd feature
+fn client_hello(timing: bool) -> Value {
+    let mut hello = hello_request();
+    let features = hello["features"].as_array_mut().unwrap();
+    if cfg!(feature = "zstd") {
+        features.push(json!("compression:zstd"));
+    }
+    if timing {
+        features.push(json!("debug:timing"));
+    }
+    hello
+}
+
+// Function to decode a payload the proxy sent zstd-compressed
+#[cfg(feature = "zstd")]
+fn unpack(packed: &str) -> io::Result<Value> {
+    let compressed = base64::decode(packed).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let json = zstd::stream::decode_all(compressed.as_slice())?;
+    serde_json::from_slice(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// Function to refuse compressed payloads in clients built without zstd (which never ask for them)
+#[cfg(not(feature = "zstd"))]
+fn unpack(_packed: &str) -> io::Result<Value> {
+    Err(io::Error::new(io::ErrorKind::InvalidData, "compressed payload, but the client was built without the zstd feature"))
+}
+
+/// Response returned by the proxy for every request.
+#[derive(Debug, Deserialize)]
+pub struct ProxyResponse {
+    pub status: String, // "ok" or "error" (or "ping" for keepalives)
+    pub message: String, // Human readable outcome
+    #[serde(default)]
+    pub data: Option<Value>, // Action specific payload
+    #[serde(default)]
+    pub timing: Option<Value>, // Where the request's time went (`parse_us`, `validate_us`, `redis_us`, `total_us`) with debug:timing
+    #[serde(default)]
+    data_zstd: Option<String>, // Compressed payload, decoded into data as the response is read
+}
+
+impl ProxyResponse {
+    /// Returns true if the proxy accepted the request.
+    pub fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
+}
+
+/// Error raised by [`ProxyClient`] calls.
+#[derive(Debug)]
+pub enum ClientError {
+    Io(io::Error), // The socket failed or the response could not be read
+    Proxy(String), // The proxy rejected the request
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Io(err) => write!(f, "proxy connection error: {}", err),
+            ClientError::Proxy(message) => write!(f, "proxy rejected request: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<io::Error> for ClientError {
+    fn from(err: io::Error) -> Self {
+        ClientError::Io(err)
+    }
+}
+
+/// Payload of a producer object with a schema; implemented by the generated types of [`crate::typed`].
+pub trait ProducerObject: Serialize {
+    /// Base key (`cs:<producer>:<object>`) whose schema the type was generated from.
+    const BASE_KEY: &'static str;
+}
+
+/// Builds a sampled W3C `traceparent` starting a new trace, for [`ProxyClient::set_traceparent`]
+/// and [`ProxyClient::with_traceparent`].
+pub fn new_traceparent(rng: &mut Rng) -> String {
+    format!("00-{}-{}-01", rng.hex(32), rng.hex(16))
+}
+
+/// Blocking client for the proxy's newline-delimited JSON protocol.
+pub struct ProxyClient {
+    reader: BufReader<Transport>, // Buffered read half of the socket
+    writer: Transport, // Write half of the socket
+    traceparent: Option<String>, // W3C trace context attached to requests, so the proxy traces them
+}
+
+impl ProxyClient {
+    /// Connects to the proxy (see [`Transport`] for the address forms) and negotiates
+    /// newline-framed responses, and with the `zstd` feature compression of large payloads.
+    pub fn connect(socket_path: &str) -> Result<Self, ClientError> {
+        let writer = Transport::connect(socket_path)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        let mut client = ProxyClient { reader, writer, traceparent: None };
+
+        // The proxy applies newline framing starting with the hello reply itself
+        client.send(&client_hello(false))?;
+        let response = client.read_response()?;
+        if !response.is_ok() {
+            return Err(ClientError::Proxy(response.message));
+        }
+        Ok(client)
+    }
+
+    /// Asks the proxy to add a timing breakdown to every response ([`ProxyResponse::timing`]); returns
+    /// false if the proxy was not started with `--debug-timing`.
+    pub fn enable_timing(&mut self) -> Result<bool, ClientError> {
+        let data = self.read(&client_hello(true))?;
+        Ok(data["features"].as_array().is_some_and(|features| features.iter().any(|f| f == "debug:timing")))
+    }
+
+    /// Attaches a W3C `traceparent` to every following request that does not carry its own, so the
+    /// proxy traces them as children of the caller's span; `None` stops attaching one.
+    pub fn set_traceparent(&mut self, traceparent: Option<&str>) {
+        self.traceparent = traceparent.map(str::to_string);
+    }
+
+    /// Runs calls on the client with a `traceparent` attached to their requests, e.g. one from
+    /// [`new_traceparent`] per message, then restores the client's own.
+    pub fn with_traceparent<T>(&mut self, traceparent: &str, calls: impl FnOnce(&mut Self) -> T) -> T {
+        let own = self.traceparent.replace(traceparent.to_string());
+        let result = calls(self);
+        self.traceparent = own;
+        result
+    }
+
+    /// Sends a raw request and waits for its response.
+    pub fn request(&mut self, request: &Value) -> Result<ProxyResponse, ClientError> {
+        match self.traceparent {
+            Some(ref traceparent) if request.is_object() && request.get("traceparent").is_none() => {
+                let mut traced = request.clone();
+                traced["traceparent"] = json!(traceparent);
+                self.send(&traced)?;
+            }
+            _ => self.send(request)?,
+        }
+        self.read_response()
+    }
+
+    /// Stores a JSON value under a key.
+    pub fn set(&mut self, key: &str, value: &Value) -> Result<(), ClientError> {
+        self.expect_ok(&json!({"action": "set", "key": key, "value": value}))
+    }
+
+    /// Stores a JSON value under a key with a priority from 0 (shed first when the proxy is
+    /// overloaded) to 9 (e.g. alarms); the proxy caps it at the producer's configured limit.
+    pub fn set_with_priority(&mut self, key: &str, value: &Value, priority: u8) -> Result<(), ClientError> {
+        self.expect_ok(&json!({"action": "set", "key": key, "value": value, "priority": priority}))
+    }
+
+    /// Reads the value stored under a key; with `validate` the payload also tells whether it still
+    /// matches the key's schema (`schema_valid`, `schema_error`) and carries a `normalized` copy.
+    pub fn get(&mut self, key: &str, validate: bool) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "get", "key": key, "validate": validate}))
+    }
+
+    /// Stores a typed producer object under its base key, or under `<base key>:<id>` for one instance.
+    pub fn set_object<T: ProducerObject>(&mut self, id: Option<&str>, value: &T) -> Result<(), ClientError> {
+        let key = match id {
+            Some(id) => format!("{}:{}", T::BASE_KEY, id),
+            None => T::BASE_KEY.to_string(),
+        };
+        let value = serde_json::to_value(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.set(&key, &value)
+    }
+
+    /// Stores opaque bytes under a key; no schema applies to binary values.
+    pub fn set_binary(&mut self, key: &str, bytes: &[u8]) -> Result<(), ClientError> {
+        self.expect_ok(&json!({"action": "set", "key": key, "value_b64": base64::encode(bytes)}))
+    }
+
+    /// Reads the bytes stored under a key, whatever was stored there.
+    pub fn get_binary(&mut self, key: &str) -> Result<Option<Vec<u8>>, ClientError> {
+        let data = self.read(&json!({"action": "get", "key": key, "binary": true}))?;
+        match data["value_b64"].as_str() {
+            Some(text) => base64::decode(text).map(Some).map_err(ClientError::Proxy),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the members of a set.
+    pub fn smembers(&mut self, key: &str) -> Result<Vec<Value>, ClientError> {
+        let data = self.read(&json!({"action": "smembers", "key": key}))?;
+        Ok(data["members"].as_array().cloned().unwrap_or_default())
+    }
+
+    /// Reads the fields of a hash.
+    pub fn hgetall(&mut self, key: &str) -> Result<serde_json::Map<String, Value>, ClientError> {
+        let data = self.read(&json!({"action": "hgetall", "key": key}))?;
+        Ok(data["fields"].as_object().cloned().unwrap_or_default())
+    }
+
+    /// Appends a JSON value to a stream capped at roughly `maxlen` entries, returning the entry id.
+    pub fn xadd(&mut self, key: &str, value: &Value, maxlen: Option<usize>) -> Result<String, ClientError> {
+        let response = self.request(&json!({"action": "xadd", "key": key, "value": value, "maxlen": maxlen}))?;
+        if !response.is_ok() {
+            return Err(ClientError::Proxy(response.message));
+        }
+        Ok(response.data.as_ref().and_then(|data| data["id"].as_str()).unwrap_or_default().to_string())
+    }
+
+    /// Marks the producer key as online for `ttl` seconds (the proxy's default if `None`); returns
+    /// whether it was offline before. Watchers on the key's channel see `online` and `offline` events.
+    pub fn heartbeat(&mut self, key: &str, ttl: Option<u64>) -> Result<bool, ClientError> {
+        let data = self.read(&json!({"action": "heartbeat", "key": key, "ttl": ttl}))?;
+        Ok(data["came_online"].as_bool().unwrap_or_default())
+    }
+
+    /// Raises an alert on a producer key with a severity (info, warning or critical), kept for `ttl`
+    /// seconds (the proxy's default if `None`). Until acknowledged, it is republished as `escalation`
+    /// events on the key's channel at the proxy's escalation windows. Returns the stored alert.
+    pub fn alert(&mut self, key: &str, severity: &str, value: &Value, ttl: Option<u64>) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "alert", "key": key, "severity": severity, "value": value, "ttl": ttl}))
+    }
+
+    /// Acknowledges the alert of a producer key, stopping its escalation; returns whether there was one.
+    pub fn ack(&mut self, key: &str) -> Result<bool, ClientError> {
+        let data = self.read(&json!({"action": "ack", "key": key}))?;
+        Ok(data["acked"].as_bool().unwrap_or_default())
+    }
+
+    /// Subscribes to the events of keys matching a glob pattern, optionally only those passing a
+    /// [`crate::filter::Filter`] expression. The connection then only carries events; read them
+    /// with [`ProxyClient::next_event`].
+    pub fn subscribe(&mut self, pattern: &str, filter: Option<&str>) -> Result<(), ClientError> {
+        self.expect_ok(&json!({"action": "subscribe", "pattern": pattern, "filter": filter}))
+    }
+
+    /// Waits for the next event (`{"key", "action", "value"}`) of a subscription.
+    pub fn next_event(&mut self) -> Result<Value, ClientError> {
+        let response = self.read_response()?;
+        match response.status.as_str() {
+            "event" => Ok(response.data.unwrap_or(Value::Null)),
+            _ => Err(ClientError::Proxy(response.message)),
+        }
+    }
+
+    /// Bounds how long a read such as [`ProxyClient::next_event`] may block; one that runs out fails
+    /// with an `Io` error of kind `WouldBlock` or `TimedOut`. Named pipes ignore it.
+    pub fn set_read_timeout(&self, timeout: Option<std::time::Duration>) -> Result<(), ClientError> {
+        Ok(self.writer.set_read_timeout(timeout)?)
+    }
+
+    /// Deletes a key.
+    pub fn del(&mut self, key: &str) -> Result<(), ClientError> {
+        self.expect_ok(&json!({"action": "del", "key": key}))
+    }
+
+    /// Deletes a key recoverably: its value can be restored for `grace_secs` (the proxy's default
+    /// if None). Returns whether the key existed.
+    pub fn soft_delete(&mut self, key: &str, grace_secs: Option<u64>) -> Result<bool, ClientError> {
+        let data = self.read(&json!({"action": "soft-delete", "key": key, "ttl": grace_secs}))?;
+        Ok(data["deleted"].as_bool().unwrap_or(false))
+    }
+
+    /// Brings back the value of a soft-deleted key within its grace window.
+    pub fn restore(&mut self, key: &str) -> Result<(), ClientError> {
+        self.expect_ok(&json!({"action": "restore", "key": key}))
+    }
+
+    /// Deletes up to `limit` keys matching a pattern within one producer's namespace (admin sockets
+    /// only); with `dry_run` the keys are only counted. Returns `matched`, `deleted`, `capped` and a `sample`.
+    pub fn purge(&mut self, pattern: &str, dry_run: bool, limit: Option<u64>) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "purge", "pattern": pattern, "dry_run": dry_run, "limit": limit}))
+    }
+
+    /// Reports the Redis memory used per producer namespace (admin sockets only), measuring up to
+    /// `sample` keys matching `pattern` (default `cs:*`) per namespace and listing its `top` largest.
+    pub fn memory(&mut self, pattern: Option<&str>, sample: Option<u64>, top: Option<usize>) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "memory", "pattern": pattern, "limit": sample, "top": top}))
+    }
+
+    /// Exports the keys matching a pattern (every key if None) as records, handing each to `record`
+    /// as it arrives; returns the summary (`{"exported", "skipped"}`). Only allowed on admin sockets.
+    pub fn export(&mut self, pattern: Option<&str>, mut record: impl FnMut(Value)) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "export", "pattern": pattern}))?;
+        loop {
+            let response = self.read_response()?;
+            match response.status.as_str() {
+                "record" => record(response.data.unwrap_or(Value::Null)),
+                _ if response.is_ok() => return Ok(response.data.unwrap_or(Value::Null)),
+                _ => return Err(ClientError::Proxy(response.message)),
+            }
+        }
+    }
+
+    /// Validates records as export returns them and replaces their keys; nothing is written if any
+    /// record fails validation. Returns the number of keys imported. Only allowed on admin sockets.
+    pub fn import(&mut self, records: &[Value]) -> Result<u64, ClientError> {
+        let data = self.read(&json!({"action": "import", "records": records}))?;
+        Ok(data["imported"].as_u64().unwrap_or(0))
+    }
+
+    /// Lists the proxy's client connections with their traffic, queued events and time spent
+    /// blocked writing to them; only allowed on admin sockets.
+    pub fn list_clients(&mut self) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "list-clients"}))
+    }
+
+    /// Stages a complete schema set (schemas by base key, plus shadow schemas) for the next swap and
+    /// checks up to `sample` stored keys per object type against it; returns how many were `checked`,
+    /// how many are `failing` and some `failures`. Only allowed on admin sockets.
+    pub fn stage_schemas(&mut self, schemas: &Value, shadow_schemas: Option<&Value>, sample: Option<u64>) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "stage-schemas", "schemas": schemas, "shadow_schemas": shadow_schemas, "limit": sample}))
+    }
+
+    /// Enforces the staged schema set at once; the proxy rolls it back by itself if writes start failing
+    /// validation. Returns the new schema `generation`. Only allowed on admin sockets.
+    pub fn swap_schemas(&mut self) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "swap-schemas"}))
+    }
+
+    /// Brings back the schema set the last swap replaced. Only allowed on admin sockets.
+    pub fn rollback_schemas(&mut self) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "rollback-schemas"}))
+    }
+
+    /// Announces the producer to fleet tooling as live for `ttl` seconds (the proxy's
+    /// `--registration-ttl` if None); register again before it lapses to stay listed. The proxy
+    /// announces new producers, restarts (another `pid`) and version changes on its events channel.
+    pub fn register(&mut self, name: &str, version: &str, schema_versions: &Value, pid: Option<u32>, ttl: Option<u64>) -> Result<Value, ClientError> {
+        let registration = json!({"name": name, "version": version, "schema_versions": schema_versions, "pid": pid.map(u64::from)});
+        self.read(&json!({"action": "register", "value": registration, "ttl": ttl}))
+    }
+
+    /// Lists the live registrations (`producers`) and the names of producers whose registration
+    /// lapsed (`lapsed`), limited to the producers the socket allows.
+    pub fn registry(&mut self) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "registry"}))
+    }
+
+    /// Replaces the faults a proxy built with the `chaos` feature injects, by action (or `*`) as
+    /// `{"latency_ms", "latency", "error", "drop"}` with probabilities from 0 to 1; `{}` turns
+    /// injection off. A `seed` makes which requests are hit reproducible. Only allowed on admin sockets.
+    pub fn chaos(&mut self, faults: &Value, seed: Option<u64>) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "chaos", "faults": faults, "seed": seed}))
+    }
+
+    /// Applies `writes` (`{"action": "set", "key", "value"}` or `{"action": "del", "key"}`) together,
+    /// only if every watched key (`{"key", "sha1"}`, `"sha1": null` for a key that must not exist,
+    /// no `sha1` to only report it) still holds the value with that SHA-1. Returns `applied` and the
+    /// watched keys' `hashes`, to expect in the next call; if an expectation failed, nothing was
+    /// written and `failed`, `expected` and `actual` tell which.
+    pub fn watch_and_apply(&mut self, watch: &[Value], writes: &[Value]) -> Result<Value, ClientError> {
+        let response = self.request(&json!({"action": "watch-and-apply", "watch": watch, "writes": writes}))?;
+        match response.data {
+            Some(data) if response.is_ok() || data["applied"] == false => Ok(data),
+            _ => Err(ClientError::Proxy(response.message)),
+        }
+    }
+
+    /// Starts a new proxy process from the binary on disk, handing it the listening sockets, and
+    /// returns its `pid` once it accepts clients. The old proxy keeps serving its connected clients,
+    /// this one included, for up to `drain_timeout_secs` and then exits. Unix only, admin sockets only.
+    pub fn upgrade(&mut self) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "upgrade"}))
+    }
+
+    /// Sets the value at a path (`$.field[.field...]`) of a key stored as a RedisJSON document
+    /// (proxy run with `--json-storage` or `--search`), leaving the rest of the document as it is.
+    pub fn patch(&mut self, key: &str, path: &str, value: &Value) -> Result<(), ClientError> {
+        self.expect_ok(&json!({"action": "patch", "key": key, "path": path, "value": value}))
+    }
+
+    /// Searches the documents of an object type (`cs:<producer>:<object>`, proxy run with `--search`)
+    /// with a RediSearch query; returns the `total` number of matches and up to `limit` `results`.
+    pub fn search(&mut self, object_type: &str, query: &str, limit: Option<u64>) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "search", "key": object_type, "query": query, "limit": limit}))
+    }
+
+    /// Returns the proxy's health report (uptime, request and failure counters, clients, Redis state).
+    pub fn stats(&mut self) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "stats"}))
+    }
+
+    // Function to send a read request and return its payload
+    fn read(&mut self, request: &Value) -> Result<Value, ClientError> {
+        let response = self.request(request)?;
+        if !response.is_ok() {
+            return Err(ClientError::Proxy(response.message));
+        }
+        Ok(response.data.unwrap_or(Value::Null))
+    }
+
+    // Function to send a request and turn a proxy-side rejection into an error
+    fn expect_ok(&mut self, request: &Value) -> Result<(), ClientError> {
+        let response = self.request(request)?;
+        if response.is_ok() {
+            Ok(())
+        } else {
+            Err(ClientError::Proxy(response.message))
+        }
+    }
+
+    // Function to write one newline-terminated request
+    fn send(&mut self, request: &Value) -> io::Result<()> {
+        let line = format!("{}\n", request);
+        self.writer.write_all(line.as_bytes())
+    }
+
+    // Function to read the next response, skipping keepalive pings
+    fn read_response(&mut self) -> Result<ProxyResponse, ClientError> {
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "proxy closed the connection").into());
+            }
+            let mut response: ProxyResponse = serde_json::from_str(line.trim())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if let Some(packed) = response.data_zstd.take() {
+                response.data = Some(unpack(&packed)?);
+            }
+            if response.status == "ping" {
+                self.send(&json!({"action": "pong"}))?;
+                continue;
+            }
+            return Ok(response);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+// AES-256-GCM sealing of stored values, authenticated with the Redis key they are stored under
+
+// Import necessary crates and modules
+use crate::base64; // For key files holding the key as base64 text
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload}; // For sealing values with random nonces, bound to their key
+use aes_gcm::{Aes256Gcm, Key, Nonce}; // For AES-256-GCM
+use std::borrow::Cow; // For returning plaintext values without copying them
+use std::path::Path; // For locating the key file
+
+// Define the header marking an encrypted value, followed by the nonce and the ciphertext
+const AES_MARKER: &[u8] = b"\0aes";
+
+// Define the length of AES-GCM nonces
+const NONCE_LEN: usize = 12;
+
+// Define the cipher sealing the values of sensitive keys
+pub struct ValueCipher {
+    cipher: Aes256Gcm,
+}
+
+// Function to read a 256-bit key from a file holding either the raw 32 bytes or their base64 encoding
+fn read_key(path: &Path) -> Result<Key<Aes256Gcm>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    let key = match bytes.len() {
+        32 => bytes,
+        _ => std::str::from_utf8(&bytes).ok().and_then(|text| base64::decode(text.trim()).ok()).unwrap_or_default(),
+    };
+    if key.len() != 32 {
+        return Err(format!("{} must hold a 32 byte key, raw or base64 encoded", path.display()));
+    }
+    Ok(*Key::<Aes256Gcm>::from_slice(&key))
+}
+
+// Function to check if a stored value was sealed by a `ValueCipher`
+pub fn is_encrypted(stored: &[u8]) -> bool {
+    stored.starts_with(AES_MARKER)
+}
+
+impl ValueCipher {
+    // Function to create the cipher with the key from a file
+    pub fn from_key_file(path: &Path) -> Result<Self, String> {
+        Ok(ValueCipher { cipher: Aes256Gcm::new(&read_key(path)?) })
+    }
+
+    // Function to encrypt a value of a key; the key is authenticated with it, so the ciphertext
+    // does not decrypt when copied to another key
+    pub fn encrypt(&self, key: &str, value: &[u8]) -> Vec<u8> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, Payload { msg: value, aad: key.as_bytes() }).expect("Encryption of in-memory values does not fail");
+        [AES_MARKER, nonce.as_slice(), &ciphertext].concat()
+    }
+
+    // Function to undo `encrypt` on a stored value of a key, passing values without the marker (written before encryption) through
+    pub fn decrypt<'a>(&self, key: &str, stored: &'a [u8]) -> Result<Cow<'a, [u8]>, String> {
+        let Some(sealed) = stored.strip_prefix(AES_MARKER) else { return Ok(Cow::Borrowed(stored)) };
+        if sealed.len() < NONCE_LEN {
+            return Err("truncated encrypted value".to_string());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        self.cipher.decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: key.as_bytes() })
+            .map(Cow::Owned)
+            .map_err(|_| "encrypted value failed authentication (wrong encryption key, tampered or copied from another key)".to_string())
+    }
+}
@@ -0,0 +1,90 @@
+// Import necessary crates and modules
+use crate::events::parse_event; // For structuring logged events like pub/sub ones
+use redis::streams::{StreamRangeReply, StreamReadOptions, StreamReadReply}; // For consumer group reads and replays
+use redis::{Commands, FromRedisValue}; // For Redis operations
+use serde_json::Value; // For structured events
+use std::time::Duration; // For blocking reads
+
+/// Returns the stream the proxy logs a producer's events to when run with `--event-log streams`
+/// (events outside producer namespaces, such as the proxy's own, go to the `_proxy` stream).
+pub fn stream_key(producer: &str) -> String {
+    format!("cs:_events:{}", producer)
+}
+
+/// Event read from the event log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoggedEvent {
+    pub id: String, // Stream entry id, to acknowledge the event or replay from it
+    pub event: Value, // Structured event (`{"key", "action", "value"}`)
+}
+
+// Function to turn the entries of a stream reply into events
+fn logged_events(entries: Vec<redis::streams::StreamId>) -> redis::RedisResult<Vec<LoggedEvent>> {
+    entries.into_iter()
+        .map(|entry| {
+            let field = |name: &str| entry.map.get(name).map(String::from_redis_value).transpose().map(Option::unwrap_or_default);
+            let (channel, event) = (field("channel")?, field("event")?);
+            Ok(LoggedEvent { event: parse_event(&channel, &event), id: entry.id })
+        })
+        .collect()
+}
+
+/// Consumer of one producer's event log through a Redis consumer group, giving at-least-once
+/// delivery: events stay pending until acknowledged and are delivered again after a restart.
+/// Several consumers of the same group share the events between them.
+pub struct EventLogConsumer {
+    conn: redis::Connection,
+    stream: String,
+    group: String,
+    consumer: String,
+    pending_done: bool, // Whether the events delivered before a restart were re-read
+}
+
+impl EventLogConsumer {
+    /// Connects to Redis and joins (creating it if needed) a consumer group of a producer's log;
+    /// a new group starts with the events logged from now on.
+    pub fn connect(redis_url: &str, producer: &str, group: &str, consumer: &str) -> redis::RedisResult<Self> {
+        let mut conn = crate::tunnel::open(redis_url)?.get_connection()?;
+        let stream = stream_key(producer);
+        // Creating an existing group fails with BUSYGROUP, which is fine
+        if let Err(err) = conn.xgroup_create_mkstream::<_, _, _, ()>(&stream, group, "$") {
+            if err.code() != Some("BUSYGROUP") {
+                return Err(err);
+            }
+        }
+        Ok(EventLogConsumer { conn, stream, group: group.to_string(), consumer: consumer.to_string(), pending_done: false })
+    }
+
+    /// Returns up to `count` events, waiting at most `block` for new ones. Events delivered to
+    /// this consumer but never acknowledged (e.g. before a crash) come first.
+    pub fn next_batch(&mut self, count: usize, block: Duration) -> redis::RedisResult<Vec<LoggedEvent>> {
+        let id = if self.pending_done { ">" } else { "0" };
+        let mut options = StreamReadOptions::default().group(&self.group, &self.consumer).count(count);
+        if self.pending_done {
+            options = options.block(block.as_millis() as usize);
+        }
+        let reply: Option<StreamReadReply> = self.conn.xread_options(&[&self.stream], &[id], &options)?;
+        let entries = reply.into_iter().flat_map(|reply| reply.keys).flat_map(|stream| stream.ids).collect();
+        let events = logged_events(entries)?;
+        if events.is_empty() && !self.pending_done {
+            self.pending_done = true; // Caught up on the pending ones, read new events from now on
+        }
+        Ok(events)
+    }
+
+    /// Acknowledges processed events so they are not delivered again.
+    pub fn ack(&mut self, events: &[LoggedEvent]) -> redis::RedisResult<()> {
+        let ids: Vec<&str> = events.iter().map(|event| event.id.as_str()).collect();
+        if !ids.is_empty() {
+            self.conn.xack::<_, _, _, ()>(&self.stream, &self.group, &ids)?;
+        }
+        Ok(())
+    }
+
+    /// Replays up to `count` logged events after the entry `after` ("0" for the oldest kept),
+    /// regardless of the group, e.g. to rebuild state.
+    pub fn replay(&mut self, after: &str, count: usize) -> redis::RedisResult<Vec<LoggedEvent>> {
+        let reply: StreamRangeReply = self.conn.xrange_count(&self.stream, format!("({}", after), "+", count)?;
+        logged_events(reply.ids)
+    }
+}
@@ -0,0 +1,12 @@
+// Import necessary crates and modules
+use serde_json::{json, Value}; // For building structured events
+
+/// Turns a proxy event published on a key's channel (`"set: {...}"`, `"del"`, ...) into
+/// a structured `{"key", "action", "value"}` object.
+pub fn parse_event(key: &str, message: &str) -> Value {
+    let (action, value) = match message.split_once(": ") {
+        Some((action, value)) => (action, serde_json::from_str(value).unwrap_or(Value::String(value.to_string()))),
+        None => (message, Value::Null),
+    };
+    json!({"key": key, "action": action, "value": value})
+}
@@ -0,0 +1,154 @@
+//! C ABI over [`ProxyClient`], declared in `ffi/rustredis.h`.
+//!
+//! Strings cross the boundary as NUL-terminated UTF-8; values and events as JSON text. Strings
+//! returned to the caller are owned by it and released with `rr_free_string`. Functions returning
+//! an `int` return 0 on success and -1 on failure, with the message available from `rr_last_error`.
+
+// Import necessary crates and modules
+use crate::client::{ClientError, ProxyClient}; // For speaking the proxy protocol
+use serde_json::Value; // For values passed as JSON text
+use std::ffi::{c_char, c_int, CStr, CString}; // For C strings
+use std::ptr; // For null pointers
+
+/// Client handle given to C callers.
+pub struct RrClient {
+    client: ProxyClient,
+    last_error: CString, // Message of the last failed call on this handle
+}
+
+// Function to read a C string argument
+unsafe fn arg<'a>(text: *const c_char) -> Result<&'a str, ClientError> {
+    if text.is_null() {
+        return Err(ClientError::Proxy("null argument".to_string()));
+    }
+    CStr::from_ptr(text).to_str().map_err(|_| ClientError::Proxy("argument is not UTF-8".to_string()))
+}
+
+// Function to hand a string to the caller
+fn owned_string(text: String) -> *mut c_char {
+    CString::new(text).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+impl RrClient {
+    // Function to remember the error of a failed call
+    fn fail(&mut self, err: ClientError) {
+        self.last_error = CString::new(err.to_string()).unwrap_or_default();
+    }
+
+    // Function to turn the outcome of a call into its C status
+    fn status(&mut self, result: Result<(), ClientError>) -> c_int {
+        match result {
+            Ok(()) => 0,
+            Err(err) => {
+                self.fail(err);
+                -1
+            }
+        }
+    }
+
+    // Function to turn the JSON payload of a call into a caller-owned string, or null on failure
+    fn json(&mut self, result: Result<Value, ClientError>) -> *mut c_char {
+        match result {
+            Ok(value) => owned_string(value.to_string()),
+            Err(err) => {
+                self.fail(err);
+                ptr::null_mut()
+            }
+        }
+    }
+}
+
+/// Connects to the proxy socket (the default socket if `socket_path` is null); returns null on failure.
+///
+/// # Safety
+/// `socket_path` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rr_connect(socket_path: *const c_char) -> *mut RrClient {
+    let path = if socket_path.is_null() { Ok(crate::client::DEFAULT_SOCKET_PATH) } else { arg(socket_path) };
+    match path.and_then(ProxyClient::connect) {
+        Ok(client) => Box::into_raw(Box::new(RrClient { client, last_error: CString::default() })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Closes the connection and frees the handle.
+///
+/// # Safety
+/// `client` must be null or a handle returned by `rr_connect` that is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rr_close(client: *mut RrClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Stores a JSON value under a key.
+///
+/// # Safety
+/// `client` must be a live handle; `key` and `value_json` valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn rr_set(client: *mut RrClient, key: *const c_char, value_json: *const c_char) -> c_int {
+    let client = &mut *client;
+    let result = arg(key).and_then(|key| {
+        let value: Value = serde_json::from_str(arg(value_json)?).map_err(|e| ClientError::Proxy(format!("invalid JSON value: {}", e)))?;
+        client.client.set(key, &value)
+    });
+    client.status(result)
+}
+
+/// Reads the value under a key; returns the JSON payload of the get (`{"found", "value"}`) or null on failure.
+///
+/// # Safety
+/// `client` must be a live handle; `key` a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rr_get(client: *mut RrClient, key: *const c_char) -> *mut c_char {
+    let client = &mut *client;
+    let result = arg(key).and_then(|key| client.client.get(key, false));
+    client.json(result)
+}
+
+/// Subscribes to the events of keys matching a glob pattern, filtered by `filter` unless it is null.
+/// The connection then only carries events, read with `rr_next_event`.
+///
+/// # Safety
+/// `client` must be a live handle; `pattern` a valid NUL-terminated string; `filter` null or one.
+#[no_mangle]
+pub unsafe extern "C" fn rr_subscribe(client: *mut RrClient, pattern: *const c_char, filter: *const c_char) -> c_int {
+    let client = &mut *client;
+    let result = arg(pattern).and_then(|pattern| {
+        let filter = if filter.is_null() { None } else { Some(arg(filter)?) };
+        client.client.subscribe(pattern, filter)
+    });
+    client.status(result)
+}
+
+/// Waits for the next event of a subscription; returns it as JSON (`{"key", "action", "value"}`) or null on failure.
+///
+/// # Safety
+/// `client` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn rr_next_event(client: *mut RrClient) -> *mut c_char {
+    let client = &mut *client;
+    let result = client.client.next_event();
+    client.json(result)
+}
+
+/// Returns the message of the last failed call on the handle (owned by the handle, empty if none).
+///
+/// # Safety
+/// `client` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn rr_last_error(client: *const RrClient) -> *const c_char {
+    (*client).last_error.as_ptr()
+}
+
+/// Frees a string returned by `rr_get` or `rr_next_event`.
+///
+/// # Safety
+/// `text` must be null or a string returned by this library that is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rr_free_string(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
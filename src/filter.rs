@@ -0,0 +1,175 @@
+// Import necessary crates and modules
+use crate::glob::glob_match; // For `matches` conditions
+use serde_json::Value; // For events and operands
+use std::cmp::Ordering; // For ordered comparisons
+
+/// Comparison of a filter condition.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Matches, // Redis-style glob match of a string
+}
+
+/// Condition on one field of an event, e.g. `value.usage > 90`.
+#[derive(Clone, Debug)]
+struct Condition {
+    path: Vec<String>, // Field of the event (`key`, `action`, `value` or `value.<field>...`)
+    op: Op,
+    operand: Value,
+}
+
+/// Filter over the structured events of [`crate::events::parse_event`], e.g.
+/// `key matches cs:ModemWatcher:* and value.signal < -90`.
+///
+/// A filter is a list of conditions `<field> <op> <operand>` joined by `and` / `or` (`and` binds
+/// tighter, there are no parentheses). Fields are `key`, `action`, `value` or a dotted path into
+/// the value; operators are `==`, `!=`, `>`, `>=`, `<`, `<=` and `matches`. Operands are numbers,
+/// `true`, `false`, `null`, double-quoted strings or bare words taken as strings.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    any_of: Vec<Vec<Condition>>, // Alternatives joined by `or`, each a list of conditions joined by `and`
+}
+
+// Define a token of a filter expression; quoted strings are never keywords or numbers
+enum Token {
+    Word(String),
+    Quoted(String),
+}
+
+// Function to split an expression into words and double-quoted strings
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => text.extend(chars.next()),
+                    Some(c) => text.push(c),
+                    None => return Err("unterminated string".to_string()),
+                }
+            }
+            tokens.push(Token::Quoted(text));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek().filter(|c| !c.is_whitespace()) {
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        }
+    }
+    Ok(tokens)
+}
+
+// Function to parse the field a condition tests
+fn parse_path(token: &Token) -> Result<Vec<String>, String> {
+    let Token::Word(word) = token else { return Err("expected a field, found a string".to_string()) };
+    let path: Vec<String> = word.split('.').map(str::to_string).collect();
+    match path[0].as_str() {
+        "key" | "action" if path.len() == 1 => Ok(path),
+        "value" => Ok(path),
+        _ => Err(format!("unknown field '{}', expected key, action or value[.<field>]", word)),
+    }
+}
+
+// Function to parse an operator
+fn parse_op(token: &Token) -> Result<Op, String> {
+    let word = match token {
+        Token::Word(word) => word.as_str(),
+        Token::Quoted(_) => "",
+    };
+    Ok(match word {
+        "==" => Op::Eq,
+        "!=" => Op::Ne,
+        ">" => Op::Gt,
+        ">=" => Op::Ge,
+        "<" => Op::Lt,
+        "<=" => Op::Le,
+        "matches" => Op::Matches,
+        _ => return Err(format!("unknown operator '{}'", word)),
+    })
+}
+
+// Function to parse an operand
+fn parse_operand(token: &Token) -> Value {
+    match token {
+        Token::Quoted(text) => Value::String(text.clone()),
+        Token::Word(word) => match word.as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            "null" => Value::Null,
+            _ => word.parse::<f64>().ok()
+                .and_then(serde_json::Number::from_f64)
+                .map_or_else(|| Value::String(word.clone()), Value::Number),
+        },
+    }
+}
+
+// Function to compare two values of the same kind (numbers or strings)
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.as_f64()?.partial_cmp(&r.as_f64()?),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        _ => None,
+    }
+}
+
+impl Condition {
+    // Function to test the condition against an event
+    fn matches(&self, event: &Value) -> bool {
+        let field = self.path.iter().try_fold(event, |value, part| value.get(part)).unwrap_or(&Value::Null);
+        let ordering = compare(field, &self.operand);
+        match self.op {
+            Op::Eq => ordering == Some(Ordering::Equal) || field == &self.operand,
+            Op::Ne => !(ordering == Some(Ordering::Equal) || field == &self.operand),
+            Op::Gt => ordering == Some(Ordering::Greater),
+            Op::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            Op::Lt => ordering == Some(Ordering::Less),
+            Op::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Op::Matches => match (field, &self.operand) {
+                (Value::String(text), Value::String(pattern)) => glob_match(pattern, text),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl Filter {
+    /// Parses a filter expression.
+    pub fn parse(expr: &str) -> Result<Filter, String> {
+        let tokens = tokenize(expr)?;
+        let mut any_of = vec![Vec::new()];
+        let mut rest = tokens.as_slice();
+        loop {
+            let [field, op, operand, tail @ ..] = rest else {
+                return Err("incomplete condition, expected <field> <op> <operand>".to_string());
+            };
+            any_of.last_mut().unwrap().push(Condition { path: parse_path(field)?, op: parse_op(op)?, operand: parse_operand(operand) });
+            match tail {
+                [] => return Ok(Filter { any_of }),
+                [Token::Word(joiner), tail @ ..] if joiner == "and" => rest = tail,
+                [Token::Word(joiner), tail @ ..] if joiner == "or" => {
+                    any_of.push(Vec::new());
+                    rest = tail;
+                }
+                _ => return Err("expected 'and' or 'or' between conditions".to_string()),
+            }
+        }
+    }
+
+    /// Returns true if the event (`{"key", "action", "value"}`) passes the filter.
+    pub fn matches(&self, event: &Value) -> bool {
+        self.any_of.iter().any(|all_of| all_of.iter().all(|condition| condition.matches(event)))
+    }
+}
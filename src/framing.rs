@@ -0,0 +1,32 @@
+/// Splits a byte stream into the newline-delimited messages of the proxy protocol.
+///
+/// Bytes are pushed as they arrive from the socket, in chunks of any size; complete messages
+/// (including their terminating newline) are taken out in order, and an incomplete trailing
+/// message is kept until the rest of it arrives.
+#[derive(Default)]
+pub struct LineFramer {
+    buffer: Vec<u8>, // Bytes received but not yet taken out as messages
+}
+
+impl LineFramer {
+    /// Creates an empty framer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends bytes received from the socket.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Takes out the next complete message, newline included.
+    pub fn next_message(&mut self) -> Option<Vec<u8>> {
+        let pos = self.buffer.iter().position(|&b| b == b'\n')?;
+        Some(self.buffer.drain(..=pos).collect())
+    }
+
+    /// Returns the bytes of the incomplete message received so far.
+    pub fn pending(&self) -> &[u8] {
+        &self.buffer
+    }
+}
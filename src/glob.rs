@@ -0,0 +1,25 @@
+/// Matches a key against a Redis-style glob pattern (`*` any run of characters, `?` one character).
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0); // Current positions in pattern and text
+    let mut backtrack: Option<(usize, usize)> = None; // Position after the last `*` and the text position it matched up to
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p + 1, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p; // Let the last `*` swallow one more character
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*') // Only trailing stars may remain
+}
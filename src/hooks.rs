@@ -0,0 +1,111 @@
+// Lifecycle hooks of the proxy: which changes of which keys they run on and what they do
+
+// Define the stream length kept by stream hooks that set none
+const DEFAULT_STREAM_MAXLEN: usize = 10000;
+
+// Define the change of a key a hook runs on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum On {
+    Write, // Every set, whether it creates the key or not
+    Create, // A set of a key that did not exist
+    Update, // A set of a key that existed
+    Delete, // A del of a key that existed
+}
+
+// Define what happened to a key, as far as the write knows
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Change {
+    Created,
+    Updated,
+    Written, // Set without checking whether the key existed (no create or update hook applies)
+    Deleted,
+}
+
+impl Change {
+    // Function to return the action hook events carry
+    pub fn action(self) -> &'static str {
+        match self {
+            Change::Created => "create",
+            Change::Updated => "update",
+            Change::Written => "set",
+            Change::Deleted => "delete",
+        }
+    }
+}
+
+impl On {
+    // Function to check whether a hook on this runs for a change
+    pub fn runs_on(self, change: Change) -> bool {
+        matches!(
+            (self, change),
+            (On::Write, Change::Created | Change::Updated | Change::Written) | (On::Create, Change::Created) | (On::Update, Change::Updated) | (On::Delete, Change::Deleted)
+        )
+    }
+}
+
+// Define what a hook does
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Publish { channel: String }, // Publish {"key", "action", "value"} on a channel
+    Incr { key: String }, // Increment a counter
+    Stream { key: String, maxlen: usize }, // Append key, action and value to a capped stream
+    Webhook { url: String }, // POST {"key", "action", "value", "timestamp"} once the write is committed
+}
+
+// Define the configuration of one lifecycle hook
+#[derive(Clone, Debug)]
+pub struct HookConfig {
+    pub pattern: String, // Key glob pattern of the writes the hook runs on
+    pub on: On, // Change it runs on
+    pub action: Action, // Templates with {key}, {producer}, {object}, {id} and {function} placeholders
+}
+
+impl HookConfig {
+    // Function to parse a hook specification of the form PATTERN=ON:publish,channel=TEMPLATE,
+    // PATTERN=ON:incr,key=TEMPLATE, PATTERN=ON:stream,key=TEMPLATE[,maxlen=N] or PATTERN=ON:webhook,url=URL,
+    // where ON is write, create, update or delete
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (pattern, rest) = spec.split_once('=').ok_or("expected PATTERN=ON:publish|incr|stream|webhook,OPTIONS")?;
+        let mut parts = rest.split(',');
+        let (on, kind) = parts.next().unwrap_or_default().split_once(':').ok_or_else(|| format!("expected ON:ACTION in '{}'", spec))?;
+        let on = match on {
+            "write" => On::Write,
+            "create" => On::Create,
+            "update" => On::Update,
+            "delete" => On::Delete,
+            _ => return Err(format!("invalid hook event '{}', expected write, create, update or delete", on)),
+        };
+        let (mut key, mut channel, mut url, mut maxlen) = (None, None, None, None);
+        for option in parts {
+            match option.split_once('=') {
+                Some(("key", template)) if !template.is_empty() => key = Some(template.to_string()),
+                Some(("channel", template)) if !template.is_empty() => channel = Some(template.to_string()),
+                Some(("url", address)) if address.starts_with("http://") => url = Some(address.to_string()),
+                Some(("url", address)) => return Err(format!("unsupported hook URL '{}', only http:// is supported", address)),
+                Some(("maxlen", n)) => maxlen = Some(n.parse().map_err(|_| format!("invalid maxlen '{}'", n))?),
+                _ => return Err(format!("unknown hook option '{}'", option)),
+            }
+        }
+        let action = match (kind, key, channel, url) {
+            ("publish", None, Some(channel), None) if maxlen.is_none() => Action::Publish { channel },
+            ("incr", Some(key), None, None) if maxlen.is_none() => Action::Incr { key },
+            ("stream", Some(key), None, None) => Action::Stream { key, maxlen: maxlen.unwrap_or(DEFAULT_STREAM_MAXLEN) },
+            ("webhook", None, None, Some(url)) if maxlen.is_none() => Action::Webhook { url },
+            ("publish", ..) => return Err(format!("publish hook takes channel= only, in '{}'", spec)),
+            ("incr", ..) => return Err(format!("incr hook takes key= only, in '{}'", spec)),
+            ("stream", ..) => return Err(format!("stream hook takes key= and maxlen= only, in '{}'", spec)),
+            ("webhook", ..) => return Err(format!("webhook hook takes url= only, in '{}'", spec)),
+            _ => return Err(format!("invalid hook action in '{}', expected publish, incr, stream or webhook", spec)),
+        };
+        Ok(HookConfig { pattern: pattern.to_string(), on, action })
+    }
+
+    // Function to list the templates of the hook, for checking them
+    pub fn templates(&self) -> Vec<&str> {
+        match self.action {
+            Action::Publish { ref channel } => vec![channel.as_str()],
+            Action::Incr { ref key } | Action::Stream { ref key, .. } => vec![key.as_str()],
+            Action::Webhook { .. } => Vec::new(),
+        }
+    }
+}
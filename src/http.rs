@@ -0,0 +1,69 @@
+// Import necessary crates and modules
+use std::io::{BufRead, BufReader, Write}; // For writing requests and reading the status line
+use std::net::TcpStream; // For plain HTTP connections
+use std::time::Duration; // For connection and I/O timeouts
+
+// Define how long an HTTP exchange may block before giving up
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Define the parts of an http:// URL needed to send a request
+struct Url<'a> {
+    host: &'a str, // Host name or address, including the port if given
+    path: &'a str, // Request path, "/" if empty
+}
+
+// Function to split an http:// URL into host and path
+fn parse_url(url: &str) -> Result<Url<'_>, String> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| format!("unsupported URL '{}', only http:// is supported", url))?;
+    let (host, path) = match rest.find('/') {
+        Some(pos) => (&rest[..pos], &rest[pos..]),
+        None => (rest, "/"),
+    };
+    if host.is_empty() {
+        return Err(format!("missing host in URL '{}'", url));
+    }
+    Ok(Url { host, path })
+}
+
+/// POSTs a JSON body to an http:// URL, returning the response status code.
+pub fn post_json(url: &str, body: &str) -> Result<u16, String> {
+    post(url, "application/json", body)
+}
+
+/// POSTs a body with the given content type to an http:// URL, returning the response status code.
+pub fn post(url: &str, content_type: &str, body: &str) -> Result<u16, String> {
+    request("POST", url, &[("Content-Type", content_type)], body.as_bytes())
+}
+
+/// PUTs a body with extra headers to an http:// URL, returning the response status code.
+pub fn put(url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<u16, String> {
+    request("PUT", url, headers, body)
+}
+
+// Function to send one request and return the response status code
+fn request(method: &str, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<u16, String> {
+    let url = parse_url(url)?;
+    let address = if url.host.contains(':') { url.host.to_string() } else { format!("{}:80", url.host) };
+
+    let mut stream = TcpStream::connect(&address).map_err(|e| format!("connect to {}: {}", address, e))?;
+    stream.set_read_timeout(Some(HTTP_TIMEOUT)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(HTTP_TIMEOUT)).map_err(|e| e.to_string())?;
+
+    let mut head = format!("{} {} HTTP/1.1\r\nHost: {}\r\n", method, url.path, url.host);
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", body.len()));
+    stream.write_all(head.as_bytes())
+        .and_then(|_| stream.write_all(body))
+        .map_err(|e| format!("send to {}: {}", address, e))?;
+
+    // Only the status line matters, the body is discarded with the connection
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line).map_err(|e| format!("read from {}: {}", address, e))?;
+    status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| format!("malformed HTTP response from {}: {:?}", address, status_line.trim()))
+}
@@ -0,0 +1,14 @@
+/// Fills a template with the parts of a `cs:<producer>:<object>[:<id>][:<function>]` key.
+///
+/// Supported placeholders are `{key}`, `{producer}`, `{object}`, `{id}` and `{function}`;
+/// parts missing from the key are replaced by an empty string.
+pub fn render_template(template: &str, key: &str) -> String {
+    let parts: Vec<&str> = key.split(':').collect();
+    let part = |i: usize| parts.get(i).copied().unwrap_or("");
+    template
+        .replace("{key}", key)
+        .replace("{producer}", part(1))
+        .replace("{object}", part(2))
+        .replace("{id}", part(3))
+        .replace("{function}", part(4))
+}
@@ -0,0 +1,229 @@
+// Import necessary crates and modules
+use serde_json::{json, Value}; // For summaries in results files
+use std::time::{Duration, Instant}; // For recorded latencies and timeline buckets
+
+// Define how many percentile steps HdrHistogram-style distributions print per halving of the remaining distance to 100%
+const PERCENTILE_TICKS_PER_HALF_DISTANCE: f64 = 5.0;
+
+// Define how many linear sub-buckets each power of two is split into; 64 keeps
+// every recorded value within about 1.5% of the true latency
+const SUB_BUCKETS: u64 = 64;
+
+/// Log-linear latency histogram with microsecond resolution and constant memory.
+#[derive(Clone)]
+pub struct Histogram {
+    counts: Vec<u64>, // Samples per bucket
+    total: u64, // Number of samples
+    sum_us: u128, // Sum of all samples, for the mean
+    min_us: u64, // Smallest sample
+    max_us: u64, // Largest sample
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Function to map a value to its bucket index
+fn bucket_of(value: u64) -> usize {
+    if value < SUB_BUCKETS * 2 {
+        return value as usize;
+    }
+    let shift = 63 - value.leading_zeros() as u64 - SUB_BUCKETS.trailing_zeros() as u64; // Keeps 64..127 after shifting
+    (SUB_BUCKETS * shift + (value >> shift)) as usize
+}
+
+// Function to map a bucket index back to the highest value it holds
+fn bucket_value(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUB_BUCKETS * 2 {
+        return index;
+    }
+    let shift = index / SUB_BUCKETS - 1;
+    let sub = index - SUB_BUCKETS * shift;
+    ((sub + 1) << shift) - 1
+}
+
+impl Histogram {
+    /// Creates an empty histogram.
+    pub fn new() -> Self {
+        Histogram { counts: Vec::new(), total: 0, sum_us: 0, min_us: u64::MAX, max_us: 0 }
+    }
+
+    /// Records one latency sample.
+    pub fn record(&mut self, latency: Duration) {
+        let us = latency.as_micros().min(u64::MAX as u128) as u64;
+        let index = bucket_of(us);
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        self.total += 1;
+        self.sum_us += us as u128;
+        self.min_us = self.min_us.min(us);
+        self.max_us = self.max_us.max(us);
+    }
+
+    /// Adds all samples of another histogram.
+    pub fn merge(&mut self, other: &Histogram) {
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, added) in self.counts.iter_mut().zip(&other.counts) {
+            *count += added;
+        }
+        self.total += other.total;
+        self.sum_us += other.sum_us;
+        self.min_us = self.min_us.min(other.min_us);
+        self.max_us = self.max_us.max(other.max_us);
+    }
+
+    /// Returns the number of recorded samples.
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the latency in microseconds below which the given percentile (0-100) of samples fall.
+    pub fn percentile(&self, percentile: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        self.value_at_rank(((percentile / 100.0) * self.total as f64).ceil().max(1.0) as u64)
+    }
+
+    /// Returns the mean latency in microseconds.
+    pub fn mean(&self) -> f64 {
+        if self.total == 0 { 0.0 } else { self.sum_us as f64 / self.total as f64 }
+    }
+
+    /// Returns min, mean, common percentiles and max in microseconds as JSON.
+    pub fn summary(&self) -> Value {
+        json!({
+            "count": self.total,
+            "min": if self.total == 0 { 0 } else { self.min_us },
+            "mean": (self.mean() * 10.0).round() / 10.0,
+            "p50": self.percentile(50.0),
+            "p90": self.percentile(90.0),
+            "p95": self.percentile(95.0),
+            "p99": self.percentile(99.0),
+            "p99.9": self.percentile(99.9),
+            "max": self.max_us,
+        })
+    }
+
+    // Function to return the smallest recorded value with at least `rank` samples at or below it
+    fn value_at_rank(&self, rank: u64) -> u64 {
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return bucket_value(index).clamp(self.min_us, self.max_us);
+            }
+        }
+        self.max_us
+    }
+
+    /// Returns the distribution as a text histogram with one bar per power-of-two range of microseconds
+    /// (empty ranges between the smallest and largest sample included), the longest `width` characters.
+    pub fn bars(&self, width: usize) -> String {
+        let mut ranges: Vec<(u64, u64)> = Vec::new(); // Lower bound of each range and its samples
+        for (index, count) in self.counts.iter().enumerate().filter(|(_, count)| **count > 0) {
+            let value = bucket_value(index);
+            let lower = if value == 0 { 0 } else { 1 << (63 - value.leading_zeros()) }; // Buckets never straddle a power of two
+            while ranges.last().is_some_and(|(last, _)| *last < lower) {
+                let next = ranges.last().map_or(0, |(last, _)| (last * 2).max(1));
+                ranges.push((next, 0));
+            }
+            match ranges.last_mut() {
+                Some((last, samples)) if *last == lower => *samples += count,
+                _ => ranges.push((lower, *count)),
+            }
+        }
+        let most = ranges.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+        ranges.iter().map(|(lower, count)| {
+            let upper = (lower * 2).max(1) - 1;
+            let bar = "#".repeat((*count as u128 * width as u128).div_ceil(most as u128) as usize);
+            format!(
+                "{:>9} - {:<9} us |{:<width$}| {:>10} {:>6.2}%\n",
+                lower, upper, bar, count, *count as f64 * 100.0 / self.total as f64, width = width
+            )
+        }).collect()
+    }
+
+    /// Returns the distribution in HdrHistogram's percentile output format (`.hgrm`), with values in milliseconds.
+    pub fn hgrm(&self) -> String {
+        let mut out = format!("{:>12} {:>14} {:>10} {:>14}\n\n", "Value", "Percentile", "TotalCount", "1/(1-Percentile)");
+        if self.total > 0 {
+            let mut level: f64 = 0.0;
+            loop {
+                let rank = ((level / 100.0) * self.total as f64).ceil().max(1.0) as u64;
+                let value_ms = self.value_at_rank(rank) as f64 / 1000.0;
+                if rank >= self.total {
+                    out.push_str(&format!("{:>12.3} {:>14.12} {:>10}\n", value_ms, 1.0, self.total));
+                    break;
+                }
+                out.push_str(&format!(
+                    "{:>12.3} {:>14.12} {:>10} {:>14.2}\n",
+                    value_ms, level / 100.0, rank, 1.0 / (1.0 - level / 100.0)
+                ));
+                // Steps halve each time the remaining distance to 100% halves, as in HdrHistogram
+                let half_distance = 2f64.powf((100.0 / (100.0 - level)).log2().floor() + 1.0);
+                level += 100.0 / (half_distance * PERCENTILE_TICKS_PER_HALF_DISTANCE);
+            }
+        }
+
+        let mean_ms = self.mean() / 1000.0;
+        let variance = self.counts.iter().enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(index, count)| (bucket_value(index) as f64 / 1000.0 - mean_ms).powi(2) * *count as f64)
+            .sum::<f64>() / self.total.max(1) as f64;
+        out.push_str(&format!("#[Mean    = {:>12.3}, StdDeviation   = {:>12.3}]\n", mean_ms, variance.sqrt()));
+        out.push_str(&format!("#[Max     = {:>12.3}, Total count    = {:>12}]\n", self.max_us as f64 / 1000.0, self.total));
+        out.push_str(&format!("#[Buckets = {:>12}, SubBuckets     = {:>12}]\n", self.counts.len(), SUB_BUCKETS));
+        out
+    }
+}
+
+/// Latency histograms per fixed time bucket (e.g. per second) of a run, to show how latency evolves.
+#[derive(Clone)]
+pub struct Timeline {
+    start: Instant, // Beginning of the first bucket
+    bucket: Duration, // Length of each bucket
+    buckets: Vec<Histogram>, // Samples by completion time
+}
+
+impl Timeline {
+    /// Creates an empty timeline starting at `start`.
+    pub fn new(start: Instant, bucket: Duration) -> Self {
+        Timeline { start, bucket, buckets: Vec::new() }
+    }
+
+    /// Records a sample completing now.
+    pub fn record(&mut self, latency: Duration) {
+        let index = (self.start.elapsed().as_secs_f64() / self.bucket.as_secs_f64()) as usize;
+        if index >= self.buckets.len() {
+            self.buckets.resize(index + 1, Histogram::new());
+        }
+        self.buckets[index].record(latency);
+    }
+
+    /// Adds the samples of another timeline with the same start and bucket length.
+    pub fn merge(&mut self, other: &Timeline) {
+        if other.buckets.len() > self.buckets.len() {
+            self.buckets.resize(other.buckets.len(), Histogram::new());
+        }
+        for (bucket, added) in self.buckets.iter_mut().zip(&other.buckets) {
+            bucket.merge(added);
+        }
+    }
+
+    /// Returns one summary per bucket, with `t` the bucket's start in seconds.
+    pub fn summary(&self) -> Value {
+        Value::Array(self.buckets.iter().enumerate().map(|(index, histogram)| {
+            let mut summary = histogram.summary();
+            summary["t"] = json!(index as f64 * self.bucket.as_secs_f64());
+            summary
+        }).collect())
+    }
+}
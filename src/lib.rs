@@ -0,0 +1,29 @@
+//! Shared building blocks for the rustredis binaries.
+
+#[cfg(all(feature = "async", unix))]
+pub mod async_client; // Tokio client for the Redis proxy, with subscription streams
+pub mod base64; // Base64 encoding of binary values in JSON requests
+pub mod check; // Diagnostics of the tools' --check-config mode
+pub mod client; // Client for the Redis proxy Unix socket protocol
+#[cfg(feature = "proxy")]
+pub mod encryption; // AES-256-GCM sealing of the values of sensitive keys
+pub mod event_log; // Consumer groups over the proxy's stream event log
+pub mod events; // Structured view of the events the proxy publishes
+pub mod ffi; // C interface of the proxy client (ffi/rustredis.h)
+pub mod filter; // Expressions selecting events for subscribers
+pub mod framing; // Newline framing of the proxy protocol
+pub mod glob; // Redis-style glob matching of keys
+pub mod hooks; // Lifecycle hook specifications of the proxy
+pub mod http; // Minimal HTTP client for outbound integrations
+pub mod keys; // Parts of cs:<producer>:<object>[:<id>][:<function>] keys
+pub mod latency; // Latency histograms for the benchmarks
+pub mod multicall; // Subcommand selection of the rustredis multicall binary
+pub mod multiplex; // One proxy connection shared fairly by many logical clients
+pub mod reconnect; // Proxy client buffering writes through proxy restarts
+pub mod rng; // Seedable random numbers for reproducible workloads
+#[cfg(feature = "proxy")]
+pub mod schema; // Key grammar and JSON schemas of the cs: namespace
+pub mod storage; // Redis connections of the proxy, or an in-memory store standing in for Redis
+pub mod transport; // Unix socket, TCP and Windows named pipe connections to the proxy
+pub mod tunnel; // SOCKS5 and SSH tunnels to remote Redis servers
+pub mod typed; // Typed payloads of the producer objects, generated by schema_codegen
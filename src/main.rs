@@ -1,89 +1,53 @@
-use clap::Parser;
-use redis::{Commands, Client};
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
-};
-use std::thread::sleep;
-use std::time::{Duration, Instant};
-
-/// Optimized Redis Performance Test Script (Sequential Data)
-#[derive(Parser)]
-#[command(author, version, about)]
-struct Args {
-    /// Redis key to set
-    #[arg(long, default_value = "test_key")]
-    key: String,
-
-    /// Rate of sets per second
-    #[arg(long)]
-    rate: f64,
-}
+// rustredis: the Redis performance test tool, and the multicall binary of the other tools.
+//
+//   rustredis [--config FILE] <proxy|bench|disk-monitor|client> [OPTIONS]
+//
+// Each subcommand takes the options of its standalone binary (redis_proxy, the benchmark,
+// disk_monitor, redis_proxy_client_test); without one the benchmark runs, as before. Symlinks
+// named after a subcommand (disk-monitor -> rustredis) run it directly. The YAML config file
+// holds one section of options per subcommand:
+//
+//   proxy:
+//     max_in_flight: 64
+//     backend: ["central=redis://10.0.0.2/"]
+//     route: ["cs:gps:*=central"]
+//   disk-monitor:
+//     stream: true
+
+#[cfg(feature = "bench")]
+mod bench; // The performance test
+#[path = "bin/redis_proxy_client_test.rs"]
+mod client;
+#[cfg(feature = "monitors")]
+#[path = "bin/disk_monitor/main.rs"]
+mod disk_monitor;
+#[cfg(feature = "bench")]
+mod perf;
+#[cfg(feature = "proxy")]
+#[path = "bin/redis_proxy/main.rs"]
+mod proxy;
 
 fn main() {
-    let args = Args::parse();
-
-    println!("Starting Redis performance test...");
-    println!("Key: {}", args.key);
-    println!("Rate: {} sets/sec", args.rate);
-
-    // Connect to Redis
-    let client =
-        Client::open("redis://127.0.0.1/").expect("Failed to create Redis client");
-    let mut con = client
-        .get_connection()
-        .expect("Failed to connect to Redis");
-
-    // Preloaded random data table
-    let preloaded_data = [
-        "Lorem ipsum dolor sit amet, consectetur adipiscing elit.",
-        "The quick brown fox jumps over the lazy dog.",
-        "Redis is an in-memory data structure store, used as a database, cache, and message broker.",
-        "High-performance computing requires efficient memory access patterns.",
-        "Unix domain sockets provide efficient interprocess communication.",
-    ];
-
-    // Create a cycle iterator to loop through preloaded data sequentially
-    let mut data_iter = preloaded_data.iter().cycle();
-
-    let interval = Duration::from_secs_f64(1.0 / args.rate);
-    let start_time = Instant::now();
-    let mut count: u64 = 0;
-
-    // Set up a flag to catch Ctrl-C
-    let running = Arc::new(AtomicBool::new(true));
-    let running_ctrlc = running.clone();
-    ctrlc::set_handler(move || {
-        running_ctrlc.store(false, Ordering::SeqCst);
-    })
-    .expect("Error setting Ctrl-C handler");
-
-    // Main loop: set the Redis key repeatedly at the specified rate
-    while running.load(Ordering::SeqCst) {
-        // Fetch next data item from preloaded table
-        let value = data_iter.next().unwrap();
-
-        // Store in Redis
-        //let res: redis::RedisResult<()> =
-        //    redis::cmd("SET").arg(&args.key).arg(*value).query(&mut con);
-        let res: redis::RedisResult<()> = con.set(&args.key, *value);
-        if let Err(e) = res {
-            eprintln!("Error: {}", e);
-            break;
+    let applet = rustredis::multicall::select().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(2);
+    });
+    match applet {
+        #[cfg(feature = "proxy")]
+        "proxy" => {
+            if let Err(err) = proxy::main() {
+                eprintln!("Error: {:?}", err);
+                std::process::exit(1);
+            }
         }
-
-        count += 1;
-        if count % 1000 == 0 {
-            let elapsed = start_time.elapsed();
-            println!(
-                "[{:.2?}] Set {} keys at {} in Redis.",
-                elapsed, count, args.key
-            );
+        #[cfg(feature = "monitors")]
+        "disk-monitor" => disk_monitor::main(),
+        "client" => client::main(),
+        #[cfg(feature = "bench")]
+        "bench" => bench::main(),
+        _ => {
+            eprintln!("This rustredis was built without the {} subcommand", applet);
+            std::process::exit(2);
         }
-
-        // Sleep to maintain the desired rate
-        sleep(interval);
     }
-
-    println!("\nTest stopped by user.");
 }
@@ -0,0 +1,100 @@
+// Import necessary crates and modules
+use serde_yaml::Value; // For the per-subcommand config sections
+use std::ffi::OsString; // For command line arguments
+use std::path::Path; // For the name the binary was invoked as
+use std::sync::OnceLock; // For the arguments of the selected subcommand
+
+/// Subcommands of the `rustredis` multicall binary.
+pub const APPLETS: [&str; 4] = ["proxy", "bench", "disk-monitor", "client"];
+
+// Define the arguments of the selected subcommand, set by the multicall binary
+static ARGS: OnceLock<Vec<OsString>> = OnceLock::new();
+
+/// Returns the command line arguments of the running tool: those of the selected subcommand
+/// inside the multicall binary, the process arguments otherwise.
+pub fn args() -> Vec<OsString> {
+    ARGS.get().cloned().unwrap_or_else(|| std::env::args_os().collect())
+}
+
+/// Selects the subcommand of the multicall binary from the process arguments and prepares its
+/// arguments for [`args`]. The subcommand is taken from the name the binary was invoked as
+/// (e.g. a `disk-monitor` or `rustredis-disk-monitor` symlink), else from the first argument
+/// after an optional `--config FILE`; it defaults to `bench`. Options of the subcommand's
+/// section of the YAML config file become flags, unless given on the command line.
+pub fn select() -> Result<&'static str, String> {
+    let mut process_args: Vec<OsString> = std::env::args_os().collect();
+    let invoked_as = process_args.first()
+        .and_then(|arg0| Path::new(arg0).file_name())
+        .map(|name| name.to_string_lossy().trim_start_matches("rustredis-").to_string())
+        .unwrap_or_default();
+    let mut rest = process_args.split_off(1.min(process_args.len()));
+
+    let mut config = None;
+    let applet = match applet_named(&invoked_as) {
+        Some(applet) => applet,
+        None => {
+            match rest.first().and_then(|arg| arg.to_str()) {
+                Some("--config") if rest.len() > 1 => config = Some(rest.drain(..2).nth(1).unwrap()),
+                Some(arg) if arg.starts_with("--config=") => config = Some(OsString::from(&rest.remove(0).to_string_lossy()["--config=".len()..])),
+                _ => {}
+            }
+            match rest.first().and_then(|arg| applet_named(&arg.to_string_lossy())) {
+                Some(applet) => {
+                    rest.remove(0);
+                    applet
+                }
+                None => "bench", // The binary ran the benchmark alone before it had subcommands
+            }
+        }
+    };
+
+    let mut args = vec![OsString::from(format!("rustredis {}", applet))];
+    if let Some(path) = config {
+        args.extend(config_flags(Path::new(&path), applet, &rest)?);
+    }
+    args.extend(rest);
+    let _ = ARGS.set(args);
+    Ok(applet)
+}
+
+// Function to find the subcommand of a name (disk_monitor and disk-monitor alike)
+fn applet_named(name: &str) -> Option<&'static str> {
+    let name = name.replace('_', "-");
+    APPLETS.iter().copied().find(|applet| *applet == name)
+}
+
+// Function to turn the options of a subcommand's config section into flags, skipping those given on the command line
+fn config_flags(path: &Path, applet: &str, given: &[OsString]) -> Result<Vec<OsString>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+    let config: Value = serde_yaml::from_str(&text).map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+    let section = config.get(applet).or_else(|| config.get(applet.replace('-', "_")));
+    let Some(section) = section else { return Ok(Vec::new()) };
+    let options = section.as_mapping().ok_or_else(|| format!("Config section {} must be a mapping of options", applet))?;
+
+    let mut flags = Vec::new();
+    for (name, value) in options {
+        let name = name.as_str().ok_or_else(|| format!("Config section {} has a non-string option name", applet))?;
+        let flag = format!("--{}", name.replace('_', "-"));
+        let overridden = given.iter().any(|arg| {
+            let arg = arg.to_string_lossy();
+            arg == flag || arg.starts_with(&format!("{}=", flag))
+        });
+        if overridden {
+            continue;
+        }
+        let values = match value {
+            Value::Sequence(items) => items.clone(),
+            value => vec![value.clone()],
+        };
+        for value in values {
+            match value {
+                Value::Bool(true) => flags.push(OsString::from(&flag)),
+                Value::Bool(false) | Value::Null => {}
+                Value::String(text) => flags.extend([OsString::from(&flag), OsString::from(text)]),
+                Value::Number(number) => flags.extend([OsString::from(&flag), OsString::from(number.to_string())]),
+                _ => return Err(format!("Config option {}.{} must be a scalar or a list of scalars", applet, name)),
+            }
+        }
+    }
+    Ok(flags)
+}
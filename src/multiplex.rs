@@ -0,0 +1,216 @@
+// Import necessary crates and modules
+use crate::client::{ClientError, ProxyClient, ProxyResponse}; // For the shared connection
+use serde_json::{json, Value}; // For building requests
+use std::collections::{BTreeMap, VecDeque}; // For the logical clients and their queues
+use std::io; // For connection errors
+use std::sync::mpsc; // For handing responses back to logical clients
+use std::sync::{Arc, Condvar, Mutex}; // For the scheduler shared with the dispatcher thread
+use std::thread::{self, JoinHandle}; // For the dispatcher thread
+use std::time::{Duration, Instant}; // For queueing times
+
+/// Counters of one logical client of a [`Multiplexer`].
+#[derive(Clone, Debug, Default)]
+pub struct LogicalStats {
+    pub requests: u64, // Requests answered by the proxy
+    pub errors: u64, // Requests rejected by the proxy or lost with the connection
+    pub queued: usize, // Requests waiting for their turn
+    pub wait_time: Duration, // Total time requests spent waiting for their turn
+}
+
+// Define a request waiting for its turn on the shared connection
+struct Job {
+    request: Value,
+    reply: mpsc::Sender<Result<ProxyResponse, ClientError>>,
+    queued_at: Instant,
+}
+
+// Define the queue and counters of one logical client
+struct Queue {
+    name: String,
+    jobs: VecDeque<Job>,
+    stats: LogicalStats,
+}
+
+// Define the queues of all logical clients, served round robin
+#[derive(Default)]
+struct Scheduler {
+    queues: BTreeMap<u64, Queue>, // Logical clients by id
+    turns: VecDeque<u64>, // Logical clients with queued requests, next to be served first
+    next_id: u64,
+    closed: bool, // Set when the multiplexer is dropped
+}
+
+// Define the state shared by the multiplexer, its logical clients and the dispatcher
+#[derive(Default)]
+struct Shared {
+    scheduler: Mutex<Scheduler>,
+    work: Condvar, // Signalled when a request is queued or the multiplexer closes
+}
+
+// Function to build the error of a request that could not be delivered
+fn not_delivered(message: &str) -> ClientError {
+    io::Error::new(io::ErrorKind::NotConnected, message.to_string()).into()
+}
+
+/// One proxy connection shared by many logical clients (e.g. the producers of a gateway
+/// process), cutting the sockets and proxy threads they need. Requests are sent one at a time,
+/// taking one from each logical client with queued requests in turn, so a busy producer cannot
+/// starve the others. A lost connection is re-established for the next request.
+pub struct Multiplexer {
+    shared: Arc<Shared>,
+    dispatcher: Option<JoinHandle<()>>,
+}
+
+impl Multiplexer {
+    /// Connects to the proxy and starts dispatching the requests of the logical clients.
+    pub fn connect(socket_path: &str) -> Result<Self, ClientError> {
+        let client = ProxyClient::connect(socket_path)?;
+        let shared = Arc::new(Shared::default());
+        let (socket_path, dispatcher_shared) = (socket_path.to_string(), Arc::clone(&shared));
+        let dispatcher = thread::spawn(move || dispatch(&dispatcher_shared, &socket_path, client));
+        Ok(Multiplexer { shared, dispatcher: Some(dispatcher) })
+    }
+
+    /// Creates a logical client; `name` labels its counters.
+    pub fn client(&self, name: &str) -> LogicalClient {
+        let mut scheduler = self.shared.scheduler.lock().unwrap();
+        let id = scheduler.next_id;
+        scheduler.next_id += 1;
+        scheduler.queues.insert(id, Queue { name: name.to_string(), jobs: VecDeque::new(), stats: LogicalStats::default() });
+        LogicalClient { id, shared: Arc::clone(&self.shared) }
+    }
+
+    /// Returns the counters of every logical client, by name.
+    pub fn stats(&self) -> Vec<(String, LogicalStats)> {
+        let scheduler = self.shared.scheduler.lock().unwrap();
+        scheduler.queues.values().map(|queue| (queue.name.clone(), LogicalStats { queued: queue.jobs.len(), ..queue.stats.clone() })).collect()
+    }
+}
+
+impl Drop for Multiplexer {
+    fn drop(&mut self) {
+        self.shared.scheduler.lock().unwrap().closed = true;
+        self.shared.work.notify_all();
+        if let Some(dispatcher) = self.dispatcher.take() {
+            let _ = dispatcher.join();
+        }
+    }
+}
+
+// Function to send the queued requests over the shared connection until the multiplexer closes
+fn dispatch(shared: &Shared, socket_path: &str, client: ProxyClient) {
+    let mut client = Some(client);
+    loop {
+        // Take the oldest request of the logical client whose turn it is
+        let (id, job) = {
+            let mut scheduler = shared.scheduler.lock().unwrap();
+            loop {
+                if scheduler.closed {
+                    return; // Dropping the queued jobs fails their requests
+                }
+                if let Some(id) = scheduler.turns.pop_front() {
+                    let Some(queue) = scheduler.queues.get_mut(&id) else { continue }; // Client dropped meanwhile
+                    let Some(job) = queue.jobs.pop_front() else { continue };
+                    queue.stats.wait_time += job.queued_at.elapsed();
+                    if !queue.jobs.is_empty() {
+                        scheduler.turns.push_back(id); // Back of the line for its next request
+                    }
+                    break (id, job);
+                }
+                scheduler = shared.work.wait(scheduler).unwrap();
+            }
+        };
+
+        if client.is_none() {
+            client = ProxyClient::connect(socket_path).ok();
+        }
+        let result = match client.as_mut() {
+            Some(connection) => connection.request(&job.request),
+            None => Err(not_delivered("proxy unreachable")),
+        };
+        if matches!(result, Err(ClientError::Io(_))) {
+            client = None; // Reconnect for the next request
+        }
+
+        if let Some(queue) = shared.scheduler.lock().unwrap().queues.get_mut(&id) {
+            match result {
+                Ok(ref response) if response.is_ok() => queue.stats.requests += 1,
+                Ok(_) => {
+                    queue.stats.requests += 1;
+                    queue.stats.errors += 1;
+                }
+                Err(_) => queue.stats.errors += 1,
+            }
+        }
+        let _ = job.reply.send(result); // The logical client may be gone
+    }
+}
+
+/// Handle of one logical client of a [`Multiplexer`]; may be moved to its own thread.
+pub struct LogicalClient {
+    id: u64,
+    shared: Arc<Shared>,
+}
+
+impl LogicalClient {
+    /// Queues a raw request and waits for its response.
+    pub fn request(&self, request: &Value) -> Result<ProxyResponse, ClientError> {
+        let (reply, response) = mpsc::channel();
+        {
+            let mut scheduler = self.shared.scheduler.lock().unwrap();
+            if scheduler.closed {
+                return Err(not_delivered("multiplexer closed"));
+            }
+            let queue = scheduler.queues.get_mut(&self.id).expect("Logical clients stay registered until dropped");
+            queue.jobs.push_back(Job { request: request.clone(), reply, queued_at: Instant::now() });
+            if queue.jobs.len() == 1 {
+                scheduler.turns.push_back(self.id); // Had nothing queued, so was not waiting for a turn
+            }
+        }
+        self.shared.work.notify_one();
+        response.recv().map_err(|_| not_delivered("multiplexer closed"))?
+    }
+
+    /// Stores a JSON value under a key.
+    pub fn set(&self, key: &str, value: &Value) -> Result<(), ClientError> {
+        self.read(&json!({"action": "set", "key": key, "value": value})).map(|_| ())
+    }
+
+    /// Reads the value stored under a key (`{"found", "value"}`).
+    pub fn get(&self, key: &str) -> Result<Value, ClientError> {
+        self.read(&json!({"action": "get", "key": key}))
+    }
+
+    /// Appends a JSON value to a stream capped at roughly `maxlen` entries, returning the entry id.
+    pub fn xadd(&self, key: &str, value: &Value, maxlen: Option<usize>) -> Result<String, ClientError> {
+        let data = self.read(&json!({"action": "xadd", "key": key, "value": value, "maxlen": maxlen}))?;
+        Ok(data["id"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Deletes a key.
+    pub fn del(&self, key: &str) -> Result<(), ClientError> {
+        self.read(&json!({"action": "del", "key": key})).map(|_| ())
+    }
+
+    /// Returns the counters of this logical client.
+    pub fn stats(&self) -> LogicalStats {
+        let scheduler = self.shared.scheduler.lock().unwrap();
+        let queue = &scheduler.queues[&self.id];
+        LogicalStats { queued: queue.jobs.len(), ..queue.stats.clone() }
+    }
+
+    // Function to send a request and return its payload, turning a proxy-side rejection into an error
+    fn read(&self, request: &Value) -> Result<Value, ClientError> {
+        let response = self.request(request)?;
+        if !response.is_ok() {
+            return Err(ClientError::Proxy(response.message));
+        }
+        Ok(response.data.unwrap_or(Value::Null))
+    }
+}
+
+impl Drop for LogicalClient {
+    fn drop(&mut self) {
+        self.shared.scheduler.lock().unwrap().queues.remove(&self.id);
+    }
+}
@@ -0,0 +1,151 @@
+// Import necessary crates and modules
+use clap::ValueEnum; // For selecting the drill on the command line
+use redis::{ConnectionAddr, ConnectionInfo}; // For resolving and connecting to the target
+use serde_json::{json, Value}; // For the drill report
+use std::sync::atomic::{AtomicBool, Ordering}; // For fast checks on the success path
+use std::sync::Mutex; // For the shared failure log
+use std::thread; // For waiting until the drill is due
+use std::time::{Duration, Instant}; // For drill timing
+
+// Define how often a waiting drill checks whether the run was stopped
+const STOP_POLL: Duration = Duration::from_millis(50);
+
+/// Disruptions a failover drill can cause.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Drill {
+    /// Block the server with DEBUG SLEEP
+    DebugSleep,
+    /// Stop the server with SHUTDOWN NOSAVE; recovery needs a supervisor restarting it
+    Shutdown,
+    /// Ask Sentinel to fail the master over to a replica
+    Sentinel,
+}
+
+/// Connects to the target, finding the current master through Sentinel when one is configured.
+pub struct Connector {
+    info: ConnectionInfo, // Target, or only its credentials and database when Sentinel is used
+    sentinel: Option<(ConnectionInfo, String)>, // Sentinel to ask and the monitored master's name
+    timeout: Option<Duration>, // Read/write timeout so a stalled server shows up as errors
+}
+
+impl Connector {
+    pub fn new(info: ConnectionInfo, sentinel: Option<(ConnectionInfo, String)>, timeout: Option<Duration>) -> Connector {
+        Connector { info, sentinel, timeout }
+    }
+
+    // Function to return the current target, asking Sentinel for the master's address if configured
+    fn resolve(&self) -> redis::RedisResult<ConnectionInfo> {
+        let Some((ref sentinel, ref master)) = self.sentinel else {
+            return Ok(self.info.clone());
+        };
+        let mut conn = redis::Client::open(sentinel.clone())?.get_connection()?;
+        let (host, port): (String, u16) = redis::cmd("SENTINEL").arg("get-master-addr-by-name").arg(master).query(&mut conn)?;
+        Ok(ConnectionInfo { addr: ConnectionAddr::Tcp(host, port), redis: self.info.redis.clone() })
+    }
+
+    /// Opens a new connection to the target.
+    pub fn connect(&self) -> redis::RedisResult<redis::Connection> {
+        let conn = redis::Client::open(self.resolve()?)?.get_connection()?;
+        conn.set_read_timeout(self.timeout)?;
+        conn.set_write_timeout(self.timeout)?;
+        Ok(conn)
+    }
+}
+
+// Define what the client observed around the disruption
+#[derive(Default)]
+struct FailureLog {
+    count: u64, // Failed commands
+    first: Option<Instant>, // First failure
+    last: Option<Instant>, // Latest failure
+    recovered: Option<Instant>, // First success after the latest failure
+}
+
+/// Tracks failed commands and recovery while a drill runs; load loops keep going after errors while one is attached.
+#[derive(Default)]
+pub struct Disruption {
+    failed: AtomicBool, // Set once anything failed, so successes normally skip the lock
+    log: Mutex<FailureLog>,
+    triggered: Mutex<Option<Result<Instant, String>>>, // When the drill fired, or why it could not
+}
+
+impl Disruption {
+    /// Records a failed command.
+    pub fn failure(&self) {
+        let now = Instant::now();
+        self.failed.store(true, Ordering::SeqCst);
+        let mut log = self.log.lock().unwrap();
+        log.count += 1;
+        log.first.get_or_insert(now);
+        log.last = Some(now);
+        log.recovered = None;
+    }
+
+    /// Records a successful command.
+    pub fn success(&self) {
+        if self.failed.load(Ordering::SeqCst) {
+            let mut log = self.log.lock().unwrap();
+            if log.recovered.is_none() {
+                log.recovered = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Waits until `at` after `start`, then runs the drill against the target (unless the run stopped first).
+    pub fn run_drill(&self, drill: Drill, start: Instant, at: Duration, target: &Connector, sleep_secs: u64, running: &AtomicBool) {
+        while start.elapsed() < at {
+            if !running.load(Ordering::SeqCst) {
+                return;
+            }
+            thread::sleep(STOP_POLL.min(at - start.elapsed()));
+        }
+        let fired = Instant::now();
+        let result = match drill {
+            Drill::DebugSleep => fire(target, redis::cmd("DEBUG").arg("SLEEP").arg(sleep_secs)),
+            Drill::Shutdown => match fire(target, redis::cmd("SHUTDOWN").arg("NOSAVE")) {
+                Err(err) if err.contains("connection") || err.contains("closed") => Ok(()), // The server exits instead of replying
+                other => other,
+            },
+            Drill::Sentinel => match target.sentinel {
+                Some((ref sentinel, ref master)) => redis::Client::open(sentinel.clone())
+                    .and_then(|c| c.get_connection())
+                    .and_then(|mut conn| redis::cmd("SENTINEL").arg("FAILOVER").arg(master).query::<()>(&mut conn))
+                    .map_err(|e| e.to_string()),
+                None => Err("the sentinel drill needs --sentinel".to_string()),
+            },
+        };
+        match result {
+            Ok(()) => println!("Failover drill fired at {:.2?}", fired - start),
+            Err(ref err) => eprintln!("Failover drill failed: {}", err),
+        }
+        *self.triggered.lock().unwrap() = Some(result.map(|_| fired));
+    }
+
+    /// Returns the error window and recovery time as seen by the client.
+    pub fn report(&self, drill: Drill, run_end: Instant) -> Value {
+        let log = self.log.lock().unwrap();
+        let secs = |from: Instant, to: Instant| (to.saturating_duration_since(from).as_secs_f64() * 1000.0).round() / 1000.0;
+        let fired = match *self.triggered.lock().unwrap() {
+            Some(Ok(fired)) => fired,
+            Some(Err(ref err)) => return json!({"drill": drill.to_possible_value().unwrap().get_name(), "error": err}),
+            None => return json!({"drill": drill.to_possible_value().unwrap().get_name(), "error": "run ended before the drill was due"}),
+        };
+        json!({
+            "drill": drill.to_possible_value().unwrap().get_name(),
+            "failed_commands": log.count,
+            "first_error_after_secs": log.first.map(|t| secs(fired, t)),
+            // From the first failure until commands succeeded again (or the run ended unrecovered)
+            "error_window_secs": log.first.map(|first| secs(first, log.recovered.unwrap_or(run_end))),
+            "recovered": log.first.is_none() || log.recovered.is_some(),
+            "recovery_secs": log.recovered.map(|t| secs(fired, t)),
+        })
+    }
+}
+
+// Function to send one drill command to the current target
+fn fire(target: &Connector, command: &redis::Cmd) -> Result<(), String> {
+    let mut conn = redis::Client::open(target.resolve().map_err(|e| e.to_string())?)
+        .and_then(|c| c.get_connection())
+        .map_err(|e| e.to_string())?;
+    command.query::<()>(&mut conn).map_err(|e| e.to_string())
+}
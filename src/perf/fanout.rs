@@ -0,0 +1,223 @@
+// Import necessary crates and modules
+use rustredis::client::{ClientError, ProxyClient}; // For writing and subscribing through the proxy
+use rustredis::latency::Histogram; // For recording write and fan-out latencies
+use serde_json::{json, Value}; // For the written values and the summary
+use std::io::ErrorKind; // For recognizing read timeouts
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering}; // For shared counters and stop flags
+use std::sync::Mutex; // For collecting the first error
+use std::thread::{self, sleep}; // For writer and subscriber threads and pacing
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH}; // For schedules and latencies
+
+// Define how long a subscriber waits for an event before checking whether to stop
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How the fan-out benchmark drives the proxy.
+pub struct FanoutConfig<'a> {
+    pub proxy: &'a str, // Address of the proxy
+    pub key: &'a str, // Key the writers set and the subscribers follow
+    pub rate: f64, // Sets per second over all writers
+    pub duration: Option<Duration>, // Stop writing after this long, or when `running` is cleared
+    pub writers: usize, // Writer connections sharing the rate
+    pub subscribers: usize, // Connections subscribed to the key
+    pub drain: Duration, // How long subscribers wait for outstanding events once the writers stopped
+    pub running: &'a AtomicBool, // Cleared on Ctrl-C
+}
+
+/// What the fan-out benchmark measured.
+pub struct FanoutOutcome {
+    pub writes: u64, // Sets the proxy accepted
+    pub elapsed: Duration, // Time the writers ran
+    pub write_latency: Histogram, // Time per set
+    pub fanout_latency: Histogram, // Time from sending a set until a subscriber received its event, over all subscribers
+    pub delivered: u64, // Events received, over all subscribers, without duplicates
+    pub duplicates: u64, // Events received more than once by the same subscriber
+    pub max_lost: u64, // Most events one subscriber missed
+    pub subscribers: usize, // Subscriptions held
+    pub error: Option<String>, // First error of a writer or subscriber
+}
+
+impl FanoutOutcome {
+    /// Returns the events that never reached a subscriber.
+    pub fn lost(&self) -> u64 {
+        (self.writes * self.subscribers as u64).saturating_sub(self.delivered)
+    }
+
+    /// Returns the counts, loss and latencies as JSON.
+    pub fn summary(&self) -> Value {
+        let expected = self.writes * self.subscribers as u64;
+        json!({
+            "subscribers": self.subscribers,
+            "writes": self.writes,
+            "elapsed_secs": self.elapsed.as_secs_f64(),
+            "throughput": self.writes as f64 / self.elapsed.as_secs_f64(),
+            "write_latency_us": self.write_latency.summary(),
+            "fanout_latency_us": self.fanout_latency.summary(),
+            "events_expected": expected,
+            "events_delivered": self.delivered,
+            "events_lost": self.lost(),
+            "loss_ratio": (expected > 0).then(|| self.lost() as f64 / expected as f64),
+            "max_lost_by_subscriber": self.max_lost,
+            "duplicates": self.duplicates,
+            "error": self.error,
+        })
+    }
+}
+
+// Define what one subscriber received
+struct Received {
+    seen: Vec<Vec<bool>>, // Sequence numbers received, by writer
+    delivered: u64, // Distinct events received
+    duplicates: u64, // Events received again
+    latency: Histogram, // Time from the set to the event
+}
+
+impl Received {
+    // Function to record an event; returns false for one received before
+    fn insert(&mut self, writer: usize, seq: usize) -> bool {
+        let seen = &mut self.seen[writer];
+        if seen.len() <= seq {
+            seen.resize(seq + 1, false);
+        }
+        if seen[seq] {
+            self.duplicates += 1;
+            return false;
+        }
+        seen[seq] = true;
+        self.delivered += 1;
+        true
+    }
+}
+
+// Function to return the wall clock in microseconds, which writers stamp into values and subscribers
+// compare with (both run in this process)
+fn now_us() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_micros() as u64).unwrap_or_default()
+}
+
+// Function to record the error ending a thread, keeping the first one
+fn record_error(error: &Mutex<Option<String>>, message: String) {
+    error.lock().unwrap().get_or_insert(message);
+}
+
+// Function to set the key at the writer's share of the rate until the run ends
+fn write(config: &FanoutConfig, writer: u64, mut client: ProxyClient, written: &AtomicU64, error: &Mutex<Option<String>>, start: Instant) -> Histogram {
+    let interval = Duration::from_secs_f64(config.writers as f64 / config.rate);
+    let mut latency = Histogram::new();
+    let mut due = interval.mul_f64(writer as f64 / config.writers as f64); // Writers take turns rather than sending together
+    let mut seq = 0;
+    while config.running.load(Ordering::SeqCst) && config.duration.is_none_or(|d| due < d) {
+        if let Some(wait) = due.checked_sub(start.elapsed()) {
+            sleep(wait);
+        }
+        let sent = Instant::now();
+        if let Err(e) = client.set(config.key, &json!({"writer": writer, "seq": seq, "sent_us": now_us()})) {
+            record_error(error, format!("writer {}: {}", writer, e));
+            break;
+        }
+        latency.record(sent.elapsed());
+        written.fetch_add(1, Ordering::SeqCst);
+        seq += 1;
+        due += interval;
+    }
+    latency
+}
+
+// Function to receive the key's events until every write arrived after the writers stopped, or `stop` is set
+fn subscribe(mut client: ProxyClient, writers: usize, written: &AtomicU64, writers_done: &AtomicBool, stop: &AtomicBool, error: &Mutex<Option<String>>) -> Received {
+    let mut received = Received { seen: vec![Vec::new(); writers], delivered: 0, duplicates: 0, latency: Histogram::new() };
+    while !stop.load(Ordering::SeqCst) {
+        if writers_done.load(Ordering::SeqCst) && received.delivered >= written.load(Ordering::SeqCst) {
+            break;
+        }
+        let event = match client.next_event() {
+            Ok(event) => event,
+            Err(ClientError::Io(ref e)) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => {
+                record_error(error, format!("subscriber: {}", e));
+                break;
+            }
+        };
+        let value = &event["value"];
+        let (Some(writer), Some(seq), Some(sent_us)) = (value["writer"].as_u64(), value["seq"].as_u64(), value["sent_us"].as_u64()) else {
+            continue; // Not one of the benchmark's sets
+        };
+        if writer as usize >= writers {
+            continue; // Set by another run's writers
+        }
+        if received.insert(writer as usize, seq as usize) {
+            received.latency.record(Duration::from_micros(now_us().saturating_sub(sent_us)));
+        }
+    }
+    received
+}
+
+/// Subscribes `subscribers` connections to the key, then sets it from `writers` connections at the
+/// rate and measures how long each set takes to reach every subscriber and how many never do.
+pub fn run(config: &FanoutConfig) -> Result<FanoutOutcome, String> {
+    // Every subscription is in place before the first write, so none misses the start
+    let mut subscriptions = Vec::with_capacity(config.subscribers);
+    for i in 0..config.subscribers {
+        let mut client = ProxyClient::connect(config.proxy).map_err(|e| format!("subscriber {}: {}", i, e))?;
+        client.subscribe(config.key, None).map_err(|e| format!("subscriber {}: {}", i, e))?;
+        client.set_read_timeout(Some(POLL_INTERVAL)).map_err(|e| format!("subscriber {}: {}", i, e))?;
+        subscriptions.push(client);
+    }
+    let writers = (0..config.writers)
+        .map(|i| ProxyClient::connect(config.proxy).map_err(|e| format!("writer {}: {}", i, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let written = AtomicU64::new(0);
+    let writers_done = AtomicBool::new(false);
+    let stop = AtomicBool::new(false);
+    let error = Mutex::new(None);
+    let start = Instant::now();
+    let (write_latencies, elapsed, received) = thread::scope(|scope| {
+        let subscribers: Vec<_> = subscriptions.into_iter()
+            .map(|client| {
+                let (written, writers_done, stop, error) = (&written, &writers_done, &stop, &error);
+                scope.spawn(move || subscribe(client, config.writers, written, writers_done, stop, error))
+            })
+            .collect();
+        let writers: Vec<_> = writers.into_iter().enumerate()
+            .map(|(i, client)| {
+                let (written, error) = (&written, &error);
+                scope.spawn(move || write(config, i as u64, client, written, error, start))
+            })
+            .collect();
+        let write_latencies: Vec<_> = writers.into_iter().map(|w| w.join().expect("Writer panicked")).collect();
+        let elapsed = start.elapsed();
+
+        // Events still on their way get the drain time to arrive; what hasn't by then counts as lost
+        writers_done.store(true, Ordering::SeqCst);
+        let draining = Instant::now();
+        while !subscribers.iter().all(|s| s.is_finished()) && draining.elapsed() < config.drain {
+            sleep(Duration::from_millis(10));
+        }
+        stop.store(true, Ordering::SeqCst);
+        let received: Vec<_> = subscribers.into_iter().map(|s| s.join().expect("Subscriber panicked")).collect();
+        (write_latencies, elapsed, received)
+    });
+
+    let writes = written.load(Ordering::SeqCst);
+    let mut outcome = FanoutOutcome {
+        writes,
+        elapsed,
+        write_latency: Histogram::new(),
+        fanout_latency: Histogram::new(),
+        delivered: 0,
+        duplicates: 0,
+        max_lost: 0,
+        subscribers: config.subscribers,
+        error: error.into_inner().unwrap(),
+    };
+    for latency in &write_latencies {
+        outcome.write_latency.merge(latency);
+    }
+    for subscriber in &received {
+        outcome.fanout_latency.merge(&subscriber.latency);
+        outcome.delivered += subscriber.delivered;
+        outcome.duplicates += subscriber.duplicates;
+        outcome.max_lost = outcome.max_lost.max(writes.saturating_sub(subscriber.delivered));
+    }
+    Ok(outcome)
+}
@@ -0,0 +1,131 @@
+// Import necessary crates and modules
+use super::keyspace::KeySpace; // For choosing keys
+use clap::ValueEnum; // For selecting the operation on the command line
+use redis::RedisResult; // For preloading errors
+use rustredis::rng::Rng; // For random choices
+use serde::Deserialize; // For selecting the operation in workload files
+
+// Preloaded random data table
+const PRELOADED_DATA: [&str; 5] = [
+    "Lorem ipsum dolor sit amet, consectetur adipiscing elit.",
+    "The quick brown fox jumps over the lazy dog.",
+    "Redis is an in-memory data structure store, used as a database, cache, and message broker.",
+    "High-performance computing requires efficient memory access patterns.",
+    "Unix domain sockets provide efficient interprocess communication.",
+];
+
+// Define how many keys one preload pipeline sets
+const PRELOAD_BATCH: u64 = 1000;
+
+/// What the workload does.
+#[derive(Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Operation {
+    /// SET KEY VALUE, mixed with GETs by --get-ratio
+    Set,
+    /// GET KEY of preloaded keys (and of missing ones by --miss-ratio)
+    Get,
+    /// INCR KEY
+    Incr,
+    /// HSET KEY value VALUE
+    Hset,
+    /// LPUSH KEY VALUE; lists grow for as long as the run lasts
+    Lpush,
+    /// SADD KEY VALUE
+    Sadd,
+    /// PUBLISH KEY VALUE, with the key as the channel
+    Publish,
+    /// GETs and SETs of preloaded keys, a --get-ratio of them GETs (half without it)
+    Mixed,
+}
+
+impl Operation {
+    /// Returns the command of the writes and the fraction of commands that are gets, given `--get-ratio`.
+    pub fn mix(self, get_ratio: Option<f64>) -> (OpKind, f64) {
+        match self {
+            Operation::Set => (OpKind::Set, get_ratio.unwrap_or(0.0)),
+            Operation::Get => (OpKind::Set, 1.0),
+            Operation::Incr => (OpKind::Incr, 0.0),
+            Operation::Hset => (OpKind::Hset, 0.0),
+            Operation::Lpush => (OpKind::Lpush, 0.0),
+            Operation::Sadd => (OpKind::Sadd, 0.0),
+            Operation::Publish => (OpKind::Publish, 0.0),
+            Operation::Mixed => (OpKind::Set, get_ratio.unwrap_or(0.5)),
+        }
+    }
+}
+
+/// Commands the workload sends.
+#[derive(Clone, Copy, PartialEq)]
+pub enum OpKind {
+    Set,
+    Get,
+    Incr,
+    Hset,
+    Lpush,
+    Sadd,
+    Publish,
+}
+
+/// One command of the workload.
+pub struct Op {
+    pub kind: OpKind, // Command to send
+    pub key: String, // Key (or channel) to write or get
+    pub value: &'static str, // Value to store or publish (unused by gets and incrs)
+}
+
+/// Produces the workload's commands; the same seed always produces the same sequence.
+pub struct OpGenerator {
+    rng: Rng, // Source of all random choices
+    key_space: KeySpace, // Keys to spread commands over
+    random_values: bool, // Whether values are drawn at random instead of in sequence
+    next_value: usize, // Position in the preloaded data for sequential values
+    write: OpKind, // Command of the commands that are not gets
+    get_ratio: f64, // Fraction of commands that are gets
+    miss_ratio: f64, // Fraction of gets sent to keys outside the key space
+}
+
+impl OpGenerator {
+    pub fn new(seed: u64, key_space: KeySpace, random_values: bool, write: OpKind, get_ratio: f64, miss_ratio: f64) -> OpGenerator {
+        OpGenerator { rng: Rng::new(seed), key_space, random_values, next_value: 0, write, get_ratio, miss_ratio }
+    }
+
+    /// Sets every key of the key space so that gets drawn from it hit; returns the number of keys set.
+    pub fn preload(&self, con: &mut redis::Connection) -> RedisResult<u64> {
+        let size = self.key_space.size();
+        for batch in (0..size).step_by(PRELOAD_BATCH as usize) {
+            let mut pipe = redis::pipe();
+            for index in batch..(batch + PRELOAD_BATCH).min(size) {
+                pipe.set(self.key_space.key(index), PRELOADED_DATA[index as usize % PRELOADED_DATA.len()]).ignore();
+            }
+            pipe.query::<()>(con)?;
+        }
+        Ok(size)
+    }
+
+    /// Returns the next command.
+    pub fn next_op(&mut self) -> Op {
+        // Only mixes with gets draw for them, so set-only workloads keep their sequence for a seed
+        if self.get_ratio > 0.0 && self.rng.next_f64() < self.get_ratio {
+            let key = if self.miss_ratio > 0.0 && self.rng.next_f64() < self.miss_ratio {
+                self.key_space.missing_key(&mut self.rng)
+            } else {
+                self.key_space.next_key(&mut self.rng)
+            };
+            return Op { kind: OpKind::Get, key, value: "" };
+        }
+        let key = self.key_space.next_key(&mut self.rng);
+        if self.write == OpKind::Incr {
+            return Op { kind: OpKind::Incr, key, value: "" };
+        }
+        let value = if self.random_values {
+            PRELOADED_DATA[self.rng.below(PRELOADED_DATA.len() as u64) as usize]
+        } else {
+            // Loop through preloaded data sequentially
+            let value = PRELOADED_DATA[self.next_value];
+            self.next_value = (self.next_value + 1) % PRELOADED_DATA.len();
+            value
+        };
+        Op { kind: self.write, key, value }
+    }
+}
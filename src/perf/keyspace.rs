@@ -0,0 +1,69 @@
+// Import necessary crates and modules
+use clap::ValueEnum; // For selecting the distribution on the command line
+use rustredis::rng::Rng; // For drawing keys
+use serde::Deserialize; // For selecting the distribution in workload files
+
+/// Distributions of accesses over a key space.
+#[derive(Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Distribution {
+    /// Every key is equally likely
+    Uniform,
+    /// A few hot keys get most accesses (Zipf, exponent 1)
+    Zipf,
+}
+
+/// Chooses keys `<prefix>:<n>` from a key space of a given size; a size of 1 always yields the prefix itself.
+pub struct KeySpace {
+    prefix: String, // Base key name
+    size: u64, // Number of distinct keys
+    cdf: Vec<f64>, // Cumulative Zipf probabilities, empty for uniform draws
+}
+
+impl KeySpace {
+    pub fn new(prefix: &str, size: u64, distribution: Distribution) -> KeySpace {
+        let cdf = match distribution {
+            Distribution::Uniform => Vec::new(),
+            Distribution::Zipf => {
+                let weights: Vec<f64> = (1..=size).map(|rank| 1.0 / rank as f64).collect();
+                let total: f64 = weights.iter().sum();
+                weights.iter()
+                    .scan(0.0, |acc, weight| {
+                        *acc += weight / total;
+                        Some(*acc)
+                    })
+                    .collect()
+            }
+        };
+        KeySpace { prefix: prefix.to_string(), size: size.max(1), cdf }
+    }
+
+    /// Returns the key with the given index.
+    pub fn key(&self, index: u64) -> String {
+        if self.size == 1 { self.prefix.clone() } else { format!("{}:{}", self.prefix, index) }
+    }
+
+    /// Returns the number of distinct keys.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Draws a key outside the key space, which the workload never sets.
+    pub fn missing_key(&self, rng: &mut Rng) -> String {
+        format!("{}:miss:{}", self.prefix, rng.hex(16))
+    }
+
+    /// Draws the next key.
+    pub fn next_key(&self, rng: &mut Rng) -> String {
+        if self.size == 1 {
+            return self.prefix.clone();
+        }
+        let index = if self.cdf.is_empty() {
+            rng.below(self.size)
+        } else {
+            let draw = rng.next_f64();
+            (self.cdf.partition_point(|p| *p < draw) as u64).min(self.size - 1)
+        };
+        self.key(index)
+    }
+}
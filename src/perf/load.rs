@@ -0,0 +1,368 @@
+// Import necessary crates and modules
+use super::failover::{Connector, Disruption}; // For connecting and surviving failover drills
+use super::generator::{Op, OpGenerator, OpKind}; // For the commands to send
+use clap::ValueEnum; // For selecting the loop mode on the command line
+use rustredis::latency::{Histogram, Timeline}; // For recording latencies
+use serde::Deserialize; // For selecting the loop mode in workload files
+use serde_json::{json, Value}; // For the cache summary
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering}; // For shared counters and stop flags
+use std::sync::{mpsc, Mutex}; // For handing scheduled commands to workers
+use std::thread::{self, sleep}; // For workers and pacing
+use std::time::{Duration, Instant}; // For schedules and latencies
+
+/// How commands are paced.
+#[derive(Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LoopMode {
+    /// Each command waits for the previous response, then the interval (measures service time)
+    Closed,
+    /// Commands are issued on a fixed schedule regardless of outstanding responses (measures response time at a given arrival rate)
+    Open,
+}
+
+/// When and how long to generate load.
+pub struct Pacing<'a> {
+    pub rate: f64, // Commands per second
+    pub pipeline: usize, // Commands sent together in one pipeline, paced as a group
+    pub ramp_to: Option<f64>, // Rate reached at the end of the duration, rising or falling linearly from `rate`
+    pub duration: Option<Duration>, // Stop after this long, or run until `running` is cleared
+    pub running: &'a AtomicBool, // Cleared on Ctrl-C
+    pub label: &'a str, // Key name shown in progress lines
+    pub disruption: Option<&'a Disruption>, // During failover drills errors are recorded here and the loop reconnects instead of stopping
+    pub interval: Option<&'a Mutex<Histogram>>, // Latencies since the last periodic report, when reports are printed
+}
+
+impl Pacing<'_> {
+    // Function to return the rate at a point of the run
+    fn rate_at(&self, elapsed: Duration) -> f64 {
+        match (self.ramp_to, self.duration) {
+            (Some(to), Some(duration)) => self.rate + (to - self.rate) * (elapsed.as_secs_f64() / duration.as_secs_f64()).min(1.0),
+            _ => self.rate,
+        }
+    }
+
+    // Function to record the latency of answered commands for the next periodic report
+    fn report(&self, latency: Duration, commands: usize) {
+        if let Some(interval) = self.interval {
+            let mut interval = interval.lock().unwrap();
+            for _ in 0..commands {
+                interval.record(latency);
+            }
+        }
+    }
+}
+
+/// What a load loop measured.
+pub struct Outcome {
+    pub count: u64, // Completed commands
+    pub elapsed: Duration, // Time from the first command until the loop ended
+    pub latency: Histogram, // Closed: time per command; open: time from the scheduled send to the response
+    pub timeline: Timeline, // The same latencies per second of the run
+    pub service_time: Option<Histogram>, // Open loop only: time from the actual send to the response
+    pub max_backlog: u64, // Open loop only: most commands ever waiting for a free connection
+    pub error: Option<String>, // Error that ended the run early
+    pub cache: CacheStats, // The same latencies split into writes, get hits and get misses
+}
+
+/// Latencies by command result, to compare cache hits with misses.
+#[derive(Default)]
+pub struct CacheStats {
+    pub sets: Histogram, // Sets and the other writes
+    pub hits: Histogram, // Gets that found their key
+    pub misses: Histogram, // Gets that found nothing
+}
+
+impl CacheStats {
+    // Function to record the latency of one answered command
+    fn record(&mut self, reply: Reply, latency: Duration) {
+        match reply {
+            Reply::Stored => self.sets.record(latency),
+            Reply::Hit => self.hits.record(latency),
+            Reply::Miss => self.misses.record(latency),
+        }
+    }
+
+    // Function to add the samples of another worker
+    fn merge(&mut self, other: &CacheStats) {
+        self.sets.merge(&other.sets);
+        self.hits.merge(&other.hits);
+        self.misses.merge(&other.misses);
+    }
+
+    /// Returns the fraction of gets that hit, or `None` without gets.
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let gets = self.hits.count() + self.misses.count();
+        (gets > 0).then(|| self.hits.count() as f64 / gets as f64)
+    }
+
+    /// Returns hit and miss counts, the hit ratio and the latencies of each result as JSON.
+    pub fn summary(&self) -> Value {
+        json!({
+            "gets": self.hits.count() + self.misses.count(),
+            "hits": self.hits.count(),
+            "misses": self.misses.count(),
+            "hit_ratio": self.hit_ratio(),
+            "set_latency_us": self.sets.summary(),
+            "hit_latency_us": self.hits.summary(),
+            "miss_latency_us": self.misses.summary(),
+            // Positive when misses are slower than hits
+            "miss_minus_hit_p50_us": self.misses.percentile(50.0) as i64 - self.hits.percentile(50.0) as i64,
+        })
+    }
+}
+
+// Define what an answered command returned
+#[derive(Clone, Copy)]
+enum Reply {
+    Stored, // A set or other write succeeded
+    Hit, // A get found its key
+    Miss, // A get found nothing
+}
+
+// Define how long a loop waits before reconnecting after a failed command during a drill
+const RECONNECT_DELAY: Duration = Duration::from_millis(100);
+
+// Define the length of the timeline buckets latency is also recorded in
+const TIMELINE_BUCKET: Duration = Duration::from_secs(1);
+
+// Function to print a progress line every 1000 commands, given the count after a batch of `added`
+fn progress(count: u64, added: u64, start: Instant, label: &str) {
+    if count / 1000 > (count - added) / 1000 {
+        println!(
+            "[{:.2?}] Sent {} commands on {} to Redis.",
+            start.elapsed(), count, label
+        );
+    }
+}
+
+// Function to queue one command in a pipeline
+fn queue(pipe: &mut redis::Pipeline, op: &Op) {
+    match op.kind {
+        OpKind::Set => pipe.set(&op.key, op.value),
+        OpKind::Get => pipe.get(&op.key),
+        OpKind::Incr => pipe.incr(&op.key, 1),
+        OpKind::Hset => pipe.hset(&op.key, "value", op.value),
+        OpKind::Lpush => pipe.lpush(&op.key, op.value),
+        OpKind::Sadd => pipe.sadd(&op.key, op.value),
+        OpKind::Publish => pipe.publish(&op.key, op.value),
+    };
+}
+
+// Function to send a batch of commands in one round trip (a single command is sent as is), connecting
+// first if there is no connection
+fn send(con: &mut Option<redis::Connection>, connector: &Connector, batch: &[Op]) -> redis::RedisResult<Vec<Reply>> {
+    if con.is_none() {
+        *con = Some(connector.connect()?);
+    }
+    let conn = con.as_mut().unwrap();
+    let mut pipe = redis::pipe();
+    for op in batch {
+        queue(&mut pipe, op);
+    }
+    let result = pipe.query::<Vec<redis::Value>>(conn).map(|values| {
+        batch.iter().zip(values).map(|(op, value)| match op.kind {
+            OpKind::Get if matches!(value, redis::Value::Nil) => Reply::Miss,
+            OpKind::Get => Reply::Hit,
+            _ => Reply::Stored,
+        }).collect()
+    });
+    if result.is_err() {
+        *con = None; // The connection may be broken or pointing at a demoted master
+    }
+    result
+}
+
+// Function to handle a failed command; returns whether the loop should stop
+fn handle_error(err: &redis::RedisError, pacing: &Pacing) -> bool {
+    match pacing.disruption {
+        Some(disruption) => {
+            disruption.failure();
+            sleep(RECONNECT_DELAY);
+            false
+        }
+        None => {
+            eprintln!("Error: {}", err);
+            true
+        }
+    }
+}
+
+/// Runs one closed loop per generator, each in its own thread on its own connection at an equal share
+/// of the rate, and merges what they measured.
+pub fn closed_loop(connector: &Connector, generators: &mut [OpGenerator], pacing: &Pacing) -> Outcome {
+    let threads = generators.len() as f64;
+    let share = Pacing { rate: pacing.rate / threads, ramp_to: pacing.ramp_to.map(|to| to / threads), ..*pacing };
+    let completed = AtomicU64::new(0); // Commands answered over all threads
+    let aborted = AtomicBool::new(false); // Set by the first thread hitting an error that ends the run
+    let start = Instant::now();
+
+    let histograms: Vec<_> = thread::scope(|scope| {
+        let workers: Vec<_> = generators.iter_mut().enumerate().map(|(i, ops)| {
+            let (share, completed, aborted) = (&share, &completed, &aborted);
+            scope.spawn(move || {
+                sleep(Duration::from_secs_f64(i as f64 / pacing.rate)); // Threads take turns rather than sending together
+                closed_worker(connector, ops, share, start, completed, aborted)
+            })
+        }).collect();
+        workers.into_iter().map(|w| w.join().expect("Worker panicked")).collect()
+    });
+
+    let mut latency = Histogram::new();
+    let mut timeline = Timeline::new(start, TIMELINE_BUCKET);
+    let mut cache = CacheStats::default();
+    let mut error = None;
+    for (worker_latency, worker_timeline, worker_cache, worker_error) in histograms {
+        latency.merge(&worker_latency);
+        timeline.merge(&worker_timeline);
+        cache.merge(&worker_cache);
+        error = error.or(worker_error);
+    }
+    Outcome { count: completed.load(Ordering::SeqCst), elapsed: start.elapsed(), latency, timeline, service_time: None, max_backlog: 0, error, cache }
+}
+
+// Function to send commands one at a time on a single connection, sleeping the interval after each
+// response; returns the latencies and the error that ended the run, counting commands in `completed`
+fn closed_worker(connector: &Connector, ops: &mut OpGenerator, pacing: &Pacing, start: Instant, completed: &AtomicU64, aborted: &AtomicBool) -> (Histogram, Timeline, CacheStats, Option<String>) {
+    let mut latency = Histogram::new();
+    let mut timeline = Timeline::new(start, TIMELINE_BUCKET);
+    let mut cache = CacheStats::default();
+    let mut error = None;
+    let mut con = None;
+
+    // Main loop: set the Redis key repeatedly at the specified rate
+    while pacing.running.load(Ordering::SeqCst) && !aborted.load(Ordering::SeqCst) && pacing.duration.is_none_or(|d| start.elapsed() < d) {
+        let batch: Vec<Op> = (0..pacing.pipeline).map(|_| ops.next_op()).collect();
+        let sent = Instant::now();
+        let replies = match send(&mut con, connector, &batch) {
+            Ok(replies) => replies,
+            Err(e) => {
+                if handle_error(&e, pacing) {
+                    aborted.store(true, Ordering::SeqCst);
+                    error = Some(e.to_string());
+                    break;
+                }
+                continue;
+            }
+        };
+        if let Some(disruption) = pacing.disruption {
+            disruption.success();
+        }
+        // Every command of a pipeline waited for the whole round trip
+        let took = sent.elapsed();
+        pacing.report(took, replies.len());
+        for reply in replies {
+            latency.record(took);
+            timeline.record(took);
+            cache.record(reply, took);
+        }
+
+        let added = batch.len() as u64;
+        progress(completed.fetch_add(added, Ordering::SeqCst) + added, added, start, pacing.label);
+
+        // Sleep to maintain the desired rate, a pipeline counting as its number of commands
+        sleep(Duration::from_secs_f64(batch.len() as f64 / pacing.rate_at(start.elapsed())));
+    }
+
+    (latency, timeline, cache, error)
+}
+
+/// Issues commands on a fixed schedule to up to `max_in_flight` connections; commands due while
+/// all connections are busy wait in a backlog, and that wait counts towards their latency.
+pub fn open_loop(connector: &Connector, ops: &mut OpGenerator, max_in_flight: usize, pacing: &Pacing) -> Outcome {
+    let (sender, receiver) = mpsc::channel::<(Instant, Vec<Op>)>();
+    let receiver = Mutex::new(receiver);
+    let started = AtomicU64::new(0); // Commands taken by a worker
+    let completed = AtomicU64::new(0); // Commands answered
+    let aborted = AtomicBool::new(false); // Set by the first worker hitting an error that ends the run
+    let error = Mutex::new(None);
+    let start = Instant::now();
+
+    let (histograms, max_backlog) = thread::scope(|scope| {
+        let workers: Vec<_> = (0..max_in_flight).map(|_| scope.spawn(|| {
+            let mut latency = Histogram::new();
+            let mut timeline = Timeline::new(start, TIMELINE_BUCKET);
+            let mut service_time = Histogram::new();
+            let mut cache = CacheStats::default();
+            let mut con = None;
+            loop {
+                let next = receiver.lock().unwrap().recv();
+                let Ok((scheduled, batch)) = next else { break }; // Schedule finished and backlog drained
+                if !pacing.running.load(Ordering::SeqCst) || aborted.load(Ordering::SeqCst) {
+                    break; // Abandon the backlog
+                }
+                started.fetch_add(batch.len() as u64, Ordering::SeqCst);
+                let sent = Instant::now();
+                let replies = match send(&mut con, connector, &batch) {
+                    Ok(replies) => replies,
+                    Err(e) => {
+                        if handle_error(&e, pacing) {
+                            aborted.store(true, Ordering::SeqCst);
+                            error.lock().unwrap().get_or_insert(e.to_string());
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                if let Some(disruption) = pacing.disruption {
+                    disruption.success();
+                }
+                let (service, response) = (sent.elapsed(), scheduled.elapsed());
+                pacing.report(response, replies.len());
+                for reply in replies {
+                    service_time.record(service);
+                    latency.record(response);
+                    timeline.record(response);
+                    cache.record(reply, response);
+                }
+                let added = batch.len() as u64;
+                progress(completed.fetch_add(added, Ordering::SeqCst) + added, added, start, pacing.label);
+            }
+            (latency, timeline, service_time, cache)
+        })).collect();
+
+        // Scheduler: each command (or pipeline) is due one interval at the current rate after the previous one, whether or not earlier ones were answered
+        let mut issued: u64 = 0;
+        let mut due = Duration::ZERO;
+        let mut max_backlog = 0;
+        while pacing.running.load(Ordering::SeqCst) && !aborted.load(Ordering::SeqCst) {
+            if pacing.duration.is_some_and(|d| due >= d) {
+                break;
+            }
+            if let Some(wait) = due.checked_sub(start.elapsed()) {
+                sleep(wait);
+            }
+            max_backlog = max_backlog.max(issued - started.load(Ordering::SeqCst)); // Due commands no worker took yet
+            let batch: Vec<Op> = (0..pacing.pipeline).map(|_| ops.next_op()).collect();
+            if sender.send((start + due, batch)).is_err() {
+                break; // All workers are gone
+            }
+            issued += pacing.pipeline as u64;
+            due += Duration::from_secs_f64(pacing.pipeline as f64 / pacing.rate_at(due));
+        }
+        drop(sender);
+
+        let histograms: Vec<_> = workers.into_iter().map(|w| w.join().expect("Worker panicked")).collect();
+        (histograms, max_backlog)
+    });
+
+    let mut latency = Histogram::new();
+    let mut timeline = Timeline::new(start, TIMELINE_BUCKET);
+    let mut service_time = Histogram::new();
+    let mut cache = CacheStats::default();
+    for (worker_latency, worker_timeline, worker_service_time, worker_cache) in &histograms {
+        latency.merge(worker_latency);
+        timeline.merge(worker_timeline);
+        service_time.merge(worker_service_time);
+        cache.merge(worker_cache);
+    }
+    Outcome {
+        count: completed.load(Ordering::SeqCst),
+        elapsed: start.elapsed(),
+        latency,
+        timeline,
+        service_time: Some(service_time),
+        max_backlog,
+        error: error.into_inner().unwrap(),
+        cache,
+    }
+}
@@ -0,0 +1,10 @@
+//! Building blocks of the performance test that only the perf tool uses.
+
+pub mod failover; // Failover drills and reconnection
+pub mod fanout; // Subscriber fan-out through the proxy under write load
+pub mod generator; // Commands of the workload
+pub mod keyspace; // Key choice over a key space with a configurable distribution
+pub mod load; // Closed- and open-loop load generation
+pub mod sampler; // CPU and memory sampling of the client and the Redis server
+pub mod server_stats; // INFO, SLOWLOG and LATENCY DOCTOR snapshots of the Redis server
+pub mod workload; // Multi-phase test plans read from YAML files
@@ -0,0 +1,133 @@
+// Import necessary crates and modules
+use redis::ConnectionInfo; // For connecting to the server sampled with INFO
+use serde_json::{json, Map, Value}; // For samples in results files
+use std::collections::HashMap; // For parsed INFO fields
+use std::sync::atomic::{AtomicBool, Ordering}; // For stopping the sampler
+use std::sync::Arc; // For sharing the stop flag
+use std::thread::{self, JoinHandle}; // For sampling in the background
+use std::time::{Duration, Instant}; // For the sampling interval
+use sysinfo::{get_current_pid, System}; // For the benchmark's own CPU and RSS
+
+// Define the INFO fields kept in every server sample
+const SERVER_FIELDS: [&str; 4] = ["used_memory", "connected_clients", "instantaneous_ops_per_sec", "total_commands_processed"];
+
+// Define how often the sampler checks whether it should stop
+const STOP_POLL: Duration = Duration::from_millis(50);
+
+/// Samples CPU and memory of the benchmark process and, optionally, the Redis server while a run is in progress.
+pub struct Sampler {
+    stop: Arc<AtomicBool>, // Set to end sampling
+    handle: JoinHandle<Vec<Value>>, // Sampling thread returning its samples
+}
+
+// Function to parse INFO output into field -> value
+fn parse_info(info: &str) -> HashMap<&str, &str> {
+    info.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.trim_end().split_once(':'))
+        .collect()
+}
+
+// Function to round to one decimal, keeping whole numbers as integers
+fn rounded(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < u64::MAX as f64 {
+        json!(value as u64)
+    } else {
+        json!((value * 10.0).round() / 10.0)
+    }
+}
+
+// Function to read the server's CPU seconds and selected INFO fields
+fn server_sample(conn: &mut redis::Connection) -> redis::RedisResult<(f64, Map<String, Value>)> {
+    let info: String = redis::cmd("INFO").query(conn)?;
+    let fields = parse_info(&info);
+    let number = |name: &str| fields.get(name).and_then(|v| v.parse::<f64>().ok());
+    let cpu_seconds = number("used_cpu_sys").unwrap_or(0.0) + number("used_cpu_user").unwrap_or(0.0);
+    let selected = SERVER_FIELDS.iter()
+        .filter_map(|name| number(name).map(|value| (name.to_string(), rounded(value))))
+        .collect();
+    Ok((cpu_seconds, selected))
+}
+
+impl Sampler {
+    /// Starts sampling every `interval`; the server is sampled through INFO when `server` is given.
+    pub fn start(interval: Duration, server: Option<ConnectionInfo>) -> Sampler {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_thread = stop.clone();
+        let handle = thread::spawn(move || {
+            let pid = get_current_pid().expect("Failed to get the benchmark's process id");
+            let mut system = System::new();
+            system.refresh_process(pid); // CPU usage is measured between two refreshes
+            let mut server_conn = server.and_then(|info| match redis::Client::open(info).and_then(|c| c.get_connection()) {
+                Ok(conn) => Some(conn),
+                Err(err) => {
+                    eprintln!("Server sampling disabled: {}", err);
+                    None
+                }
+            });
+            let mut last_server_cpu = None;
+
+            let start = Instant::now();
+            let mut samples = Vec::new();
+            let mut next = interval;
+            while !stop_thread.load(Ordering::SeqCst) {
+                if start.elapsed() < next {
+                    thread::sleep(STOP_POLL.min(next - start.elapsed()));
+                    continue;
+                }
+                let elapsed = start.elapsed().as_secs_f64();
+                next += interval;
+
+                system.refresh_process(pid);
+                let mut sample = json!({"t": (elapsed * 1000.0).round() / 1000.0});
+                if let Some(process) = system.process(pid) {
+                    sample["cpu_percent"] = rounded(process.cpu_usage() as f64);
+                    sample["rss_bytes"] = json!(process.memory());
+                }
+
+                if let Some(conn) = server_conn.as_mut() {
+                    match server_sample(conn) {
+                        Ok((cpu_seconds, mut fields)) => {
+                            if let Some((last_t, last_cpu)) = last_server_cpu {
+                                let percent: f64 = (cpu_seconds - last_cpu) / (elapsed - last_t) * 100.0;
+                                fields.insert("cpu_percent".to_string(), rounded(percent));
+                            }
+                            last_server_cpu = Some((elapsed, cpu_seconds));
+                            sample["server"] = Value::Object(fields);
+                        }
+                        Err(err) => eprintln!("Failed to sample server INFO: {}", err),
+                    }
+                }
+                samples.push(sample);
+            }
+            samples
+        });
+        Sampler { stop, handle }
+    }
+
+    /// Stops sampling and returns the samples with averages and peaks.
+    pub fn finish(self) -> Value {
+        self.stop.store(true, Ordering::SeqCst);
+        let samples = self.handle.join().expect("Sampler thread panicked");
+
+        let series = |pointer: &str| -> Vec<f64> { samples.iter().filter_map(|s| s.pointer(pointer)?.as_f64()).collect() };
+        let average = |values: &[f64]| if values.is_empty() { Value::Null } else { rounded(values.iter().sum::<f64>() / values.len() as f64) };
+        let peak = |values: &[f64]| values.iter().cloned().reduce(f64::max).map_or(Value::Null, rounded);
+
+        let client_cpu = series("/cpu_percent");
+        let client_rss = series("/rss_bytes");
+        let server_cpu = series("/server/cpu_percent");
+        let server_memory = series("/server/used_memory");
+        json!({
+            "summary": {
+                "client_cpu_percent_avg": average(&client_cpu),
+                "client_cpu_percent_max": peak(&client_cpu),
+                "client_rss_bytes_max": peak(&client_rss),
+                "server_cpu_percent_avg": average(&server_cpu),
+                "server_cpu_percent_max": peak(&server_cpu),
+                "server_used_memory_max": peak(&server_memory),
+            },
+            "samples": samples,
+        })
+    }
+}
@@ -0,0 +1,119 @@
+// Import necessary crates and modules
+use redis::ConnectionInfo; // For connecting to the captured server
+use serde_json::{json, Map, Value}; // For snapshots in results files
+use std::sync::atomic::{AtomicBool, Ordering}; // For stopping periodic captures
+use std::sync::Arc; // For sharing the stop flag
+use std::thread::{self, JoinHandle}; // For capturing during the run
+use std::time::{Duration, Instant}; // For the capture interval
+
+// Define how many slow log entries are captured per snapshot
+const SLOWLOG_ENTRIES: usize = 128;
+
+// Define how often the capture thread checks whether it should stop
+const STOP_POLL: Duration = Duration::from_millis(50);
+
+/// Captures INFO, SLOWLOG and LATENCY DOCTOR snapshots before, during and after a run.
+pub struct ServerStats {
+    info: ConnectionInfo, // Server to capture, reconnected for the final snapshot
+    before: Value, // Snapshot taken when the run started
+    stop: Arc<AtomicBool>, // Set to end periodic captures
+    handle: Option<JoinHandle<Vec<Value>>>, // Periodic capture thread, if an interval was given
+}
+
+// Function to parse INFO output into section -> field -> value
+fn parse_info(info: &str) -> Value {
+    let mut sections = Map::new();
+    let mut current = "default".to_string();
+    for line in info.lines().map(str::trim_end).filter(|line| !line.is_empty()) {
+        if let Some(section) = line.strip_prefix("# ") {
+            current = section.to_lowercase();
+        } else if let Some((field, value)) = line.split_once(':') {
+            let value = value.parse::<i64>().map(Value::from)
+                .or_else(|_| value.parse::<f64>().map(Value::from))
+                .unwrap_or_else(|_| Value::from(value));
+            sections.entry(current.clone()).or_insert_with(|| json!({}))[field] = value;
+        }
+    }
+    Value::Object(sections)
+}
+
+// Function to convert a Redis reply to JSON
+fn reply_to_json(reply: redis::Value) -> Value {
+    match reply {
+        redis::Value::Nil => Value::Null,
+        redis::Value::Int(n) => json!(n),
+        redis::Value::Data(bytes) => json!(String::from_utf8_lossy(&bytes)),
+        redis::Value::Bulk(items) => Value::Array(items.into_iter().map(reply_to_json).collect()),
+        redis::Value::Status(status) => json!(status),
+        redis::Value::Okay => json!("OK"),
+    }
+}
+
+// Function to run one command for a snapshot, recording failures (e.g. disabled commands) in place of the reply
+fn capture_command(conn: &mut redis::Connection, command: &redis::Cmd) -> Value {
+    match command.query::<redis::Value>(conn) {
+        Ok(reply) => reply_to_json(reply),
+        Err(err) => json!({"error": err.to_string()}),
+    }
+}
+
+// Function to take one snapshot of the server
+fn snapshot(conn: &mut redis::Connection) -> Value {
+    let info = match redis::cmd("INFO").arg("everything").query::<String>(conn) {
+        Ok(info) => parse_info(&info),
+        Err(err) => json!({"error": err.to_string()}),
+    };
+    json!({
+        "info": info,
+        "slowlog": capture_command(conn, redis::cmd("SLOWLOG").arg("GET").arg(SLOWLOG_ENTRIES)),
+        "latency_doctor": capture_command(conn, redis::cmd("LATENCY").arg("DOCTOR")),
+    })
+}
+
+// Function to connect to the captured server
+fn connect(info: &ConnectionInfo) -> redis::RedisResult<redis::Connection> {
+    redis::Client::open(info.clone())?.get_connection()
+}
+
+impl ServerStats {
+    /// Takes the "before" snapshot and, if `interval` is given, starts capturing periodically.
+    pub fn start(info: ConnectionInfo, interval: Option<Duration>) -> redis::RedisResult<ServerStats> {
+        let before = snapshot(&mut connect(&info)?);
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = match interval {
+            Some(interval) => {
+                let mut conn = connect(&info)?;
+                let stop_thread = stop.clone();
+                Some(thread::spawn(move || {
+                    let start = Instant::now();
+                    let mut snapshots = Vec::new();
+                    let mut next = interval;
+                    while !stop_thread.load(Ordering::SeqCst) {
+                        if start.elapsed() < next {
+                            thread::sleep(STOP_POLL.min(next - start.elapsed()));
+                            continue;
+                        }
+                        next += interval;
+                        let mut taken = snapshot(&mut conn);
+                        taken["t"] = json!((start.elapsed().as_secs_f64() * 1000.0).round() / 1000.0);
+                        snapshots.push(taken);
+                    }
+                    snapshots
+                }))
+            }
+            None => None,
+        };
+        Ok(ServerStats { info, before, stop, handle })
+    }
+
+    /// Stops periodic captures, takes the "after" snapshot and returns all of them.
+    pub fn finish(self) -> Value {
+        self.stop.store(true, Ordering::SeqCst);
+        let during = self.handle.map(|h| h.join().expect("Server stats thread panicked")).unwrap_or_default();
+        let after = match connect(&self.info) {
+            Ok(mut conn) => snapshot(&mut conn),
+            Err(err) => json!({"error": err.to_string()}),
+        };
+        json!({"before": self.before, "during": during, "after": after})
+    }
+}
@@ -0,0 +1,124 @@
+// Import necessary crates and modules
+use super::generator::Operation; // For per-phase operations
+use super::keyspace::Distribution; // For per-phase key distributions
+use super::load::LoopMode; // For per-phase loop modes
+use serde::Deserialize; // For reading workload files
+use serde_json::Value; // For checking run results
+
+/// A test plan: phases run one after the other against every target.
+///
+/// ```yaml
+/// phases:
+///   - name: preload
+///     type: preload
+///     key_space: 10000
+///   - name: ramp
+///     rate: 100
+///     ramp_to: 1000
+///     duration: 30
+///   - name: steady
+///     rate: 1000
+///     duration: 60
+///     op: mixed
+///     get_ratio: 0.8
+///     miss_ratio: 0.1
+///     assert:
+///       max_p99_us: 2000
+///       min_hit_ratio: 0.85
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workload {
+    pub phases: Vec<Phase>,
+}
+
+/// What a phase does.
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PhaseKind {
+    /// Generate load with the phase's settings
+    #[default]
+    Run,
+    /// Set every key of the key space once, so that later gets hit
+    Preload,
+}
+
+/// One phase of a workload; settings it leaves out are taken from the command line.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Phase {
+    pub name: String, // Shown in output and results
+    #[serde(rename = "type", default)]
+    pub kind: PhaseKind,
+    pub rate: Option<f64>, // Commands per second
+    pub ramp_to: Option<f64>, // Rate reached at the end of the phase
+    pub duration: Option<f64>, // Seconds the phase runs
+    pub key: Option<String>, // Base key name
+    pub key_space: Option<u64>, // Number of distinct keys
+    pub key_distribution: Option<Distribution>,
+    pub random_values: Option<bool>,
+    pub op: Option<Operation>, // Command to send
+    pub get_ratio: Option<f64>, // Fraction of commands that are gets
+    pub miss_ratio: Option<f64>, // Fraction of gets sent to keys that were never set
+    pub loop_mode: Option<LoopMode>,
+    pub max_in_flight: Option<usize>,
+    pub pipeline: Option<usize>, // Commands sent together in one round trip
+    pub threads: Option<usize>, // Closed-loop threads sharing the rate
+    #[serde(rename = "assert", default)]
+    pub assertions: Assertions,
+}
+
+/// Limits every run of a phase must meet; a run that ended with an error fails unless `allow_errors` is set.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Assertions {
+    pub max_p50_us: Option<u64>,
+    pub max_p99_us: Option<u64>,
+    pub max_p999_us: Option<u64>,
+    pub min_throughput: Option<f64>, // Commands per second
+    pub min_hit_ratio: Option<f64>, // Fraction of gets that hit
+    #[serde(default)]
+    pub allow_errors: bool,
+}
+
+impl Workload {
+    /// Reads a workload file.
+    pub fn load(path: &str) -> Result<Workload, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("failed to read workload {}: {}", path, e))?;
+        let workload: Workload = serde_yaml::from_str(&text).map_err(|e| format!("invalid workload {}: {}", path, e))?;
+        if workload.phases.is_empty() {
+            return Err(format!("workload {} has no phases", path));
+        }
+        Ok(workload)
+    }
+}
+
+impl Assertions {
+    /// Returns a description of every limit a run's results break.
+    pub fn check(&self, run: &Value) -> Vec<String> {
+        let mut failures = Vec::new();
+        let target = run["target"].as_str().unwrap_or_default();
+        if let (Some(error), false) = (run["error"].as_str(), self.allow_errors) {
+            failures.push(format!("{}: run ended with an error: {}", target, error));
+        }
+        let latencies = [("p50", self.max_p50_us), ("p99", self.max_p99_us), ("p99.9", self.max_p999_us)];
+        for (percentile, limit) in latencies {
+            let value = run["latency_us"][percentile].as_u64().unwrap_or_default();
+            if let Some(limit) = limit.filter(|limit| value > *limit) {
+                failures.push(format!("{}: {} latency {}us above {}us", target, percentile, value, limit));
+            }
+        }
+        let throughput = run["throughput"].as_f64().unwrap_or_default();
+        if let Some(limit) = self.min_throughput.filter(|limit| throughput < *limit) {
+            failures.push(format!("{}: throughput {:.1}/s below {}/s", target, throughput, limit));
+        }
+        if let Some(limit) = self.min_hit_ratio {
+            match run["cache"]["hit_ratio"].as_f64() {
+                Some(ratio) if ratio < limit => failures.push(format!("{}: hit ratio {:.3} below {}", target, ratio, limit)),
+                Some(_) => {}
+                None => failures.push(format!("{}: no gets to measure the hit ratio of", target)),
+            }
+        }
+        failures
+    }
+}
@@ -0,0 +1,186 @@
+// Import necessary crates and modules
+use crate::client::{ClientError, ProxyClient, DEFAULT_SOCKET_PATH}; // For the underlying connection
+use serde_json::{json, Value}; // For building requests
+use std::collections::VecDeque; // For the writes waiting for the proxy
+use std::time::{Duration, Instant}; // For pacing reconnection attempts
+
+/// Connectivity of a [`ReconnectingClient`], reported to its state change callback.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected, // Connected to the proxy; buffered writes go out with the next call
+    Disconnected, // Proxy unreachable; writes are buffered
+}
+
+/// What happened to a write.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Delivery {
+    Sent(Option<Value>), // Accepted by the proxy, with the payload of its response
+    Buffered, // Kept until the proxy is reachable again
+}
+
+/// [`ProxyClient`] that survives proxy restarts: while the socket is down, writes are kept in
+/// a bounded buffer (dropping the oldest when full) and delivered in order once a reconnection
+/// succeeds. Reconnection is attempted on the next call after the retry interval has passed.
+pub struct ReconnectingClient {
+    socket_path: String,
+    client: Option<ProxyClient>, // Live connection, if any
+    buffer: VecDeque<Value>, // Writes waiting for the proxy, oldest first
+    max_buffered: usize,
+    retry_interval: Duration,
+    next_attempt: Instant, // Earliest time of the next reconnection attempt
+    dropped: u64, // Buffered writes dropped because the buffer was full
+    on_state_change: Option<Box<dyn FnMut(ConnectionState) + Send>>,
+}
+
+impl ReconnectingClient {
+    /// Creates a client for a proxy socket; the first connection is made on the first call.
+    pub fn new(socket_path: &str) -> Self {
+        ReconnectingClient {
+            socket_path: socket_path.to_string(),
+            client: None,
+            buffer: VecDeque::new(),
+            max_buffered: 10_000,
+            retry_interval: Duration::from_secs(1),
+            next_attempt: Instant::now(),
+            dropped: 0,
+            on_state_change: None,
+        }
+    }
+
+    /// Creates a client for the proxy's default socket.
+    pub fn new_default() -> Self {
+        Self::new(DEFAULT_SOCKET_PATH)
+    }
+
+    /// Sets how many writes are kept while the proxy is unreachable (default 10000).
+    pub fn max_buffered(mut self, max_buffered: usize) -> Self {
+        self.max_buffered = max_buffered;
+        self
+    }
+
+    /// Sets the least time between reconnection attempts (default 1s).
+    pub fn retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Registers a callback run whenever the client connects to or loses the proxy.
+    pub fn on_state_change(mut self, callback: impl FnMut(ConnectionState) + Send + 'static) -> Self {
+        self.on_state_change = Some(Box::new(callback));
+        self
+    }
+
+    /// Returns the current connectivity.
+    pub fn state(&self) -> ConnectionState {
+        if self.client.is_some() { ConnectionState::Connected } else { ConnectionState::Disconnected }
+    }
+
+    /// Returns the number of writes waiting for the proxy.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns the number of writes dropped because the buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Stores a JSON value under a key, or buffers it while the proxy is unreachable.
+    pub fn set(&mut self, key: &str, value: &Value) -> Result<Delivery, ClientError> {
+        self.write(json!({"action": "set", "key": key, "value": value}))
+    }
+
+    /// Appends a JSON value to a stream, or buffers it while the proxy is unreachable.
+    pub fn xadd(&mut self, key: &str, value: &Value, maxlen: Option<usize>) -> Result<Delivery, ClientError> {
+        self.write(json!({"action": "xadd", "key": key, "value": value, "maxlen": maxlen}))
+    }
+
+    /// Deletes a key, or buffers the deletion while the proxy is unreachable.
+    pub fn del(&mut self, key: &str) -> Result<Delivery, ClientError> {
+        self.write(json!({"action": "del", "key": key}))
+    }
+
+    /// Reads the value stored under a key; fails while the proxy is unreachable.
+    pub fn get(&mut self, key: &str) -> Result<Value, ClientError> {
+        let result = match self.connected()? {
+            Some(client) => client.get(key, false),
+            None => Err(ClientError::Io(std::io::Error::new(std::io::ErrorKind::NotConnected, "proxy unreachable"))),
+        };
+        if let Err(ref err) = result {
+            self.check_lost(err);
+        }
+        result
+    }
+
+    /// Sends a write request (any request the proxy acknowledges, such as set, sadd or xadd),
+    /// buffering it while the proxy is unreachable. Proxy rejections are returned, not buffered;
+    /// a rejected buffered write is reported by the call that delivers it.
+    pub fn write(&mut self, request: Value) -> Result<Delivery, ClientError> {
+        self.buffer.push_back(request); // Queued behind earlier writes to keep their order
+        if self.buffer.len() > self.max_buffered {
+            self.buffer.pop_front();
+            self.dropped += 1;
+        }
+        let last = self.deliver()?;
+        Ok(if self.buffer.is_empty() { Delivery::Sent(last) } else { Delivery::Buffered })
+    }
+
+    /// Delivers the buffered writes if the proxy is reachable; returns how many remain buffered.
+    pub fn flush(&mut self) -> Result<usize, ClientError> {
+        self.deliver()?;
+        Ok(self.buffer.len())
+    }
+
+    // Function to send buffered writes in order until the buffer is empty or the proxy is lost;
+    // returns the response payload of the last write delivered
+    fn deliver(&mut self) -> Result<Option<Value>, ClientError> {
+        let mut last = None;
+        while let Some(request) = self.buffer.front().cloned() {
+            let Some(client) = self.connected()? else { break };
+            match client.request(&request) {
+                Ok(response) => {
+                    self.buffer.pop_front();
+                    if !response.is_ok() {
+                        return Err(ClientError::Proxy(response.message)); // Retrying would be rejected again
+                    }
+                    last = response.data;
+                }
+                Err(err) => {
+                    self.check_lost(&err);
+                    break; // Stays buffered for the next attempt
+                }
+            }
+        }
+        Ok(last)
+    }
+
+    // Function to return the live connection, reconnecting if the retry interval has passed
+    fn connected(&mut self) -> Result<Option<&mut ProxyClient>, ClientError> {
+        if self.client.is_none() && Instant::now() >= self.next_attempt {
+            match ProxyClient::connect(&self.socket_path) {
+                Ok(client) => {
+                    self.client = Some(client);
+                    self.notify(ConnectionState::Connected);
+                }
+                Err(ClientError::Io(_)) => self.next_attempt = Instant::now() + self.retry_interval,
+                Err(err) => return Err(err), // The proxy refused the handshake; retrying won't help
+            }
+        }
+        Ok(self.client.as_mut())
+    }
+
+    // Function to drop the connection after an I/O failure
+    fn check_lost(&mut self, err: &ClientError) {
+        if matches!(err, ClientError::Io(_)) && self.client.take().is_some() {
+            self.next_attempt = Instant::now() + self.retry_interval;
+            self.notify(ConnectionState::Disconnected);
+        }
+    }
+
+    // Function to report a change of connectivity
+    fn notify(&mut self, state: ConnectionState) {
+        if let Some(ref mut callback) = self.on_state_change {
+            callback(state);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+// Import necessary crates and modules
+use std::collections::hash_map::RandomState; // For seeds from OS randomness
+use std::hash::{BuildHasher, Hasher}; // For drawing from RandomState
+use std::time::{SystemTime, UNIX_EPOCH}; // For mixing the clock into entropy seeds
+
+/// Small seedable pseudo-random generator (SplitMix64). The same seed always yields the
+/// same sequence, which makes benchmark workloads reproducible; it is not cryptographically secure.
+#[derive(Clone)]
+pub struct Rng {
+    state: u64, // Advanced by a fixed increment per draw
+}
+
+/// Returns a seed drawn from OS randomness, for runs that were not given one.
+pub fn entropy_seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos());
+    hasher.finish()
+}
+
+impl Rng {
+    /// Creates a generator producing the sequence of `seed`.
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Returns the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number uniformly distributed in `0..bound` (`bound` must not be 0).
+    pub fn below(&mut self, bound: u64) -> u64 {
+        // Multiply-shift keeps the bias negligible for the bounds benchmarks use
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+
+    /// Returns a number uniformly distributed in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns `digits` random lowercase hex digits.
+    pub fn hex(&mut self, digits: usize) -> String {
+        let mut text = String::with_capacity(digits + 16);
+        while text.len() < digits {
+            text.push_str(&format!("{:016x}", self.next_u64()));
+        }
+        text.truncate(digits);
+        text
+    }
+}
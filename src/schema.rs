@@ -0,0 +1,487 @@
+// Import necessary crates and modules
+use lazy_static::lazy_static; // For defining static variables initialized at runtime
+use regex::Regex; // For regular expression matching
+use serde_json::Value; // For working with JSON values
+use std::collections::HashMap; // For using HashMap data structure
+use std::path::Path; // For locating schema files
+use std::sync::{Arc, RwLock}; // For the schema sets swapped in at runtime, kept alive while in use
+
+// Define the key grammar and value schemas shared by the proxy and the tools around it
+lazy_static! {
+    pub static ref VALID_PRODUCERS: Vec<&'static str> = vec!["DiskUsage", "ModemWatcher", "Psmon", "SerialPort"]; // Valid producers
+    pub static ref VALID_OBJECTS: Vec<&'static str> = vec!["object1", "object2"]; // Valid objects
+    pub static ref KEY_PATTERN: Regex = generate_key_pattern(); // Compiled regex pattern for key validation
+    pub static ref SCHEMAS: HashMap<&'static str, serde_json::Value> = { // JSON schemas for validating values
+        let mut m = HashMap::new();
+        m.insert("cs:DiskUsage:object1", serde_json::json!({
+            "type": "object",
+            "properties": {
+                "version": {"type": "number"},
+                "disk": {"type": "string"},
+                "usage": {"type": "number"}
+            },
+            "required": ["version", "disk", "usage"]
+        }));
+        m.insert("cs:ModemWatcher:object2", serde_json::json!({
+            "type": "object",
+            "properties": {
+                "version": {"type": "number"},
+                "status": {"type": "string"},
+                "signal_strength": {"type": "integer"}
+            },
+            "required": ["version", "status", "signal_strength"]
+        }));
+        m
+    };
+    static ref BUILT_IN: HashMap<&'static str, Arc<Value>> = SCHEMAS.iter().map(|(base, schema)| (*base, Arc::new(schema.clone()))).collect(); // Built-in schemas as lookups return them
+}
+
+// Define a set of schemas loaded from a schema directory or staged through the proxy
+struct LoadedSchemas {
+    active: HashMap<String, Arc<Value>>, // Enforced, taking precedence over the built-in ones
+    shadow: HashMap<String, Arc<Value>>, // Candidates checked alongside the active schema without rejecting anything
+}
+
+impl LoadedSchemas {
+    // Function to build a set from active and shadow schemas by base key
+    fn new(active: HashMap<String, Value>, shadow: HashMap<String, Value>) -> Arc<Self> {
+        let shared = |schemas: HashMap<String, Value>| schemas.into_iter().map(|(base, schema)| (base, Arc::new(schema))).collect();
+        Arc::new(LoadedSchemas { active: shared(active), shadow: shared(shadow) })
+    }
+}
+
+// Define the schema sets in use: the enforced one, one staged for the next swap and the one the last swap
+// replaced, kept for a rollback. A set is freed once it left every slot and no lookup holds its schemas
+struct SchemaSlots {
+    active: Option<Arc<LoadedSchemas>>, // None until schemas are loaded: only the built-in ones apply
+    staged: Option<Arc<LoadedSchemas>>,
+    previous: Option<Option<Arc<LoadedSchemas>>>, // Set while the last swap can be rolled back
+    generation: u64, // Bumped by every load, swap and rollback
+}
+
+static SLOTS: RwLock<SchemaSlots> = RwLock::new(SchemaSlots { active: None, staged: None, previous: None, generation: 0 });
+
+/// Summary of the schema sets in use, for status reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaStatus {
+    pub generation: u64, // Bumped by every load, swap and rollback
+    pub active: usize, // Schemas of the enforced set (active and shadow), 0 if only the built-in ones apply
+    pub staged: Option<usize>, // Schemas of the staged set, if any
+    pub rollback: bool, // Whether the last swap can be rolled back
+}
+
+// Function to name the file holding the schema of a base key in a schema directory
+// (cs:DiskUsage:object1 -> DiskUsage.object1.json, or DiskUsage.object1.shadow.json for a shadow schema)
+pub fn schema_file_name(base_key: &str, shadow: bool) -> String {
+    let name = base_key.trim_start_matches("cs:").replace(':', ".");
+    if shadow { format!("{}.shadow.json", name) } else { format!("{}.json", name) }
+}
+
+// Function to check that a schema compiles, naming where it came from in the error
+fn compile_check(origin: &str, schema: &Value) -> Result<(), String> {
+    jsonschema::JSONSchema::compile(schema).map(drop).map_err(|e| format!("invalid schema in {}: {}", origin, e))
+}
+
+/// Active and shadow schemas by base key, as read from a schema directory.
+pub type SchemaMaps = (HashMap<String, Value>, HashMap<String, Value>);
+
+// Function to read every <producer>.<object>[.shadow].json schema of a directory, as (active, shadow) schemas by base key
+pub fn read_schema_dir(dir: &Path) -> Result<SchemaMaps, String> {
+    let (mut active, mut shadow) = (HashMap::new(), HashMap::new());
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("failed to read schema directory {}: {}", dir.display(), e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        let Some(stem) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".json")) else {
+            continue; // Not a schema file
+        };
+        let (stem, is_shadow) = match stem.strip_suffix(".shadow") {
+            Some(stem) => (stem, true),
+            None => (stem, false),
+        };
+        let Some((producer, object)) = stem.split_once('.').filter(|(_, object)| !object.contains('.')) else {
+            continue; // Not named <producer>.<object>[.shadow].json
+        };
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let schema: Value = serde_json::from_str(&text).map_err(|e| format!("invalid JSON in {}: {}", path.display(), e))?;
+        compile_check(&path.display().to_string(), &schema)?;
+        let schemas = if is_shadow { &mut shadow } else { &mut active };
+        schemas.insert(format!("cs:{}:{}", producer, object), schema);
+    }
+    Ok((active, shadow))
+}
+
+/// One field of an object type in the concise constraint syntax, compiled into its JSON schema by
+/// [`compile_constraints`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldConstraint {
+    pub base: String, // Base key of the object type (cs:<producer>:<object>)
+    pub field: String, // Top-level field of the object
+    pub kind: String, // JSON type: string, number, integer, boolean, object or array
+    pub min: Option<f64>, // Lowest number, or shortest string or array
+    pub max: Option<f64>, // Highest number, or longest string or array
+    pub values: Option<Vec<Value>>, // The only values allowed
+    pub optional: bool, // Whether objects may leave the field out
+}
+
+// Define the JSON types a constraint may give a field
+const CONSTRAINT_TYPES: [&str; 6] = ["string", "number", "integer", "boolean", "object", "array"];
+
+impl FieldConstraint {
+    // Function to parse a constraint of the form BASE_KEY=FIELD:TYPE[,min=N][,max=N][,enum=A|B|C][,optional],
+    // e.g. cs:ModemWatcher:object2=signal_strength:integer,min=-120,max=0
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (base, rest) = spec.split_once('=').ok_or("expected BASE_KEY=FIELD:TYPE[,OPTIONS]")?;
+        let mut parts = rest.split(',');
+        let (field, kind) = parts.next().unwrap_or_default().split_once(':').ok_or_else(|| format!("expected FIELD:TYPE in '{}'", spec))?;
+        if field.is_empty() {
+            return Err(format!("missing field name in '{}'", spec));
+        }
+        if !CONSTRAINT_TYPES.contains(&kind) {
+            return Err(format!("invalid type '{}' of {}, expected one of {}", kind, field, CONSTRAINT_TYPES.join(", ")));
+        }
+        let mut constraint = FieldConstraint {
+            base: base.to_string(),
+            field: field.to_string(),
+            kind: kind.to_string(),
+            min: None,
+            max: None,
+            values: None,
+            optional: false,
+        };
+        let bound = |n: &str| n.parse::<f64>().ok().filter(|n| n.is_finite()).ok_or_else(|| format!("invalid bound '{}' of {}", n, field));
+        for option in parts {
+            match option.split_once('=') {
+                Some(("min", n)) => constraint.min = Some(bound(n)?),
+                Some(("max", n)) => constraint.max = Some(bound(n)?),
+                Some(("enum", values)) => constraint.values = Some(values.split('|').map(|v| constraint.enum_value(v)).collect::<Result<_, _>>()?),
+                None if option == "optional" => constraint.optional = true,
+                _ => return Err(format!("unknown constraint option '{}' of {}", option, field)),
+            }
+        }
+        if matches!(kind, "boolean" | "object") && (constraint.min.is_some() || constraint.max.is_some()) {
+            return Err(format!("{} fields take no min or max, in '{}'", kind, spec));
+        }
+        if constraint.min.zip(constraint.max).is_some_and(|(min, max)| min > max) {
+            return Err(format!("min above max of {}", field));
+        }
+        Ok(constraint)
+    }
+
+    // Function to parse one enum value as the field's type
+    fn enum_value(&self, text: &str) -> Result<Value, String> {
+        let invalid = || format!("enum value '{}' of {} is not a {}", text, self.field, self.kind);
+        match self.kind.as_str() {
+            "string" => Ok(Value::String(text.to_string())),
+            "integer" => text.parse::<i64>().map(Value::from).map_err(|_| invalid()),
+            "number" => text.parse::<f64>().ok().and_then(serde_json::Number::from_f64).map(Value::Number).ok_or_else(invalid),
+            "boolean" => text.parse::<bool>().map(Value::Bool).map_err(|_| invalid()),
+            _ => Err(format!("{} fields take no enum, in {}", self.kind, self.field)),
+        }
+    }
+
+    // Function to return the JSON schema of the field
+    fn property(&self) -> Value {
+        let mut property = serde_json::json!({"type": self.kind});
+        let (min, max) = match self.kind.as_str() {
+            "string" => ("minLength", "maxLength"),
+            "array" => ("minItems", "maxItems"),
+            _ => ("minimum", "maximum"),
+        };
+        let length = self.kind == "string" || self.kind == "array"; // Lengths are whole numbers
+        let bound = |n: f64| if n.fract() == 0.0 && n.abs() < 1e15 { Value::from(n as i64) } else { Value::from(n) }; // -120 rather than -120.0
+        if let Some(n) = self.min {
+            property[min] = if length { Value::from(n.ceil().max(0.0) as u64) } else { bound(n) };
+        }
+        if let Some(n) = self.max {
+            property[max] = if length { Value::from(n.floor().max(0.0) as u64) } else { bound(n) };
+        }
+        if let Some(ref values) = self.values {
+            property["enum"] = Value::Array(values.clone());
+        }
+        property
+    }
+}
+
+// Function to compile field constraints into one object schema per base key; every base key must be
+// valid, and a field may only be constrained once
+pub fn compile_constraints(constraints: &[FieldConstraint]) -> Result<HashMap<String, Value>, String> {
+    let mut schemas: HashMap<String, Value> = HashMap::new();
+    for constraint in constraints {
+        if !is_valid_key(&constraint.base) || base_key(&constraint.base) != constraint.base {
+            return Err(format!("{} is not a cs:<producer>:<object> base key", constraint.base));
+        }
+        let schema = schemas.entry(constraint.base.clone())
+            .or_insert_with(|| serde_json::json!({"type": "object", "properties": {}, "required": []}));
+        if schema["properties"].get(&constraint.field).is_some() {
+            return Err(format!("field {} of {} is constrained twice", constraint.field, constraint.base));
+        }
+        schema["properties"][&constraint.field] = constraint.property();
+        if !constraint.optional {
+            schema["required"].as_array_mut().unwrap().push(Value::String(constraint.field.clone()));
+        }
+    }
+    for (base, schema) in &schemas {
+        compile_check(&format!("the constraints of {}", base), schema)?;
+    }
+    Ok(schemas)
+}
+
+// Function to add the schemas compiled from field constraints to the active schemas of a set; an object
+// type may not have both
+pub fn add_constraints(active: &mut HashMap<String, Value>, constraints: &[FieldConstraint]) -> Result<(), String> {
+    for (base, schema) in compile_constraints(constraints)? {
+        if active.contains_key(&base) {
+            return Err(format!("{} has both a schema file and field constraints", base));
+        }
+        active.insert(base, schema);
+    }
+    Ok(())
+}
+
+// Function to enforce a complete schema set (active and shadow schemas by base key) from startup;
+// returns the number loaded
+pub fn load_schemas(active: HashMap<String, Value>, shadow: HashMap<String, Value>) -> Result<usize, String> {
+    let count = active.len() + shadow.len();
+    let mut slots = SLOTS.write().unwrap();
+    if slots.active.is_some() {
+        return Err("schemas were already loaded".to_string());
+    }
+    slots.active = Some(LoadedSchemas::new(active, shadow));
+    slots.generation += 1;
+    Ok(count)
+}
+
+// Function to load every <producer>.<object>[.shadow].json schema of a directory; returns the number loaded
+pub fn load_schema_dir(dir: &Path) -> Result<usize, String> {
+    let (active, shadow) = read_schema_dir(dir)?;
+    load_schemas(active, shadow)
+}
+
+// Function to stage a complete schema set (active and shadow schemas by base key) for the next swap,
+// replacing any set staged before; every base key must be valid and every schema must compile.
+// Returns the number of schemas staged
+pub fn stage_schemas(active: HashMap<String, Value>, shadow: HashMap<String, Value>) -> Result<usize, String> {
+    let mut errors = Vec::new();
+    for (base, schema) in active.iter().chain(shadow.iter()) {
+        if !is_valid_key(base) || base_key(base) != *base {
+            errors.push(format!("{} is not a cs:<producer>:<object> base key", base));
+        } else if let Err(err) = compile_check(base, schema) {
+            errors.push(err);
+        }
+    }
+    if !errors.is_empty() {
+        errors.sort();
+        return Err(errors.join("; "));
+    }
+    let count = active.len() + shadow.len();
+    SLOTS.write().unwrap().staged = Some(LoadedSchemas::new(active, shadow));
+    Ok(count)
+}
+
+// Function to make the staged schema set the enforced one at once, keeping the replaced set for a
+// rollback; returns the new generation
+pub fn swap_schemas() -> Result<u64, String> {
+    let mut slots = SLOTS.write().unwrap();
+    let staged = slots.staged.take().ok_or("no schema set is staged")?;
+    slots.previous = Some(slots.active.replace(staged));
+    slots.generation += 1;
+    Ok(slots.generation)
+}
+
+// Function to bring back the schema set the last swap replaced; returns the new generation
+pub fn rollback_schemas() -> Result<u64, String> {
+    let mut slots = SLOTS.write().unwrap();
+    let previous = slots.previous.take().ok_or("no swap to roll back")?;
+    slots.active = previous;
+    slots.generation += 1;
+    Ok(slots.generation)
+}
+
+// Function to summarize the schema sets in use
+pub fn schema_status() -> SchemaStatus {
+    let slots = SLOTS.read().unwrap();
+    let size = |set: &Arc<LoadedSchemas>| set.active.len() + set.shadow.len();
+    SchemaStatus {
+        generation: slots.generation,
+        active: slots.active.as_ref().map_or(0, size),
+        staged: slots.staged.as_ref().map(size),
+        rollback: slots.previous.is_some(),
+    }
+}
+
+// Function to return the enforced schema set, if any was loaded
+fn active_set() -> Option<Arc<LoadedSchemas>> {
+    SLOTS.read().unwrap().active.clone()
+}
+
+// Function to look up the schema of a base key in a set, falling back to the built-in one
+fn lookup(set: Option<Arc<LoadedSchemas>>, base: &str) -> Option<Arc<Value>> {
+    set.and_then(|loaded| loaded.active.get(base).cloned()).or_else(|| BUILT_IN.get(base).cloned())
+}
+
+// Function to generate the key validation regex pattern
+fn generate_key_pattern() -> Regex {
+    let producers = VALID_PRODUCERS.join("|"); // Join producers with |
+    let objects = VALID_OBJECTS.join("|"); // Join objects with |
+    Regex::new(&format!(
+        r"^cs:(?P<producer>{}):(?P<object>{})(?::(?P<id>[\w\d]+))?(?::(?P<function>\w+))?$",
+        producers, objects
+    ))
+    .unwrap() // Panic if regex compilation fails
+}
+
+// Function to check if a key matches the valid pattern
+pub fn is_valid_key(key: &str) -> bool {
+    KEY_PATTERN.is_match(key)
+}
+
+// Define the namespace reserved for the proxy's own bookkeeping (presence, registry, tombstones, ...);
+// no producer name starts with _, so clients can never address it directly
+pub const SYSTEM_PREFIX: &str = "cs:_";
+
+// Function to check if a key belongs to the reserved system namespace
+pub fn is_system_key(key: &str) -> bool {
+    key.starts_with(SYSTEM_PREFIX)
+}
+
+// Function to extract the producer name from a valid key
+pub fn key_producer(key: &str) -> Option<&str> {
+    KEY_PATTERN.captures(key).and_then(|caps| caps.name("producer")).map(|m| m.as_str())
+}
+
+// Function to return the producer whose namespace a glob pattern stays within (cs:<producer>:...)
+pub fn pattern_producer(pattern: &str) -> Option<&'static str> {
+    let rest = pattern.strip_prefix("cs:")?;
+    let (producer, tail) = rest.split_once(':')?;
+    if tail.is_empty() {
+        return None; // Would only match the namespace itself
+    }
+    VALID_PRODUCERS.iter().find(|p| **p == producer).copied()
+}
+
+// Function to validate a JSON value against a schema
+fn validate_against(schema: &Value, value: &Value) -> Result<(), String> {
+    jsonschema::JSONSchema::compile(schema)
+        .map_err(|e| e.to_string())? // Compile schema or return error
+        .validate(value)
+        .map_err(|errors| {
+            errors.map(|e| e.to_string()).collect::<Vec<String>>().join(", ") // Collect validation errors
+        })
+}
+
+// Function to validate a JSON value against the schema for the given key
+pub fn validate_json_schema(key: &str, value: &Value) -> Result<(), String> {
+    match schema_for(key) {
+        Some(schema) => validate_against(&schema, value),
+        None => Ok(()), // Keys without a schema accept any value
+    }
+}
+
+// Function to validate a fragment of a value, found at the given fields of an object, against
+// the part of the key's schema describing it; fragments the schema says nothing about are accepted
+pub fn validate_json_schema_at(key: &str, fields: &[&str], value: &Value) -> Result<(), String> {
+    let Some(root) = schema_for(key) else {
+        return Ok(());
+    };
+    let mut schema = root.as_ref();
+    for field in fields {
+        match schema["properties"].get(*field) {
+            Some(property) => schema = property,
+            None if schema["additionalProperties"] == Value::Bool(false) => return Err(format!("Unknown field {}", fields.join("."))),
+            None => return Ok(()),
+        }
+    }
+    validate_against(schema, value)
+}
+
+// Function to validate a JSON value against the shadow schema for the given key, if one is loaded
+pub fn validate_shadow_schema(key: &str, value: &Value) -> Result<(), String> {
+    match shadow_schema_for(key) {
+        Some(schema) => validate_against(&schema, value),
+        None => Ok(()),
+    }
+}
+
+// Function to extract the base key (cs:<producer>:<object>) that schemas are registered under
+pub fn base_key(key: &str) -> String {
+    key.splitn(4, ':').take(3).collect::<Vec<&str>>().join(":")
+}
+
+// Function to look up the schema governing a key
+pub fn schema_for(key: &str) -> Option<Arc<Value>> {
+    lookup(active_set(), &base_key(key))
+}
+
+// Function to look up the shadow schema being trialled for a key
+pub fn shadow_schema_for(key: &str) -> Option<Arc<Value>> {
+    active_set().and_then(|loaded| loaded.shadow.get(&base_key(key)).cloned())
+}
+
+// Function to validate a JSON value against the schema the staged set would enforce for the given key
+pub fn validate_staged_schema(key: &str, value: &Value) -> Result<(), String> {
+    let staged = SLOTS.read().unwrap().staged.clone();
+    match lookup(staged, &base_key(key)) {
+        Some(schema) => validate_against(&schema, value),
+        None => Ok(()),
+    }
+}
+
+// Define what redacted fields are replaced with
+pub const REDACTED: &str = "***";
+
+// Function to list the fields the key's schema marks for redaction, as dotted paths (`"x-redact": ["imsi", "sim.iccid"]`)
+fn redacted_fields(key: &str) -> Vec<String> {
+    schema_for(key)
+        .and_then(|schema| schema["x-redact"].as_array().map(|fields| fields.iter().filter_map(Value::as_str).map(str::to_string).collect()))
+        .unwrap_or_default()
+}
+
+// Function to visit the value at a dotted path, descending into every element of arrays on the way
+fn visit_path(value: &mut Value, path: &str, visit: &mut impl FnMut(&mut Value)) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| visit_path(item, path, visit)),
+        Value::Object(map) => {
+            let (name, rest) = path.split_once('.').map_or((path, None), |(name, rest)| (name, Some(rest)));
+            match (map.get_mut(name), rest) {
+                (Some(field), None) => visit(field),
+                (Some(field), Some(rest)) => visit_path(field, rest, visit),
+                (None, _) => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+// Function to mask the fields the key's schema marks for redaction, for events and logs (stored values keep them)
+pub fn redact(key: &str, value: &Value) -> Value {
+    let mut redacted = value.clone();
+    for path in redacted_fields(key) {
+        visit_path(&mut redacted, &path, &mut |field| *field = Value::String(REDACTED.to_string()));
+    }
+    redacted
+}
+
+// Function to mask the values of redacted fields wherever a message (e.g. a validation error) quotes them
+pub fn redact_message(key: &str, value: &Value, message: &str) -> String {
+    let mut quoted = Vec::new();
+    for path in redacted_fields(key) {
+        let mut value = value.clone();
+        visit_path(&mut value, &path, &mut |field| quoted.push(field.to_string()));
+    }
+    quoted.iter().filter(|q| q.len() > 1).fold(message.to_string(), |message, q| message.replace(q.as_str(), REDACTED))
+}
+
+// Function to reduce an object to the properties the key's schema declares (other values are returned unchanged)
+pub fn normalize(key: &str, value: &Value) -> Value {
+    let schema = schema_for(key);
+    let properties = schema.as_deref().and_then(|schema| schema["properties"].as_object());
+    match (properties, value) {
+        (Some(properties), Value::Object(map)) => Value::Object(
+            map.iter()
+                .filter(|(name, _)| properties.contains_key(*name))
+                .map(|(name, field)| (name.clone(), field.clone()))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
@@ -0,0 +1,809 @@
+// Storage the proxy runs its Redis commands on: a Redis server, or an in-memory stand-in for tests and fuzzing
+
+// Import necessary crates and modules
+use crate::glob::glob_match; // For KEYS and SCAN MATCH patterns
+use redis::{ConnectionLike, ErrorKind, RedisError, RedisResult, Value}; // For answering commands as a Redis server does
+use std::collections::{BTreeMap, BTreeSet}; // For keys, members and fields kept in a stable order
+use std::sync::{Arc, Mutex, PoisonError}; // For sharing the data between connections of the store
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH}; // For key expiry and stream ids
+
+/// Backend URL selecting the in-memory store instead of a Redis server.
+pub const MEMORY_URL: &str = "memory://";
+
+/// Connection the proxy sends its commands to.
+pub enum Storage {
+    Redis(redis::Connection), // Connection to a Redis server
+    Memory(MemoryStore), // Connection to an in-memory store
+}
+
+impl Storage {
+    /// Bounds how long reading a reply may block (the in-memory store never blocks).
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> RedisResult<()> {
+        match self {
+            Storage::Redis(conn) => conn.set_read_timeout(timeout),
+            Storage::Memory(_) => Ok(()),
+        }
+    }
+
+    /// Bounds how long sending a command may block (the in-memory store never blocks).
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> RedisResult<()> {
+        match self {
+            Storage::Redis(conn) => conn.set_write_timeout(timeout),
+            Storage::Memory(_) => Ok(()),
+        }
+    }
+}
+
+impl ConnectionLike for Storage {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        match self {
+            Storage::Redis(conn) => conn.req_packed_command(cmd),
+            Storage::Memory(store) => store.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands(&mut self, cmd: &[u8], offset: usize, count: usize) -> RedisResult<Vec<Value>> {
+        match self {
+            Storage::Redis(conn) => conn.req_packed_commands(cmd, offset, count),
+            Storage::Memory(store) => store.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            Storage::Redis(conn) => conn.get_db(),
+            Storage::Memory(store) => store.get_db(),
+        }
+    }
+
+    fn check_connection(&mut self) -> bool {
+        match self {
+            Storage::Redis(conn) => conn.check_connection(),
+            Storage::Memory(store) => store.check_connection(),
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        match self {
+            Storage::Redis(conn) => conn.is_open(),
+            Storage::Memory(store) => store.is_open(),
+        }
+    }
+}
+
+// Define the commands that leave the data as it is; any other command fails the transactions watching it
+const READ_COMMANDS: &[&str] = &[
+    "GET", "MGET", "STRLEN", "EXISTS", "TYPE", "TTL", "PTTL", "KEYS", "SCAN", "DBSIZE", "SMEMBERS", "SCARD", "SISMEMBER",
+    "HGET", "HGETALL", "HLEN", "HEXISTS", "ZCARD", "ZSCORE", "ZRANGE", "XLEN", "XRANGE", "MEMORY", "PUBLISH", "SPUBLISH",
+    "PING", "ECHO", "INFO", "MODULE", "CONFIG", "CLIENT", "SELECT",
+];
+
+// Define a stream entry: its id, then its fields and values in turn
+type StreamEntry = ((u64, u64), Vec<Vec<u8>>);
+
+// Define a value of the in-memory store
+enum Item {
+    String(Vec<u8>),
+    Set(BTreeSet<Vec<u8>>),
+    Hash(BTreeMap<Vec<u8>, Vec<u8>>),
+    SortedSet(BTreeMap<Vec<u8>, f64>), // Scores by member
+    Stream(Vec<StreamEntry>), // Entries in id order
+}
+
+impl Item {
+    // Function to return the name TYPE reports for the value
+    fn type_name(&self) -> &'static str {
+        match self {
+            Item::String(_) => "string",
+            Item::Set(_) => "set",
+            Item::Hash(_) => "hash",
+            Item::SortedSet(_) => "zset",
+            Item::Stream(_) => "stream",
+        }
+    }
+
+    // Function to check if the value is a collection left without elements, which Redis drops
+    fn is_empty(&self) -> bool {
+        match self {
+            Item::String(_) => false,
+            Item::Set(members) => members.is_empty(),
+            Item::Hash(fields) => fields.is_empty(),
+            Item::SortedSet(scores) => scores.is_empty(),
+            Item::Stream(_) => false, // Streams stay until deleted
+        }
+    }
+
+    // Function to estimate the bytes the value occupies, for MEMORY USAGE
+    fn size(&self) -> usize {
+        match self {
+            Item::String(bytes) => bytes.len(),
+            Item::Set(members) => members.iter().map(Vec::len).sum(),
+            Item::Hash(fields) => fields.iter().map(|(field, value)| field.len() + value.len()).sum(),
+            Item::SortedSet(scores) => scores.keys().map(|member| member.len() + 8).sum(),
+            Item::Stream(entries) => entries.iter().map(|(_, fields)| 16 + fields.iter().map(Vec::len).sum::<usize>()).sum(),
+        }
+    }
+
+    // Function to view the value as a string, None for other types
+    fn as_string(&mut self) -> Option<&mut Vec<u8>> {
+        match self {
+            Item::String(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    // Function to view the value as a set, None for other types
+    fn as_set(&mut self) -> Option<&mut BTreeSet<Vec<u8>>> {
+        match self {
+            Item::Set(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    // Function to view the value as a hash, None for other types
+    fn as_hash(&mut self) -> Option<&mut BTreeMap<Vec<u8>, Vec<u8>>> {
+        match self {
+            Item::Hash(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    // Function to view the value as a sorted set, None for other types
+    fn as_sorted_set(&mut self) -> Option<&mut BTreeMap<Vec<u8>, f64>> {
+        match self {
+            Item::SortedSet(scores) => Some(scores),
+            _ => None,
+        }
+    }
+
+    // Function to view the value as a stream, None for other types
+    fn as_stream(&mut self) -> Option<&mut Vec<StreamEntry>> {
+        match self {
+            Item::Stream(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
+
+// Define a key of the in-memory store
+struct Entry {
+    item: Item,
+    expires: Option<Instant>, // When the key disappears, if it has a TTL
+}
+
+// Define the data shared by the connections of an in-memory store
+#[derive(Default)]
+struct Data {
+    keys: BTreeMap<Vec<u8>, Entry>,
+    writes: u64, // Commands run that may have changed the data, for failing watching transactions
+    last_stream_id: (u64, u64), // Id given to the last entry added to any stream
+}
+
+impl Data {
+    // Function to look up a key, dropping it first if it has expired
+    fn entry(&mut self, key: &[u8]) -> Option<&mut Entry> {
+        if self.keys.get(key).is_some_and(|entry| entry.expires.is_some_and(|at| at <= Instant::now())) {
+            self.keys.remove(key);
+        }
+        self.keys.get_mut(key)
+    }
+
+    // Function to drop every expired key, before commands going over all keys
+    fn expire_all(&mut self) {
+        let now = Instant::now();
+        self.keys.retain(|_, entry| entry.expires.is_none_or(|at| at > now));
+    }
+
+    // Function to borrow a key's value as the type a command works on (None if the key is missing)
+    fn find<T>(&mut self, key: &[u8], view: fn(&mut Item) -> Option<&mut T>) -> RedisResult<Option<&mut T>> {
+        match self.entry(key) {
+            Some(entry) => view(&mut entry.item).map(Some).ok_or_else(wrong_type),
+            None => Ok(None),
+        }
+    }
+
+    // Function to borrow a key's value as the type a command writes, creating an empty one if the key is missing
+    fn create<T>(&mut self, key: &[u8], empty: fn() -> Item, view: fn(&mut Item) -> Option<&mut T>) -> RedisResult<&mut T> {
+        if self.entry(key).is_none() {
+            self.keys.insert(key.to_vec(), Entry { item: empty(), expires: None });
+        }
+        let entry = self.keys.get_mut(key).expect("Inserted above if missing");
+        view(&mut entry.item).ok_or_else(wrong_type)
+    }
+
+    // Function to drop a key whose collection lost its last element
+    fn drop_if_empty(&mut self, key: &[u8]) {
+        if self.keys.get(key).is_some_and(|entry| entry.item.is_empty()) {
+            self.keys.remove(key);
+        }
+    }
+
+    // Function to give a key a TTL in milliseconds from now, deleting it if the TTL is not positive;
+    // returns 1 if the key exists, as EXPIRE does
+    fn expire_in(&mut self, key: &[u8], millis: i64) -> i64 {
+        if self.entry(key).is_none() {
+            return 0;
+        }
+        match u64::try_from(millis) {
+            Ok(millis) if millis > 0 => self.keys.get_mut(key).expect("Checked above").expires = Some(Instant::now() + Duration::from_millis(millis)),
+            _ => {
+                self.keys.remove(key);
+            }
+        }
+        1
+    }
+
+    // Function to return the next stream entry id: the current time, with a sequence number
+    // keeping ids increasing within the same millisecond
+    fn next_stream_id(&mut self) -> (u64, u64) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        let (ms, seq) = self.last_stream_id;
+        self.last_stream_id = if now > ms { (now, 0) } else { (ms, seq + 1) };
+        self.last_stream_id
+    }
+}
+
+/// In-memory stand-in for a Redis server, answering the commands the proxy sends for its actions so
+/// they can run without Redis. Commands it has no counterpart for (scripts, modules, subscriptions)
+/// fail as on a server without them. Clones share the data like connections to one server.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    data: Arc<Mutex<Data>>, // Keys shared by every connection
+    queued: Option<Vec<Vec<Vec<u8>>>>, // Commands of this connection queued since MULTI
+    watched: Option<u64>, // Writes counted when this connection sent WATCH
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens another connection to the same data, without this one's transaction state.
+    pub fn connection(&self) -> Self {
+        MemoryStore { data: Arc::clone(&self.data), queued: None, watched: None }
+    }
+
+    // Function to run one command of this connection, queuing it instead while a transaction is open
+    fn run(&mut self, data: &mut Data, args: Vec<Vec<u8>>) -> RedisResult<Value> {
+        let name = String::from_utf8_lossy(&args[0]).to_ascii_uppercase();
+        match (name.as_str(), self.queued.as_mut()) {
+            ("MULTI", None) => {
+                self.queued = Some(Vec::new());
+                Ok(Value::Okay)
+            }
+            ("WATCH", None) => {
+                self.watched.get_or_insert(data.writes);
+                Ok(Value::Okay)
+            }
+            ("MULTI" | "WATCH", Some(_)) => Err(error("ERR", format!("{} inside MULTI is not allowed", name))),
+            ("UNWATCH", _) => {
+                self.watched = None;
+                Ok(Value::Okay)
+            }
+            ("DISCARD", _) => {
+                self.watched = None;
+                self.queued.take().map(|_| Value::Okay).ok_or_else(|| error("ERR", "DISCARD without MULTI"))
+            }
+            ("EXEC", _) => {
+                let queued = self.queued.take().ok_or_else(|| error("ERR", "EXEC without MULTI"))?;
+                if self.watched.take().is_some_and(|writes| writes != data.writes) {
+                    return Ok(Value::Nil); // A watched key may have changed
+                }
+                let replies: Vec<RedisResult<Value>> = queued.into_iter().map(|args| execute(data, args)).collect(); // All run, as in Redis
+                replies.into_iter().collect::<RedisResult<Vec<Value>>>().map(Value::Bulk)
+            }
+            (_, Some(queued)) => {
+                queued.push(args);
+                Ok(Value::Status("QUEUED".to_string()))
+            }
+            (_, None) => execute(data, args),
+        }
+    }
+}
+
+impl ConnectionLike for MemoryStore {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.req_packed_commands(cmd, 0, 1)?.pop().ok_or_else(|| error("ERR", "no command sent"))
+    }
+
+    fn req_packed_commands(&mut self, cmd: &[u8], offset: usize, count: usize) -> RedisResult<Vec<Value>> {
+        let commands = parse_commands(cmd)?;
+        let data = Arc::clone(&self.data);
+        let mut data = data.lock().unwrap_or_else(PoisonError::into_inner); // Commands of one call run together, like a pipeline
+        let replies: Vec<RedisResult<Value>> = commands.into_iter().map(|args| self.run(&mut data, args)).collect();
+        let replies = replies.into_iter().collect::<RedisResult<Vec<Value>>>()?; // The first error fails the call, as redis-rs reports it
+        Ok(replies.into_iter().skip(offset).take(count).collect())
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+
+    fn check_connection(&mut self) -> bool {
+        true
+    }
+
+    fn is_open(&self) -> bool {
+        true
+    }
+}
+
+// Function to build an error reply, read as the same reply of a server would be
+fn error(code: &str, detail: impl AsRef<str>) -> RedisError {
+    let reply = format!("-{} {}\r\n", code, detail.as_ref().replace(['\r', '\n'], " "));
+    redis::parse_redis_value(reply.as_bytes()).expect_err("Error replies parse as errors")
+}
+
+// Function to build the error of a command run on a key of another type
+fn wrong_type() -> RedisError {
+    error("WRONGTYPE", "Operation against a key holding the wrong kind of value")
+}
+
+// Function to build the error of a malformed command
+fn syntax_error() -> RedisError {
+    error("ERR", "syntax error")
+}
+
+// Function to read one line of a packed command, a count after its marker
+fn read_count(packed: &mut &[u8], marker: u8) -> RedisResult<usize> {
+    let malformed = || RedisError::from((ErrorKind::ClientError, "Malformed packed command"));
+    let end = packed.windows(2).position(|window| window == b"\r\n").ok_or_else(malformed)?;
+    let count = packed[..end].strip_prefix(&[marker])
+        .and_then(|digits| std::str::from_utf8(digits).ok()?.parse().ok())
+        .ok_or_else(malformed)?;
+    *packed = &packed[end + 2..];
+    Ok(count)
+}
+
+// Function to split packed commands into their arguments
+fn parse_commands(mut packed: &[u8]) -> RedisResult<Vec<Vec<Vec<u8>>>> {
+    let mut commands = Vec::new();
+    while !packed.is_empty() {
+        let count = read_count(&mut packed, b'*')?;
+        let mut args = Vec::new();
+        for _ in 0..count {
+            let len = read_count(&mut packed, b'$')?;
+            if packed.len() < len + 2 {
+                return Err(RedisError::from((ErrorKind::ClientError, "Truncated packed command")));
+            }
+            args.push(packed[..len].to_vec());
+            packed = &packed[len + 2..];
+        }
+        if args.is_empty() {
+            return Err(RedisError::from((ErrorKind::ClientError, "Empty packed command")));
+        }
+        commands.push(args);
+    }
+    Ok(commands)
+}
+
+// Function to read an integer argument
+fn integer(arg: &[u8]) -> RedisResult<i64> {
+    std::str::from_utf8(arg).ok().and_then(|text| text.parse().ok()).ok_or_else(|| error("ERR", "value is not an integer or out of range"))
+}
+
+// Function to read a score argument
+fn score(arg: &[u8]) -> RedisResult<f64> {
+    std::str::from_utf8(arg).ok().and_then(|text| text.parse().ok()).filter(|score: &f64| !score.is_nan())
+        .ok_or_else(|| error("ERR", "value is not a valid float"))
+}
+
+// Function to format a score as Redis replies with it
+fn format_score(score: f64) -> Vec<u8> {
+    score.to_string().into_bytes()
+}
+
+// Function to read a stream id bound of XRANGE; `-` and `+` are the smallest and largest ids and a
+// bare time covers every sequence number of that millisecond
+fn stream_bound(arg: &[u8], end: bool) -> RedisResult<(u64, u64)> {
+    let invalid = || error("ERR", "Invalid stream ID specified as stream command argument");
+    let text = std::str::from_utf8(arg).map_err(|_| invalid())?;
+    match text {
+        "-" => Ok((0, 0)),
+        "+" => Ok((u64::MAX, u64::MAX)),
+        _ => {
+            let (ms, seq) = text.split_once('-').map_or((text, None), |(ms, seq)| (ms, Some(seq)));
+            let ms = ms.parse().map_err(|_| invalid())?;
+            let seq = match seq {
+                Some(seq) => seq.parse().map_err(|_| invalid())?,
+                None if end => u64::MAX,
+                None => 0,
+            };
+            Ok((ms, seq))
+        }
+    }
+}
+
+// Function to format a stream id
+fn format_id((ms, seq): (u64, u64)) -> Vec<u8> {
+    format!("{}-{}", ms, seq).into_bytes()
+}
+
+// Function to return the milliseconds from now until a Unix time in milliseconds
+fn millis_until(unix_millis: i64) -> i64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64;
+    unix_millis.saturating_sub(now)
+}
+
+// Function to turn byte strings into a reply
+fn bulk(items: impl IntoIterator<Item = Vec<u8>>) -> Value {
+    Value::Bulk(items.into_iter().map(Value::Data).collect())
+}
+
+// Function to run one command on the data
+fn execute(data: &mut Data, args: Vec<Vec<u8>>) -> RedisResult<Value> {
+    let name = String::from_utf8_lossy(&args[0]).to_ascii_uppercase();
+    let args = &args[1..];
+    let arity = |min: usize| if args.len() < min {
+        Err(error("ERR", format!("wrong number of arguments for '{}' command", name.to_ascii_lowercase())))
+    } else {
+        Ok(())
+    };
+    if !READ_COMMANDS.contains(&name.as_str()) {
+        data.writes += 1;
+    }
+
+    match name.as_str() {
+        "PING" => Ok(args.first().map_or(Value::Status("PONG".to_string()), |message| Value::Data(message.clone()))),
+        "ECHO" => {
+            arity(1)?;
+            Ok(Value::Data(args[0].clone()))
+        }
+        "SELECT" | "CLIENT" => Ok(Value::Okay),
+        "INFO" => Ok(Value::Data(b"# Server\r\nredis_version:7.2.0\r\nredis_mode:standalone\r\n\r\n# Replication\r\nrole:master\r\nconnected_slaves:0\r\nmaster_repl_offset:0\r\n".to_vec())),
+        "MODULE" => Ok(Value::Bulk(Vec::new())), // No modules loaded
+        "CONFIG" => {
+            arity(2)?;
+            match args[0].to_ascii_uppercase().as_slice() {
+                b"GET" => Ok(bulk([args[1].clone(), Vec::new()])),
+                b"SET" => Ok(Value::Okay),
+                _ => Err(syntax_error()),
+            }
+        }
+        "DBSIZE" => {
+            data.expire_all();
+            Ok(Value::Int(data.keys.len() as i64))
+        }
+        "FLUSHDB" | "FLUSHALL" => {
+            data.keys.clear();
+            Ok(Value::Okay)
+        }
+        "PUBLISH" | "SPUBLISH" => {
+            arity(2)?;
+            Ok(Value::Int(0)) // Nobody subscribes to an in-memory store
+        }
+
+        // Keys
+        "DEL" | "UNLINK" => {
+            arity(1)?;
+            Ok(Value::Int(args.iter().filter(|key| data.entry(key).is_some() && data.keys.remove(key.as_slice()).is_some()).count() as i64))
+        }
+        "EXISTS" => {
+            arity(1)?;
+            Ok(Value::Int(args.iter().filter(|key| data.entry(key).is_some()).count() as i64))
+        }
+        "TYPE" => {
+            arity(1)?;
+            Ok(Value::Status(data.entry(&args[0]).map_or("none", |entry| entry.item.type_name()).to_string()))
+        }
+        "EXPIRE" | "PEXPIRE" | "EXPIREAT" | "PEXPIREAT" => {
+            arity(2)?;
+            let time = integer(&args[1])?;
+            let millis = match name.as_str() {
+                "EXPIRE" => time.saturating_mul(1000),
+                "PEXPIRE" => time,
+                "EXPIREAT" => millis_until(time.saturating_mul(1000)),
+                _ => millis_until(time),
+            };
+            Ok(Value::Int(data.expire_in(&args[0], millis)))
+        }
+        "PERSIST" => {
+            arity(1)?;
+            Ok(Value::Int(data.entry(&args[0]).and_then(|entry| entry.expires.take()).map_or(0, |_| 1)))
+        }
+        "TTL" | "PTTL" => {
+            arity(1)?;
+            let ttl = match data.entry(&args[0]) {
+                None => -2,
+                Some(Entry { expires: None, .. }) => -1,
+                Some(Entry { expires: Some(at), .. }) => {
+                    let left = at.saturating_duration_since(Instant::now()).as_millis() as i64;
+                    if name == "TTL" { (left + 500) / 1000 } else { left }
+                }
+            };
+            Ok(Value::Int(ttl))
+        }
+        "RENAME" => {
+            arity(2)?;
+            if data.entry(&args[0]).is_none() {
+                return Err(error("ERR", "no such key"));
+            }
+            let entry = data.keys.remove(&args[0]).expect("Checked above");
+            data.keys.insert(args[1].clone(), entry);
+            Ok(Value::Okay)
+        }
+        "KEYS" => {
+            arity(1)?;
+            data.expire_all();
+            let pattern = String::from_utf8_lossy(&args[0]);
+            Ok(bulk(data.keys.keys().filter(|key| glob_match(&pattern, &String::from_utf8_lossy(key))).cloned()))
+        }
+        "SCAN" => {
+            // Everything in one batch, so the cursor always ends at 0
+            arity(1)?;
+            integer(&args[0])?;
+            let (mut pattern, mut kind) = (None, None);
+            for option in args[1..].chunks(2) {
+                match (option[0].to_ascii_uppercase().as_slice(), option.get(1)) {
+                    (b"MATCH", Some(value)) => pattern = Some(String::from_utf8_lossy(value).into_owned()),
+                    (b"COUNT", Some(value)) => {
+                        integer(value)?;
+                    }
+                    (b"TYPE", Some(value)) => kind = Some(String::from_utf8_lossy(value).to_ascii_lowercase()),
+                    _ => return Err(syntax_error()),
+                }
+            }
+            data.expire_all();
+            let keys = data.keys.iter()
+                .filter(|(key, _)| pattern.as_deref().is_none_or(|pattern| glob_match(pattern, &String::from_utf8_lossy(key))))
+                .filter(|(_, entry)| kind.as_deref().is_none_or(|kind| entry.item.type_name() == kind))
+                .map(|(key, _)| key.clone());
+            Ok(Value::Bulk(vec![Value::Data(b"0".to_vec()), bulk(keys)]))
+        }
+        "MEMORY" => {
+            arity(2)?;
+            if !args[0].eq_ignore_ascii_case(b"USAGE") {
+                return Err(syntax_error());
+            }
+            let key_len = args[1].len();
+            Ok(data.entry(&args[1]).map_or(Value::Nil, |entry| Value::Int((key_len + entry.item.size() + 48) as i64)))
+        }
+
+        // Strings
+        "GET" => {
+            arity(1)?;
+            Ok(data.find(&args[0], Item::as_string)?.map_or(Value::Nil, |bytes| Value::Data(bytes.clone())))
+        }
+        "MGET" => {
+            arity(1)?;
+            Ok(Value::Bulk(args.iter().map(|key| match data.entry(key) {
+                Some(Entry { item: Item::String(bytes), .. }) => Value::Data(bytes.clone()),
+                _ => Value::Nil,
+            }).collect()))
+        }
+        "SET" => {
+            arity(2)?;
+            let (mut expires, mut keep_ttl, mut condition, mut get) = (None, false, None, false);
+            let mut options = args[2..].iter();
+            while let Some(option) = options.next() {
+                let option = option.to_ascii_uppercase();
+                match option.as_slice() {
+                    b"EX" | b"PX" | b"EXAT" | b"PXAT" => {
+                        let time = integer(options.next().ok_or_else(syntax_error)?)?;
+                        if time <= 0 {
+                            return Err(error("ERR", "invalid expire time in 'set' command"));
+                        }
+                        expires = Some(match option.as_slice() {
+                            b"EX" => time.saturating_mul(1000),
+                            b"PX" => time,
+                            b"EXAT" => millis_until(time.saturating_mul(1000)),
+                            _ => millis_until(time),
+                        });
+                    }
+                    b"KEEPTTL" => keep_ttl = true,
+                    b"NX" | b"XX" if condition.is_none() => condition = Some(option),
+                    b"GET" => get = true,
+                    _ => return Err(syntax_error()),
+                }
+            }
+            let previous = if get { data.find(&args[0], Item::as_string)?.cloned() } else { None };
+            let exists = data.entry(&args[0]).is_some();
+            let reply = if get { previous.map_or(Value::Nil, Value::Data) } else { Value::Okay };
+            match condition.as_deref() {
+                Some(b"NX") if exists => return Ok(if get { reply } else { Value::Nil }),
+                Some(b"XX") if !exists => return Ok(if get { reply } else { Value::Nil }),
+                _ => {}
+            }
+            let kept = data.entry(&args[0]).and_then(|entry| entry.expires).filter(|_| keep_ttl);
+            data.keys.insert(args[0].clone(), Entry { item: Item::String(args[1].clone()), expires: kept });
+            if let Some(millis) = expires {
+                data.expire_in(&args[0], millis);
+            }
+            Ok(reply)
+        }
+        "SETEX" | "PSETEX" => {
+            arity(3)?;
+            let time = integer(&args[1])?;
+            if time <= 0 {
+                return Err(error("ERR", format!("invalid expire time in '{}' command", name.to_ascii_lowercase())));
+            }
+            data.keys.insert(args[0].clone(), Entry { item: Item::String(args[2].clone()), expires: None });
+            data.expire_in(&args[0], if name == "SETEX" { time.saturating_mul(1000) } else { time });
+            Ok(Value::Okay)
+        }
+        "STRLEN" => {
+            arity(1)?;
+            Ok(Value::Int(data.find(&args[0], Item::as_string)?.map_or(0, |bytes| bytes.len() as i64)))
+        }
+        "INCR" | "DECR" | "INCRBY" | "DECRBY" => {
+            arity(if name.ends_with("BY") { 2 } else { 1 })?;
+            let step = if name.ends_with("BY") { integer(&args[1])? } else { 1 };
+            let step = if name.starts_with("DECR") { step.checked_neg().ok_or_else(|| error("ERR", "decrement would overflow"))? } else { step };
+            let bytes = data.create(&args[0], || Item::String(b"0".to_vec()), Item::as_string)?;
+            let value = integer(bytes)?.checked_add(step).ok_or_else(|| error("ERR", "increment or decrement would overflow"))?;
+            *bytes = value.to_string().into_bytes();
+            Ok(Value::Int(value))
+        }
+
+        // Sets
+        "SADD" => {
+            arity(2)?;
+            let members = data.create(&args[0], || Item::Set(BTreeSet::new()), Item::as_set)?;
+            Ok(Value::Int(args[1..].iter().filter(|member| members.insert(member.to_vec())).count() as i64))
+        }
+        "SREM" => {
+            arity(2)?;
+            let removed = match data.find(&args[0], Item::as_set)? {
+                Some(members) => args[1..].iter().filter(|member| members.remove(member.as_slice())).count(),
+                None => 0,
+            };
+            data.drop_if_empty(&args[0]);
+            Ok(Value::Int(removed as i64))
+        }
+        "SMEMBERS" => {
+            arity(1)?;
+            Ok(bulk(data.find(&args[0], Item::as_set)?.into_iter().flat_map(|members| members.iter().cloned())))
+        }
+        "SCARD" => {
+            arity(1)?;
+            Ok(Value::Int(data.find(&args[0], Item::as_set)?.map_or(0, |members| members.len() as i64)))
+        }
+        "SISMEMBER" => {
+            arity(2)?;
+            Ok(Value::Int(data.find(&args[0], Item::as_set)?.is_some_and(|members| members.contains(&args[1])) as i64))
+        }
+
+        // Hashes
+        "HSET" | "HMSET" => {
+            if args.len() < 3 || args.len().is_multiple_of(2) {
+                return Err(error("ERR", format!("wrong number of arguments for '{}' command", name.to_ascii_lowercase())));
+            }
+            let fields = data.create(&args[0], || Item::Hash(BTreeMap::new()), Item::as_hash)?;
+            let added = args[1..].chunks(2).filter(|pair| fields.insert(pair[0].clone(), pair[1].clone()).is_none()).count();
+            Ok(if name == "HSET" { Value::Int(added as i64) } else { Value::Okay })
+        }
+        "HGET" => {
+            arity(2)?;
+            Ok(data.find(&args[0], Item::as_hash)?.and_then(|fields| fields.get(&args[1])).map_or(Value::Nil, |value| Value::Data(value.clone())))
+        }
+        "HGETALL" => {
+            arity(1)?;
+            Ok(bulk(data.find(&args[0], Item::as_hash)?.into_iter().flat_map(|fields| fields.iter().flat_map(|(field, value)| [field.clone(), value.clone()]))))
+        }
+        "HDEL" => {
+            arity(2)?;
+            let removed = match data.find(&args[0], Item::as_hash)? {
+                Some(fields) => args[1..].iter().filter(|field| fields.remove(field.as_slice()).is_some()).count(),
+                None => 0,
+            };
+            data.drop_if_empty(&args[0]);
+            Ok(Value::Int(removed as i64))
+        }
+        "HLEN" => {
+            arity(1)?;
+            Ok(Value::Int(data.find(&args[0], Item::as_hash)?.map_or(0, |fields| fields.len() as i64)))
+        }
+        "HEXISTS" => {
+            arity(2)?;
+            Ok(Value::Int(data.find(&args[0], Item::as_hash)?.is_some_and(|fields| fields.contains_key(&args[1])) as i64))
+        }
+        "HINCRBY" => {
+            arity(3)?;
+            let step = integer(&args[2])?;
+            let fields = data.create(&args[0], || Item::Hash(BTreeMap::new()), Item::as_hash)?;
+            let current = fields.get(&args[1]).map_or(Ok(0), |value| integer(value).map_err(|_| error("ERR", "hash value is not an integer")))?;
+            let value = current.checked_add(step).ok_or_else(|| error("ERR", "increment or decrement would overflow"))?;
+            fields.insert(args[1].clone(), value.to_string().into_bytes());
+            Ok(Value::Int(value))
+        }
+
+        // Sorted sets
+        "ZADD" => {
+            if args.len() < 3 || args.len().is_multiple_of(2) {
+                return Err(syntax_error()); // Only plain score and member pairs, which is all the proxy sends
+            }
+            let pairs = args[1..].chunks(2).map(|pair| Ok((score(&pair[0])?, pair[1].clone()))).collect::<RedisResult<Vec<_>>>()?;
+            let scores = data.create(&args[0], || Item::SortedSet(BTreeMap::new()), Item::as_sorted_set)?;
+            Ok(Value::Int(pairs.into_iter().filter(|(score, member)| scores.insert(member.clone(), *score).is_none()).count() as i64))
+        }
+        "ZREM" => {
+            arity(2)?;
+            let removed = match data.find(&args[0], Item::as_sorted_set)? {
+                Some(scores) => args[1..].iter().filter(|member| scores.remove(member.as_slice()).is_some()).count(),
+                None => 0,
+            };
+            data.drop_if_empty(&args[0]);
+            Ok(Value::Int(removed as i64))
+        }
+        "ZCARD" => {
+            arity(1)?;
+            Ok(Value::Int(data.find(&args[0], Item::as_sorted_set)?.map_or(0, |scores| scores.len() as i64)))
+        }
+        "ZSCORE" => {
+            arity(2)?;
+            Ok(data.find(&args[0], Item::as_sorted_set)?.and_then(|scores| scores.get(&args[1])).map_or(Value::Nil, |score| Value::Data(format_score(*score))))
+        }
+        "ZRANGE" => {
+            // By rank only, in score order
+            arity(3)?;
+            let with_scores = match args.get(3) {
+                None => false,
+                Some(option) if option.eq_ignore_ascii_case(b"WITHSCORES") && args.len() == 4 => true,
+                _ => return Err(syntax_error()),
+            };
+            let (start, stop) = (integer(&args[1])?, integer(&args[2])?);
+            let mut ranked: Vec<(f64, Vec<u8>)> = data.find(&args[0], Item::as_sorted_set)?
+                .map(|scores| scores.iter().map(|(member, score)| (*score, member.clone())).collect())
+                .unwrap_or_default();
+            ranked.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+            let len = ranked.len() as i64;
+            let resolve = |index: i64| if index < 0 { (len + index).max(0) } else { index };
+            let (start, stop) = (resolve(start), resolve(stop).min(len - 1));
+            let selected = if start > stop { &[][..] } else { &ranked[start as usize..=stop as usize] };
+            Ok(bulk(selected.iter().flat_map(|(score, member)| {
+                std::iter::once(member.clone()).chain(with_scores.then(|| format_score(*score)))
+            })))
+        }
+
+        // Streams
+        "XADD" => {
+            arity(4)?;
+            let mut rest = &args[1..];
+            let mut maxlen = None;
+            if rest[0].eq_ignore_ascii_case(b"MAXLEN") {
+                let exact = rest.get(1).is_some_and(|mode| mode.as_slice() == b"=" || mode.as_slice() == b"~");
+                let limit = rest.get(if exact { 2 } else { 1 }).ok_or_else(syntax_error)?;
+                maxlen = Some(usize::try_from(integer(limit)?).map_err(|_| error("ERR", "The MAXLEN argument must be >= 0."))?);
+                rest = &rest[if exact { 3 } else { 2 }..];
+            }
+            if rest.first().map(Vec::as_slice) != Some(b"*") {
+                return Err(error("ERR", "only ids generated by the server (*) are supported by the in-memory store"));
+            }
+            let fields = &rest[1..];
+            if fields.is_empty() || !fields.len().is_multiple_of(2) {
+                return Err(error("ERR", "wrong number of arguments for 'xadd' command"));
+            }
+            data.create(&args[0], || Item::Stream(Vec::new()), Item::as_stream)?; // Checks the type before taking an id
+            let id = data.next_stream_id();
+            let entries = data.create(&args[0], || Item::Stream(Vec::new()), Item::as_stream)?;
+            entries.push((id, fields.to_vec()));
+            if let Some(maxlen) = maxlen {
+                let excess = entries.len().saturating_sub(maxlen);
+                entries.drain(..excess);
+            }
+            Ok(Value::Data(format_id(id)))
+        }
+        "XLEN" => {
+            arity(1)?;
+            Ok(Value::Int(data.find(&args[0], Item::as_stream)?.map_or(0, |entries| entries.len() as i64)))
+        }
+        "XRANGE" => {
+            arity(3)?;
+            let (start, end) = (stream_bound(&args[1], false)?, stream_bound(&args[2], true)?);
+            let count = match &args[3..] {
+                [] => usize::MAX,
+                [option, count] if option.eq_ignore_ascii_case(b"COUNT") => usize::try_from(integer(count)?).unwrap_or(0),
+                _ => return Err(syntax_error()),
+            };
+            let entries = data.find(&args[0], Item::as_stream)?.map(|entries| entries.as_slice()).unwrap_or_default();
+            Ok(Value::Bulk(entries.iter()
+                .filter(|(id, _)| (start..=end).contains(id))
+                .take(count)
+                .map(|(id, fields)| Value::Bulk(vec![Value::Data(format_id(*id)), bulk(fields.iter().cloned())]))
+                .collect()))
+        }
+
+        _ => Err(error("ERR", format!("unknown command '{}'", name.to_ascii_lowercase()))),
+    }
+}
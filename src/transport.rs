@@ -0,0 +1,129 @@
+// Import necessary crates and modules
+use std::io::{self, Read, Write}; // For the stream interface
+use std::net::TcpStream; // For proxies reached over TCP
+#[cfg(unix)]
+use std::os::unix::net::UnixStream; // For proxies reached over a Unix socket
+use std::time::Duration; // For read timeouts
+
+/// Connection to a proxy, over whichever IPC the platform offers.
+///
+/// Addresses are `tcp:HOST:PORT`, `pipe:NAME` for the Windows named pipe `\\.\pipe\NAME`, or
+/// the path of a Unix socket.
+pub enum Transport {
+    #[cfg(unix)]
+    Unix(UnixStream),
+    Tcp(TcpStream),
+    #[cfg(windows)]
+    Pipe(std::fs::File), // Client end of a named pipe
+}
+
+// Define how long a client waits for a free named pipe instance while the proxy is accepting
+#[cfg(windows)]
+const PIPE_BUSY_RETRIES: u32 = 20;
+
+impl Transport {
+    /// Connects to a proxy address.
+    pub fn connect(address: &str) -> io::Result<Self> {
+        if let Some(host_port) = address.strip_prefix("tcp:") {
+            let stream = TcpStream::connect(host_port)?;
+            stream.set_nodelay(true)?; // Requests are small and latency matters
+            return Ok(Transport::Tcp(stream));
+        }
+        if let Some(name) = address.strip_prefix("pipe:") {
+            return Self::connect_pipe(name);
+        }
+        Self::connect_unix(address)
+    }
+
+    // Function to connect to a Unix socket
+    #[cfg(unix)]
+    fn connect_unix(path: &str) -> io::Result<Self> {
+        UnixStream::connect(path).map(Transport::Unix)
+    }
+
+    // Function to reject Unix socket paths where they do not exist
+    #[cfg(not(unix))]
+    fn connect_unix(path: &str) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, format!("Unix sockets are not available here, use pipe:NAME or tcp:HOST:PORT instead of {}", path)))
+    }
+
+    // Function to open the client end of a named pipe, waiting while every instance is busy
+    #[cfg(windows)]
+    fn connect_pipe(name: &str) -> io::Result<Self> {
+        const ERROR_PIPE_BUSY: i32 = 231;
+        let path = format!(r"\\.\pipe\{}", name);
+        let mut retries = 0;
+        loop {
+            match std::fs::OpenOptions::new().read(true).write(true).open(&path) {
+                Ok(pipe) => return Ok(Transport::Pipe(pipe)),
+                Err(err) if err.raw_os_error() == Some(ERROR_PIPE_BUSY) && retries < PIPE_BUSY_RETRIES => {
+                    retries += 1;
+                    std::thread::sleep(Duration::from_millis(50)); // The proxy creates the next instance right after accepting
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    // Function to reject named pipes where they do not exist
+    #[cfg(not(windows))]
+    fn connect_pipe(name: &str) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, format!("named pipes are only available on Windows (pipe:{})", name)))
+    }
+
+    /// Returns a second handle of the same connection, e.g. to read and write it separately.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            #[cfg(unix)]
+            Transport::Unix(stream) => stream.try_clone().map(Transport::Unix),
+            Transport::Tcp(stream) => stream.try_clone().map(Transport::Tcp),
+            #[cfg(windows)]
+            Transport::Pipe(pipe) => pipe.try_clone().map(Transport::Pipe),
+        }
+    }
+
+    /// Bounds how long a read may block; named pipes have no read timeouts and ignore it.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Transport::Unix(stream) => stream.set_read_timeout(timeout),
+            Transport::Tcp(stream) => stream.set_read_timeout(timeout),
+            #[cfg(windows)]
+            Transport::Pipe(_) => Ok(()),
+        }
+    }
+}
+
+impl Read for Transport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Transport::Unix(stream) => stream.read(buf),
+            Transport::Tcp(stream) => stream.read(buf),
+            #[cfg(windows)]
+            Transport::Pipe(pipe) => pipe.read(buf),
+        }
+    }
+}
+
+impl Write for Transport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Transport::Unix(stream) => stream.write(buf),
+            Transport::Tcp(stream) => stream.write(buf),
+            #[cfg(windows)]
+            Transport::Pipe(pipe) => pipe.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Transport::Unix(stream) => stream.flush(),
+            Transport::Tcp(stream) => stream.flush(),
+            #[cfg(windows)]
+            Transport::Pipe(pipe) => pipe.flush(),
+        }
+    }
+}
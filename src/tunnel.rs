@@ -0,0 +1,243 @@
+// Import necessary crates and modules
+use std::io::{self, Read, Write}; // For the SOCKS5 handshake and forwarding
+use std::net::{Shutdown, TcpListener, TcpStream}; // For the local end and the proxy connection
+use std::process::{Command, Stdio}; // For ssh jump hosts
+use std::thread; // For forwarding connections
+
+/// Environment variable naming a tunnel for every remote Redis URL without its own.
+pub const TUNNEL_ENV: &str = "RUSTREDIS_TUNNEL";
+
+// Define how a remote Redis server is reached
+#[derive(Clone, Debug)]
+enum Tunnel {
+    Socks5 { proxy: String, credentials: Option<(String, String)> }, // HOST:PORT of the proxy, user and password
+    Ssh { destination: String, port: Option<String> }, // [USER@]HOST of the jump host and its SSH port
+}
+
+// Function to parse a tunnel specification: socks5://[USER:PASSWORD@]HOST:PORT or ssh://[USER@]HOST[:PORT]
+fn parse_tunnel(spec: &str) -> Result<Tunnel, String> {
+    if let Some(rest) = spec.strip_prefix("socks5://") {
+        let (credentials, proxy) = match rest.rsplit_once('@') {
+            Some((userinfo, proxy)) => {
+                let (user, password) = userinfo.split_once(':').ok_or_else(|| format!("expected USER:PASSWORD in tunnel '{}'", spec))?;
+                (Some((user.to_string(), password.to_string())), proxy)
+            }
+            None => (None, rest),
+        };
+        let proxy = proxy.trim_end_matches('/');
+        if !proxy.contains(':') {
+            return Err(format!("missing port in tunnel '{}'", spec));
+        }
+        return Ok(Tunnel::Socks5 { proxy: proxy.to_string(), credentials });
+    }
+    if let Some(rest) = spec.strip_prefix("ssh://") {
+        let rest = rest.trim_end_matches('/');
+        let (destination, port) = match rest.rsplit_once(':') {
+            Some((destination, port)) => (destination, Some(port.to_string())),
+            None => (rest, None),
+        };
+        if destination.is_empty() {
+            return Err(format!("missing host in tunnel '{}'", spec));
+        }
+        return Ok(Tunnel::Ssh { destination: destination.to_string(), port });
+    }
+    Err(format!("unsupported tunnel '{}', expected socks5://HOST:PORT or ssh://[USER@]HOST[:PORT]", spec))
+}
+
+// Function to split a redis:// URL into its prefix (scheme and credentials), HOST:PORT, the
+// rest (database path) and the tunnel given with ?tunnel=
+fn split_url(url: &str) -> Result<(String, String, String, Option<String>), String> {
+    let (url, query) = url.split_once('?').map_or((url, None), |(url, query)| (url, Some(query)));
+    let mut tunnel = None;
+    let query: Vec<&str> = query.unwrap_or_default().split('&')
+        .filter(|param| !param.is_empty())
+        .filter(|param| match param.strip_prefix("tunnel=") {
+            Some(spec) => {
+                tunnel = Some(spec.to_string());
+                false
+            }
+            None => true,
+        })
+        .collect();
+
+    let scheme_end = url.find("://").ok_or_else(|| format!("invalid Redis URL '{}'", url))? + 3;
+    let authority_end = url[scheme_end..].find('/').map_or(url.len(), |pos| scheme_end + pos);
+    let authority = &url[scheme_end..authority_end];
+    let host_start = authority.rfind('@').map_or(scheme_end, |pos| scheme_end + pos + 1);
+    let host = &url[host_start..authority_end];
+    let host = if host.rsplit_once(':').is_some_and(|(_, port)| !port.contains(']')) { host.to_string() } else { format!("{}:6379", host) };
+    let mut rest = url[authority_end..].to_string();
+    if !query.is_empty() {
+        rest = format!("{}?{}", rest, query.join("&"));
+    }
+    Ok((url[..host_start].to_string(), host, rest, tunnel))
+}
+
+// Function to check if a HOST:PORT is on this machine, where no tunnel is needed
+fn is_local(host_port: &str) -> bool {
+    let host = host_port.rsplit_once(':').map_or(host_port, |(host, _)| host);
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+/// Returns the URL to connect to for a Redis URL, starting a tunnel if it needs one.
+///
+/// A tunnel is given with a `tunnel` query parameter (`redis://central:6379/0?tunnel=socks5://10.0.0.1:1080`
+/// or `?tunnel=ssh://ops@jump.example.com`), or for every remote `redis://` URL with the
+/// [`TUNNEL_ENV`] environment variable. Tunneled connections go through a forwarder on a local
+/// port, so the returned URL points at 127.0.0.1; other URLs are returned unchanged.
+pub fn resolve(url: &str) -> Result<String, String> {
+    if !url.starts_with("redis://") && !url.starts_with("rediss://") {
+        return Ok(url.to_string()); // Unix sockets are local
+    }
+    let (prefix, target, rest, tunnel) = split_url(url)?;
+    let tunnel = match tunnel {
+        Some(spec) => spec,
+        None => match std::env::var(TUNNEL_ENV) {
+            Ok(spec) if !spec.is_empty() && !is_local(&target) => spec,
+            _ => return Ok(url.to_string()),
+        },
+    };
+    if prefix.starts_with("rediss://") {
+        return Err(format!("tunnels are not supported with rediss:// URLs ({}), TLS would check the certificate against 127.0.0.1", target));
+    }
+    let tunnel = parse_tunnel(&tunnel)?;
+    let port = start_forwarder(tunnel, target.clone()).map_err(|e| format!("failed to start the tunnel to {}: {}", target, e))?;
+    Ok(format!("{}127.0.0.1:{}{}", prefix, port, rest))
+}
+
+/// Checks the tunnel a Redis URL would use (see [`resolve`]) without starting it.
+pub fn check(url: &str) -> Result<(), String> {
+    if !url.starts_with("redis://") && !url.starts_with("rediss://") {
+        return Ok(());
+    }
+    let (prefix, target, _, tunnel) = split_url(url)?;
+    let Some(tunnel) = tunnel.or_else(|| std::env::var(TUNNEL_ENV).ok().filter(|spec| !spec.is_empty() && !is_local(&target))) else {
+        return Ok(());
+    };
+    if prefix.starts_with("rediss://") {
+        return Err(format!("tunnels are not supported with rediss:// URLs ({})", target));
+    }
+    parse_tunnel(&tunnel).map(drop)
+}
+
+/// Opens a Redis client for a URL, through its tunnel if it has one (see [`resolve`]).
+pub fn open(url: &str) -> redis::RedisResult<redis::Client> {
+    let url = resolve(url).map_err(|e| redis::RedisError::from((redis::ErrorKind::InvalidClientConfig, "invalid tunnel", e)))?;
+    redis::Client::open(url.as_str())
+}
+
+// Function to listen on a local port and forward every connection to the target through the tunnel
+fn start_forwarder(tunnel: Tunnel, target: String) -> io::Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    thread::spawn(move || {
+        for client in listener.incoming().flatten() {
+            let (tunnel, target) = (tunnel.clone(), target.clone());
+            thread::spawn(move || {
+                if let Err(err) = forward(client, &tunnel, &target) {
+                    eprintln!("Tunnel to {} failed: {}", target, err);
+                }
+            });
+        }
+    });
+    Ok(port)
+}
+
+// Function to forward one local connection to the target through the tunnel until either side closes
+fn forward(client: TcpStream, tunnel: &Tunnel, target: &str) -> io::Result<()> {
+    match tunnel {
+        Tunnel::Socks5 { proxy, credentials } => {
+            let upstream = socks5_connect(proxy, credentials.as_ref(), target)?;
+            let from_target = upstream.try_clone()?;
+            splice(client, from_target, upstream, |upstream| {
+                let _ = upstream.shutdown(Shutdown::Write);
+            })
+        }
+        Tunnel::Ssh { destination, port } => {
+            let mut command = Command::new("ssh");
+            command.args(["-o", "BatchMode=yes", "-W", target]);
+            if let Some(port) = port {
+                command.args(["-p", port]);
+            }
+            let mut child = command.arg(destination).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+            let (stdin, stdout) = (child.stdin.take().unwrap(), child.stdout.take().unwrap());
+            let result = splice(client, stdout, stdin, drop); // Closing stdin ends ssh -W
+            let _ = child.kill();
+            let _ = child.wait();
+            result
+        }
+    }
+}
+
+// Function to copy bytes both ways between the local connection and the target, closing each
+// direction when its source ends
+fn splice<R, W>(client: TcpStream, mut from_target: R, mut to_target: W, close_target: impl FnOnce(W) + Send + 'static) -> io::Result<()>
+where
+    R: Read,
+    W: Write + Send + 'static,
+{
+    let mut client_reader = client.try_clone()?;
+    let upload = thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut to_target);
+        close_target(to_target);
+    });
+    let mut client_writer = client;
+    let _ = io::copy(&mut from_target, &mut client_writer);
+    let _ = client_writer.shutdown(Shutdown::Both); // Also ends the upload once the target is gone
+    let _ = upload.join();
+    Ok(())
+}
+
+// Function to connect to the target through a SOCKS5 proxy (RFC 1928, with RFC 1929 passwords)
+fn socks5_connect(proxy: &str, credentials: Option<&(String, String)>, target: &str) -> io::Result<TcpStream> {
+    let refused = |message: &str| io::Error::new(io::ErrorKind::ConnectionRefused, format!("SOCKS5 proxy {}: {}", proxy, message));
+    let mut stream = TcpStream::connect(proxy)?;
+    stream.set_nodelay(true)?;
+
+    // Offer no authentication, and username/password if we have credentials
+    let method = if credentials.is_some() { 2 } else { 0 };
+    stream.write_all(&[5, 1, method])?;
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply)?;
+    if reply != [5, method] {
+        return Err(refused("no acceptable authentication method"));
+    }
+    if let Some((user, password)) = credentials {
+        let mut auth = vec![1, user.len() as u8];
+        auth.extend(user.as_bytes());
+        auth.push(password.len() as u8);
+        auth.extend(password.as_bytes());
+        stream.write_all(&auth)?;
+        stream.read_exact(&mut reply)?;
+        if reply[1] != 0 {
+            return Err(refused("authentication failed"));
+        }
+    }
+
+    // CONNECT to the target by name, so the proxy resolves it
+    let (host, port) = target.rsplit_once(':').ok_or_else(|| refused("invalid target"))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port: u16 = port.parse().map_err(|_| refused("invalid target port"))?;
+    let mut request = vec![5, 1, 0, 3, host.len() as u8];
+    request.extend(host.as_bytes());
+    request.extend(port.to_be_bytes());
+    stream.write_all(&request)?;
+    let mut header = [0; 4];
+    stream.read_exact(&mut header)?;
+    if header[1] != 0 {
+        return Err(refused(&format!("CONNECT to {} failed with reply {}", target, header[1])));
+    }
+    let address_len = match header[3] {
+        1 => 4, // IPv4
+        4 => 16, // IPv6
+        3 => {
+            let mut len = [0; 1];
+            stream.read_exact(&mut len)?;
+            len[0] as usize
+        }
+        _ => return Err(refused("invalid bound address")),
+    };
+    let mut bound = vec![0; address_len + 2]; // Bound address and port, not needed
+    stream.read_exact(&mut bound)?;
+    Ok(stream)
+}
@@ -0,0 +1,48 @@
+//! Payload types of the producer objects with a built-in schema.
+//!
+//! Generated by `schema_codegen`; do not edit by hand.
+
+use crate::client::{ClientError, ProducerObject, ProxyClient};
+use serde::{Deserialize, Serialize};
+
+/// Value of `cs:DiskUsage:object1`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiskUsageObject1 {
+    pub disk: String,
+    pub usage: f64,
+    pub version: f64,
+}
+
+impl ProducerObject for DiskUsageObject1 {
+    const BASE_KEY: &'static str = "cs:DiskUsage:object1";
+}
+
+/// Value of `cs:ModemWatcher:object2`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ModemWatcherObject2 {
+    pub signal_strength: i64,
+    pub status: String,
+    pub version: f64,
+}
+
+impl ProducerObject for ModemWatcherObject2 {
+    const BASE_KEY: &'static str = "cs:ModemWatcher:object2";
+}
+
+/// Typed setters of the producer objects, checked against their schemas at compile time.
+pub trait TypedProducers {
+    /// Stores a `cs:DiskUsage:object1` value (under `cs:DiskUsage:object1:<id>` with an id).
+    fn set_disk_usage_object1(&mut self, id: Option<&str>, value: &DiskUsageObject1) -> Result<(), ClientError>;
+    /// Stores a `cs:ModemWatcher:object2` value (under `cs:ModemWatcher:object2:<id>` with an id).
+    fn set_modem_watcher_object2(&mut self, id: Option<&str>, value: &ModemWatcherObject2) -> Result<(), ClientError>;
+}
+
+impl TypedProducers for ProxyClient {
+    fn set_disk_usage_object1(&mut self, id: Option<&str>, value: &DiskUsageObject1) -> Result<(), ClientError> {
+        self.set_object(id, value)
+    }
+
+    fn set_modem_watcher_object2(&mut self, id: Option<&str>, value: &ModemWatcherObject2) -> Result<(), ClientError> {
+        self.set_object(id, value)
+    }
+}
@@ -0,0 +1,94 @@
+// Tests of the field constraint shortcuts compiled into JSON schemas
+use rustredis::schema::{add_constraints, compile_constraints, FieldConstraint};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+// Function to compile constraint specifications, panicking on a malformed one
+fn compile(specs: &[&str]) -> Result<HashMap<String, Value>, String> {
+    let constraints: Vec<FieldConstraint> = specs.iter().map(|spec| FieldConstraint::parse(spec).unwrap()).collect();
+    compile_constraints(&constraints)
+}
+
+#[test]
+fn constraints_compile_into_one_object_schema_per_base_key() {
+    let schemas = compile(&[
+        "cs:ModemWatcher:object2=signal_strength:integer,min=-120,max=0",
+        "cs:ModemWatcher:object2=status:string,enum=up|down|unknown",
+        "cs:ModemWatcher:object2=operator:string,min=1,max=32,optional",
+        "cs:DiskUsage:object1=usage:number,min=0,max=99.5",
+    ])
+    .unwrap();
+    assert_eq!(schemas.len(), 2);
+    assert_eq!(schemas["cs:ModemWatcher:object2"], json!({
+        "type": "object",
+        "properties": {
+            "signal_strength": {"type": "integer", "minimum": -120, "maximum": 0},
+            "status": {"type": "string", "enum": ["up", "down", "unknown"]},
+            "operator": {"type": "string", "minLength": 1, "maxLength": 32},
+        },
+        "required": ["signal_strength", "status"],
+    }));
+    assert_eq!(schemas["cs:DiskUsage:object1"]["properties"]["usage"], json!({"type": "number", "minimum": 0, "maximum": 99.5}));
+}
+
+#[test]
+fn enum_values_take_the_field_type() {
+    let schemas = compile(&[
+        "cs:Psmon:object1=level:integer,enum=1|2|3",
+        "cs:Psmon:object1=ratio:number,enum=0.5|1",
+        "cs:Psmon:object1=enabled:boolean,enum=true",
+        "cs:Psmon:object1=items:array,max=4",
+    ])
+    .unwrap();
+    let properties = &schemas["cs:Psmon:object1"]["properties"];
+    assert_eq!(properties["level"]["enum"], json!([1, 2, 3]));
+    assert_eq!(properties["ratio"]["enum"], json!([0.5, 1.0]));
+    assert_eq!(properties["enabled"]["enum"], json!([true]));
+    assert_eq!(properties["items"], json!({"type": "array", "maxItems": 4}));
+}
+
+#[test]
+fn malformed_specifications_are_rejected() {
+    for spec in [
+        "signal_strength:integer", // No base key
+        "cs:ModemWatcher:object2=signal_strength", // No type
+        "cs:ModemWatcher:object2=:integer", // No field
+        "cs:ModemWatcher:object2=signal_strength:int", // Unknown type
+        "cs:ModemWatcher:object2=signal_strength:integer,min=low", // Bound not a number
+        "cs:ModemWatcher:object2=signal_strength:integer,min=5,max=1", // Empty range
+        "cs:ModemWatcher:object2=signal_strength:integer,enum=1|x", // Enum value not an integer
+        "cs:ModemWatcher:object2=online:boolean,max=1", // Booleans take no bounds
+        "cs:ModemWatcher:object2=sim:object,enum=a", // Objects take no enum
+        "cs:ModemWatcher:object2=status:string,unique", // Unknown option
+    ] {
+        assert!(FieldConstraint::parse(spec).is_err(), "{} was accepted", spec);
+    }
+}
+
+#[test]
+fn constraints_on_invalid_or_repeated_targets_are_rejected() {
+    assert!(compile(&["cs:Unknown:object1=a:string"]).is_err()); // Unknown producer
+    assert!(compile(&["cs:ModemWatcher:object2:id=a:string"]).is_err()); // Not a base key
+    assert!(compile(&["cs:ModemWatcher:object2=a:string", "cs:ModemWatcher:object2=a:integer"]).is_err());
+}
+
+#[test]
+fn constraints_may_not_redefine_a_schema_file() {
+    let constraints = [FieldConstraint::parse("cs:DiskUsage:object1=usage:number").unwrap()];
+    let mut active = HashMap::from([("cs:DiskUsage:object1".to_string(), json!({"type": "object"}))]);
+    assert!(add_constraints(&mut active, &constraints).is_err());
+    let mut active = HashMap::from([("cs:Psmon:object1".to_string(), json!({"type": "object"}))]);
+    add_constraints(&mut active, &constraints).unwrap();
+    assert_eq!(active.len(), 2);
+}
+
+#[test]
+fn compiled_schemas_validate_values() {
+    let schemas = compile(&["cs:ModemWatcher:object2=signal_strength:integer,min=-120,max=0", "cs:ModemWatcher:object2=status:string,enum=up|down"]).unwrap();
+    let schema = jsonschema::JSONSchema::compile(&schemas["cs:ModemWatcher:object2"]).unwrap();
+    assert!(schema.is_valid(&json!({"signal_strength": -70, "status": "up"})));
+    assert!(!schema.is_valid(&json!({"signal_strength": -130, "status": "up"})));
+    assert!(!schema.is_valid(&json!({"signal_strength": -70.5, "status": "up"})));
+    assert!(!schema.is_valid(&json!({"signal_strength": -70, "status": "sideways"})));
+    assert!(!schema.is_valid(&json!({"status": "up"})));
+}
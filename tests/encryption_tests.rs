@@ -0,0 +1,78 @@
+// Tests of the AES-256-GCM sealing of sensitive values and of encryption key files
+use rustredis::base64;
+use rustredis::encryption::{is_encrypted, ValueCipher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Define a counter giving every key file of the test run its own name
+static KEY_FILES: AtomicUsize = AtomicUsize::new(0);
+
+// Function to write a key file with the given contents, returning its path
+fn key_file(contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rustredis-key-{}-{}", std::process::id(), KEY_FILES.fetch_add(1, Ordering::Relaxed)));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+// Function to load a cipher from a key file with the given contents
+fn cipher(contents: &[u8]) -> Result<ValueCipher, String> {
+    let path = key_file(contents);
+    let cipher = ValueCipher::from_key_file(&path);
+    std::fs::remove_file(path).unwrap();
+    cipher
+}
+
+#[test]
+fn values_round_trip() {
+    let cipher = cipher(&[7; 32]).unwrap();
+    let value = br#"{"pin":"1234"}"#;
+    let stored = cipher.encrypt("cs:Vault:object1", value);
+    assert!(is_encrypted(&stored));
+    assert!(!stored.windows(value.len()).any(|window| window == value)); // No plaintext left in the stored value
+    assert_eq!(cipher.decrypt("cs:Vault:object1", &stored).unwrap().as_ref(), value);
+    assert_ne!(stored, cipher.encrypt("cs:Vault:object1", value)); // Every write gets a fresh nonce
+}
+
+#[test]
+fn ciphertexts_do_not_decrypt_under_another_key() {
+    let (cipher, other) = (cipher(&[7; 32]).unwrap(), cipher(&[8; 32]).unwrap());
+    let stored = cipher.encrypt("cs:Vault:object1", b"secret");
+    let err = cipher.decrypt("cs:Vault:object2", &stored).unwrap_err();
+    assert!(err.contains("failed authentication"), "{}", err);
+    assert!(other.decrypt("cs:Vault:object1", &stored).is_err()); // Nor under another encryption key
+}
+
+#[test]
+fn damaged_values_are_rejected() {
+    let cipher = cipher(&[7; 32]).unwrap();
+    let stored = cipher.encrypt("cs:Vault:object1", b"secret");
+    assert_eq!(cipher.decrypt("cs:Vault:object1", &stored[..10]).unwrap_err(), "truncated encrypted value"); // Inside the nonce
+    assert!(cipher.decrypt("cs:Vault:object1", &stored[..stored.len() - 1]).is_err()); // Inside the tag
+    let mut tampered = stored.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(cipher.decrypt("cs:Vault:object1", &tampered).is_err());
+}
+
+#[test]
+fn values_without_the_marker_pass_through() {
+    let cipher = cipher(&[7; 32]).unwrap();
+    for plain in [&b"{\"pin\":\"1234\"}"[..], b"", b"aes", b"\0ae"] {
+        assert!(!is_encrypted(plain));
+        assert_eq!(cipher.decrypt("cs:Vault:object1", plain).unwrap().as_ref(), plain);
+    }
+}
+
+#[test]
+fn key_files_hold_32_raw_or_base64_bytes() {
+    let key: Vec<u8> = (0..32).collect();
+    let raw = cipher(&key).unwrap();
+    let encoded = cipher(format!("{}\n", base64::encode(&key)).as_bytes()).unwrap(); // Trailing newline of a text file
+    assert_eq!(encoded.decrypt("cs:Vault:object1", &raw.encrypt("cs:Vault:object1", b"secret")).unwrap().as_ref(), b"secret");
+
+    for contents in [&key[..31], &[7; 33][..], b"", base64::encode(&key[..16]).as_bytes(), b"not base64 at all!"] {
+        let err = cipher(contents).err().expect("key file accepted");
+        assert!(err.contains("must hold a 32 byte key"), "{}", err);
+    }
+    let missing = std::env::temp_dir().join("rustredis-key-missing");
+    assert!(ValueCipher::from_key_file(&missing).err().expect("missing key file accepted").starts_with("failed to read"));
+}
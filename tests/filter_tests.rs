@@ -0,0 +1,82 @@
+// Tests of the filter expressions selecting events for subscribers
+use rustredis::filter::Filter;
+use serde_json::{json, Value};
+
+// Function to check whether an expression passes an event
+fn passes(expr: &str, event: &Value) -> bool {
+    Filter::parse(expr).unwrap_or_else(|err| panic!("{}: {}", expr, err)).matches(event)
+}
+
+// Function to build a set event of a modem
+fn modem(value: Value) -> Value {
+    json!({"key": "cs:ModemWatcher:object2:wan0", "action": "set", "value": value})
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    let event = modem(json!({"signal": -95, "status": "down"}));
+    // true or (false and ...) passes, while (true or false) and false would not
+    assert!(passes("value.signal < -90 or value.status == up and value.signal > 0", &event));
+    // (false and ...) or true
+    assert!(passes("value.status == up and value.signal < -90 or action == set", &event));
+    // false or (true and false)
+    assert!(!passes("value.signal > 0 or value.status == down and action == del", &event));
+}
+
+#[test]
+fn quoted_operands_are_always_strings() {
+    let event = modem(json!({"code": "42", "level": 42, "flag": "true", "name": "and"}));
+    assert!(passes(r#"value.code == "42""#, &event));
+    assert!(!passes("value.code == 42", &event)); // Bare numbers are numbers
+    assert!(passes("value.level == 42", &event));
+    assert!(!passes(r#"value.level == "42""#, &event));
+    assert!(passes(r#"value.flag == "true""#, &event));
+    assert!(!passes("value.flag == true", &event)); // Bare true is a boolean
+    assert!(passes(r#"value.name == "and""#, &event)); // Quoted keywords are operands, not joiners
+    assert!(passes("value.name == and", &event)); // In operand position a bare word is a string
+}
+
+#[test]
+fn quoted_operands_keep_spaces_and_escapes() {
+    let event = modem(json!({"label": "north \"roof\" mast"}));
+    assert!(passes(r#"value.label == "north \"roof\" mast""#, &event));
+    assert!(passes(r#"value.label matches "north*""#, &event));
+    assert!(passes("key matches cs:ModemWatcher:*", &event));
+}
+
+#[test]
+fn missing_fields_only_equal_null() {
+    let event = modem(json!({"signal": -70}));
+    assert!(!passes("value.battery < 20", &event));
+    assert!(!passes("value.battery >= 20", &event));
+    assert!(!passes("value.battery == 0", &event));
+    assert!(passes("value.battery != 0", &event));
+    assert!(passes("value.battery == null", &event));
+    assert!(!passes("value.signal.strength < 0", &event)); // Path through a number
+    assert!(!passes("value.battery < 20", &json!({"key": "cs:ModemWatcher:object2", "action": "del"}))); // No value at all
+}
+
+#[test]
+fn comparisons_need_operands_of_the_same_kind() {
+    let event = modem(json!({"signal": -70, "status": "up"}));
+    assert!(passes("value.signal >= -70 and value.signal <= -70", &event));
+    assert!(!passes("value.status > 0", &event));
+    assert!(passes("value.status > down", &event)); // Strings compare lexically
+}
+
+#[test]
+fn malformed_expressions_are_rejected() {
+    for expr in [
+        "",
+        "value.signal <",
+        "value.signal < -90 and",
+        "value.signal < -90 nor action == set",
+        "payload.signal < -90", // Unknown field
+        "key.id == x", // key has no subfields
+        "value.signal ~ -90", // Unknown operator
+        r#""value" == x"#, // Quoted field
+        r#"key == "unterminated"#,
+    ] {
+        assert!(Filter::parse(expr).is_err(), "{:?} was accepted", expr);
+    }
+}
@@ -0,0 +1,80 @@
+// Property tests of the proxy wire framing as spoken by the client library
+use proptest::prelude::*;
+use rustredis::base64;
+use rustredis::client::ProxyClient;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+// Define a counter giving every fake proxy its own socket path
+static SOCKETS: AtomicUsize = AtomicUsize::new(0);
+
+// Strategy for arbitrary JSON values, including strings with newlines and quotes
+fn json_value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        any::<i32>().prop_map(|n| Value::from(n as f64 / 8.0)), // Exactly representable, so parsing restores them
+        "(?s).{0,16}".prop_map(Value::from),
+    ];
+    leaf.prop_recursive(3, 32, 6, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(Value::from),
+            prop::collection::btree_map("(?s).{0,8}", inner, 0..6).prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+// Function to start a fake proxy that answers hello, then echoes each request back as the payload of its
+// response, preceded by a keepalive ping when `ping` is set; returns its socket path
+fn fake_proxy(ping: bool) -> String {
+    let path = std::env::temp_dir().join(format!("rustredis-framing-{}-{}.sock", std::process::id(), SOCKETS.fetch_add(1, Ordering::Relaxed)));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut lines = BufReader::new(stream.try_clone().unwrap()).lines();
+        let mut reply = |response: Value| stream.write_all(format!("{}\n", response).as_bytes()).unwrap();
+        let hello: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+        assert_eq!(hello["action"], "hello");
+        reply(json!({"status": "ok", "message": "Hello"}));
+        while let Some(Ok(line)) = lines.next() {
+            let request: Value = serde_json::from_str(&line).expect("one JSON request per line");
+            if ping {
+                reply(json!({"status": "ping", "message": "keepalive"}));
+                let pong: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+                assert_eq!(pong["action"], "pong");
+            }
+            reply(json!({"status": "ok", "message": "echo", "data": request}));
+        }
+    });
+    path.to_string_lossy().into_owned()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn requests_round_trip_as_single_lines(values in prop::collection::vec(json_value(), 1..8), ping in any::<bool>()) {
+        let mut client = ProxyClient::connect(&fake_proxy(ping)).unwrap();
+        for value in values {
+            let request = json!({"action": "set", "key": "cs:DiskUsage:object1", "value": value});
+            let response = client.request(&request).unwrap();
+            prop_assert!(response.is_ok());
+            prop_assert_eq!(response.data, Some(request));
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn base64_round_trips(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+        let text = base64::encode(&bytes);
+        prop_assert_eq!(text.len() % 4, 0);
+        prop_assert_eq!(base64::decode(&text).unwrap(), bytes.clone());
+        prop_assert_eq!(base64::decode(text.trim_end_matches('=')).unwrap(), bytes);
+    }
+}
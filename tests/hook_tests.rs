@@ -0,0 +1,72 @@
+// Tests of the --hook lifecycle hook specifications and the changes hooks run on
+use rustredis::hooks::{Action, Change, HookConfig, On};
+
+// Function to parse a hook specification, panicking on a malformed one
+fn parse(spec: &str) -> HookConfig {
+    HookConfig::parse(spec).unwrap_or_else(|err| panic!("{}: {}", spec, err))
+}
+
+#[test]
+fn every_action_parses_with_its_options() {
+    let hook = parse("cs:ModemWatcher:*=create:publish,channel=new:{producer}");
+    assert_eq!((hook.pattern.as_str(), hook.on), ("cs:ModemWatcher:*", On::Create));
+    assert_eq!(hook.action, Action::Publish { channel: "new:{producer}".to_string() });
+    assert_eq!(hook.templates(), ["new:{producer}"]);
+
+    assert_eq!(parse("cs:*=delete:incr,key=deletes:{producer}").action, Action::Incr { key: "deletes:{producer}".to_string() });
+    assert_eq!(parse("cs:*=update:stream,key=history:{key},maxlen=50").action, Action::Stream { key: "history:{key}".to_string(), maxlen: 50 });
+    assert_eq!(parse("cs:*=write:stream,key=history:{key}").action, Action::Stream { key: "history:{key}".to_string(), maxlen: 10000 });
+    let webhook = parse("cs:Psmon:*=write:webhook,url=http://10.0.0.2:8080/hook");
+    assert_eq!(webhook.action, Action::Webhook { url: "http://10.0.0.2:8080/hook".to_string() });
+    assert!(webhook.templates().is_empty()); // URLs are not templates
+}
+
+#[test]
+fn maxlen_is_only_taken_by_stream_hooks() {
+    for spec in ["cs:*=write:publish,channel=c,maxlen=5", "cs:*=write:incr,key=k,maxlen=5", "cs:*=write:webhook,url=http://h/,maxlen=5"] {
+        let err = HookConfig::parse(spec).unwrap_err();
+        assert!(err.contains("takes"), "{}: {}", spec, err);
+    }
+    assert!(HookConfig::parse("cs:*=write:stream,key=k,maxlen=-1").unwrap_err().contains("invalid maxlen"));
+    assert!(HookConfig::parse("cs:*=write:stream,key=k,maxlen=").unwrap_err().contains("invalid maxlen"));
+}
+
+#[test]
+fn webhook_urls_must_be_http() {
+    for url in ["https://hooks.example.com/", "ftp://example.com/", "example.com/hook", ""] {
+        let err = HookConfig::parse(&format!("cs:*=write:webhook,url={}", url)).unwrap_err();
+        assert!(err.contains("only http://"), "{}: {}", url, err);
+    }
+}
+
+#[test]
+fn malformed_specifications_are_rejected() {
+    for spec in [
+        "cs:*", // No event or action
+        "cs:*=write", // No action
+        "cs:*=modify:publish,channel=c", // Unknown event
+        "cs:*=write:notify,channel=c", // Unknown action
+        "cs:*=write:publish", // No channel
+        "cs:*=write:publish,channel=", // Empty template
+        "cs:*=write:publish,key=k", // Option of another action
+        "cs:*=write:incr,key=k,channel=c", // Two targets
+        "cs:*=write:stream,key=k,ttl=60", // Unknown option
+        "cs:*=write:stream,key=k,maxlen", // Option without a value
+    ] {
+        assert!(HookConfig::parse(spec).is_err(), "{} was accepted", spec);
+    }
+}
+
+#[test]
+fn hooks_run_on_their_changes_only() {
+    let changes = [Change::Created, Change::Updated, Change::Written, Change::Deleted];
+    for (on, runs) in [
+        (On::Write, [true, true, true, false]),
+        (On::Create, [true, false, false, false]),
+        (On::Update, [false, true, false, false]),
+        (On::Delete, [false, false, false, true]),
+    ] {
+        let ran: Vec<bool> = changes.iter().map(|change| on.runs_on(*change)).collect();
+        assert_eq!(ran, runs, "{:?}", on);
+    }
+}
@@ -0,0 +1,98 @@
+// Tests of the log-linear latency histogram the benchmarks report from
+use rustredis::latency::Histogram;
+use std::time::Duration;
+
+// Function to build a histogram of the given microsecond samples
+fn histogram(samples: impl IntoIterator<Item = u64>) -> Histogram {
+    let mut histogram = Histogram::new();
+    for us in samples {
+        histogram.record(Duration::from_micros(us));
+    }
+    histogram
+}
+
+// Function to return the value the histogram reports for a sample: the highest value of its bucket,
+// seen as the median next to a much larger sample (reported values are clamped to the largest sample)
+fn reported(us: u64) -> u64 {
+    histogram([us, us * 2 + 1000]).percentile(50.0)
+}
+
+#[test]
+fn values_below_128_us_are_exact() {
+    for us in 0..128 {
+        assert_eq!(reported(us), us);
+    }
+}
+
+#[test]
+fn buckets_split_each_power_of_two_in_64() {
+    // 128..255 in buckets of 2, 256..511 in buckets of 4
+    assert_eq!(reported(128), 129);
+    assert_eq!(reported(129), 129);
+    assert_eq!(reported(130), 131);
+    assert_eq!(reported(255), 255);
+    assert_eq!(reported(256), 259);
+    assert_eq!(reported(259), 259);
+    assert_eq!(reported(260), 263);
+    assert_eq!(reported(511), 511);
+    assert_eq!(reported(512), 519);
+}
+
+#[test]
+fn reported_values_stay_within_the_bucket_error() {
+    for us in [1_000, 12_345, 999_999, 60_000_000] {
+        let value = reported(us);
+        assert!(value >= us && (value - us) as f64 <= us as f64 / 64.0, "{} reported as {}", us, value);
+    }
+}
+
+#[test]
+fn percentiles_of_known_samples() {
+    let exact = histogram(1..=100);
+    assert_eq!(exact.percentile(50.0), 50);
+    assert_eq!(exact.percentile(95.0), 95);
+    assert_eq!(exact.percentile(99.0), 99);
+    assert_eq!(exact.percentile(100.0), 100);
+    let summary = exact.summary();
+    assert_eq!((summary["count"].as_u64(), summary["min"].as_u64(), summary["max"].as_u64()), (Some(100), Some(1), Some(100)));
+    assert_eq!(summary["p95"].as_u64(), Some(95));
+
+    let bucketed = histogram((1..=1000).map(|i| i * 10)); // 10us .. 10ms
+    for (percentile, expected) in [(50.0, 5_000), (95.0, 9_500), (99.0, 9_900)] {
+        let value = bucketed.percentile(percentile);
+        assert!(value >= expected && value - expected <= expected / 64, "p{} was {}, expected {}", percentile, value, expected);
+    }
+    assert_eq!(bucketed.percentile(100.0), 10_000);
+}
+
+#[test]
+fn empty_histograms_report_zero() {
+    let empty = Histogram::new();
+    assert_eq!(empty.count(), 0);
+    assert_eq!(empty.percentile(99.0), 0);
+    assert_eq!(empty.bars(40), "");
+}
+
+#[test]
+fn merged_histograms_match_one_recording_everything() {
+    let mut merged = histogram(1..=500);
+    merged.merge(&histogram(501..=1000));
+    let whole = histogram(1..=1000);
+    assert_eq!(merged.count(), 1000);
+    for percentile in [50.0, 95.0, 99.0, 100.0] {
+        assert_eq!(merged.percentile(percentile), whole.percentile(percentile));
+    }
+}
+
+#[test]
+fn bars_have_one_line_per_power_of_two_including_empty_ranges() {
+    let bars = histogram([1, 3, 3, 10]).bars(10);
+    let lines: Vec<&str> = bars.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", bars);
+    let lower_bounds: Vec<&str> = lines.iter().map(|line| line.split_whitespace().next().unwrap()).collect();
+    assert_eq!(lower_bounds, ["1", "2", "4", "8"]);
+    assert!(lines[0].contains("|#####     |"), "{}", lines[0]); // Half the largest count
+    assert!(lines[1].contains("|##########|"), "{}", lines[1]); // The largest count fills the width
+    assert!(lines[2].contains("|          |"), "{}", lines[2]); // 4..7 us is empty
+    assert!(lines[3].ends_with("25.00%"), "{}", lines[3]);
+}
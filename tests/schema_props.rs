@@ -0,0 +1,87 @@
+// Property tests of the key grammar and schema validation shared by the proxy and its tools
+use proptest::prelude::*;
+use rustredis::schema::{base_key, is_valid_key, key_producer, pattern_producer, validate_json_schema, VALID_OBJECTS, VALID_PRODUCERS};
+use serde_json::{json, Value};
+
+// Strategy for one of the known producers
+fn producer() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(VALID_PRODUCERS.clone())
+}
+
+// Strategy for one of the known objects
+fn object() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(VALID_OBJECTS.clone())
+}
+
+// Strategy for keys of the form cs:<producer>:<object>[:<id>[:<function>]]
+fn valid_key() -> impl Strategy<Value = (String, &'static str, &'static str)> {
+    (producer(), object(), prop::option::of(("[A-Za-z0-9_]{1,12}", prop::option::of("[A-Za-z0-9_]{1,12}"))))
+        .prop_map(|(producer, object, suffix)| {
+            let key = match suffix {
+                None => format!("cs:{}:{}", producer, object),
+                Some((id, None)) => format!("cs:{}:{}:{}", producer, object, id),
+                Some((id, Some(function))) => format!("cs:{}:{}:{}:{}", producer, object, id, function),
+            };
+            (key, producer, object)
+        })
+}
+
+// Strategy for valid cs:DiskUsage:object1 payloads
+fn disk_usage() -> impl Strategy<Value = Value> {
+    (-1e6f64..1e6, "[ -~]{0,32}", 0f64..100.0).prop_map(|(version, disk, usage)| json!({"version": version, "disk": disk, "usage": usage}))
+}
+
+proptest! {
+    #[test]
+    fn valid_keys_are_accepted((key, producer, object) in valid_key()) {
+        prop_assert!(is_valid_key(&key));
+        prop_assert_eq!(key_producer(&key), Some(producer));
+        prop_assert_eq!(base_key(&key), format!("cs:{}:{}", producer, object));
+    }
+
+    #[test]
+    fn unknown_producers_are_rejected(name in "[A-Za-z]{1,16}", object in object()) {
+        prop_assume!(!VALID_PRODUCERS.contains(&name.as_str()));
+        let key = format!("cs:{}:{}", name, object);
+        prop_assert!(!is_valid_key(&key));
+        prop_assert_eq!(key_producer(&key), None);
+    }
+
+    #[test]
+    fn malformed_keys_are_rejected((key, _, _) in valid_key(), junk in "[ :./*-]{1,4}", at_end in any::<bool>()) {
+        let malformed = if at_end { format!("{}{}", key, junk) } else { format!("{}{}", junk, key) };
+        prop_assert!(!is_valid_key(&malformed));
+    }
+
+    #[test]
+    fn namespace_patterns_name_their_producer(producer in producer(), tail in "[A-Za-z0-9_*?:]{1,16}") {
+        prop_assert_eq!(pattern_producer(&format!("cs:{}:{}", producer, tail)), Some(producer));
+        prop_assert_eq!(pattern_producer(&format!("cs:{}:", producer)), None);
+        prop_assert_eq!(pattern_producer(&format!("cs:*{}:{}", producer, tail)), None);
+    }
+
+    #[test]
+    fn conforming_payloads_validate(value in disk_usage(), id in prop::option::of("[a-z0-9]{1,8}")) {
+        let key = id.map_or("cs:DiskUsage:object1".to_string(), |id| format!("cs:DiskUsage:object1:{}", id));
+        prop_assert!(validate_json_schema(&key, &value).is_ok());
+    }
+
+    #[test]
+    fn payloads_missing_a_required_field_are_rejected(value in disk_usage(), field in prop::sample::select(vec!["version", "disk", "usage"])) {
+        let mut value = value;
+        value.as_object_mut().unwrap().remove(field);
+        prop_assert!(validate_json_schema("cs:DiskUsage:object1", &value).is_err());
+    }
+
+    #[test]
+    fn payloads_with_a_mistyped_field_are_rejected(value in disk_usage(), text in "[a-z]{0,8}") {
+        let mut value = value;
+        value["usage"] = Value::String(text);
+        prop_assert!(validate_json_schema("cs:DiskUsage:object1", &value).is_err());
+    }
+
+    #[test]
+    fn keys_without_a_schema_accept_anything(value in any::<i64>().prop_map(Value::from)) {
+        prop_assert!(validate_json_schema("cs:Psmon:object1", &value).is_ok());
+    }
+}
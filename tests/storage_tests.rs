@@ -0,0 +1,103 @@
+// Tests of the in-memory store standing in for Redis behind the proxy
+use redis::streams::StreamRangeReply;
+use redis::{Commands, ConnectionLike};
+use rustredis::storage::{MemoryStore, Storage};
+use std::collections::HashMap;
+
+#[test]
+fn strings_expire_and_count() {
+    let mut conn = MemoryStore::new();
+    let _: () = conn.set("cs:Psmon:object1", "12").unwrap();
+    assert_eq!(conn.get::<_, Option<String>>("cs:Psmon:object1").unwrap().as_deref(), Some("12"));
+    assert_eq!(conn.incr::<_, _, i64>("cs:Psmon:object1", 5).unwrap(), 17);
+    assert_eq!(redis::cmd("TYPE").arg("cs:Psmon:object1").query::<String>(&mut conn).unwrap(), "string");
+
+    // SET ... GET answers with the previous value, NX only writes missing keys
+    let previous: Option<String> = redis::cmd("SET").arg("cs:Psmon:object2").arg("a").arg("EX").arg(60).arg("GET").query(&mut conn).unwrap();
+    assert_eq!(previous, None);
+    assert!(conn.ttl::<_, i64>("cs:Psmon:object2").unwrap() > 0);
+    let written: Option<String> = redis::cmd("SET").arg("cs:Psmon:object2").arg("b").arg("NX").query(&mut conn).unwrap();
+    assert_eq!(written, None);
+    assert_eq!(conn.get::<_, String>("cs:Psmon:object2").unwrap(), "a");
+
+    let _: () = redis::cmd("PSETEX").arg("cs:Psmon:object3").arg(1).arg("gone").query(&mut conn).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    assert!(!conn.exists::<_, bool>("cs:Psmon:object3").unwrap());
+    assert_eq!(conn.del::<_, u64>(&["cs:Psmon:object1", "cs:Psmon:object2", "cs:Psmon:object3"]).unwrap(), 2);
+}
+
+#[test]
+fn collections_are_dropped_once_empty() {
+    let mut conn = MemoryStore::new();
+    assert_eq!(conn.sadd::<_, _, u64>("cs:Psmon:object1", &["b", "a", "b"]).unwrap(), 2);
+    assert_eq!(conn.smembers::<_, Vec<String>>("cs:Psmon:object1").unwrap(), ["a", "b"]);
+    assert_eq!(conn.srem::<_, _, u64>("cs:Psmon:object1", &["a", "b"]).unwrap(), 2);
+    assert!(!conn.exists::<_, bool>("cs:Psmon:object1").unwrap());
+
+    let _: () = conn.hset("cs:Psmon:object2", "cpu", "7").unwrap();
+    let fields: HashMap<String, String> = conn.hgetall("cs:Psmon:object2").unwrap();
+    assert_eq!(fields, HashMap::from([("cpu".to_string(), "7".to_string())]));
+    assert_eq!(conn.hdel::<_, _, u64>("cs:Psmon:object2", "cpu").unwrap(), 1);
+    assert!(!conn.exists::<_, bool>("cs:Psmon:object2").unwrap());
+}
+
+#[test]
+fn streams_keep_their_newest_entries() {
+    let mut conn = MemoryStore::new();
+    let ids: Vec<String> = (0..5)
+        .map(|n| redis::cmd("XADD").arg("cs:Psmon:object1").arg("MAXLEN").arg("~").arg(3).arg("*").arg("data").arg(n).query(&mut conn).unwrap())
+        .collect();
+    let reply: StreamRangeReply = conn.xrange_all("cs:Psmon:object1").unwrap();
+    assert_eq!(reply.ids.iter().map(|entry| entry.id.as_str()).collect::<Vec<_>>(), &ids[2..]);
+    assert_eq!(reply.ids[0].get::<String>("data").as_deref(), Some("2"));
+}
+
+#[test]
+fn scans_match_patterns_and_types() {
+    let mut conn = MemoryStore::new();
+    let _: () = conn.set("cs:Psmon:object1", 1).unwrap();
+    let _: () = conn.set("cs:Psmon:object2", 2).unwrap();
+    let _: u64 = conn.sadd("cs:Psmon:object3", 3).unwrap();
+    let _: () = conn.set("cs:DiskUsage:object1", 4).unwrap();
+    let mut keys: Vec<String> = conn.scan_match("cs:Psmon:*").unwrap().collect();
+    keys.sort();
+    assert_eq!(keys, ["cs:Psmon:object1", "cs:Psmon:object2", "cs:Psmon:object3"]);
+    let (_, sets): (String, Vec<String>) = redis::cmd("SCAN").arg(0).arg("TYPE").arg("set").query(&mut conn).unwrap();
+    assert_eq!(sets, ["cs:Psmon:object3"]);
+}
+
+#[test]
+fn transactions_fail_when_watched_data_changes() {
+    let store = MemoryStore::new();
+    let (mut conn, mut other) = (store.connection(), store.connection());
+    let _: () = conn.set("cs:Psmon:object1", "a").unwrap();
+
+    let _: () = redis::cmd("WATCH").arg("cs:Psmon:object1").query(&mut conn).unwrap();
+    let _: () = other.set("cs:Psmon:object1", "b").unwrap(); // Written by another connection meanwhile
+    let committed: Option<()> = redis::pipe().atomic().set("cs:Psmon:object1", "c").ignore().query(&mut conn).unwrap();
+    assert_eq!(committed, None);
+    assert_eq!(conn.get::<_, String>("cs:Psmon:object1").unwrap(), "b");
+
+    // Without interference the transaction commits, as redis::transaction expects
+    let (value,): (String,) = redis::transaction(&mut conn, &["cs:Psmon:object1"], |conn, pipe| {
+        let current: String = conn.get("cs:Psmon:object1")?;
+        pipe.set("cs:Psmon:object1", format!("{}c", current)).ignore().get("cs:Psmon:object1").query(conn)
+    }).unwrap();
+    assert_eq!(value, "bc");
+}
+
+#[test]
+fn unsupported_commands_fail_like_redis() {
+    let mut conn = Storage::Memory(MemoryStore::new());
+    assert!(conn.is_open());
+    conn.set_read_timeout(Some(std::time::Duration::from_millis(10))).unwrap();
+    let _: () = conn.set("cs:Psmon:object1", "a").unwrap();
+
+    let err = conn.sadd::<_, _, u64>("cs:Psmon:object1", "b").unwrap_err();
+    assert!(err.to_string().contains("WRONGTYPE"), "{}", err);
+    let err = redis::cmd("EVAL").arg("return 1").arg(0).query::<i64>(&mut conn).unwrap_err();
+    assert!(err.to_string().contains("unknown command 'eval'"), "{}", err);
+    let err = redis::cmd("JSON.GET").arg("cs:Psmon:object1").query::<String>(&mut conn).unwrap_err();
+    assert!(err.to_string().contains("unknown command"), "{}", err);
+    assert_eq!(conn.get::<_, String>("cs:Psmon:object1").unwrap(), "a"); // Failed commands change nothing
+}